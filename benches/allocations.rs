@@ -0,0 +1,204 @@
+//! Count the allocations of the benchmarked operations, which unlike timings
+//! are the same on every machine, and compare them with `allocations.txt`.
+//!
+//! Run with `cargo bench --bench allocations`, with `PHONENUMBER_BLESS=1` to
+//! update the baseline after an intended change.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use phonenumber::metadata::{Database, DATABASE};
+use phonenumber::{Mode, PhoneNumber};
+
+mod fixtures;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Count the allocations of the second run, so lazily compiled expressions
+/// and per-thread caches don't count.
+fn count<F: FnMut()>(mut body: F) -> usize {
+    body();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    body();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let fixtures = fixtures::numbers();
+    let parsed = fixtures
+        .iter()
+        .map(|n| phonenumber::parse(None, n.international).unwrap())
+        .collect::<Vec<PhoneNumber>>();
+    let bytes = DATABASE.to_bytes().unwrap();
+    let e164 = parsed
+        .iter()
+        .map(|n| n.format().mode(Mode::E164).to_string())
+        .collect::<Vec<String>>();
+    // The same numbers with their last digit changed, so they only differ in
+    // the national number.
+    let mismatched = fixtures
+        .iter()
+        .map(|n| {
+            let (rest, last) = n.international.split_at(n.international.len() - 1);
+            let last = (last.parse::<u8>().unwrap() + 1) % 10;
+            phonenumber::parse(None, format!("{}{}", rest, last)).unwrap()
+        })
+        .collect::<Vec<PhoneNumber>>();
+
+    // Whether numbers of a country can start with a zero is only worked out
+    // on first use.
+    for number in &e164 {
+        assert!(phonenumber::is_e164(number), "{}", number);
+    }
+
+    let mut counts = BTreeMap::new();
+
+    counts.insert(
+        "parse international",
+        count(|| {
+            for number in &fixtures {
+                phonenumber::parse(None, number.international).unwrap();
+            }
+        }),
+    );
+
+    counts.insert(
+        "parse national",
+        count(|| {
+            for number in &fixtures {
+                phonenumber::parse(Some(number.region), number.national).unwrap();
+            }
+        }),
+    );
+
+    counts.insert(
+        "format E.164",
+        count(|| {
+            for number in &parsed {
+                number.format().mode(Mode::E164).to_string();
+            }
+        }),
+    );
+
+    let mut buffer = String::with_capacity(32 * parsed.len());
+    counts.insert(
+        "append E.164",
+        count(|| {
+            buffer.clear();
+
+            for number in &parsed {
+                number.format_append(Mode::E164, &mut buffer);
+            }
+        }),
+    );
+
+    counts.insert(
+        "format national",
+        count(|| {
+            for number in &parsed {
+                number.format().mode(Mode::National).to_string();
+            }
+        }),
+    );
+
+    counts.insert(
+        "is_valid",
+        count(|| {
+            for number in &parsed {
+                number.is_valid();
+            }
+        }),
+    );
+
+    counts.insert(
+        "is_e164",
+        count(|| {
+            for number in &e164 {
+                phonenumber::is_e164(number);
+            }
+        }),
+    );
+
+    counts.insert(
+        "match mismatch",
+        count(|| {
+            for (a, b) in parsed.iter().zip(&mismatched) {
+                phonenumber::is_number_match(a, b);
+            }
+        }),
+    );
+
+    counts.insert(
+        "load database",
+        count(|| {
+            Database::from_bytes(&bytes).unwrap();
+        }),
+    );
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/allocations.txt");
+    let current = counts
+        .iter()
+        .map(|(name, count)| format!("{}: {}\n", name, count))
+        .collect::<String>();
+
+    if env::var_os("PHONENUMBER_BLESS").is_some() {
+        fs::write(&path, current).unwrap();
+        return;
+    }
+
+    let baseline = fs::read_to_string(&path).unwrap_or_default();
+    let baseline = baseline
+        .lines()
+        .filter_map(|line| {
+            let (name, count) = line.split_once(": ")?;
+            Some((name, count.parse::<usize>().ok()?))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut regressed = false;
+
+    println!("{:<20} {:>10} {:>10}", "", "baseline", "current");
+    for (name, &count) in &counts {
+        let before = baseline.get(name).copied();
+        regressed |= before.map_or(false, |before| count > before);
+
+        println!(
+            "{:<20} {:>10} {:>10}",
+            name,
+            before.map_or("-".into(), |b| b.to_string()),
+            count
+        );
+    }
+
+    if regressed {
+        eprintln!("error: more allocations than in {}", path.display());
+        process::exit(1);
+    }
+}
@@ -0,0 +1,63 @@
+//! Validate the fixtures repeated to 100k numbers one by one and with
+//! `bulk::validate`, built with the `rayon` feature.
+//!
+//! The parallel run should get close to a linear speedup, to check it compare
+//! a single thread with the cores available:
+//!
+//! ```sh
+//! RAYON_NUM_THREADS=1 cargo bench --features rayon --bench bulk
+//! RAYON_NUM_THREADS=8 cargo bench --features rayon --bench bulk
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use phonenumber::bulk;
+use phonenumber::metadata::DATABASE;
+use rayon::prelude::*;
+
+// Only the international format is used here.
+#[allow(dead_code)]
+mod fixtures;
+
+const COUNT: usize = 100_000;
+
+fn validate(c: &mut Criterion) {
+    let fixtures = fixtures::numbers();
+    let numbers = fixtures
+        .iter()
+        .map(|n| n.international)
+        .cycle()
+        .take(COUNT)
+        .collect::<Vec<&str>>();
+
+    // Make sure the database is loaded and the expressions are compiled
+    // outside of the measurements.
+    bulk::validate(
+        numbers[..fixtures.len()].par_iter().copied(),
+        None,
+        &DATABASE,
+    );
+
+    let mut group = c.benchmark_group("validate");
+    group.sample_size(10);
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for number in &numbers {
+                let number = phonenumber::parse_with(&DATABASE, None, black_box(number)).unwrap();
+                black_box((
+                    number.is_valid_with(&DATABASE),
+                    number.number_type_with(&DATABASE),
+                ));
+            }
+        })
+    });
+
+    group.bench_function("parallel", |b| {
+        b.iter(|| black_box(bulk::validate(numbers.par_iter().copied(), None, &DATABASE)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, validate);
+criterion_main!(benches);
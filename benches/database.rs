@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use phonenumber::country;
+use phonenumber::metadata::{Database, DATABASE};
+
+fn database(c: &mut Criterion) {
+    let bytes = DATABASE.to_bytes().unwrap();
+
+    c.bench_function("load database", |b| {
+        b.iter(|| black_box(Database::from_bytes(black_box(&bytes)).unwrap()))
+    });
+
+    c.bench_function("first parse from a cold database", |b| {
+        b.iter_batched(
+            || Database::from_bytes(&bytes).unwrap(),
+            |database| {
+                let number =
+                    phonenumber::parse_with(&database, Some(country::US), "(650) 253-0000")
+                        .unwrap();
+
+                black_box(number.is_valid_with(&database))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, database);
+criterion_main!(benches);
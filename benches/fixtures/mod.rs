@@ -0,0 +1,26 @@
+use phonenumber::country;
+
+/// A number from `numbers.txt`.
+pub struct Number {
+    pub region: country::Id,
+    pub national: &'static str,
+    pub international: &'static str,
+}
+
+/// The numbers used by the benchmarks, all valid.
+pub fn numbers() -> Vec<Number> {
+    include_str!("numbers.txt")
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split('\t');
+            let mut field = || fields.next().unwrap();
+
+            Number {
+                region: field().parse().unwrap(),
+                national: field(),
+                international: field(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,103 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use phonenumber::{country, Mode, PhoneNumber, Type};
+
+mod fixtures;
+
+fn numbers(c: &mut Criterion) {
+    let fixtures = fixtures::numbers();
+    let parsed = fixtures
+        .iter()
+        .map(|n| phonenumber::parse(None, n.international).unwrap())
+        .collect::<Vec<PhoneNumber>>();
+
+    // The same numbers with their last digit changed, so they only differ in
+    // the national number.
+    let mismatched = fixtures
+        .iter()
+        .map(|n| {
+            let (rest, last) = n.international.split_at(n.international.len() - 1);
+            let last = (last.parse::<u8>().unwrap() + 1) % 10;
+            phonenumber::parse(None, format!("{}{}", rest, last)).unwrap()
+        })
+        .collect::<Vec<PhoneNumber>>();
+
+    // Argentina has one of the longest lists of formats to pick from.
+    let argentina = [
+        Type::FixedLine,
+        Type::Mobile,
+        Type::TollFree,
+        Type::PremiumRate,
+        Type::Uan,
+    ]
+    .iter()
+    .filter_map(|&kind| phonenumber::example_number_for_type(country::AR, kind))
+    .collect::<Vec<PhoneNumber>>();
+
+    // Make sure the database is loaded and the expressions are compiled
+    // outside of the measurements.
+    for number in parsed.iter().chain(&argentina) {
+        number.is_valid();
+        number.format().mode(Mode::National).to_string();
+    }
+
+    c.bench_function("parse international", |b| {
+        b.iter(|| {
+            for number in &fixtures {
+                black_box(phonenumber::parse(None, black_box(number.international)).unwrap());
+            }
+        })
+    });
+
+    c.bench_function("parse national", |b| {
+        b.iter(|| {
+            for number in &fixtures {
+                black_box(
+                    phonenumber::parse(Some(number.region), black_box(number.national)).unwrap(),
+                );
+            }
+        })
+    });
+
+    c.bench_function("format E.164", |b| {
+        b.iter(|| {
+            for number in &parsed {
+                black_box(number.format().mode(Mode::E164).to_string());
+            }
+        })
+    });
+
+    c.bench_function("format national", |b| {
+        b.iter(|| {
+            for number in &parsed {
+                black_box(number.format().mode(Mode::National).to_string());
+            }
+        })
+    });
+
+    c.bench_function("format national AR", |b| {
+        b.iter(|| {
+            for number in &argentina {
+                black_box(number.format().mode(Mode::National).to_string());
+            }
+        })
+    });
+
+    c.bench_function("is_valid", |b| {
+        b.iter(|| {
+            for number in &parsed {
+                black_box(black_box(number).is_valid());
+            }
+        })
+    });
+
+    c.bench_function("match mismatch", |b| {
+        b.iter(|| {
+            for (x, y) in parsed.iter().zip(&mismatched) {
+                black_box(phonenumber::is_number_match(black_box(x), black_box(y)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, numbers);
+criterion_main!(benches);
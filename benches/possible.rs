@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use phonenumber::country;
+
+const NUMBERS: &[(Option<country::Id>, &str)] = &[
+    (None, "+1 650 253 0000"),
+    (Some(country::GB), "020 7031 3000"),
+    (None, "+49 30 123456"),
+    (Some(country::US), "011 44 20 7031 3000"),
+    (None, "+44 20 7031 30001"),
+];
+
+fn possible(c: &mut Criterion) {
+    // Make sure the database is loaded outside of the measurements.
+    phonenumber::is_possible_str(None, "+1 650 253 0000");
+
+    c.bench_function("parse and is_possible", |b| {
+        b.iter(|| {
+            for &(country, number) in NUMBERS {
+                black_box(
+                    phonenumber::parse(country, black_box(number))
+                        .map(|n| n.is_possible())
+                        .unwrap_or(false),
+                );
+            }
+        })
+    });
+
+    c.bench_function("is_possible_str", |b| {
+        b.iter(|| {
+            for &(country, number) in NUMBERS {
+                black_box(phonenumber::is_possible_str(country, black_box(number)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, possible);
+criterion_main!(benches);
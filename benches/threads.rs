@@ -0,0 +1,63 @@
+//! Parse and validate the fixtures with the global database from 1 to 16
+//! threads, each thread handling the same amount of numbers.
+//!
+//! With no contention between the threads the throughput should grow with
+//! the threads up to the cores available.
+
+use std::thread;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use phonenumber::metadata::DATABASE;
+
+// Only the international format is used here.
+#[allow(dead_code)]
+mod fixtures;
+
+const PER_THREAD: usize = 10_000;
+
+fn work(numbers: &[&'static str]) {
+    for number in numbers {
+        let number = phonenumber::parse(None, black_box(number)).unwrap();
+        black_box((number.is_valid(), number.number_type()));
+    }
+}
+
+fn threads(c: &mut Criterion) {
+    let numbers = fixtures::numbers()
+        .iter()
+        .map(|n| n.international)
+        .cycle()
+        .take(PER_THREAD)
+        .collect::<Vec<&'static str>>();
+
+    // Make sure the database is loaded and the expressions are compiled
+    // outside of the measurements.
+    DATABASE.compile();
+    work(&numbers);
+
+    let mut group = c.benchmark_group("threads");
+    group.sample_size(10);
+
+    for &count in &[1, 2, 4, 8, 16] {
+        group.throughput(Throughput::Elements((count * PER_THREAD) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter(|| {
+                let threads = (0..count)
+                    .map(|_| {
+                        let numbers = numbers.clone();
+                        thread::spawn(move || work(&numbers))
+                    })
+                    .collect::<Vec<_>>();
+
+                for thread in threads {
+                    thread.join().unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, threads);
+criterion_main!(benches);
@@ -1,6 +1,7 @@
+use std::collections::BTreeMap;
 use std::env;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
 use std::path::Path;
 
 extern crate quick_xml as xml;
@@ -20,19 +21,175 @@ mod loader;
 #[path = "src/error.rs"]
 mod error;
 
+#[path = "src/prefix_map.rs"]
+mod prefix_map;
+
+/// Load the upstream prefix descriptions in the given directory, by language
+/// and country calling code.
+fn prefixes(dir: &str) -> prefix_map::PrefixMaps {
+    let mut result = BTreeMap::new();
+
+    for lang in fs::read_dir(dir).expect("could not read prefix directory") {
+        let lang = lang.unwrap().path();
+
+        if !lang.is_dir() {
+            continue;
+        }
+
+        let maps = result
+            .entry(lang.file_name().unwrap().to_string_lossy().into_owned())
+            .or_insert_with(BTreeMap::new);
+
+        for file in fs::read_dir(&lang).unwrap() {
+            let file = file.unwrap().path();
+            let code = match file.file_stem().and_then(|s| s.to_str()).map(str::parse) {
+                Some(Ok(code)) => code,
+                _ => continue,
+            };
+
+            let content = fs::read_to_string(&file).expect("could not read prefix file");
+            maps.insert(
+                code,
+                prefix_map::PrefixMap::parse(&content)
+                    .unwrap_or_else(|err| panic!("malformed prefix file {:?}: {}", file, err)),
+            );
+        }
+    }
+
+    result
+}
+
 fn main() {
-    let metadata = loader::load(BufReader::new(
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-changed=src/metadata/loader.rs");
+    println!("cargo:rerun-if-changed=src/error.rs");
+    println!("cargo:rerun-if-env-changed=PHONENUMBER_REGIONS");
+
+    // The upstream metadata version is kept as the build metadata of the crate
+    // version, for example `8.12.9` in `0.3.1+8.12.9`.
+    let version = env::var("CARGO_PKG_VERSION")
+        .unwrap()
+        .split_once('+')
+        .map(|(_, version)| version.to_owned())
+        .expect("crate version is missing the metadata version");
+
+    println!("cargo:rustc-env=PHONENUMBER_METADATA_VERSION={}", version);
+
+    let mut metadata = loader::load(BufReader::new(
         File::open("assets/PhoneNumberMetadata.xml").expect("could not open metadata file"),
     ))
     .expect("failed to load metadata");
 
-    let mut out = BufWriter::new(
-        File::create(Path::new(&env::var("OUT_DIR").unwrap()).join("database.bin"))
-            .expect("could not create database file"),
-    );
+    // Keep track of every calling code, so numbers from regions left out of
+    // the embedded metadata can be told apart from invalid ones.
+    let mut codes = metadata
+        .iter()
+        .filter_map(|m| m.country_code)
+        .collect::<Vec<_>>();
+    codes.sort_unstable();
+    codes.dedup();
+
+    writeln!(
+        File::create(Path::new(&env::var("OUT_DIR").unwrap()).join("calling_codes.rs"))
+            .expect("could not create calling codes file"),
+        "pub(crate) const CALLING_CODES: &[u16] = &{:?};",
+        codes
+    )
+    .expect("could not write calling codes file");
+
+    // Only embed the regions listed in `PHONENUMBER_REGIONS`, if set, to
+    // shrink the embedded metadata.
+    let regions = env::var("PHONENUMBER_REGIONS").ok().map(|value| {
+        value
+            .split(',')
+            .map(|id| id.trim().to_uppercase())
+            .filter(|id| !id.is_empty())
+            .collect::<Vec<_>>()
+    });
+
+    if let Some(regions) = regions.as_ref() {
+        metadata.retain(|m| {
+            m.id.as_ref()
+                .map(|id| regions.contains(id))
+                .unwrap_or(false)
+        });
+    }
+
+    let mut short = loader::load(BufReader::new(
+        File::open("assets/ShortNumberMetadata.xml")
+            .expect("could not open short number metadata file"),
+    ))
+    .expect("failed to load short number metadata");
+
+    // Short number territories don't carry a country code, so take it from the
+    // main metadata.
+    for meta in &mut short {
+        meta.country_code = metadata
+            .iter()
+            .find(|m| m.id == meta.id)
+            .and_then(|m| m.country_code);
+    }
+
+    short.retain(|m| m.country_code.is_some());
+
+    if let Some(regions) = regions.as_ref() {
+        short.retain(|m| {
+            m.id.as_ref()
+                .map(|id| regions.contains(id))
+                .unwrap_or(false)
+        });
+    }
+
+    // Alternate formats are only used to find numbers in text.
+    let alternate = if env::var_os("CARGO_FEATURE_ALTERNATE_FORMATS").is_some() {
+        loader::load(BufReader::new(
+            File::open("assets/PhoneNumberAlternateFormats.xml")
+                .expect("could not open alternate formats file"),
+        ))
+        .expect("failed to load alternate formats")
+    } else {
+        Vec::new()
+    };
+
+    File::create(Path::new(&env::var("OUT_DIR").unwrap()).join("database.bin"))
+        .expect("could not create database file")
+        .write_all(
+            &loader::encode(&loader::Bundle {
+                version,
+                metadata,
+                short,
+                alternate,
+            })
+            .expect("failed to serialize database"),
+        )
+        .expect("could not write database file");
+
+    if env::var_os("CARGO_FEATURE_CARRIER").is_some() {
+        let carriers = prefixes("assets/carrier");
+
+        File::create(Path::new(&env::var("OUT_DIR").unwrap()).join("carrier.bin"))
+            .expect("could not create carrier file")
+            .write_all(
+                &bincode::options()
+                    .with_varint_encoding()
+                    .serialize(&carriers)
+                    .expect("failed to serialize carriers"),
+            )
+            .expect("could not write carrier file");
+    }
+
+    if env::var_os("CARGO_FEATURE_GEOCODING").is_some() {
+        let geocoding = prefixes("assets/geocoding");
 
-    bincode::options()
-        .with_varint_encoding()
-        .serialize_into(&mut out, &metadata)
-        .expect("failed to serialize database");
+        File::create(Path::new(&env::var("OUT_DIR").unwrap()).join("geocoding.bin"))
+            .expect("could not create geocoding file")
+            .write_all(
+                &bincode::options()
+                    .with_varint_encoding()
+                    .serialize(&geocoding)
+                    .expect("failed to serialize geocoding"),
+            )
+            .expect("could not write geocoding file");
+    }
 }
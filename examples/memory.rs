@@ -0,0 +1,64 @@
+//! Measure the heap used by a loaded database, before and after its regular
+//! expressions get compiled by validating the example numbers of every region.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+extern crate phonenumber;
+use phonenumber::metadata::{Database, DATABASE};
+use phonenumber::Type;
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn main() {
+    let bytes = DATABASE.to_bytes().unwrap();
+
+    let before = LIVE.load(Ordering::Relaxed);
+    let database = Database::from_bytes(&bytes).unwrap();
+    let loaded = LIVE.load(Ordering::Relaxed) - before;
+
+    let types = [
+        Type::FixedLine,
+        Type::Mobile,
+        Type::TollFree,
+        Type::PremiumRate,
+        Type::SharedCost,
+        Type::PersonalNumber,
+        Type::Voip,
+        Type::Pager,
+        Type::Uan,
+        Type::Voicemail,
+    ];
+
+    for region in database.supported_regions().collect::<Vec<_>>() {
+        for &kind in &types {
+            if let Some(number) = phonenumber::example_number_for_type_with(&database, region, kind)
+            {
+                number.is_valid_with(&database);
+                number.format_with(&database).to_string();
+            }
+        }
+    }
+
+    let compiled = LIVE.load(Ordering::Relaxed) - before;
+
+    println!("loaded:   {:>8} KiB", loaded / 1024);
+    println!("compiled: {:>8} KiB", compiled / 1024);
+}
@@ -0,0 +1,134 @@
+#ifndef PHONENUMBER_H
+#define PHONENUMBER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Error codes, the parsing ones match the `phonenumber::ParseError`
+ * variants.
+ */
+typedef enum PnError {
+  PN_ERROR_OK,
+  PN_ERROR_NULL_POINTER,
+  PN_ERROR_INVALID_UTF8,
+  PN_ERROR_INVALID_REGION,
+  PN_ERROR_NO_NUMBER,
+  PN_ERROR_INVALID_COUNTRY_CODE,
+  PN_ERROR_UNSUPPORTED_REGION,
+  PN_ERROR_TOO_SHORT_AFTER_IDD,
+  PN_ERROR_TOO_SHORT_NSN,
+  PN_ERROR_TOO_LONG,
+  PN_ERROR_MALFORMED_INTEGER,
+  PN_ERROR_INVALID_EXTENSION,
+  PN_ERROR_INVALID_METADATA,
+  PN_ERROR_NOT_E164,
+  PN_ERROR_BUFFER_TOO_SMALL,
+  PN_ERROR_PANIC,
+} PnError;
+
+/**
+ * The formatting modes, see `phonenumber::Mode`.
+ */
+typedef enum PnMode {
+  PN_MODE_E164,
+  PN_MODE_INTERNATIONAL,
+  PN_MODE_NATIONAL,
+  PN_MODE_RFC3966,
+} PnMode;
+
+/**
+ * The phone number types, see `phonenumber::Type`.
+ */
+typedef enum PnType {
+  PN_TYPE_FIXED_LINE,
+  PN_TYPE_MOBILE,
+  PN_TYPE_FIXED_LINE_OR_MOBILE,
+  PN_TYPE_TOLL_FREE,
+  PN_TYPE_PREMIUM_RATE,
+  PN_TYPE_SHARED_COST,
+  PN_TYPE_PERSONAL_NUMBER,
+  PN_TYPE_VOIP,
+  PN_TYPE_PAGER,
+  PN_TYPE_UAN,
+  PN_TYPE_EMERGENCY,
+  PN_TYPE_VOICEMAIL,
+  PN_TYPE_SHORT_CODE,
+  PN_TYPE_STANDARD_RATE,
+  PN_TYPE_CARRIER,
+  PN_TYPE_NO_INTERNATIONAL,
+  PN_TYPE_UNKNOWN,
+} PnType;
+
+/**
+ * A parsed phone number, freed with `pn_free`.
+ */
+typedef struct PnNumber PnNumber;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parse a phone number, in the given region when it's not in the
+ * international format.
+ *
+ * On success the number is stored in `out` and has to be freed with
+ * `pn_free`, otherwise `out` is set to `NULL`.
+ *
+ * # Safety
+ *
+ * `input` must be a NUL terminated string, `region` one or `NULL`, and `out`
+ * must point to writable memory.
+ */
+PnError pn_parse(const char *input, const char *region, PnNumber **out);
+
+/**
+ * Format the number into the buffer of the given length, NUL terminated.
+ *
+ * If the buffer is too small, `PN_ERROR_BUFFER_TOO_SMALL` is returned and the
+ * buffer is left untouched.
+ *
+ * # Safety
+ *
+ * `number` must be a number from `pn_parse` or `NULL`, and `buf` must point to
+ * at least `len` bytes of writable memory.
+ */
+PnError pn_format(const PnNumber *number, PnMode mode, char *buf, size_t len);
+
+/**
+ * Check if the number is valid, `false` for `NULL`.
+ *
+ * # Safety
+ *
+ * `number` must be a number from `pn_parse` or `NULL`.
+ */
+bool pn_is_valid(const PnNumber *number);
+
+/**
+ * Get the type of the number, `PN_TYPE_UNKNOWN` for `NULL`.
+ *
+ * # Safety
+ *
+ * `number` must be a number from `pn_parse` or `NULL`.
+ */
+PnType pn_number_type(const PnNumber *number);
+
+/**
+ * Free a number from `pn_parse`, doing nothing for `NULL`.
+ *
+ * # Safety
+ *
+ * `number` must be a number from `pn_parse` or `NULL`, and not be used
+ * afterwards.
+ */
+void pn_free(PnNumber *number);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PHONENUMBER_H */
@@ -0,0 +1,359 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C bindings for parsing, validating and formatting phone numbers, declared
+//! in `include/phonenumber.h`.
+//!
+//! No function unwinds into C, a panic is reported as `PN_ERROR_PANIC`, or
+//! the answer for a missing number by the functions without an error code.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use phonenumber::{country, Mode, ParseError, PhoneNumber, Type};
+
+/// A parsed phone number, freed with `pn_free`.
+pub struct PnNumber(PhoneNumber);
+
+/// Error codes, the parsing ones match the `phonenumber::ParseError`
+/// variants.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PnError {
+    Ok,
+    NullPointer,
+    InvalidUtf8,
+    InvalidRegion,
+    NoNumber,
+    InvalidCountryCode,
+    UnsupportedRegion,
+    TooShortAfterIdd,
+    TooShortNsn,
+    TooLong,
+    MalformedInteger,
+    InvalidExtension,
+    InvalidMetadata,
+    NotE164,
+    BufferTooSmall,
+    Panic,
+}
+
+/// The formatting modes, see `phonenumber::Mode`.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PnMode {
+    E164,
+    International,
+    National,
+    Rfc3966,
+}
+
+/// The phone number types, see `phonenumber::Type`.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PnType {
+    FixedLine,
+    Mobile,
+    FixedLineOrMobile,
+    TollFree,
+    PremiumRate,
+    SharedCost,
+    PersonalNumber,
+    Voip,
+    Pager,
+    Uan,
+    Emergency,
+    Voicemail,
+    ShortCode,
+    StandardRate,
+    Carrier,
+    NoInternational,
+    Unknown,
+}
+
+impl From<ParseError> for PnError {
+    fn from(err: ParseError) -> PnError {
+        match err {
+            ParseError::NoNumber => PnError::NoNumber,
+            ParseError::InvalidCountryCode => PnError::InvalidCountryCode,
+            ParseError::UnsupportedRegion => PnError::UnsupportedRegion,
+            ParseError::TooShortAfterIdd => PnError::TooShortAfterIdd,
+            ParseError::TooShortNsn => PnError::TooShortNsn,
+            ParseError::TooLong => PnError::TooLong,
+            ParseError::NotE164 => PnError::NotE164,
+            ParseError::MalformedInteger(_) => PnError::MalformedInteger,
+            ParseError::InvalidExtension(_) => PnError::InvalidExtension,
+            ParseError::Metadata(_) => PnError::InvalidMetadata,
+        }
+    }
+}
+
+impl From<PnMode> for Mode {
+    fn from(mode: PnMode) -> Mode {
+        match mode {
+            PnMode::E164 => Mode::E164,
+            PnMode::International => Mode::International,
+            PnMode::National => Mode::National,
+            PnMode::Rfc3966 => Mode::Rfc3966,
+        }
+    }
+}
+
+impl From<Type> for PnType {
+    fn from(kind: Type) -> PnType {
+        match kind {
+            Type::FixedLine => PnType::FixedLine,
+            Type::Mobile => PnType::Mobile,
+            Type::FixedLineOrMobile => PnType::FixedLineOrMobile,
+            Type::TollFree => PnType::TollFree,
+            Type::PremiumRate => PnType::PremiumRate,
+            Type::SharedCost => PnType::SharedCost,
+            Type::PersonalNumber => PnType::PersonalNumber,
+            Type::Voip => PnType::Voip,
+            Type::Pager => PnType::Pager,
+            Type::Uan => PnType::Uan,
+            Type::Emergency => PnType::Emergency,
+            Type::Voicemail => PnType::Voicemail,
+            Type::ShortCode => PnType::ShortCode,
+            Type::StandardRate => PnType::StandardRate,
+            Type::Carrier => PnType::Carrier,
+            Type::NoInternational => PnType::NoInternational,
+            Type::Unknown => PnType::Unknown,
+        }
+    }
+}
+
+/// Run the body, turning a panic into the given value.
+fn guard<T, F: FnOnce() -> T>(panicked: T, body: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(panicked)
+}
+
+/// Parse a phone number, in the given region when it's not in the
+/// international format.
+///
+/// On success the number is stored in `out` and has to be freed with
+/// `pn_free`, otherwise `out` is set to `NULL`.
+///
+/// # Safety
+///
+/// `input` must be a NUL terminated string, `region` one or `NULL`, and `out`
+/// must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pn_parse(
+    input: *const c_char,
+    region: *const c_char,
+    out: *mut *mut PnNumber,
+) -> PnError {
+    if out.is_null() {
+        return PnError::NullPointer;
+    }
+
+    *out = ptr::null_mut();
+
+    guard(PnError::Panic, || {
+        if input.is_null() {
+            return PnError::NullPointer;
+        }
+
+        let input = match CStr::from_ptr(input).to_str() {
+            Ok(input) => input,
+            Err(_) => return PnError::InvalidUtf8,
+        };
+
+        let region = if region.is_null() {
+            None
+        } else {
+            match CStr::from_ptr(region).to_str() {
+                Ok(region) => match region.parse::<country::Id>() {
+                    Ok(id) => Some(id),
+                    Err(_) => return PnError::InvalidRegion,
+                },
+                Err(_) => return PnError::InvalidUtf8,
+            }
+        };
+
+        match phonenumber::parse(region, input) {
+            Ok(number) => {
+                *out = Box::into_raw(Box::new(PnNumber(number)));
+                PnError::Ok
+            }
+
+            Err(err) => err.into(),
+        }
+    })
+}
+
+/// Format the number into the buffer of the given length, NUL terminated.
+///
+/// If the buffer is too small, `PN_ERROR_BUFFER_TOO_SMALL` is returned and the
+/// buffer is left untouched.
+///
+/// # Safety
+///
+/// `number` must be a number from `pn_parse` or `NULL`, and `buf` must point to
+/// at least `len` bytes of writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn pn_format(
+    number: *const PnNumber,
+    mode: PnMode,
+    buf: *mut c_char,
+    len: usize,
+) -> PnError {
+    if number.is_null() || buf.is_null() {
+        return PnError::NullPointer;
+    }
+
+    guard(PnError::Panic, || {
+        let formatted = (*number).0.format().mode(mode.into()).to_string();
+
+        if formatted.len() >= len {
+            return PnError::BufferTooSmall;
+        }
+
+        ptr::copy_nonoverlapping(formatted.as_ptr(), buf as *mut u8, formatted.len());
+        *buf.add(formatted.len()) = 0;
+
+        PnError::Ok
+    })
+}
+
+/// Check if the number is valid, `false` for `NULL`.
+///
+/// # Safety
+///
+/// `number` must be a number from `pn_parse` or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn pn_is_valid(number: *const PnNumber) -> bool {
+    if number.is_null() {
+        return false;
+    }
+
+    guard(false, || (*number).0.is_valid())
+}
+
+/// Get the type of the number, `PN_TYPE_UNKNOWN` for `NULL`.
+///
+/// # Safety
+///
+/// `number` must be a number from `pn_parse` or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn pn_number_type(number: *const PnNumber) -> PnType {
+    if number.is_null() {
+        return PnType::Unknown;
+    }
+
+    guard(PnType::Unknown, || (*number).0.number_type().into())
+}
+
+/// Free a number from `pn_parse`, doing nothing for `NULL`.
+///
+/// # Safety
+///
+/// `number` must be a number from `pn_parse` or `NULL`, and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pn_free(number: *mut PnNumber) {
+    if !number.is_null() {
+        guard((), || drop(Box::from_raw(number)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    use crate::*;
+
+    fn parse(input: &[u8], region: Option<&[u8]>) -> (PnError, *mut PnNumber) {
+        let mut number = ptr::null_mut();
+        let region = region.map_or(ptr::null(), |r| r.as_ptr() as *const c_char);
+        let err = unsafe { pn_parse(input.as_ptr() as *const c_char, region, &mut number) };
+
+        (err, number)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (err, number) = parse(b"030 123456\0", Some(b"DE\0"));
+        assert_eq!(PnError::Ok, err);
+
+        let mut buf = [0 as c_char; 32];
+        unsafe {
+            assert!(pn_is_valid(number));
+            assert_eq!(PnType::FixedLine, pn_number_type(number));
+
+            assert_eq!(
+                PnError::Ok,
+                pn_format(number, PnMode::International, buf.as_mut_ptr(), buf.len())
+            );
+            assert_eq!(
+                "+49 30 123456",
+                CStr::from_ptr(buf.as_ptr()).to_str().unwrap()
+            );
+
+            // The terminator has to fit too.
+            assert_eq!(
+                PnError::BufferTooSmall,
+                pn_format(number, PnMode::E164, buf.as_mut_ptr(), 11)
+            );
+            assert_eq!(
+                PnError::Ok,
+                pn_format(number, PnMode::E164, buf.as_mut_ptr(), 12)
+            );
+
+            pn_free(number);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(PnError::NoNumber, parse(b"a\0", None).0);
+        assert_eq!(PnError::InvalidCountryCode, parse(b"030 123456\0", None).0);
+        assert_eq!(
+            PnError::InvalidRegion,
+            parse(b"030 123456\0", Some(b"XX\0")).0
+        );
+        assert_eq!(
+            PnError::TooLong,
+            parse(b"+49 1234567890123456789\0", None).0
+        );
+        assert_eq!(PnError::InvalidUtf8, parse(b"+49 30 \xff\0", None).0);
+
+        let (err, number) = parse(b"a\0", None);
+        assert_eq!(PnError::NoNumber, err);
+        assert!(number.is_null());
+
+        unsafe {
+            assert_eq!(
+                PnError::NullPointer,
+                pn_parse(ptr::null(), ptr::null(), &mut ptr::null_mut())
+            );
+            assert_eq!(
+                PnError::NullPointer,
+                pn_parse(
+                    b"1\0".as_ptr() as *const c_char,
+                    ptr::null(),
+                    ptr::null_mut()
+                )
+            );
+            assert!(!pn_is_valid(ptr::null()));
+            assert_eq!(PnType::Unknown, pn_number_type(ptr::null()));
+            pn_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compile `test.c` against the header and the shared library, and run it.
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("phonenumber-ffi-test");
+
+    // The shared library is next to the test binary.
+    let exe = env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests/test.c"))
+        .arg("-o")
+        .arg(&out)
+        .arg("-L")
+        .arg(deps)
+        .arg(format!("-Wl,-rpath,{}", deps.display()))
+        .arg("-lphonenumber_ffi")
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+
+    let status = Command::new(&out).status().unwrap();
+    assert!(status.success());
+}
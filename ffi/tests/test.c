@@ -0,0 +1,52 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "phonenumber.h"
+
+#define CHECK(cond)                                                    \
+  do {                                                                 \
+    if (!(cond)) {                                                     \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+              #cond);                                                  \
+      return 1;                                                        \
+    }                                                                  \
+  } while (0)
+
+int main(void) {
+  PnNumber *number = NULL;
+  char buf[64];
+
+  CHECK(pn_parse("+1 650 253 0000", NULL, &number) == PN_ERROR_OK);
+  CHECK(pn_is_valid(number));
+  CHECK(pn_number_type(number) == PN_TYPE_FIXED_LINE_OR_MOBILE);
+  CHECK(pn_format(number, PN_MODE_E164, buf, sizeof buf) == PN_ERROR_OK);
+  CHECK(strcmp(buf, "+16502530000") == 0);
+  CHECK(pn_format(number, PN_MODE_NATIONAL, buf, sizeof buf) == PN_ERROR_OK);
+  CHECK(strcmp(buf, "(650) 253-0000") == 0);
+  CHECK(pn_format(number, PN_MODE_RFC3966, buf, sizeof buf) == PN_ERROR_OK);
+  CHECK(strcmp(buf, "tel:+1-650-253-0000") == 0);
+  CHECK(pn_format(number, PN_MODE_E164, buf, 12) == PN_ERROR_BUFFER_TOO_SMALL);
+  pn_free(number);
+
+  CHECK(pn_parse("030 123456", "DE", &number) == PN_ERROR_OK);
+  CHECK(pn_number_type(number) == PN_TYPE_FIXED_LINE);
+  CHECK(pn_format(number, PN_MODE_INTERNATIONAL, buf, sizeof buf) ==
+        PN_ERROR_OK);
+  CHECK(strcmp(buf, "+49 30 123456") == 0);
+  pn_free(number);
+
+  CHECK(pn_parse("not a number", NULL, &number) == PN_ERROR_NO_NUMBER);
+  CHECK(number == NULL);
+  CHECK(pn_parse("030 123456", NULL, &number) ==
+        PN_ERROR_INVALID_COUNTRY_CODE);
+  CHECK(pn_parse("030 123456", "XX", &number) == PN_ERROR_INVALID_REGION);
+  CHECK(pn_parse(NULL, NULL, &number) == PN_ERROR_NULL_POINTER);
+
+  CHECK(!pn_is_valid(NULL));
+  CHECK(pn_number_type(NULL) == PN_TYPE_UNKNOWN);
+  CHECK(pn_format(NULL, PN_MODE_E164, buf, sizeof buf) ==
+        PN_ERROR_NULL_POINTER);
+  pn_free(NULL);
+
+  return 0;
+}
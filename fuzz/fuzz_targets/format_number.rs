@@ -0,0 +1,46 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use phonenumber::PhoneNumber;
+use phonenumber_fuzz::Region;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    code: u16,
+    national: u64,
+    zeros: u8,
+    extension: Option<String>,
+    carrier: Option<String>,
+    region: Region,
+}
+
+fuzz_target!(|input: Input| {
+    let mut builder = PhoneNumber::builder(input.code, input.national).leading_zeros(input.zeros);
+
+    if let Some(extension) = input.extension {
+        builder = builder.extension(extension);
+    }
+
+    if let Some(carrier) = input.carrier {
+        builder = builder.carrier(carrier);
+    }
+
+    if let Ok(number) = builder.build() {
+        phonenumber_fuzz::exercise(&number, input.region.id());
+    }
+});
@@ -0,0 +1,40 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use phonenumber_fuzz::Region;
+
+fuzz_target!(|input: (Region, &str)| {
+    let (region, input) = input;
+    let region = region.id();
+
+    if let Ok(number) = phonenumber::parse(region, input) {
+        phonenumber_fuzz::exercise(&number, region);
+    }
+
+    if let Ok(number) = phonenumber::parse_and_keep_raw_input(region, input) {
+        phonenumber_fuzz::exercise(&number, region);
+    }
+
+    let _ = phonenumber::is_possible_str(region, input);
+    let _ = phonenumber::canonicalize(input, region);
+    let _ = phonenumber::assess(input, region);
+    let _ = phonenumber::infer_regions(input);
+
+    if let Some(region) = region {
+        let _ = phonenumber::shortnumber::connects_to_emergency_number(input, region);
+    }
+});
@@ -0,0 +1,60 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! What the fuzz targets run on the numbers they come up with, none of which
+//! may panic.
+
+use arbitrary::Arbitrary;
+use phonenumber::{country, Mode, PhoneNumber};
+
+/// A region out of any two letters, most of which aren't regions.
+#[derive(Arbitrary, Debug)]
+pub struct Region(Option<(u8, u8)>);
+
+impl Region {
+    pub fn id(&self) -> Option<country::Id> {
+        let (a, b) = self.0?;
+        let code = [b'A' + a % 26, b'A' + b % 26];
+
+        std::str::from_utf8(&code).ok()?.parse().ok()
+    }
+}
+
+/// Format and validate the number every way there is.
+pub fn exercise(number: &PhoneNumber, region: Option<country::Id>) {
+    for &mode in &[
+        Mode::E164,
+        Mode::International,
+        Mode::National,
+        Mode::Rfc3966,
+    ] {
+        let _ = number.format().mode(mode).to_string();
+        let _ = number.format().mode(mode).calling_from(region).to_string();
+        let _ = number.format().mode(mode).keep_alpha(true).to_string();
+        let _ = number.format().mode(mode).parts();
+    }
+
+    let _ = number.canonical_bytes();
+    let _ = number.redacted().to_string();
+    let _ = number.is_valid();
+    let _ = number.is_valid_for_region(region);
+    let _ = number.validate();
+    let _ = number.is_possible_with_reason();
+    let _ = number.number_type();
+    let _ = number.components();
+    let _ = number.national_dialing_prefix();
+    let _ = number.clone().truncate_too_long();
+    let _ = phonenumber::shortnumber::is_valid_short_number(number, region);
+    let _ = phonenumber::shortnumber::expected_cost(number, region);
+}
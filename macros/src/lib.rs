@@ -0,0 +1,108 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Phone numbers parsed and validated at compile time, with the metadata
+//! bundled with `phonenumber`.
+//!
+//! The macros can't be re-exported by `phonenumber` itself, since they use it
+//! to parse the numbers, so both crates have to be dependencies.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Parse and validate a phone number in the international format at compile
+/// time, expanding to a `phonenumber::PhoneNumber`.
+///
+/// Numbers without an extension can be used in constants.
+///
+/// ```
+/// use phonenumber::PhoneNumber;
+/// use phonenumber_macros::phonenumber;
+///
+/// const HOTLINE: PhoneNumber = phonenumber!("+1 650 253 0000");
+///
+/// assert_eq!(HOTLINE, phonenumber::parse(None, "+16502530000").unwrap());
+/// assert!(HOTLINE.is_valid());
+///
+/// let office = phonenumber!("+39 06 6988 3461 ext. 12");
+/// assert_eq!("0669883461", office.national_number_str());
+/// assert_eq!("12", office.extension().unwrap().as_ref());
+/// ```
+///
+/// Invalid numbers fail to compile:
+///
+/// ```compile_fail
+/// use phonenumber_macros::phonenumber;
+///
+/// let number = phonenumber!("+1 650 253 000");
+/// ```
+///
+/// And so do numbers missing the leading "+", since there's no region to
+/// parse them in:
+///
+/// ```compile_fail
+/// use phonenumber_macros::phonenumber;
+///
+/// let number = phonenumber!("650 253 0000");
+/// ```
+#[proc_macro]
+pub fn phonenumber(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let value = literal.value();
+
+    let error = |message: String| {
+        syn::Error::new(literal.span(), message)
+            .to_compile_error()
+            .into()
+    };
+
+    if !value.trim_start().starts_with('+') {
+        return error(format!(
+            "phone number {:?} has to start with \"+\" and its country calling code",
+            value
+        ));
+    }
+
+    let number = match phonenumber::parse(None, &value) {
+        Ok(number) => number,
+        Err(err) => return error(format!("invalid phone number {:?}: {}", value, err)),
+    };
+
+    if !number.is_valid() {
+        return error(format!("invalid phone number {:?}", value));
+    }
+
+    let code = number.code().value();
+    let national = number.national().value();
+    let zeros = number.national().zeros();
+    let number_expr = quote! {
+        ::phonenumber::PhoneNumber::__from_checked_parts(#code, #national, #zeros)
+    };
+
+    match number.extension() {
+        None => number_expr.into(),
+
+        Some(extension) => {
+            let extension = extension.as_ref();
+
+            quote! {{
+                let mut number = #number_expr;
+                number.set_extension(#extension).unwrap();
+                number
+            }}
+            .into()
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use either::*;
+
+use crate::consts;
+use crate::country;
+use crate::metadata::{self, Database, Metadata};
+use crate::parser::helper;
+use crate::phone_number::Type;
+use crate::validator::{self, Validation};
+
+/// The verdict on a partially typed phone number.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Verdict {
+    /// The number is too short to be possible yet, but typing more digits can
+    /// make it valid.
+    Incomplete,
+
+    /// The number has a possible length, but isn't valid, and typing more
+    /// digits can still make it valid.
+    PossiblyValid,
+
+    /// The number is valid.
+    Valid,
+
+    /// The number isn't valid, and typing more digits can't make it valid.
+    Invalid,
+}
+
+/// The verdict on a partially typed phone number, see `assess`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Assessment {
+    /// Whether the number is valid, or can still become valid.
+    pub verdict: Verdict,
+
+    /// The best guess of the region of the number, the region it's valid for
+    /// or the first one it can still become valid for, `None` when it's not
+    /// known yet or for numbers of non-geographical entities.
+    pub region: Option<country::Id>,
+}
+
+impl Assessment {
+    fn new(verdict: Verdict, region: Option<country::Id>) -> Self {
+        Assessment { verdict, region }
+    }
+}
+
+/// Assess a partially typed phone number, like the content of a form field
+/// after every keystroke, in the given region when it's not in the
+/// international format.
+///
+/// Unlike parsing and validating the input, this tells input that needs more
+/// digits apart from input that can never become a valid number, by whether
+/// the digits typed so far can start a number of one of the possible lengths
+/// matching the metadata.
+///
+/// Punctuation is skipped, while any other character that isn't a digit, or
+/// a leading plus sign, makes the number invalid.
+pub fn assess(partial: &str, default: Option<country::Id>) -> Assessment {
+    metadata::with_global(|database| assess_with(database, partial, default))
+        .unwrap_or_else(|_| Assessment::new(Verdict::Invalid, None))
+}
+
+/// Assess a partially typed phone number with the given `Database`.
+pub fn assess_with(database: &Database, partial: &str, default: Option<country::Id>) -> Assessment {
+    let invalid = Assessment::new(Verdict::Invalid, None);
+    let (plus, digits) = match helper::digits(partial) {
+        Some(found) => found,
+        None => return invalid,
+    };
+
+    if plus {
+        return international(database, &digits);
+    }
+
+    let meta = match default.and_then(|c| database.by_id(c.as_ref())) {
+        Some(meta) => meta,
+        None if digits.is_empty() => return Assessment::new(Verdict::Incomplete, None),
+        None => return invalid,
+    };
+
+    // Numbers dialled with the international prefix, like "00 46" in Germany.
+    if let Some(idd) = meta.international_prefix() {
+        if let Some(found) = idd.find(&digits).filter(|m| m.start() == 0) {
+            return international(database, &digits[found.end()..]);
+        }
+    }
+
+    let mut national = &digits[..];
+
+    if let Some(prefix) = meta.national_prefix() {
+        if let Some(rest) = national.strip_prefix(prefix) {
+            national = rest;
+        } else if prefix.starts_with(national) {
+            national = "";
+        }
+    }
+
+    // The default region goes first, followed by the others sharing its
+    // country code, since it can be dialled like a national number.
+    let mut metas = vec![meta];
+    metas.extend(
+        database
+            .by_code(&meta.country_code())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|m| m.id() != meta.id()),
+    );
+
+    let assessment = number(database, &metas, national);
+
+    // Still typing the international prefix, like "01" in the US, after which
+    // the region isn't known until the country code is complete, like after
+    // the whole prefix or a plus sign.
+    if assessment.verdict == Verdict::Invalid
+        && meta
+            .international_prefix()
+            .map(|idd| idd.prefix_of_match(&digits))
+            .unwrap_or(false)
+    {
+        return Assessment::new(Verdict::Incomplete, None);
+    }
+
+    assessment
+}
+
+/// Assess the digits of an international number, after the plus sign.
+fn international(database: &Database, digits: &str) -> Assessment {
+    if let Some((metas, national)) = helper::split_country_code(database, digits) {
+        return number(database, &metas, national);
+    }
+
+    let incomplete = database
+        .iter_calling_codes()
+        .any(|(code, _)| code.to_string().starts_with(digits));
+
+    if incomplete {
+        Assessment::new(Verdict::Incomplete, None)
+    } else {
+        Assessment::new(Verdict::Invalid, None)
+    }
+}
+
+/// Assess the national significant number typed so far for the regions, the
+/// main one first.
+fn number(database: &Database, metas: &[&Metadata], national: &str) -> Assessment {
+    let code = metas[0].country_code();
+    let id = |meta: &Metadata| meta.id().parse().ok();
+
+    let valid = validator::source_for(database, code, national)
+        .and_then(|source| match source {
+            Left(region) => database.by_id(region.as_ref()),
+            Right(code) => database.non_geographical(code),
+        })
+        .filter(|meta| metas.iter().any(|m| m.id() == meta.id()))
+        .filter(|meta| validator::number_type(meta, national) != Type::Unknown);
+
+    if let Some(meta) = valid {
+        return Assessment::new(Verdict::Valid, id(meta));
+    }
+
+    // The lengths of regions sharing a country code are all held by the main
+    // region.
+    let main = match database.main_by_code(code) {
+        Some(main) => main,
+        None => return Assessment::new(Verdict::Invalid, None),
+    };
+
+    let longest = main
+        .descriptors()
+        .general()
+        .possible_length()
+        .last()
+        .map(|&len| len as usize)
+        .unwrap_or(consts::MAX_LENGTH_FOR_NSN);
+
+    let region = metas.iter().find(|meta| {
+        national.is_empty()
+            || meta
+                .descriptors()
+                .general()
+                .national_number()
+                .is_prefix(national)
+    });
+
+    match region {
+        Some(meta) if national.len() < longest => {
+            let verdict = match validator::length(main, national, Type::Unknown) {
+                Validation::IsPossible => Verdict::PossiblyValid,
+                _ => Verdict::Incomplete,
+            };
+
+            Assessment::new(verdict, id(meta))
+        }
+
+        _ => Assessment::new(Verdict::Invalid, None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::assess::{assess, Verdict};
+    use crate::country::*;
+
+    fn verdict(partial: &str, default: Option<Id>) -> (Verdict, Option<Id>) {
+        let assessment = assess(partial, default);
+        (assessment.verdict, assessment.region)
+    }
+
+    #[test]
+    fn typing() {
+        let input = "+46701234567";
+
+        for len in 0..=input.len() {
+            let expected = match len {
+                0..=2 => (Verdict::Incomplete, None),
+                3..=8 => (Verdict::Incomplete, Some(SE)),
+                9..=11 => (Verdict::PossiblyValid, Some(SE)),
+                _ => (Verdict::Valid, Some(SE)),
+            };
+
+            assert_eq!(expected, verdict(&input[..len], None), "{}", &input[..len]);
+        }
+
+        // No Swedish mobile number is any longer.
+        assert_eq!((Verdict::Invalid, None), verdict("+467012345678", None));
+    }
+
+    #[test]
+    fn national() {
+        assert_eq!((Verdict::Incomplete, Some(SE)), verdict("", Some(SE)));
+        assert_eq!((Verdict::Incomplete, Some(SE)), verdict("0", Some(SE)));
+        assert_eq!((Verdict::Incomplete, Some(SE)), verdict("07", Some(SE)));
+        assert_eq!(
+            (Verdict::Valid, Some(SE)),
+            verdict("070-123 45 67", Some(SE))
+        );
+        assert_eq!(
+            (Verdict::Incomplete, Some(US)),
+            verdict("(650) 253", Some(US))
+        );
+        assert_eq!(
+            (Verdict::Valid, Some(US)),
+            verdict("(650) 253-0000", Some(US))
+        );
+
+        // Regions sharing the country code of the default one.
+        assert_eq!(
+            (Verdict::Valid, Some(CA)),
+            verdict("613 555 0123", Some(US))
+        );
+
+        // Through the international prefix of the default region.
+        assert_eq!((Verdict::Incomplete, None), verdict("00", Some(SE)));
+        assert_eq!(
+            (Verdict::Valid, Some(SE)),
+            verdict("0046 70 123 45 67", Some(SE))
+        );
+        assert_eq!((Verdict::Incomplete, None), verdict("01", Some(US)));
+        assert_eq!((Verdict::Incomplete, None), verdict("011", Some(US)));
+        assert_eq!(
+            (Verdict::Valid, Some(SE)),
+            verdict("011 46 70 123 45 67", Some(US))
+        );
+    }
+
+    #[test]
+    fn non_geographical() {
+        assert_eq!((Verdict::Incomplete, None), verdict("+800 1234", None));
+        assert_eq!((Verdict::Valid, None), verdict("+800 1234 5678", None));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!((Verdict::Invalid, None), verdict("+0", None));
+        assert_eq!((Verdict::Invalid, None), verdict("+999", None));
+        assert_eq!((Verdict::Invalid, None), verdict("+1 0", None));
+        assert_eq!((Verdict::Invalid, None), verdict("+44 20 7031 30001", None));
+        assert_eq!((Verdict::Invalid, None), verdict("+46 70 12a", None));
+
+        // National numbers need a region.
+        assert_eq!((Verdict::Incomplete, None), verdict("", None));
+        assert_eq!((Verdict::Invalid, None), verdict("4670", None));
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parse, find and generate phone numbers from the command line, built with
+//! the `cli` feature.
+//!
+//! - `phonenumber parse [--region <ID>] [--json] <number>`: print the parsed
+//!   number, if it's valid, its type and all its formats.
+//! - `phonenumber find [--region <ID>] [--json] <file>`: print the valid
+//!   numbers in a text file.
+//! - `phonenumber example --region <ID> [--type <type>] [--json]`: print an
+//!   example number, of the given type like `mobile` or fixed line by default.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use phonenumber::{country, Leniency, Mode, PhoneNumber, Type};
+use regex::Regex;
+use serde_json::{json, Value};
+
+const USAGE: &str = "usage:
+  phonenumber parse [--region <ID>] [--json] <number>
+  phonenumber find [--region <ID>] [--json] <file>
+  phonenumber example --region <ID> [--type <type>] [--json]";
+
+/// The options shared by the commands.
+#[derive(Default)]
+struct Options {
+    region: Option<country::Id>,
+    kind: Option<Type>,
+    json: bool,
+    args: Vec<String>,
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+
+    let result = options(args).and_then(|options| match command.as_str() {
+        "parse" => parse(options),
+        "find" => find(options),
+        "example" => example(options),
+        _ => Err(USAGE.into()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn options<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+
+            "--region" => {
+                let id = args.next().ok_or(USAGE)?;
+                options.region = Some(id.parse().map_err(|_| format!("unknown region {:?}", id))?);
+            }
+
+            "--type" => {
+                let kind = args.next().ok_or(USAGE)?;
+                options.kind = Some(
+                    serde_json::from_value(Value::String(kind.clone()))
+                        .map_err(|_| format!("unknown type {:?}", kind))?,
+                );
+            }
+
+            _ if arg.starts_with("--") => return Err(USAGE.into()),
+            _ => options.args.push(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Everything there is to know about a number.
+fn describe(number: &PhoneNumber) -> Value {
+    json!({
+        "number": number,
+        "valid": number.is_valid(),
+        "type": number.number_type(),
+        "formats": {
+            "e164": number.format().mode(Mode::E164).to_string(),
+            "international": number.format().mode(Mode::International).to_string(),
+            "national": number.format().mode(Mode::National).to_string(),
+            "rfc3966": number.format().mode(Mode::Rfc3966).to_string(),
+        },
+    })
+}
+
+fn print(number: &PhoneNumber) {
+    let kind = serde_json::to_value(number.number_type()).unwrap();
+
+    println!("{:#?}", number);
+    println!();
+    println!("        Valid: {}", number.is_valid());
+    println!("         Type: {}", kind.as_str().unwrap_or_default());
+    println!("        E.164: {}", number.format().mode(Mode::E164));
+    println!(
+        "International: {}",
+        number.format().mode(Mode::International)
+    );
+    println!("     National: {}", number.format().mode(Mode::National));
+    println!("      RFC3966: {}", number.format().mode(Mode::Rfc3966));
+}
+
+fn parse(options: Options) -> Result<(), String> {
+    let input = match options.args.as_slice() {
+        [input] => input,
+        _ => return Err(USAGE.into()),
+    };
+
+    let number = phonenumber::parse(options.region, input).map_err(|err| err.to_string())?;
+
+    if options.json {
+        println!("{}", describe(&number));
+    } else {
+        print(&number);
+    }
+
+    Ok(())
+}
+
+fn find(options: Options) -> Result<(), String> {
+    let path = match options.args.as_slice() {
+        [path] => path,
+        _ => return Err(USAGE.into()),
+    };
+
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let candidates = Regex::new(r"\+?\(?\d[\d \t().\-/]{4,}\d").unwrap();
+    let mut found = Vec::new();
+
+    for (line, content) in text.lines().enumerate() {
+        for candidate in candidates.find_iter(content) {
+            let number = match phonenumber::parse(options.region, candidate.as_str()) {
+                Ok(number) if Leniency::Valid.verify(&number, candidate.as_str()) => number,
+                _ => continue,
+            };
+
+            if options.json {
+                let mut value = describe(&number);
+                value["line"] = json!(line + 1);
+                value["column"] = json!(candidate.start() + 1);
+                value["text"] = json!(candidate.as_str());
+                found.push(value);
+            } else {
+                println!(
+                    "{}:{}: {} {}",
+                    line + 1,
+                    candidate.start() + 1,
+                    candidate.as_str(),
+                    number.format().mode(Mode::E164)
+                );
+            }
+        }
+    }
+
+    if options.json {
+        println!("{}", Value::Array(found));
+    }
+
+    Ok(())
+}
+
+fn example(options: Options) -> Result<(), String> {
+    let region = match (options.region, options.args.as_slice()) {
+        (Some(region), []) => region,
+        _ => return Err(USAGE.into()),
+    };
+
+    let kind = options.kind.unwrap_or(Type::FixedLine);
+    let number = phonenumber::example_number_for_type(region, kind)
+        .ok_or_else(|| format!("no example number for {:?} in {}", kind, region.as_ref()))?;
+
+    if options.json {
+        println!("{}", describe(&number));
+    } else {
+        print(&number);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parallel validation of many numbers at once with `rayon`, built with the
+//! `rayon` feature.
+//!
+//! The database is only read while validating, the expressions are compiled
+//! once and then shared without locking, and the parser keeps its scratch
+//! space per thread, so this scales with the number of threads.
+//!
+//! ```
+//! use phonenumber::{bulk, country, metadata::DATABASE, Type};
+//!
+//! let results = bulk::validate(vec!["030 123456", "+1 650 253 0000", "a"], Some(country::DE), &DATABASE);
+//!
+//! assert!(results[0].valid);
+//! assert_eq!(Type::FixedLine, results[0].kind);
+//! assert!(results[1].valid);
+//! assert!(results[2].number.is_err());
+//! ```
+
+use rayon::prelude::*;
+
+use crate::country;
+use crate::error;
+use crate::metadata::Database;
+use crate::parser;
+use crate::phone_number::{PhoneNumber, Type};
+
+/// The outcome of validating a number with `validate`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BulkResult {
+    /// The parsed number, or why it couldn't be parsed.
+    pub number: Result<PhoneNumber, error::Parse>,
+
+    /// Whether the number is valid, `false` if it couldn't be parsed.
+    pub valid: bool,
+
+    /// The type of the number, `Type::Unknown` if it couldn't be parsed.
+    pub kind: Type,
+}
+
+impl BulkResult {
+    fn new(database: &Database, default: Option<country::Id>, input: &str) -> Self {
+        let number = parser::parse_with(database, default, input);
+        let (valid, kind) = match &number {
+            Ok(number) => (
+                number.is_valid_with(database),
+                number.number_type_with(database),
+            ),
+
+            Err(_) => (false, Type::Unknown),
+        };
+
+        BulkResult {
+            number,
+            valid,
+            kind,
+        }
+    }
+}
+
+/// Parse and validate the numbers in parallel, in the given region when
+/// they're not in the international format.
+///
+/// The results are in the same order as the numbers.
+pub fn validate<'a, I>(
+    numbers: I,
+    default: Option<country::Id>,
+    database: &Database,
+) -> Vec<BulkResult>
+where
+    I: IntoParallelIterator<Item = &'a str>,
+{
+    numbers
+        .into_par_iter()
+        .map(|input| BulkResult::new(database, default, input))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bulk;
+    use crate::country;
+    use crate::metadata::DATABASE;
+    use crate::parser;
+    use crate::phone_number::Type;
+
+    #[test]
+    fn validate() {
+        let inputs = [
+            "030 123456",
+            "+49 30 123456",
+            "+1 650 253 0000",
+            "(650) 253-0000",
+            "070-123 45 67",
+            "+46 70 123 45 67",
+            "0800 123 4567",
+            "+39 06 698 83461",
+            "+800 1234 5678",
+            "12",
+            "a",
+            "+999 123456",
+        ];
+        let numbers = inputs
+            .iter()
+            .cycle()
+            .take(10_000)
+            .copied()
+            .collect::<Vec<_>>();
+
+        let parallel = bulk::validate(numbers.clone(), Some(country::DE), &DATABASE);
+        assert_eq!(numbers.len(), parallel.len());
+
+        for (input, result) in numbers.iter().zip(&parallel) {
+            let number = parser::parse_with(&DATABASE, Some(country::DE), input);
+            assert_eq!(number, result.number, "{}", input);
+
+            match number {
+                Ok(number) => {
+                    assert_eq!(number.is_valid_with(&DATABASE), result.valid);
+                    assert_eq!(number.number_type_with(&DATABASE), result.kind);
+                }
+
+                Err(_) => {
+                    assert!(!result.valid);
+                    assert_eq!(Type::Unknown, result.kind);
+                }
+            }
+        }
+
+        assert!(parallel[0].valid);
+        assert_eq!(Type::FixedLine, parallel[0].kind);
+        assert!(parallel[10].number.is_err());
+    }
+}
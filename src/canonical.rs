@@ -0,0 +1,210 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::iter::FromIterator;
+
+use crate::country;
+use crate::error;
+use crate::formatter::Mode;
+use crate::metadata::{self, Database};
+use crate::parser;
+use crate::phone_number::{PhoneNumber, Type};
+
+/// A stored number brought into the E.164 format by `canonicalize`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Canonical {
+    /// The parsed number, with the extension if there was one.
+    pub number: PhoneNumber,
+
+    /// The number in the E.164 format, without the extension.
+    pub e164: String,
+
+    /// The region the number belongs to, `None` for numbers of
+    /// non-geographical entities and numbers not valid in any region.
+    pub region: Option<country::Id>,
+
+    /// The type of the number.
+    pub kind: Type,
+
+    /// Whether the number is valid.
+    pub valid: bool,
+
+    /// Whether the input already was the E.164 string, so it doesn't need to
+    /// be written back.
+    pub unchanged: bool,
+}
+
+/// Bring a stored number into the E.164 format, in the given region when
+/// it's not in the international format.
+///
+/// The input is parsed as leniently as by `parse`, so punctuation is
+/// dropped, the letters of vanity numbers are turned into digits and
+/// extensions are split off.
+pub fn canonicalize<S: AsRef<str>>(
+    input: S,
+    default: Option<country::Id>,
+) -> Result<Canonical, error::Parse> {
+    metadata::with_global(|database| canonicalize_with(database, input, default))?
+}
+
+/// Bring a stored number into the E.164 format using a specific `Database`.
+pub fn canonicalize_with<S: AsRef<str>>(
+    database: &Database,
+    input: S,
+    default: Option<country::Id>,
+) -> Result<Canonical, error::Parse> {
+    let input = input.as_ref();
+    let number = parser::parse_with(database, default, input)?;
+    let e164 = number.format_with(database).mode(Mode::E164).to_string();
+
+    Ok(Canonical {
+        region: number.country().id_with(database),
+        kind: number.number_type_with(database),
+        valid: number.is_valid_with(database),
+        unchanged: input == e164,
+        number,
+        e164,
+    })
+}
+
+/// How many numbers of a batch ended up with each outcome of `canonicalize`.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct CanonicalReport {
+    /// Valid numbers already stored in the E.164 format.
+    pub unchanged: usize,
+
+    /// Valid numbers that have to be written back in the E.164 format.
+    pub rewritten: usize,
+
+    /// Numbers that could be parsed, but aren't valid.
+    pub invalid: usize,
+
+    /// Numbers that couldn't be parsed at all.
+    pub failed: usize,
+}
+
+impl CanonicalReport {
+    /// Count the outcome of canonicalizing a number.
+    pub fn add(&mut self, result: &Result<Canonical, error::Parse>) {
+        match result {
+            Ok(canonical) if !canonical.valid => self.invalid += 1,
+            Ok(canonical) if canonical.unchanged => self.unchanged += 1,
+            Ok(_) => self.rewritten += 1,
+            Err(_) => self.failed += 1,
+        }
+    }
+
+    /// The number of numbers counted.
+    pub fn total(&self) -> usize {
+        self.unchanged + self.rewritten + self.invalid + self.failed
+    }
+}
+
+impl<'a> FromIterator<&'a Result<Canonical, error::Parse>> for CanonicalReport {
+    fn from_iter<I: IntoIterator<Item = &'a Result<Canonical, error::Parse>>>(iter: I) -> Self {
+        let mut report = CanonicalReport::default();
+
+        for result in iter {
+            report.add(result);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::canonical::{canonicalize, CanonicalReport};
+    use crate::country;
+    use crate::phone_number::Type;
+
+    #[test]
+    fn canonical() {
+        let canonical = canonicalize("+1 (650) 253-0000 ext. 123", Some(country::US)).unwrap();
+        assert_eq!("+16502530000", canonical.e164);
+        assert_eq!(Some(country::US), canonical.region);
+        assert_eq!(Type::FixedLineOrMobile, canonical.kind);
+        assert_eq!(Some("123"), canonical.number.extension().map(|e| &**e));
+        assert!(canonical.valid);
+        assert!(!canonical.unchanged);
+
+        let canonical = canonicalize("+447912345678", Some(country::DE)).unwrap();
+        assert_eq!(Some(country::GB), canonical.region);
+        assert_eq!(Type::Mobile, canonical.kind);
+        assert!(canonical.unchanged);
+
+        let canonical = canonicalize("+800 1234 5678", None).unwrap();
+        assert_eq!(None, canonical.region);
+        assert_eq!(Type::TollFree, canonical.kind);
+        assert!(canonical.valid);
+
+        let mut report = CanonicalReport::default();
+        report.add(&canonicalize("+4930123456", None));
+        report.add(&canonicalize("030 123456", None));
+        assert_eq!(1, report.unchanged);
+        assert_eq!(1, report.failed);
+        assert_eq!(2, report.total());
+    }
+
+    #[test]
+    fn fixtures() {
+        let mut results = Vec::new();
+
+        for line in include_str!("../tests/fixtures/canonical.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let region = match fields[0] {
+                "-" => None,
+                id => Some(id.parse().unwrap()),
+            };
+
+            let result = canonicalize(fields[1], region);
+
+            match (&result, fields[3]) {
+                (Ok(canonical), outcome) => {
+                    assert_eq!(fields[2], canonical.e164, "{}", line);
+                    assert_eq!(
+                        outcome,
+                        match (canonical.valid, canonical.unchanged) {
+                            (false, _) => "invalid",
+                            (true, true) => "unchanged",
+                            (true, false) => "rewritten",
+                        },
+                        "{}",
+                        line
+                    );
+                }
+
+                (Err(err), outcome) => {
+                    assert_eq!("failed", outcome, "{}", line);
+                    assert_eq!(fields[2], format!("{:?}", err), "{}", line);
+                }
+            }
+
+            results.push(result);
+        }
+
+        assert_eq!(
+            CanonicalReport {
+                unchanged: 3,
+                rewritten: 20,
+                invalid: 4,
+                failed: 3,
+            },
+            results.iter().collect()
+        );
+    }
+}
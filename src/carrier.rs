@@ -12,16 +12,43 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Phone number carriers, and with the `carrier` feature, looking up the
+//! carrier a mobile number was originally assigned to.
+
 use std::fmt;
 use std::ops::Deref;
 
+#[cfg(feature = "carrier")]
+use bincode::Options;
+#[cfg(feature = "carrier")]
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "carrier")]
+use crate::metadata::{self, Database};
+#[cfg(feature = "carrier")]
+use crate::phone_number::{PhoneNumber, Type};
+#[cfg(feature = "carrier")]
+use crate::prefix_map::{self, PrefixMaps};
+
+#[cfg(feature = "carrier")]
+const CARRIERS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/carrier.bin"));
+
+#[cfg(feature = "carrier")]
+/// The Google provided carrier names, by language and country calling code.
+static DEFAULT: Lazy<PrefixMaps> = Lazy::new(|| {
+    bincode::options()
+        .with_varint_encoding()
+        .deserialize(CARRIERS)
+        .unwrap()
+});
+
 /// A phone number carrier.
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
-pub struct Carrier(pub(crate) String);
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash, Debug)]
+pub struct Carrier(pub(crate) Box<str>);
 
 impl<T: Into<String>> From<T> for Carrier {
     fn from(value: T) -> Carrier {
-        Carrier(value.into())
+        Carrier(value.into().into_boxed_str())
     }
 }
 
@@ -44,3 +71,62 @@ impl fmt::Display for Carrier {
         write!(f, "{}", self.0)
     }
 }
+
+/// Get the name of the carrier the mobile number was originally assigned to in
+/// the given language, like "en" or "zh_Hant", falling back to English.
+///
+/// Names are only returned for numbers which are mobile numbers, or could be.
+/// Note that in regions with mobile number portability the number may have
+/// moved to another carrier since.
+#[cfg(feature = "carrier")]
+pub fn name_for_number(number: &PhoneNumber, lang: &str) -> Option<&'static str> {
+    name_for_number_with(&metadata::global(), number, lang)
+}
+
+/// Get the name of the carrier the mobile number was originally assigned to in
+/// the given language with the given `Database`.
+#[cfg(feature = "carrier")]
+pub fn name_for_number_with(
+    database: &Database,
+    number: &PhoneNumber,
+    lang: &str,
+) -> Option<&'static str> {
+    if !matches!(
+        number.number_type_with(database),
+        Type::Mobile | Type::FixedLineOrMobile
+    ) {
+        return None;
+    }
+
+    let code = number.code().value();
+    prefix_map::describe(
+        &DEFAULT,
+        lang,
+        code,
+        &format!("{}{}", code, number.national()),
+    )
+}
+
+#[cfg(all(test, feature = "carrier"))]
+mod test {
+    use crate::carrier;
+    use crate::country;
+    use crate::parser;
+
+    #[test]
+    fn name_for_number() {
+        let number = parser::parse(Some(country::DE), "0172 1234567").unwrap();
+        assert_eq!(Some("Vodafone"), carrier::name_for_number(&number, "en"));
+        assert_eq!(Some("Vodafone"), carrier::name_for_number(&number, "de"));
+
+        let number = parser::parse(Some(country::IN), "98012 34567").unwrap();
+        assert_eq!(Some("Airtel"), carrier::name_for_number(&number, "en"));
+
+        let number = parser::parse(Some(country::BR), "11 95472 1234").unwrap();
+        assert_eq!(Some("Vivo"), carrier::name_for_number(&number, "en"));
+
+        // Landlines have no carrier.
+        let number = parser::parse(Some(country::DE), "030 123456").unwrap();
+        assert_eq!(None, carrier::name_for_number(&number, "en"));
+    }
+}
@@ -13,10 +13,29 @@
 // limitations under the License.
 
 //! Country related types.
+//!
+//! Regions are identified by an `Id`. Non-geographical entities, like the
+//! "+800" international freephone service or satellite networks, aren't
+//! regions and share the "001" ID in the metadata, which isn't an `Id`.
+//!
+//! Instead `None` stands for them wherever an `Option<Id>` is returned, like
+//! by `PhoneNumber::country().id()`, and wherever a region is taken as an
+//! `Into<Option<Id>>`, like by `is_valid_for_region`, `example_number`,
+//! `Formatter::calling_from` and the short number functions. That way the
+//! region of any number can be passed back without special-casing them,
+//! while existing callers can keep passing an `Id`. As the functions are
+//! generic, the `Id` type can't be inferred from them anymore, which is a
+//! breaking change listed in the changelog.
 
-use crate::error;
+use ::serde::de::{
+    self, Deserialize, Deserializer, EnumAccess, Unexpected, VariantAccess, Visitor,
+};
+use std::cell::Cell;
+use std::fmt;
 use std::str;
 
+use crate::error;
+
 #[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
 pub struct Code {
     /// The country code value.
@@ -28,7 +47,12 @@ pub struct Code {
 
 /// The source from which the country code is derived. This is not set in the
 /// general parsing method, but in the method that parses and keeps raw_input.
-#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash, Debug)]
+///
+/// Sources are serialized by their snake case names, like "plus", and
+/// deserialized from those in any case, or from the names of upstream's
+/// `CountryCodeSource`, like "FROM_NUMBER_WITH_PLUS_SIGN". Any other name
+/// falls back to `Source::Default`, see `Source::track_fallbacks`.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Default, Serialize, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum Source {
     /// The country code is derived based on a phone number with a leading "+",
@@ -50,15 +74,179 @@ pub enum Source {
     /// format (without country code). For example, this would be set when
     /// parsing the French number "01 42 68 53 00", when the default country is
     /// supplied as France.
+    #[default]
     Default,
 }
 
-impl Default for Source {
-    fn default() -> Self {
+impl Source {
+    /// Every source, in the order of `NAMES`.
+    const VARIANTS: &'static [Source] =
+        &[Source::Plus, Source::Idd, Source::Number, Source::Default];
+
+    /// The names of the sources, as they're serialized.
+    const NAMES: &'static [&'static str] = &["plus", "idd", "number", "default"];
+
+    /// The names of the sources in upstream's `CountryCodeSource`.
+    const UPSTREAM_NAMES: &'static [&'static str] = &[
+        "FROM_NUMBER_WITH_PLUS_SIGN",
+        "FROM_NUMBER_WITH_IDD",
+        "FROM_NUMBER_WITHOUT_PLUS_SIGN",
+        "FROM_DEFAULT_COUNTRY",
+    ];
+
+    /// The name of the source, like "plus".
+    pub fn name(&self) -> &'static str {
+        Source::NAMES[*self as usize]
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parse the name of a source in any case, like "idd" or "Default".
+impl str::FromStr for Source {
+    type Err = error::Name;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        error::Name::lookup(
+            "country code source",
+            Source::NAMES,
+            Source::VARIANTS,
+            value,
+        )
+    }
+}
+
+thread_local! {
+    /// The number of sources deserialized from unknown names, while tracked.
+    static FALLBACKS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+impl Source {
+    /// Run the closure deserializing sources, directly or as part of phone
+    /// numbers, and count the ones that fell back to `Source::Default` for
+    /// an unknown name.
+    ///
+    /// ```
+    /// use phonenumber::country::Source;
+    ///
+    /// let (source, fallbacks) =
+    ///     Source::track_fallbacks(|| serde_json::from_str::<Source>(r#""FROM_SPACE""#));
+    /// assert_eq!(Source::Default, source.unwrap());
+    /// assert_eq!(1, fallbacks);
+    /// ```
+    pub fn track_fallbacks<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+        /// Restore the outer count, even when the closure panics.
+        struct Guard(Option<usize>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                FALLBACKS.with(|count| {
+                    let inner = count.get().unwrap_or(0);
+                    count.set(self.0.map(|outer| outer + inner));
+                });
+            }
+        }
+
+        let guard = Guard(FALLBACKS.with(|count| count.replace(Some(0))));
+        let value = f();
+        let fallbacks = FALLBACKS.with(|count| count.get().unwrap_or(0));
+        drop(guard);
+
+        (value, fallbacks)
+    }
+
+    /// The source for a deserialized name.
+    fn from_name(name: &str) -> Source {
+        if let Ok(source) = name.parse() {
+            return source;
+        }
+
+        if name == "UNSPECIFIED" {
+            return Source::Default;
+        }
+
+        if let Some(index) = Source::UPSTREAM_NAMES.iter().position(|&n| n == name) {
+            return Source::VARIANTS[index];
+        }
+
+        FALLBACKS.with(|count| count.set(count.get().map(|n| n + 1)));
         Source::Default
     }
 }
 
+impl<'de> Deserialize<'de> for Source {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// The name or index of a variant.
+        struct Tag(Source);
+
+        impl<'de> Deserialize<'de> for Tag {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_identifier(TagVisitor)
+            }
+        }
+
+        struct TagVisitor;
+
+        impl<'de> Visitor<'de> for TagVisitor {
+            type Value = Tag;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a country code source")
+            }
+
+            // Formats like bincode use the index of the variant.
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Tag, E> {
+                Source::VARIANTS
+                    .get(value as usize)
+                    .map(|&source| Tag(source))
+                    .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Tag, E> {
+                Ok(Tag(Source::from_name(value)))
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Tag, E> {
+                Ok(Tag(Source::from_name(&String::from_utf8_lossy(value))))
+            }
+        }
+
+        struct SourceVisitor;
+
+        impl<'de> Visitor<'de> for SourceVisitor {
+            type Value = Source;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a country code source")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Source, A::Error> {
+                let (Tag(source), variant) = data.variant()?;
+                variant.unit_variant()?;
+
+                Ok(source)
+            }
+        }
+
+        deserializer.deserialize_enum("Source", Source::NAMES, SourceVisitor)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for Source {
+    fn value_variants<'a>() -> &'a [Self] {
+        Source::VARIANTS
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<clap::PossibleValue<'a>> {
+        Some(clap::PossibleValue::new(self.name()))
+    }
+}
+
 impl Code {
     /// The country code number.
     pub fn value(&self) -> u16 {
@@ -77,8 +265,8 @@ impl From<Code> for u16 {
     }
 }
 
-/// CLDR country IDs.
-#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash, Debug)]
+/// CLDR country IDs, ordered alphabetically.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Serialize, Deserialize, Hash, Debug)]
 pub enum Id {
     AC,
     AD,
@@ -835,3 +1023,124 @@ impl AsRef<str> for Id {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::country::Source;
+
+    #[test]
+    fn source_names() {
+        for (source, names) in &[
+            (Source::Plus, ["plus", "Plus"]),
+            (Source::Idd, ["idd", "Idd"]),
+            (Source::Number, ["number", "Number"]),
+            (Source::Default, ["default", "Default"]),
+        ] {
+            for name in names {
+                assert_eq!(Ok(*source), name.parse(), "{}", name);
+            }
+
+            assert_eq!(*source, source.to_string().parse().unwrap());
+        }
+
+        assert_eq!("idd", Source::Idd.to_string());
+        assert_eq!(Ok(Source::Idd), "IDD".parse());
+
+        // The same names as serialized.
+        assert_eq!(
+            format!("\"{}\"", Source::Default),
+            serde_json::to_string(&Source::Default).unwrap()
+        );
+
+        assert_eq!(
+            "unknown country code source \"+\", expected one of: plus, idd, number, default",
+            "+".parse::<Source>().unwrap_err().to_string()
+        );
+
+        #[cfg(feature = "clap")]
+        {
+            use clap::ValueEnum;
+
+            assert_eq!(Ok(Source::Plus), Source::from_str("PLUS", true));
+            assert!(Source::from_str("PLUS", false).is_err());
+        }
+    }
+
+    #[test]
+    fn source_serde() {
+        for &source in Source::VARIANTS {
+            let json = serde_json::to_string(&source).unwrap();
+            assert_eq!(source, serde_json::from_str::<Source>(&json).unwrap());
+
+            let bytes = bincode::serialize(&source).unwrap();
+            assert_eq!(source, bincode::deserialize::<Source>(&bytes).unwrap());
+        }
+
+        assert!(bincode::deserialize::<Source>(&[4, 0, 0, 0]).is_err());
+
+        // The names of upstream's `CountryCodeSource`, from older rows.
+        let (sources, fallbacks) = Source::track_fallbacks(|| {
+            [
+                (Source::Plus, "FROM_NUMBER_WITH_PLUS_SIGN"),
+                (Source::Idd, "FROM_NUMBER_WITH_IDD"),
+                (Source::Number, "FROM_NUMBER_WITHOUT_PLUS_SIGN"),
+                (Source::Default, "FROM_DEFAULT_COUNTRY"),
+                (Source::Default, "UNSPECIFIED"),
+                (Source::Plus, "Plus"),
+            ]
+            .iter()
+            .map(|&(source, name)| (source, serde_json::from_str(&format!("\"{}\"", name))))
+            .collect::<Vec<_>>()
+        });
+
+        for (source, result) in sources {
+            assert_eq!(source, result.unwrap());
+        }
+        assert_eq!(0, fallbacks);
+
+        let (source, fallbacks) =
+            Source::track_fallbacks(|| serde_json::from_str::<Source>(r#""from_space""#));
+        assert_eq!(Source::Default, source.unwrap());
+        assert_eq!(1, fallbacks);
+
+        // Without tracking the fallback happens all the same.
+        assert_eq!(
+            Source::Default,
+            serde_json::from_str::<Source>(r#""from_space""#).unwrap()
+        );
+
+        // Nested tracking adds up in the outer one.
+        let ((_, inner), outer) = Source::track_fallbacks(|| {
+            let _ = serde_json::from_str::<Source>(r#""a""#);
+            Source::track_fallbacks(|| serde_json::from_str::<Source>(r#""b""#))
+        });
+        assert_eq!((1, 2), (inner, outer));
+    }
+
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn source_serde_in_number() {
+        use crate::PhoneNumber;
+
+        let number = PhoneNumber::builder(39, 669883461)
+            .source(Source::Plus)
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&number).unwrap();
+        assert!(json.contains(r#""source":"plus""#), "{}", json);
+
+        let (legacy, fallbacks) = Source::track_fallbacks(|| {
+            serde_json::from_str::<PhoneNumber>(
+                &json.replace(r#""plus""#, r#""FROM_NUMBER_WITH_PLUS_SIGN""#),
+            )
+        });
+        assert!(number.exactly_equal(&legacy.unwrap()));
+        assert_eq!(0, fallbacks);
+
+        let (unknown, fallbacks) = Source::track_fallbacks(|| {
+            serde_json::from_str::<PhoneNumber>(&json.replace(r#""plus""#, r#""FROM_SPACE""#))
+        });
+        assert_eq!(Source::Default, unknown.unwrap().code().source());
+        assert_eq!(1, fallbacks);
+    }
+}
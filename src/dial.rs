@@ -0,0 +1,272 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use thiserror::Error;
+
+use crate::consts;
+use crate::country;
+use crate::formatter;
+use crate::metadata::Database;
+use crate::phone_number::PhoneNumber;
+
+/// Reasons a phone number can't be turned into a dial string.
+#[derive(Error, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DialError {
+    /// There is no metadata for the region called from.
+    #[error("no metadata for region {0:?}")]
+    UnknownRegion(country::Id),
+
+    /// There is no metadata for the country code of the number.
+    #[error("invalid country code: {0}")]
+    InvalidCountryCode(u16),
+
+    /// The region called from has several international prefixes and no
+    /// preferred one.
+    #[error("no unique international prefix in region {0:?}")]
+    NoInternationalPrefix(country::Id),
+
+    /// The number can only be dialled from within its country.
+    #[error("the number can't be dialled internationally")]
+    NotInternationallyDiallable,
+}
+
+/// How numbers are dialled, for example by a PBX originating calls.
+///
+/// The dial strings are only digits, and whatever the trunk access code
+/// contains, without any separators.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DialPlan {
+    /// The region calls are made from.
+    pub calling_from: country::Id,
+
+    /// The code dialled first to get an outside line, like "9".
+    pub trunk_access: Option<String>,
+
+    /// Whether to dial numbers with the international prefix even when they
+    /// share the country code of the region called from.
+    pub force_international: bool,
+}
+
+impl DialPlan {
+    /// Create a dial plan for calls from the given region, without a trunk
+    /// access code.
+    pub fn new(calling_from: country::Id) -> Self {
+        DialPlan {
+            calling_from,
+            trunk_access: None,
+            force_international: false,
+        }
+    }
+}
+
+/// Compose the digits to dial to reach the number with the given `Database`.
+///
+/// Within the country this is the national prefix, or the carrier code the
+/// number was parsed with if its format has a rule for one, followed by the
+/// national significant number. In the US the national prefix "1" is always
+/// dialled, like for long distance calls. From other countries it's the
+/// international prefix of the region called from, followed by the number in
+/// the E.164 format without the plus sign.
+///
+/// Any extension is left out, it can't be dialled as part of the number.
+pub fn dial_string_with(
+    database: &Database,
+    number: &PhoneNumber,
+    plan: &DialPlan,
+) -> Result<String, DialError> {
+    let code = number.code().value();
+    let from = database
+        .by_id(plan.calling_from.as_ref())
+        .ok_or(DialError::UnknownRegion(plan.calling_from))?;
+    let meta = database
+        .main_by_code(code)
+        .ok_or(DialError::InvalidCountryCode(code))?;
+
+    let national = number.national().to_string();
+    let mut dial = plan.trunk_access.clone().unwrap_or_default();
+
+    if from.country_code() == code && !plan.force_international {
+        let carrier = number.carrier().and_then(|carrier| {
+            formatter::formatter(&national, meta.formats())
+                .and_then(|f| f.domestic_carrier())
+                .map(|rule| (carrier, rule))
+        });
+
+        if let Some((carrier, rule)) = carrier {
+            // The digits of the rule before the first group, like the "0" of
+            // "0 $CC ($FG)" in Brazil.
+            let head = &rule[..rule.find(consts::FG).unwrap_or(rule.len())];
+            let head = head
+                .replace(consts::NP, meta.national_prefix().unwrap_or(""))
+                .replace(consts::CC, carrier);
+
+            dial.extend(head.chars().filter(char::is_ascii_digit));
+        } else if let Some(prefix) = formatter::national_dialing_prefix(meta, &national) {
+            dial.push_str(prefix);
+        }
+    } else {
+        let region = number.metadata(database).unwrap_or(meta);
+        let domestic_only = region
+            .descriptors()
+            .no_international()
+            .map(|d| d.is_match(&national))
+            .unwrap_or(false);
+
+        if domestic_only {
+            return Err(DialError::NotInternationallyDiallable);
+        }
+
+        let idd = from
+            .preferred_international_prefix()
+            .or_else(|| {
+                from.international_prefix().map(|p| p.as_str()).filter(|p| {
+                    consts::UNIQUE_INTERNATIONAL_PREFIX
+                        .find(p)
+                        .map(|m| m.start() == 0 && m.end() == p.len())
+                        .unwrap_or(false)
+                })
+            })
+            .ok_or(DialError::NoInternationalPrefix(plan.calling_from))?;
+
+        dial.push_str(idd);
+        dial.push_str(&code.to_string());
+    }
+
+    dial.push_str(&national);
+    Ok(dial)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::dial::{DialError, DialPlan};
+    use crate::parser;
+
+    fn dial(from: country::Id, trunk: Option<&str>, input: &str) -> Result<String, DialError> {
+        let plan = DialPlan {
+            trunk_access: trunk.map(Into::into),
+            ..DialPlan::new(from)
+        };
+
+        parser::parse(None, input).unwrap().dial_string(&plan)
+    }
+
+    #[test]
+    fn domestic() {
+        assert_eq!(
+            Ok("030123456".into()),
+            dial(country::DE, None, "+49 30 123456")
+        );
+        assert_eq!(
+            Ok("0030123456".into()),
+            dial(country::DE, Some("0"), "+49 30 123456")
+        );
+        assert_eq!(
+            Ok("02079460958".into()),
+            dial(country::GB, None, "+44 20 7946 0958 ext. 21")
+        );
+
+        // Italian numbers keep their leading zero, without a national prefix.
+        assert_eq!(
+            Ok("0669883461".into()),
+            dial(country::IT, None, "+39 06 6988 3461")
+        );
+
+        // The carrier code is dialled after the national prefix.
+        let number =
+            parser::parse_and_keep_raw_input(Some(country::BR), "0 15 11 2345 6789").unwrap();
+        assert_eq!(Some("15"), number.carrier().map(|c| &**c));
+        assert_eq!(
+            Ok("0151123456789".into()),
+            number.dial_string(&DialPlan::new(country::BR))
+        );
+    }
+
+    #[test]
+    fn international() {
+        assert_eq!(
+            Ok("011493012345".into()),
+            dial(country::US, None, "+49 30 12345")
+        );
+        assert_eq!(
+            Ok("9011493012345".into()),
+            dial(country::US, Some("9"), "+49 30 12345")
+        );
+        assert_eq!(
+            Ok("0016502530000".into()),
+            dial(country::DE, None, "+1 650 253 0000")
+        );
+        assert_eq!(
+            Ok("00390669883461".into()),
+            dial(country::DE, None, "+39 06 6988 3461")
+        );
+        assert_eq!(
+            Ok("0080012345678".into()),
+            dial(country::DE, None, "+800 1234 5678")
+        );
+
+        // Forced even within the country.
+        let plan = DialPlan {
+            force_international: true,
+            ..DialPlan::new(country::DE)
+        };
+        assert_eq!(
+            Ok("004930123456".into()),
+            parser::parse(None, "+49 30 123456")
+                .unwrap()
+                .dial_string(&plan)
+        );
+    }
+
+    #[test]
+    fn nanpa() {
+        // Calls within the NANPA are dialled with the national prefix, even to
+        // other countries sharing the country code.
+        assert_eq!(
+            Ok("16502530000".into()),
+            dial(country::US, None, "+1 650 253 0000")
+        );
+        assert_eq!(
+            Ok("916502530000".into()),
+            dial(country::US, Some("9"), "+1 650 253 0000")
+        );
+        assert_eq!(
+            Ok("14165550123".into()),
+            dial(country::US, None, "+1 416 555 0123")
+        );
+        assert_eq!(
+            Ok("916502530000".into()),
+            dial(country::CA, Some("9"), "+1 650 253 0000")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        // Shared cost numbers in Italy can only be dialled from within.
+        assert_eq!(
+            Ok("848123456".into()),
+            dial(country::IT, None, "+39 848 123456")
+        );
+        assert_eq!(
+            Err(DialError::NotInternationallyDiallable),
+            dial(country::DE, None, "+39 848 123456")
+        );
+
+        // Cambodia has several international prefixes, and no preferred one.
+        assert_eq!(
+            Err(DialError::NoInternationalPrefix(country::KH)),
+            dial(country::KH, None, "+49 30 123456")
+        );
+    }
+}
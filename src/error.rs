@@ -15,7 +15,7 @@
 use thiserror::Error;
 
 /// Metadata loading errors.
-#[derive(Error, Clone, Debug)]
+#[derive(Error, Clone, PartialEq, Debug)]
 pub enum Metadata {
     /// EOF was reached before the parsing was complete.
     #[error("unexpected end of file")]
@@ -45,10 +45,26 @@ pub enum Metadata {
     /// An event was not handled.
     #[error("{phase}: unhandled event: {event:?}")]
     UnhandledEvent { phase: String, event: String },
+
+    /// The binary metadata doesn't start with the expected magic bytes.
+    #[error("not binary metadata")]
+    #[allow(unused)] // This is unused in the build script
+    InvalidMagic,
+
+    /// The binary metadata was encoded with a different version.
+    #[error("binary metadata version mismatch: expected {expected}, found {found}")]
+    #[allow(unused)] // This is unused in the build script
+    VersionMismatch { expected: u16, found: u16 },
+
+    /// The embedded metadata couldn't be loaded.
+    #[error("corrupt embedded metadata: {0}")]
+    #[allow(unused)] // This is unused in the build script
+    Corrupt(String),
 }
 
 /// Parsing errors.
-#[derive(Error, Clone, Debug)]
+#[derive(Error, Clone, PartialEq, Debug)]
+#[allow(unused)] // This is unused in the build script
 pub enum Parse {
     /// This generally indicates the string passed in had less than 3 digits in
     /// it.
@@ -62,6 +78,13 @@ pub enum Parse {
     #[allow(unused)] // This is unused in the build script
     InvalidCountryCode,
 
+    /// The country code or region exists, but its metadata is not part of the
+    /// database, for example because it was left out of the embedded metadata
+    /// with `PHONENUMBER_REGIONS`.
+    #[error("unsupported region")]
+    #[allow(unused)] // This is unused in the build script
+    UnsupportedRegion,
+
     /// This indicates the string started with an international dialing prefix,
     /// but after this was stripped from the number, had less digits than any
     /// valid phone number (including country code) could have.
@@ -81,9 +104,142 @@ pub enum Parse {
     #[allow(unused)] // This is unused in the build script
     TooLong,
 
+    /// The string isn't a number in the strict E.164 format, for example
+    /// because of spaces or a missing plus sign.
+    #[error("not in the E.164 format")]
+    #[allow(unused)] // This is unused in the build script
+    NotE164,
+
     /// A integer parts of a number is malformed, normally this should be caught by the parsing regexes.
     #[error("malformed integer part in phone number: {0}")]
     MalformedInteger(#[from] std::num::ParseIntError),
+
+    /// The extension is invalid, normally this should be caught by the parsing
+    /// regexes.
+    #[error("{0}")]
+    InvalidExtension(#[from] Extension),
+
+    /// The metadata used by the functions not taking a database explicitly
+    /// couldn't be loaded.
+    #[error("{0}")]
+    Metadata(#[from] Metadata),
+}
+
+/// Invalid extensions.
+#[derive(Error, Clone, Eq, PartialEq, Debug)]
+#[allow(unused)] // This is unused in the build script
+pub enum Extension {
+    /// The extension is empty.
+    #[error("empty extension")]
+    #[allow(unused)] // This is unused in the build script
+    Empty,
+
+    /// The extension is longer than any extension can be, or than the
+    /// `ExtensionPolicy` allows.
+    #[error("the extension is too long: {0} characters")]
+    #[allow(unused)] // This is unused in the build script
+    TooLong(usize),
+
+    /// The number has an extension, which the `ExtensionPolicy` doesn't allow.
+    #[error("extensions are not allowed")]
+    #[allow(unused)] // This is unused in the build script
+    NotAllowed,
+
+    /// The extension contains a character which isn't a digit, or one of the
+    /// allowed dialling characters.
+    #[error("invalid character in extension: {0:?}")]
+    #[allow(unused)] // This is unused in the build script
+    InvalidCharacter(char),
+}
+
+/// A name that isn't one of the spellings of an enum, like a formatting mode.
+#[derive(Error, Clone, Eq, PartialEq, Debug)]
+#[error("unknown {kind} {value:?}, expected one of: {}", .expected.join(", "))]
+#[allow(unused)] // This is unused in the build script
+pub struct Name {
+    pub(crate) kind: &'static str,
+    pub(crate) value: String,
+    pub(crate) expected: &'static [&'static str],
+}
+
+#[allow(unused)] // This is unused in the build script
+impl Name {
+    /// The name that was given.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The accepted spellings, in any case.
+    pub fn expected(&self) -> &[&str] {
+        self.expected
+    }
+
+    /// Find the variant with the name, ignoring case, the names being in the
+    /// order of the variants.
+    pub(crate) fn lookup<T: Copy>(
+        kind: &'static str,
+        names: &'static [&'static str],
+        variants: &[T],
+        value: &str,
+    ) -> Result<T, Name> {
+        names
+            .iter()
+            .position(|name| value.chars().flat_map(char::to_lowercase).eq(name.chars()))
+            .map(|i| variants[i])
+            .ok_or_else(|| Name {
+                kind,
+                value: value.into(),
+                expected: names,
+            })
+    }
+}
+
+/// Errors building a phone number from its parts.
+#[derive(Error, Clone, Debug)]
+#[allow(unused)] // This is unused in the build script
+pub enum Build {
+    /// The country calling code isn't used by any region or non-geographical
+    /// entity.
+    #[error("invalid country code: {0}")]
+    #[allow(unused)] // This is unused in the build script
+    InvalidCountryCode(u16),
+
+    /// The national number, with its leading zeros, has more digits than any
+    /// phone number could have.
+    #[error("the number is too long")]
+    #[allow(unused)] // This is unused in the build script
+    TooLong,
+
+    /// The country calling code doesn't fit in a `u16`, so it can't be valid.
+    #[error("country code out of range: {0}")]
+    #[allow(unused)] // This is unused in the build script
+    CountryCodeOutOfRange(i32),
+
+    /// The extension is invalid.
+    #[error("{0}")]
+    Extension(#[from] Extension),
+}
+
+/// Protobuf decoding errors.
+#[cfg(feature = "proto")]
+#[derive(Error, Clone, Debug)]
+#[allow(unused)] // This is unused in the build script
+pub enum Proto {
+    /// The message ended in the middle of a field.
+    #[error("truncated protobuf message")]
+    Truncated,
+
+    /// A field uses a wire type the message doesn't have.
+    #[error("unsupported protobuf wire type: {0}")]
+    WireType(u8),
+
+    /// A required field is missing.
+    #[error("missing required protobuf field: {0}")]
+    MissingField(&'static str),
+
+    /// A string field isn't valid UTF-8.
+    #[error("invalid UTF-8 in protobuf string: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
 }
 
 /// Loading of Database) Error
@@ -116,4 +272,29 @@ pub enum LoadMetadata {
     /// Malformed Regex in Metadata XML database
     #[error("Malformed Regex: {0}")]
     Regex(#[from] regex::Error),
+
+    /// Malformed binary metadata.
+    #[error("Malformed binary Metadata: {0}")]
+    Bincode(#[from] bincode::Error),
+
+    /// Loading the metadata of a territory failed.
+    #[error("Malformed Metadata for territory {id:?}: {error}")]
+    Territory {
+        id: String,
+        #[source]
+        error: Box<LoadMetadata>,
+    },
+}
+
+impl LoadMetadata {
+    /// Attach the ID of the territory being loaded to the error.
+    pub(crate) fn territory<S: Into<String>>(self, id: S) -> Self {
+        match self {
+            LoadMetadata::Territory { .. } => self,
+            error => LoadMetadata::Territory {
+                id: id.into(),
+                error: Box::new(error),
+            },
+        }
+    }
 }
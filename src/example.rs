@@ -0,0 +1,505 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::consts;
+use crate::country;
+use crate::metadata::{self, Database, Metadata};
+use crate::parser;
+use crate::phone_number::{PhoneNumber, Type};
+use crate::validator;
+
+/// Get a valid example number for the given country, a fixed-line one for
+/// regions.
+///
+/// For `None` it's one of the non-geographical entities, the one with the
+/// lowest country calling code having an example of the first type of mobile,
+/// toll free, shared cost, VoIP, voicemail, UAN and premium rate numbers,
+/// since they have no fixed-line numbers.
+pub fn example_number<C: Into<Option<country::Id>>>(country: C) -> Option<PhoneNumber> {
+    example_number_with(&metadata::global(), country)
+}
+
+/// Get a valid example number for the given country with the given
+/// `Database`.
+pub fn example_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    country: C,
+) -> Option<PhoneNumber> {
+    let country = country.into();
+    let (code, example) = example(database, country, default_types(country))?;
+
+    parse(database, country, code, example)
+}
+
+/// Get a valid example number of the given type for the given country, or
+/// for `None` of the non-geographical entity with the lowest country calling
+/// code having one.
+pub fn example_number_for_type<C: Into<Option<country::Id>>>(
+    country: C,
+    kind: Type,
+) -> Option<PhoneNumber> {
+    example_number_for_type_with(&metadata::global(), country, kind)
+}
+
+/// Get a valid example number of the given type for the given country with the
+/// given `Database`.
+pub fn example_number_for_type_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    country: C,
+    kind: Type,
+) -> Option<PhoneNumber> {
+    let country = country.into();
+    let (code, example) = example(database, country, &[kind])?;
+
+    parse(database, country, code, example)
+}
+
+/// Get an invalid, but plausible looking, example number for the given
+/// country, or for `None` a non-geographical entity like `example_number`.
+pub fn invalid_example_number<C: Into<Option<country::Id>>>(country: C) -> Option<PhoneNumber> {
+    invalid_example_number_with(&metadata::global(), country)
+}
+
+/// Get an invalid, but plausible looking, example number for the given
+/// country with the given `Database`.
+pub fn invalid_example_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    country: C,
+) -> Option<PhoneNumber> {
+    let country = country.into();
+    let (code, example) = example(database, country, default_types(country))?;
+
+    // Shorten the valid example one digit at a time, since numbers shorter
+    // than the valid ones are still quite likely to be possible.
+    (consts::MIN_LENGTH_FOR_NSN..example.len())
+        .rev()
+        .filter_map(|length| parse(database, country, code, &example[..length]))
+        .find(|number| !validator::is_valid_with(database, number))
+}
+
+/// The types tried for example numbers when none is given.
+fn default_types(country: Option<country::Id>) -> &'static [Type] {
+    if country.is_some() {
+        &[Type::FixedLine]
+    } else {
+        NON_GEO_TYPES
+    }
+}
+
+/// Find the country calling code and the first example of the given types of
+/// the country, or for `None` of the non-geographical entity with the lowest
+/// country calling code having one.
+fn example<'a>(
+    database: &'a Database,
+    country: Option<country::Id>,
+    kinds: &[Type],
+) -> Option<(u16, &'a str)> {
+    let find = |meta: &'a Metadata| {
+        kinds.iter().find_map(|&kind| {
+            Some((
+                meta.country_code(),
+                meta.descriptors().get(kind)?.example()?,
+            ))
+        })
+    };
+
+    match country {
+        Some(country) => find(database.by_id(country.as_ref())?),
+
+        None => {
+            let mut codes = database
+                .supported_global_network_calling_codes()
+                .collect::<Vec<_>>();
+            codes.sort_unstable();
+
+            codes
+                .into_iter()
+                .filter_map(|code| database.non_geographical(code))
+                .find_map(find)
+        }
+    }
+}
+
+/// Parse the national number of an example, in the country, or with the
+/// country calling code for the non-geographical entities.
+fn parse(
+    database: &Database,
+    country: Option<country::Id>,
+    code: u16,
+    national: &str,
+) -> Option<PhoneNumber> {
+    match country {
+        Some(country) => parser::parse_with(database, Some(country), national).ok(),
+        None => parser::parse_with(database, None, format!("+{}{}", code, national)).ok(),
+    }
+}
+
+/// Generate a random valid number from the given source of entropy, for
+/// fuzzing and property tests.
+///
+/// See `random_number_with`.
+pub fn random_number<F: FnMut() -> u64>(entropy: F) -> Option<PhoneNumber> {
+    random_number_with(&metadata::global(), entropy)
+}
+
+/// Generate a random valid number from the given source of entropy with the
+/// given `Database`.
+///
+/// A region and one of its number types are picked, and a random number of
+/// the type is generated with `random_number_for_type_with`. Sometimes an
+/// extension is added.
+///
+/// The numbers are valid according to the metadata, which doesn't mean they
+/// are allocated to anyone. The same entropy gives the same number, so it can
+/// come from `arbitrary::Unstructured` or a `proptest` strategy for `u64`.
+///
+/// Returns `None` if there are no example numbers in the database.
+pub fn random_number_with<F: FnMut() -> u64>(
+    database: &Database,
+    mut entropy: F,
+) -> Option<PhoneNumber> {
+    let examples = database
+        .iter_regions()
+        .flat_map(|(country, meta)| {
+            RANDOM_TYPES.iter().filter_map(move |&kind| {
+                meta.descriptors().get(kind)?.example()?;
+                Some((country, kind))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if examples.is_empty() {
+        return None;
+    }
+
+    let (country, kind) = examples[(entropy() % examples.len() as u64) as usize];
+    let mut number = random_number_for_type_with(database, country, kind, &mut entropy)?;
+
+    if entropy() % 4 == 0 {
+        let extension = (entropy() % 100_000).to_string();
+        number.set_extension(extension).ok()?;
+    }
+
+    Some(number)
+}
+
+/// Generate a random valid number for the given country from the given source
+/// of entropy, for load tests.
+///
+/// See `random_number_for_region_with`.
+pub fn random_number_for_region<F: FnMut() -> u64>(
+    country: country::Id,
+    entropy: F,
+) -> Option<PhoneNumber> {
+    random_number_for_region_with(&metadata::global(), country, entropy)
+}
+
+/// Generate a random valid number for the given country from the given source
+/// of entropy with the given `Database`.
+///
+/// One of the number types with an example number is picked, and a random
+/// number of the type is generated with `random_number_for_type_with`.
+pub fn random_number_for_region_with<F: FnMut() -> u64>(
+    database: &Database,
+    country: country::Id,
+    mut entropy: F,
+) -> Option<PhoneNumber> {
+    let meta = database.by_id(country.as_ref())?;
+    let kinds = RANDOM_TYPES
+        .iter()
+        .filter(|&&kind| {
+            meta.descriptors()
+                .get(kind)
+                .and_then(|d| d.example())
+                .is_some()
+        })
+        .collect::<Vec<_>>();
+
+    if kinds.is_empty() {
+        return None;
+    }
+
+    let kind = *kinds[(entropy() % kinds.len() as u64) as usize];
+    random_number_for_type_with(database, country, kind, entropy)
+}
+
+/// Generate a random valid number of the given type for the given country
+/// from the given source of entropy.
+///
+/// See `random_number_for_type_with`.
+pub fn random_number_for_type<F: FnMut() -> u64>(
+    country: country::Id,
+    kind: Type,
+    entropy: F,
+) -> Option<PhoneNumber> {
+    random_number_for_type_with(&metadata::global(), country, kind, entropy)
+}
+
+/// Generate a random valid number of the given type for the given country
+/// from the given source of entropy with the given `Database`.
+///
+/// Numbers of one of the possible lengths of the type are generated keeping
+/// the leading digits of its example number, a few more after every few
+/// failed tries, until one is valid and of the type, for at most a few dozen
+/// tries before falling back to the example itself. In regions like the US where fixed line and mobile
+/// numbers can't be told apart, `Type::FixedLineOrMobile` counts as both.
+///
+/// The numbers are valid according to the metadata, which doesn't mean they
+/// are allocated to anyone.
+///
+/// Returns `None` if there is no example number of the type.
+pub fn random_number_for_type_with<F: FnMut() -> u64>(
+    database: &Database,
+    country: country::Id,
+    kind: Type,
+    mut entropy: F,
+) -> Option<PhoneNumber> {
+    let meta = database.by_id(country.as_ref())?;
+    let descriptor = meta.descriptors().get(kind)?;
+    let example = descriptor.example()?;
+
+    let lengths = if descriptor.possible_length().is_empty() {
+        meta.descriptors().general().possible_length()
+    } else {
+        descriptor.possible_length()
+    };
+
+    let matches = |number: &PhoneNumber| {
+        validator::is_valid_for_region_with(database, number, country)
+            && match number.number_type_with(database) {
+                Type::FixedLineOrMobile => {
+                    matches!(
+                        kind,
+                        Type::FixedLine | Type::Mobile | Type::FixedLineOrMobile
+                    )
+                }
+                other => other == kind,
+            }
+    };
+
+    for tries in 0..MAX_RANDOM_TRIES {
+        let length = match lengths {
+            [] => example.len(),
+            lengths => lengths[(entropy() % lengths.len() as u64) as usize] as usize,
+        };
+
+        // Keep more of the example the more tries fail, so numbers differ in
+        // as many digits as possible.
+        let kept = (1 + tries / 4).min(example.len().min(length).saturating_sub(1).max(1));
+        let mut digits = example[..kept].to_owned();
+
+        while digits.len() < length {
+            digits.push(char::from(b'0' + (entropy() % 10) as u8));
+        }
+
+        if !descriptor.is_match(&digits) {
+            continue;
+        }
+
+        match parser::parse_with(database, Some(country), &digits) {
+            Ok(number) if matches(&number) => return Some(number),
+            _ => (),
+        }
+    }
+
+    parser::parse_with(database, Some(country), example).ok()
+}
+
+/// The types random numbers are generated for.
+const RANDOM_TYPES: &[Type] = &[
+    Type::FixedLine,
+    Type::Mobile,
+    Type::TollFree,
+    Type::PremiumRate,
+    Type::SharedCost,
+    Type::PersonalNumber,
+    Type::Voip,
+    Type::Pager,
+    Type::Uan,
+    Type::Voicemail,
+];
+
+/// The types tried in order for examples of non-geographical entities, like
+/// `getExampleNumberForNonGeoEntity` of libphonenumber does.
+const NON_GEO_TYPES: &[Type] = &[
+    Type::Mobile,
+    Type::TollFree,
+    Type::SharedCost,
+    Type::Voip,
+    Type::Voicemail,
+    Type::Uan,
+    Type::PremiumRate,
+];
+
+/// The number of random numbers tried before falling back to the example.
+const MAX_RANDOM_TRIES: usize = 64;
+
+#[cfg(test)]
+mod test {
+    use crate::consts;
+    use crate::country;
+    use crate::example;
+    use crate::metadata::DATABASE;
+    use crate::phone_number::Type;
+    use crate::validator;
+
+    #[test]
+    fn examples() {
+        let number = example::example_number(country::US).unwrap();
+        assert!(validator::is_valid(&number));
+        assert_eq!(Type::FixedLineOrMobile, number.number_type());
+
+        let number = example::example_number_for_type(country::GB, Type::Mobile).unwrap();
+        assert!(validator::is_valid(&number));
+        assert_eq!(Type::Mobile, number.number_type());
+
+        let number = example::example_number_for_type(country::US, Type::TollFree).unwrap();
+        assert_eq!(Type::TollFree, number.number_type());
+
+        assert!(example::example_number_for_type(country::US, Type::Voicemail).is_none());
+    }
+
+    #[test]
+    fn all_regions() {
+        for code in 1..1000 {
+            for region in DATABASE.region(&code).unwrap_or_default() {
+                // Non-geographical entities are checked separately.
+                let country: country::Id = match region.parse() {
+                    Ok(country) => country,
+                    Err(_) if region == consts::REGION_CODE_FOR_NON_GEO_ENTITY => continue,
+                    Err(err) => panic!("{:?}: {}", region, err),
+                };
+
+                // Every region has examples, so any missing one is a bug.
+                let number = example::example_number(country)
+                    .unwrap_or_else(|| panic!("no example for {:?}", country));
+                assert!(validator::is_valid(&number), "{:?}", country);
+
+                // Regions sharing a calling code can have overlapping
+                // patterns, so check against the region's own metadata.
+                let meta = DATABASE.by_id(region).unwrap();
+                let national = number.national().to_string();
+                assert_ne!(
+                    Type::Unknown,
+                    validator::number_type(meta, &national),
+                    "{:?}",
+                    country
+                );
+
+                let number = example::invalid_example_number(country)
+                    .unwrap_or_else(|| panic!("no invalid example for {:?}", country));
+                assert!(!validator::is_valid(&number), "{:?}", country);
+            }
+        }
+    }
+
+    #[test]
+    fn random() {
+        // A simple linear congruential generator, for reproducible numbers.
+        let mut state = 0x853c_49e6_748f_ea9bu64;
+        let mut entropy = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+
+        let mut numbers = (0..1000)
+            .map(|_| example::random_number(&mut entropy).unwrap())
+            .collect::<Vec<_>>();
+
+        for number in &numbers {
+            assert!(validator::is_valid(number), "{:?}", number);
+        }
+
+        assert!(numbers.iter().any(|n| n.extension().is_some()));
+
+        numbers.sort();
+        numbers.dedup();
+        assert!(numbers.len() > 900);
+    }
+
+    #[test]
+    fn random_for_type() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut entropy = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+
+        for (country, kind) in [
+            (country::US, Type::FixedLine),
+            (country::US, Type::TollFree),
+            (country::DE, Type::FixedLine),
+            (country::DE, Type::Mobile),
+            (country::IN, Type::FixedLine),
+            (country::IN, Type::Mobile),
+        ] {
+            let mut numbers = (0..1000)
+                .map(|_| example::random_number_for_type(country, kind, &mut entropy).unwrap())
+                .collect::<Vec<_>>();
+
+            for number in &numbers {
+                assert!(
+                    validator::is_valid_for_region(number, country),
+                    "{:?}",
+                    number
+                );
+
+                match number.number_type() {
+                    Type::FixedLineOrMobile => {
+                        assert!(matches!(kind, Type::FixedLine | Type::Mobile))
+                    }
+                    other => assert_eq!(kind, other, "{:?}", number),
+                }
+            }
+
+            numbers.sort();
+            numbers.dedup();
+            assert!(numbers.len() > 900, "{:?} {:?}", country, kind);
+        }
+
+        for country in [country::US, country::DE, country::IN] {
+            let number = example::random_number_for_region(country, &mut entropy).unwrap();
+            assert!(validator::is_valid_for_region(&number, country));
+        }
+
+        assert!(example::random_number_for_type(country::US, Type::Voicemail, entropy).is_none());
+    }
+
+    #[test]
+    fn non_geographical() {
+        let number = example::example_number(None).unwrap();
+        assert_eq!(800, number.code().value());
+        assert_eq!(Type::TollFree, number.number_type());
+        assert_eq!(None, number.country().id());
+        assert!(number.is_valid_for_region(None));
+
+        let number = example::example_number_for_type(None, Type::Mobile).unwrap();
+        assert_eq!(870, number.code().value());
+        assert!(number.is_valid_for_region(None));
+
+        // No non-geographical entity has fixed-line numbers.
+        assert_eq!(
+            None,
+            example::example_number_for_type(None, Type::FixedLine)
+        );
+
+        let number = example::invalid_example_number(None).unwrap();
+        assert_eq!(800, number.code().value());
+        assert!(!validator::is_valid(&number));
+    }
+}
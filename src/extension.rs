@@ -15,13 +15,69 @@
 use std::fmt;
 use std::ops::Deref;
 
+use crate::consts;
+use crate::error;
+
 /// A phone number extension.
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
-pub struct Extension(pub(crate) String);
+///
+/// Extensions are at most `consts::MAX_LENGTH_FOR_EXTENSION` characters long,
+/// made of digits and the dialling characters `,` (a pause), `*` and `#`.
+/// They are serialized as plain strings.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Extension(pub(crate) Box<str>);
+
+impl Extension {
+    /// Create an extension, made of digits and dialling characters.
+    pub fn new<S: Into<String>>(value: S) -> Result<Self, error::Extension> {
+        Extension::check(value.into(), consts::EXTENSION_DIALLING_CHARS)
+    }
 
-impl<T: Into<String>> From<T> for Extension {
-    fn from(value: T) -> Extension {
-        Extension(value.into())
+    /// Create an extension made only of digits.
+    pub fn digits<S: Into<String>>(value: S) -> Result<Self, error::Extension> {
+        Extension::check(value.into(), &[])
+    }
+
+    fn check(value: String, allowed: &[char]) -> Result<Self, error::Extension> {
+        if value.is_empty() {
+            return Err(error::Extension::Empty);
+        }
+
+        let length = value.chars().count();
+        if length > consts::MAX_LENGTH_FOR_EXTENSION {
+            return Err(error::Extension::TooLong(length));
+        }
+
+        if let Some(c) = value
+            .chars()
+            .find(|c| !c.is_ascii_digit() && !allowed.contains(c))
+        {
+            return Err(error::Extension::InvalidCharacter(c));
+        }
+
+        Ok(Extension(value.into_boxed_str()))
+    }
+}
+
+impl TryFrom<String> for Extension {
+    type Error = error::Extension;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Extension::new(value)
+    }
+}
+
+impl TryFrom<&str> for Extension {
+    type Error = error::Extension;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Extension::new(value)
+    }
+}
+
+impl From<Extension> for String {
+    fn from(value: Extension) -> String {
+        value.0.into()
     }
 }
 
@@ -44,3 +100,72 @@ impl fmt::Display for Extension {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::consts;
+    use crate::error;
+    use crate::extension::Extension;
+    use crate::parser;
+
+    #[test]
+    fn validation() {
+        assert_eq!("1234", Extension::new("1234").unwrap().as_ref());
+        assert_eq!("12,34#", Extension::new("12,34#").unwrap().as_ref());
+        assert!(Extension::new("1".repeat(consts::MAX_LENGTH_FOR_EXTENSION)).is_ok());
+        assert_eq!(
+            Err(error::Extension::TooLong(
+                consts::MAX_LENGTH_FOR_EXTENSION + 1
+            )),
+            Extension::new("1".repeat(consts::MAX_LENGTH_FOR_EXTENSION + 1))
+        );
+
+        assert_eq!(Err(error::Extension::Empty), Extension::new(""));
+        assert_eq!(
+            Err(error::Extension::TooLong(60)),
+            Extension::new("1".repeat(60))
+        );
+        assert_eq!(
+            Err(error::Extension::InvalidCharacter('a')),
+            Extension::new("12a")
+        );
+        assert_eq!(
+            Err(error::Extension::InvalidCharacter(' ')),
+            Extension::new("12 3")
+        );
+
+        assert!(Extension::digits("1234").is_ok());
+        assert_eq!(
+            Err(error::Extension::InvalidCharacter(',')),
+            Extension::digits("12,34")
+        );
+
+        assert!(Extension::try_from("1234").is_ok());
+        assert!(Extension::try_from(String::from("12x")).is_err());
+    }
+
+    #[test]
+    fn parsed() {
+        let number = parser::parse(None, "+1 650 253 0000 ext. 1234").unwrap();
+        assert_eq!(Some(&Extension::new("1234").unwrap()), number.extension());
+    }
+
+    #[test]
+    fn serde() {
+        let extension = Extension::new("1234").unwrap();
+        let json = serde_json::to_string(&extension).unwrap();
+        assert_eq!(r#""1234""#, json);
+        assert_eq!(extension, serde_json::from_str::<Extension>(&json).unwrap());
+
+        assert!(serde_json::from_str::<Extension>(r#""12a""#).is_err());
+        assert!(serde_json::from_str::<Extension>(&format!(r#""{}""#, "1".repeat(60))).is_err());
+    }
+
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn serde_number() {
+        let number = parser::parse(None, "+1 650 253 0000 ext. 1234").unwrap();
+        let json = serde_json::to_value(&number).unwrap();
+        assert_eq!("1234", json["extension"]);
+    }
+}
@@ -12,17 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// Formatting runs on numbers parsed from untrusted input, where a panic is a
+// bug.
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+
 use crate::{
-    consts,
-    metadata::{Database, Format, Metadata, DATABASE},
+    consts, country, error,
+    metadata::{self, Database, Format, Metadata},
     phone_number::PhoneNumber,
 };
-use std::{borrow::Cow, fmt};
+use regex::Captures;
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    str::FromStr,
+};
 
 /// Formatting modes for phone number.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Mode {
     /// E.164 formatting, no spaces, no decorations.
+    ///
+    /// The output is always pure ASCII, the plus sign followed by the digits
+    /// of the number, so it can be written as bytes as is.
     E164,
 
     /// International formatting, contains country code and country dependent
@@ -36,6 +48,252 @@ pub enum Mode {
     Rfc3966,
 }
 
+impl Mode {
+    /// Every mode, in the order of `NAMES`.
+    const VARIANTS: &'static [Mode] = &[
+        Mode::E164,
+        Mode::International,
+        Mode::National,
+        Mode::Rfc3966,
+    ];
+
+    /// The names of the modes.
+    const NAMES: &'static [&'static str] = &["e164", "international", "national", "rfc3966"];
+
+    /// The name of the mode, like "e164".
+    pub fn name(&self) -> &'static str {
+        Mode::NAMES[*self as usize]
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parse the name of a mode in any case, like "e164" or "International".
+impl FromStr for Mode {
+    type Err = error::Name;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        error::Name::lookup("formatting mode", Mode::NAMES, Mode::VARIANTS, value)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for Mode {
+    fn value_variants<'a>() -> &'a [Self] {
+        Mode::VARIANTS
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<clap::PossibleValue<'a>> {
+        Some(clap::PossibleValue::new(self.name()))
+    }
+}
+
+/// Options for the human readable formatting modes, the international and
+/// national ones.
+///
+/// The grouping of the metadata is kept, only the separators are swapped, so
+/// other punctuation like dashes or parentheses is left alone.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FormatOptions<'a> {
+    /// The separator replacing the spaces between groups of digits, like a
+    /// narrow no-break space.
+    pub group_separator: char,
+
+    /// The prefix of an extension, like " poste ", instead of the preferred
+    /// one of the country.
+    pub extension_prefix: Option<&'a str>,
+
+    /// Whether spaces are non-breaking, this includes the spaces of the
+    /// extension prefix and the group separator if it's a space.
+    pub use_nbsp: bool,
+}
+
+impl<'a> Default for FormatOptions<'a> {
+    fn default() -> Self {
+        FormatOptions {
+            group_separator: ' ',
+            extension_prefix: None,
+            use_nbsp: false,
+        }
+    }
+}
+
+impl<'a> FormatOptions<'a> {
+    /// The character replacing the spaces outside of the digit groups.
+    fn space(&self) -> char {
+        if self.use_nbsp {
+            '\u{a0}'
+        } else {
+            ' '
+        }
+    }
+
+    /// The character replacing the spaces between digit groups.
+    fn separator(&self) -> char {
+        if self.group_separator == ' ' {
+            self.space()
+        } else {
+            self.group_separator
+        }
+    }
+}
+
+/// The kind of a `Segment` of a formatted number.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SegmentKind {
+    /// The "tel:" scheme of the RFC3966 format.
+    Scheme,
+
+    /// The plus sign before the country code.
+    Plus,
+
+    /// The international prefix of the country called from, used instead of
+    /// the plus sign.
+    InternationalPrefix,
+
+    /// The country code.
+    CountryCode,
+
+    /// The national prefix, dialled before the number within its country.
+    NationalPrefix,
+
+    /// The carrier code, dialled before the number within its country.
+    Carrier,
+
+    /// A group of digits, or of letters for vanity numbers.
+    Group,
+
+    /// The spaces or punctuation between the other segments.
+    Separator,
+
+    /// The text before the extension, like " ext. ".
+    ExtensionMarker,
+
+    /// The extension.
+    Extension,
+}
+
+/// A labeled part of a formatted number.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Segment {
+    kind: SegmentKind,
+    text: String,
+}
+
+impl Segment {
+    /// The kind of the segment.
+    pub fn kind(&self) -> SegmentKind {
+        self.kind
+    }
+
+    /// The text of the segment, as it appears in the formatted number.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A formatted number broken into labeled segments, see `Formatter::parts`.
+///
+/// It's displayed as the formatted number.
+#[derive(Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct FormattedNumber {
+    segments: Vec<Segment>,
+}
+
+impl FormattedNumber {
+    /// The segments of the formatted number, in order.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for FormattedNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in &self.segments {
+            f.write_str(&segment.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Receives the segments of a formatted number, with the spaces replaced by
+/// the given character.
+trait Sink {
+    fn segment(&mut self, kind: SegmentKind, text: fmt::Arguments, space: char) -> fmt::Result;
+}
+
+impl Sink for fmt::Formatter<'_> {
+    fn segment(&mut self, _: SegmentKind, text: fmt::Arguments, space: char) -> fmt::Result {
+        Separated {
+            f: self,
+            separator: space,
+        }
+        .write_fmt(text)
+    }
+}
+
+impl Sink for Vec<Segment> {
+    fn segment(&mut self, kind: SegmentKind, text: fmt::Arguments, space: char) -> fmt::Result {
+        let mut text = fmt::format(text);
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        if space != ' ' {
+            text = text.replace(' ', space.encode_utf8(&mut [0; 4]));
+        }
+
+        self.push(Segment { kind, text });
+        Ok(())
+    }
+}
+
+/// Writes through to a formatter, replacing spaces with a separator.
+struct Separated<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    separator: char,
+}
+
+impl<'a, 'b> fmt::Write for Separated<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.separator == ' ' {
+            return self.f.write_str(s);
+        }
+
+        for (i, part) in s.split(' ').enumerate() {
+            if i > 0 {
+                fmt::Write::write_char(self.f, self.separator)?;
+            }
+
+            self.f.write_str(part)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes UTF-8 into a byte buffer.
+struct Bytes<'a>(&'a mut Vec<u8>);
+
+impl<'a> fmt::Write for Bytes<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
 /// A formatter for a `PhoneNumber`.
 #[derive(Copy, Clone, Debug)]
 pub struct Formatter<'n, 'd, 'f> {
@@ -43,6 +301,9 @@ pub struct Formatter<'n, 'd, 'f> {
     database: Option<&'d Database>,
     mode: Mode,
     format: Option<&'f Format>,
+    options: FormatOptions<'f>,
+    from: Option<country::Id>,
+    alpha: bool,
 }
 
 impl<'n, 'd, 'f> Formatter<'n, 'd, 'f> {
@@ -53,6 +314,9 @@ impl<'n, 'd, 'f> Formatter<'n, 'd, 'f> {
             database: Some(database),
             mode: self.mode,
             format: self.format,
+            options: self.options,
+            from: self.from,
+            alpha: self.alpha,
         }
     }
 
@@ -63,56 +327,221 @@ impl<'n, 'd, 'f> Formatter<'n, 'd, 'f> {
     }
 
     /// Define a custom `Format` to use for formatting.
-    pub fn with<'a>(self, format: &'a Format) -> Formatter<'n, 'd, 'a> {
+    pub fn with<'a>(self, format: &'a Format) -> Formatter<'n, 'd, 'a>
+    where
+        'f: 'a,
+    {
         Formatter {
             number: self.number,
             database: self.database,
             mode: self.mode,
             format: Some(format),
+            options: self.options,
+            from: self.from,
+            alpha: self.alpha,
         }
     }
-}
 
-/// Create a new `Formatter` for the given phone number.
-pub fn format(number: &PhoneNumber) -> Formatter<'_, 'static, 'static> {
-    Formatter {
-        number,
-        database: None,
-        mode: Mode::E164,
-        format: None,
+    /// Define the separators used by the international and national formatting
+    /// modes, the other modes are left alone.
+    pub fn options<'a>(self, options: FormatOptions<'a>) -> Formatter<'n, 'd, 'a>
+    where
+        'f: 'a,
+    {
+        Formatter {
+            number: self.number,
+            database: self.database,
+            mode: self.mode,
+            format: self.format,
+            options,
+            from: self.from,
+            alpha: self.alpha,
+        }
     }
-}
 
-/// Create a new `Formatter` for the given phone number using the given
-/// metadata database.
-pub fn format_with<'d, 'n>(
-    database: &'d Database,
-    number: &'n PhoneNumber,
-) -> Formatter<'n, 'd, 'static> {
-    Formatter {
-        number,
-        database: Some(database),
-        mode: Mode::E164,
-        format: None,
+    /// Define the country the number is called from, this only affects the
+    /// international formatting mode.
+    ///
+    /// When calling from a country sharing the country code of the number, the
+    /// national format is used, preceded by the country code for NANPA
+    /// countries. Otherwise the international format is preceded by the
+    /// preferred international prefix of the calling country, or its only
+    /// international prefix, falling back to the plus sign.
+    ///
+    /// Calling from `None`, a non-geographical entity with no international
+    /// prefix of its own, always uses the plus sign.
+    pub fn calling_from<C: Into<Option<country::Id>>>(
+        mut self,
+        country: C,
+    ) -> Formatter<'n, 'd, 'f> {
+        self.from = country.into();
+        self
     }
-}
 
-impl<'n, 'd, 'f> fmt::Display for Formatter<'n, 'd, 'f> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let db = self.database.unwrap_or(&DATABASE);
+    /// Define whether the alpha characters of vanity numbers should be kept
+    /// when calling from a country sharing the country code of the number.
+    ///
+    /// This requires the number to be parsed with `parse_and_keep_raw_input`,
+    /// callers from other countries always get the digits.
+    pub fn keep_alpha(mut self, value: bool) -> Formatter<'n, 'd, 'f> {
+        self.alpha = value;
+        self
+    }
+
+    /// Check if the formatted number includes the national prefix, like
+    /// "030 123456" in Germany but unlike "(650) 253-0000" in the US.
+    ///
+    /// This is only ever the case for the national format, or the
+    /// international one when calling from a country sharing the country code.
+    pub fn includes_national_prefix(&self) -> bool {
+        let global;
+        let db = match self.database {
+            Some(database) => database,
+            None => {
+                global = metadata::global();
+                &global
+            }
+        };
+
+        let meta = match db.main_by_code(self.number.country().code()) {
+            Some(meta) if meta.national_prefix().is_some() => meta,
+            _ => return false,
+        };
+
+        let national = match self.mode {
+            Mode::National => meta.id() != consts::REGION_CODE_FOR_NON_GEO_ENTITY,
+            Mode::International => self
+                .from
+                .and_then(|c| db.by_id(c.as_ref()))
+                .map(|calling| calling.country_code() == meta.country_code())
+                .unwrap_or(false),
+            _ => false,
+        };
+
+        if !national {
+            return false;
+        }
+
+        let number = self.number.national().to_string();
+        let format = match self.format.or_else(|| formatter(&number, meta.formats())) {
+            Some(format) => format,
+            None => return false,
+        };
+
+        let rule = if self.number.carrier().is_some() {
+            format
+                .domestic_carrier()
+                .or_else(|| format.national_prefix())
+        } else {
+            format.national_prefix()
+        };
+
+        rule.map(|r| r.contains(consts::NP)).unwrap_or(false)
+    }
+
+    /// Break the formatted number into labeled segments, which are displayed
+    /// the same as the formatter.
+    ///
+    /// Numbers with a country code without metadata, which can't be displayed
+    /// in the other modes, are broken down in the E.164 format.
+    pub fn parts(&self) -> FormattedNumber {
+        let mut segments = Vec::new();
+
+        if self.emit(&mut segments).is_err() {
+            segments.clear();
+            self.mode(Mode::E164)
+                .emit(&mut segments)
+                .expect("E.164 formatting doesn't fail");
+        }
+
+        FormattedNumber { segments }
+    }
+
+    /// Append the formatted number to the string, which doesn't allocate
+    /// when it has the capacity for it, unlike `to_string`.
+    ///
+    /// Like with `parts`, numbers the metadata can't format are appended in
+    /// the E.164 format instead.
+    pub fn append(&self, out: &mut String) {
+        let start = out.len();
+
+        if write!(out, "{}", self).is_err() {
+            out.truncate(start);
+            write!(out, "{}", self.mode(Mode::E164)).expect("E.164 formatting doesn't fail");
+        }
+    }
+
+    /// Append the formatted number to the buffer in UTF-8, like `append`.
+    pub fn append_bytes(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+
+        if write!(Bytes(out), "{}", self).is_err() {
+            out.truncate(start);
+            write!(Bytes(out), "{}", self.mode(Mode::E164)).expect("E.164 formatting doesn't fail");
+        }
+    }
+
+    /// Pass the segments of the formatted number on to the sink.
+    fn emit<S: Sink>(&self, sink: &mut S) -> fmt::Result {
+        let code = self.number.country().code();
+
+        // Requires no formatting at all, easy life, the metadata isn't even
+        // needed.
+        if self.mode == Mode::E164 {
+            self.push(sink, SegmentKind::Plus, "+")?;
+            self.push(sink, SegmentKind::CountryCode, code)?;
+            return self.push(sink, SegmentKind::Group, self.number.national());
+        }
 
-        // If the country code is invalid, return an error.
-        let meta = db
-            .by_code(&self.number.country().code())
-            .map(|m| m.into_iter().next())
-            .flatten()
-            .ok_or(fmt::Error)?;
+        let global;
+        let db = match self.database {
+            Some(database) => database,
+            None => {
+                global = metadata::global();
+                &global
+            }
+        };
+
+        // If the country code is invalid, return an error. Formatting data for
+        // regions sharing a country code is only held by the main region, and
+        // non-geographical entities are looked up by their own code.
+        let meta = db.main_by_code(code).ok_or(fmt::Error)?;
+
+        // Calling from another country changes the international prefix, or
+        // means the number is formatted domestically.
+        let mut idd = None;
+
+        if let (Mode::International, Some(calling)) =
+            (self.mode, self.from.and_then(|c| db.by_id(c.as_ref())))
+        {
+            if calling.country_code() == meta.country_code() {
+                return self.domestic(sink, meta);
+            }
+
+            idd = calling.preferred_international_prefix().or_else(|| {
+                calling
+                    .international_prefix()
+                    .map(|p| p.as_str())
+                    .filter(|p| {
+                        consts::UNIQUE_INTERNATIONAL_PREFIX
+                            .find(p)
+                            .map(|m| m.start() == 0 && m.end() == p.len())
+                            .unwrap_or(false)
+                    })
+            });
+        }
+
+        // Numbers from non-geographical entities have no national format, so
+        // the national mode is the international one without the plus sign.
+        let non_geo = meta.id() == consts::REGION_CODE_FOR_NON_GEO_ENTITY;
 
         let national = self.number.national().to_string();
         let formatter = self.format.or_else(|| {
             formatter(
                 &national,
-                if meta.international_formats().is_empty() || self.mode == Mode::National {
+                if meta.international_formats().is_empty()
+                    || (self.mode == Mode::National && !non_geo)
+                {
                     meta.formats()
                 } else {
                     meta.international_formats()
@@ -120,173 +549,520 @@ impl<'n, 'd, 'f> fmt::Display for Formatter<'n, 'd, 'f> {
             )
         });
 
-        match self.mode {
-            // Requires no formatting at all, easy life.
-            Mode::E164 => {
-                write!(f, "+{}{}", self.number.country().code(), national)?;
+        // The E.164 format was handled above, without the metadata.
+        if self.mode == Mode::National && !non_geo {
+            if let Some(formatter) = formatter {
+                let carrier = self
+                    .number
+                    .carrier()
+                    .and_then(|c| formatter.domestic_carrier().map(|f| (c, f)));
+
+                if let Some((carrier, rule)) = carrier {
+                    self.apply(sink, &national, meta, formatter, Some(rule), Some(carrier))?;
+                } else {
+                    let rule = formatter.national_prefix();
+                    self.apply(sink, &national, meta, formatter, rule, None)?;
+                }
+            } else {
+                self.push(sink, SegmentKind::Group, &national)?;
             }
 
+            self.extension(sink, meta)?;
+        } else if self.mode != Mode::Rfc3966 {
             // Space separated formatting with national specific rules.
-            Mode::International => {
-                write!(f, "+{} ", self.number.country().code())?;
-
-                if let Some(formatter) = formatter {
-                    write!(f, "{}", replace(&national, meta, formatter, None, None))?;
+            if self.mode == Mode::International {
+                if let Some(idd) = idd {
+                    self.push(sink, SegmentKind::InternationalPrefix, idd)?;
+                    self.push(sink, SegmentKind::Separator, " ")?;
                 } else {
-                    write!(f, "{}", national)?;
+                    self.push(sink, SegmentKind::Plus, "+")?;
                 }
+            }
 
-                if let Some(ext) = self.number.extension() {
-                    write!(
-                        f,
-                        "{}{}",
-                        meta.preferred_extension_prefix().unwrap_or(" ext. "),
-                        ext
-                    )?;
-                }
+            self.push(sink, SegmentKind::CountryCode, code)?;
+            self.push(sink, SegmentKind::Separator, " ")?;
+
+            if let Some(formatter) = formatter {
+                self.apply(sink, &national, meta, formatter, None, None)?;
+            } else {
+                self.push(sink, SegmentKind::Group, &national)?;
+            }
+
+            self.extension(sink, meta)?;
+        } else {
+            self.push(sink, SegmentKind::Scheme, "tel:")?;
+            self.push(sink, SegmentKind::Plus, "+")?;
+            self.push(sink, SegmentKind::CountryCode, code)?;
+            self.push(sink, SegmentKind::Separator, "-")?;
+
+            if let Some(formatter) = formatter {
+                self.apply(sink, &national, meta, formatter, None, None)?;
+            } else {
+                self.push(sink, SegmentKind::Group, &national)?;
+            }
+
+            if let Some(ext) = self.number.extension() {
+                self.push(sink, SegmentKind::ExtensionMarker, ";ext=")?;
+                self.push(sink, SegmentKind::Extension, &**ext)?;
             }
+        }
+
+        Ok(())
+    }
+
+    /// Pass a segment on to the sink, with the spaces swapped as chosen in the
+    /// human readable modes.
+    fn push<S: Sink, T: fmt::Display>(
+        &self,
+        sink: &mut S,
+        kind: SegmentKind,
+        text: T,
+    ) -> fmt::Result {
+        let space = match (self.mode, kind) {
+            (Mode::E164, _) | (Mode::Rfc3966, _) => ' ',
+            (_, SegmentKind::ExtensionMarker) => self.options.space(),
+            _ => self.options.separator(),
+        };
+
+        sink.segment(kind, format_args!("{}", text), space)
+    }
+
+    /// Apply the format to the national number, passing on the groups of
+    /// digits and the text between them, with the national prefix formatting
+    /// rule applied to the first group.
+    fn apply<S: Sink>(
+        &self,
+        sink: &mut S,
+        national: &str,
+        meta: &Metadata,
+        format: &Format,
+        rule: Option<&str>,
+        carrier: Option<&str>,
+    ) -> fmt::Result {
+        let captures = match format.pattern().captures(national) {
+            Some(captures) => captures,
+            None => return self.push(sink, SegmentKind::Group, national),
+        };
 
-            Mode::National => {
-                if let Some(formatter) = formatter {
-                    let carrier = self
-                        .number
-                        .carrier()
-                        .and_then(|c| formatter.domestic_carrier().map(|f| (c, f)));
-
-                    if let Some((carrier, format)) = carrier {
-                        write!(
-                            f,
-                            "{}",
-                            replace(&national, meta, formatter, Some(format), Some(carrier))
-                        )?;
-                    } else if let Some(prefix) = formatter.national_prefix() {
-                        write!(
-                            f,
-                            "{}",
-                            replace(&national, meta, formatter, Some(prefix), None)
-                        )?;
-                    } else {
-                        write!(f, "{}", replace(&national, meta, formatter, None, None))?;
+        let whole = match captures.get(0) {
+            Some(whole) => whole,
+            None => return self.push(sink, SegmentKind::Group, national),
+        };
+        let template = format.format();
+        let mut applied = Applied::default();
+
+        applied.add(SegmentKind::Group, &national[..whole.start()]);
+
+        match rule.and_then(|rule| consts::FIRST_GROUP.find(template).map(|m| (rule, m))) {
+            Some((rule, first)) => {
+                applied.expand(&captures, &template[..first.start()]);
+
+                placeholders(rule, |text, placeholder| {
+                    match (placeholder, text) {
+                        (true, consts::NP) => applied.add(
+                            SegmentKind::NationalPrefix,
+                            meta.national_prefix().unwrap_or(""),
+                        ),
+                        (true, consts::CC) => {
+                            applied.add(SegmentKind::Carrier, carrier.unwrap_or(""))
+                        }
+                        (true, consts::FG) => applied.expand(&captures, first.as_str()),
+                        _ => applied.expand(&captures, text),
                     }
-                } else {
-                    write!(f, "{}", national)?;
-                }
 
-                if let Some(ext) = self.number.extension() {
-                    write!(
-                        f,
-                        "{}{}",
-                        meta.preferred_extension_prefix().unwrap_or(" ext. "),
-                        ext
-                    )?;
-                }
+                    Ok(())
+                })?;
+
+                applied.expand(&captures, &template[first.end()..]);
             }
 
-            Mode::Rfc3966 => {
-                write!(f, "tel:+{}-", self.number.country().code())?;
+            None => applied.expand(&captures, template),
+        }
 
-                if let Some(formatter) = formatter {
-                    write!(
-                        f,
-                        "{}",
-                        consts::SEPARATOR_PATTERN
-                            .replace_all(&replace(&national, meta, formatter, None, None), "-")
-                    )?;
-                } else {
-                    write!(f, "{}", national)?;
-                }
+        applied.add(SegmentKind::Group, &national[whole.end()..]);
 
-                if let Some(ext) = self.number.extension() {
-                    write!(f, ";ext={}", ext)?;
-                }
+        for (kind, text) in applied.0 {
+            if self.mode == Mode::Rfc3966 && kind == SegmentKind::Separator {
+                self.push(
+                    sink,
+                    kind,
+                    consts::SEPARATOR_PATTERN.replace_all(&text, "-"),
+                )?;
+            } else {
+                self.push(sink, kind, text)?;
             }
         }
 
         Ok(())
     }
-}
 
-fn formatter<'a>(number: &str, formats: &'a [Format]) -> Option<&'a Format> {
-    for format in formats {
-        let leading = format.leading_digits();
+    /// Format the number when called from the same country code.
+    fn domestic<S: Sink>(&self, sink: &mut S, meta: &Metadata) -> fmt::Result {
+        let code = self.number.country().code();
 
-        if leading.is_empty()
-            || leading
-                .last()
-                .unwrap()
-                .find(number)
-                .map(|m| m.start() == 0)
-                .unwrap_or(false)
-                && format
-                    .pattern()
-                    .find(number)
-                    .map(|m| m.start() == 0 && m.end() == number.len())
-                    .unwrap_or(false)
-        {
-            return Some(format);
+        if code == consts::NANPA_COUNTRY_CODE {
+            self.push(sink, SegmentKind::CountryCode, code)?;
+            self.push(sink, SegmentKind::Separator, " ")?;
+        }
+
+        let raw = if let Some(raw) = self.number.raw_input().filter(|_| self.alpha) {
+            raw
+        } else {
+            return self.mode(Mode::National).emit(sink);
+        };
+
+        let national = self.number.national().to_string();
+        let raw = vanity(raw, &national);
+
+        if code == consts::NANPA_COUNTRY_CODE {
+            return self.letters(sink, &raw);
+        }
+
+        // The first group is the first group of digits that was written
+        // together, which gets the national prefix if required.
+        let rule = formatter(&national, meta.formats()).and_then(Format::national_prefix);
+        let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+
+        match rule {
+            Some(rule) if split > 0 => {
+                placeholders(rule, |text, placeholder| match (placeholder, text) {
+                    (true, consts::NP) => self.push(
+                        sink,
+                        SegmentKind::NationalPrefix,
+                        meta.national_prefix().unwrap_or(""),
+                    ),
+                    (true, consts::FG) => self.push(sink, SegmentKind::Group, &raw[..split]),
+                    _ => self.push(sink, SegmentKind::Separator, text),
+                })?;
+
+                self.letters(sink, &raw[split..])
+            }
+
+            _ => self.letters(sink, &raw),
         }
     }
 
-    None
-}
+    /// Pass on the groups of a vanity number, and the grouping symbols
+    /// between them.
+    fn letters<S: Sink>(&self, sink: &mut S, mut raw: &str) -> fmt::Result {
+        while let Some(c) = raw.chars().next() {
+            let group = c.is_alphanumeric();
+            let end = raw
+                .find(|c: char| c.is_alphanumeric() != group)
+                .unwrap_or(raw.len());
 
-fn replace(
-    national: &str,
-    meta: &Metadata,
-    formatter: &Format,
-    transform: Option<&str>,
-    carrier: Option<&str>,
-) -> String {
-    formatter
-        .pattern()
-        .replace(
-            national,
-            &*if let Some(transform) = transform {
-                let first = consts::FIRST_GROUP
-                    .captures(formatter.format())
-                    .unwrap()
-                    .get(1)
-                    .unwrap()
-                    .as_str();
-                let format = transform.replace(*consts::NP, meta.national_prefix().unwrap_or(""));
-                let format = format.replace(*consts::FG, &format!("${}", first));
-                let format = format.replace(*consts::CC, carrier.unwrap_or(""));
-
-                consts::FIRST_GROUP.replace(formatter.format(), &*format)
+            let kind = if group {
+                SegmentKind::Group
             } else {
-                Cow::Borrowed(formatter.format())
-            },
-        )
-        .into()
-}
+                SegmentKind::Separator
+            };
 
-#[cfg(test)]
-mod test {
-    use crate::country;
-    use crate::formatter::Mode;
-    use crate::parser;
+            self.push(sink, kind, &raw[..end])?;
+            raw = &raw[end..];
+        }
 
-    #[test]
-    fn us() {
-        assert_eq!(
-            "(650) 253-0000",
-            parser::parse(Some(country::US), "+1 6502530000")
-                .unwrap()
-                .format()
-                .mode(Mode::National)
-                .to_string()
-        );
+        Ok(())
+    }
 
-        assert_eq!(
-            "+1 650-253-0000",
-            parser::parse(Some(country::US), "+1 6502530000")
-                .unwrap()
-                .format()
-                .mode(Mode::International)
-                .to_string()
-        );
+    /// Pass on the extension if there is one, with the chosen prefix or the
+    /// preferred one of the country.
+    fn extension<S: Sink>(&self, sink: &mut S, meta: &Metadata) -> fmt::Result {
+        if let Some(ext) = self.number.extension() {
+            let prefix = self
+                .options
+                .extension_prefix
+                .or_else(|| meta.preferred_extension_prefix())
+                .unwrap_or(" ext. ");
 
-        assert_eq!(
+            self.push(sink, SegmentKind::ExtensionMarker, prefix)?;
+            self.push(sink, SegmentKind::Extension, &**ext)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a new `Formatter` for the given phone number.
+pub fn format(number: &PhoneNumber) -> Formatter<'_, 'static, 'static> {
+    Formatter {
+        number,
+        database: None,
+        mode: Mode::E164,
+        format: None,
+        options: FormatOptions::default(),
+        from: None,
+        alpha: false,
+    }
+}
+
+/// Create a new `Formatter` for the given phone number using the given
+/// metadata database.
+pub fn format_with<'d, 'n>(
+    database: &'d Database,
+    number: &'n PhoneNumber,
+) -> Formatter<'n, 'd, 'static> {
+    Formatter {
+        number,
+        database: Some(database),
+        mode: Mode::E164,
+        format: None,
+        options: FormatOptions::default(),
+        from: None,
+        alpha: false,
+    }
+}
+
+impl<'n, 'd, 'f> fmt::Display for Formatter<'n, 'd, 'f> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The most common mode, written in one go instead of segment by
+        // segment.
+        if self.mode == Mode::E164 {
+            return write!(
+                f,
+                "+{}{}",
+                self.number.country().code(),
+                self.number.national()
+            );
+        }
+
+        self.emit(f)
+    }
+}
+
+/// Strip anything before the national number from the raw input, keeping
+/// only alpha characters, digits and grouping symbols.
+fn vanity(raw: &str, national: &str) -> String {
+    let raw = raw
+        .chars()
+        .filter_map(|c| consts::ALL_PLUS_NUMBER_GROUPING_SYMBOLS.get(&c))
+        .collect::<String>();
+
+    if national.len() > 3 {
+        if let Some(index) = raw.find(&national[..3]) {
+            return raw[index..].into();
+        }
+    }
+
+    raw
+}
+
+/// The national prefix needed to dial the number from within its country.
+///
+/// A format rule without the national prefix means the number is dialled
+/// without one, without any rule the prefix is dialled but not written, like
+/// for long distance calls in the US.
+pub(crate) fn national_dialing_prefix<'a>(meta: &'a Metadata, national: &str) -> Option<&'a str> {
+    let prefix = meta.national_prefix()?;
+
+    match formatter(national, meta.formats()).and_then(Format::national_prefix) {
+        Some(rule) if !rule.contains(consts::NP) => None,
+        _ => Some(prefix),
+    }
+}
+
+pub(crate) fn formatter<'a>(number: &str, formats: &'a [Format]) -> Option<&'a Format> {
+    // Rule out the formats whose leading digits can't match the first two
+    // digits before running any expression.
+    let prefix = match number.as_bytes() {
+        [a @ b'0'..=b'9', b @ b'0'..=b'9', ..] => Some(10 * (a - b'0') + (b - b'0')),
+        _ => None,
+    };
+
+    for format in formats {
+        let leading = format.leading_digits();
+
+        if let (Some(prefix), Some(last)) = (prefix, leading.last()) {
+            if last.prefixes() & (1 << prefix) == 0 {
+                continue;
+            }
+        }
+
+        // Formats without leading digits apply to any number.
+        let applies = leading.last().map_or(true, |last| {
+            last.find(number).map(|m| m.start() == 0).unwrap_or(false)
+                && format
+                    .pattern()
+                    .find(number)
+                    .map(|m| m.start() == 0 && m.end() == number.len())
+                    .unwrap_or(false)
+        });
+
+        if applies {
+            return Some(format);
+        }
+    }
+
+    None
+}
+
+/// The segments of a format applied to a number, borrowing as much as
+/// possible, with adjacent separators merged.
+#[derive(Default)]
+struct Applied<'a>(Vec<(SegmentKind, Cow<'a, str>)>);
+
+impl<'a> Applied<'a> {
+    fn add(&mut self, kind: SegmentKind, text: &'a str) {
+        if text.is_empty() {
+            return;
+        }
+
+        if let (SegmentKind::Separator, Some((SegmentKind::Separator, last))) =
+            (kind, self.0.last_mut())
+        {
+            last.to_mut().push_str(text);
+        } else {
+            self.0.push((kind, Cow::Borrowed(text)));
+        }
+    }
+
+    /// Expand the replacement template like `Regex::replace` does, with the
+    /// captured groups as groups and the rest as separators.
+    fn expand(&mut self, captures: &Captures<'a>, mut template: &'a str) {
+        while let Some(index) = template.find('$') {
+            self.add(SegmentKind::Separator, &template[..index]);
+            template = &template[index + 1..];
+
+            if template.starts_with('$') {
+                self.add(SegmentKind::Separator, "$");
+                template = &template[1..];
+                continue;
+            }
+
+            let (name, rest) = if let Some(braced) = template.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) => (&braced[..end], &braced[end + 1..]),
+                    None => ("", template),
+                }
+            } else {
+                let end = template
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(template.len());
+
+                (&template[..end], &template[end..])
+            };
+
+            if name.is_empty() {
+                // Not a reference to a group, so the dollar sign is literal.
+                self.add(SegmentKind::Separator, "$");
+                continue;
+            }
+
+            let group = match name.parse::<usize>() {
+                Ok(index) => captures.get(index),
+                Err(_) => captures.name(name),
+            };
+
+            if let Some(group) = group {
+                self.add(SegmentKind::Group, group.as_str());
+            }
+
+            template = rest;
+        }
+
+        self.add(SegmentKind::Separator, template);
+    }
+}
+
+/// Split a national prefix formatting rule into its placeholders, `$NP`,
+/// `$FG` and `$CC`, and the text around them.
+fn placeholders<'a, F>(mut rule: &'a str, mut each: F) -> fmt::Result
+where
+    F: FnMut(&'a str, bool) -> fmt::Result,
+{
+    while !rule.is_empty() {
+        let next = [consts::NP, consts::FG, consts::CC]
+            .iter()
+            .filter_map(|p| rule.find(p).map(|index| (index, p.len())))
+            .min();
+
+        match next {
+            Some((index, len)) => {
+                if index > 0 {
+                    each(&rule[..index], false)?;
+                }
+
+                each(&rule[index..index + len], true)?;
+                rule = &rule[index + len..];
+            }
+
+            None => {
+                each(rule, false)?;
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts;
+    use crate::country;
+    use crate::example;
+    use crate::formatter::{self, FormatOptions, Mode, SegmentKind};
+    use crate::metadata::{Format, DATABASE};
+    use crate::national_number::NationalNumber;
+    use crate::parser;
+    use crate::phone_number::{PhoneNumber, Type};
+
+    #[test]
+    fn mode_names() {
+        for (mode, names) in &[
+            (Mode::E164, ["e164", "E164"]),
+            (Mode::International, ["international", "International"]),
+            (Mode::National, ["national", "National"]),
+            (Mode::Rfc3966, ["rfc3966", "Rfc3966"]),
+        ] {
+            for name in names {
+                assert_eq!(Ok(*mode), name.parse(), "{}", name);
+            }
+
+            assert_eq!(*mode, mode.to_string().parse().unwrap());
+        }
+
+        assert_eq!("e164", Mode::E164.to_string());
+        assert_eq!(Ok(Mode::Rfc3966), "RFC3966".parse());
+        assert_eq!(Ok(Mode::International), "INTERNATIONAL".parse());
+
+        let err = "e.164".parse::<Mode>().unwrap_err();
+        assert_eq!("e.164", err.value());
+        assert_eq!(
+            "unknown formatting mode \"e.164\", expected one of: e164, international, national, rfc3966",
+            err.to_string()
+        );
+        assert!("".parse::<Mode>().is_err());
+
+        #[cfg(feature = "clap")]
+        {
+            use clap::ValueEnum;
+
+            assert_eq!(Ok(Mode::National), Mode::from_str("National", true));
+            assert_eq!(4, Mode::value_variants().len());
+        }
+    }
+
+    #[test]
+    fn us() {
+        assert_eq!(
+            "(650) 253-0000",
+            parser::parse(Some(country::US), "+1 6502530000")
+                .unwrap()
+                .format()
+                .mode(Mode::National)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+1 650-253-0000",
+            parser::parse(Some(country::US), "+1 6502530000")
+                .unwrap()
+                .format()
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
             "(800) 253-0000",
             parser::parse(Some(country::US), "+1 8002530000")
                 .unwrap()
@@ -379,4 +1155,796 @@ mod test {
                 .to_string()
         );
     }
+
+    #[test]
+    fn ar() {
+        for (input, kind, outputs) in [
+            (
+                "+54 9 11 2345 6789",
+                Type::Mobile,
+                [
+                    "+5491123456789",
+                    "+54 9 11 2345-6789",
+                    "011 15-2345-6789",
+                    "tel:+54-9-11-2345-6789",
+                ],
+            ),
+            (
+                "+54 9 3715 65 4320",
+                Type::Mobile,
+                [
+                    "+5493715654320",
+                    "+54 9 3715 65-4320",
+                    "03715 15-65-4320",
+                    "tel:+54-9-3715-65-4320",
+                ],
+            ),
+            (
+                "+54 11 8765 4321",
+                Type::FixedLine,
+                [
+                    "+541187654321",
+                    "+54 11 8765-4321",
+                    "011 8765-4321",
+                    "tel:+54-11-8765-4321",
+                ],
+            ),
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            assert_eq!(kind, number.number_type(), "{}", input);
+
+            for (mode, output) in [
+                Mode::E164,
+                Mode::International,
+                Mode::National,
+                Mode::Rfc3966,
+            ]
+            .into_iter()
+            .zip(outputs)
+            {
+                let formatted = number.format().mode(mode).to_string();
+                assert_eq!(output, formatted, "{}", input);
+
+                // Every format parses back to the same number.
+                assert_eq!(
+                    number.national(),
+                    parser::parse(Some(country::AR), &formatted)
+                        .unwrap()
+                        .national(),
+                    "{}",
+                    formatted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn non_geo() {
+        assert_eq!(
+            "+80012345678",
+            parser::parse(None, "+800 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::E164)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+800 1234 5678",
+            parser::parse(None, "+800 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
+            "800 1234 5678",
+            parser::parse(None, "+800 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::National)
+                .to_string()
+        );
+
+        assert_eq!(
+            "tel:+800-1234-5678",
+            parser::parse(None, "+800 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::Rfc3966)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+80812345678",
+            parser::parse(None, "+808 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::E164)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+808 1234 5678",
+            parser::parse(None, "+808 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
+            "808 1234 5678",
+            parser::parse(None, "+808 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::National)
+                .to_string()
+        );
+
+        assert_eq!(
+            "tel:+808-1234-5678",
+            parser::parse(None, "+808 1234 5678")
+                .unwrap()
+                .format()
+                .mode(Mode::Rfc3966)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+870773111632",
+            parser::parse(None, "+870 773 111 632")
+                .unwrap()
+                .format()
+                .mode(Mode::E164)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+870 773 111 632",
+            parser::parse(None, "+870 773 111 632")
+                .unwrap()
+                .format()
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
+            "870 773 111 632",
+            parser::parse(None, "+870 773 111 632")
+                .unwrap()
+                .format()
+                .mode(Mode::National)
+                .to_string()
+        );
+
+        assert_eq!(
+            "tel:+870-773-111-632",
+            parser::parse(None, "+870 773 111 632")
+                .unwrap()
+                .format()
+                .mode(Mode::Rfc3966)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+979123456789",
+            parser::parse(None, "+979 123456789")
+                .unwrap()
+                .format()
+                .mode(Mode::E164)
+                .to_string()
+        );
+
+        assert_eq!(
+            "+979 1 2345 6789",
+            parser::parse(None, "+979 123456789")
+                .unwrap()
+                .format()
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
+            "979 1 2345 6789",
+            parser::parse(None, "+979 123456789")
+                .unwrap()
+                .format()
+                .mode(Mode::National)
+                .to_string()
+        );
+
+        assert_eq!(
+            "tel:+979-1-2345-6789",
+            parser::parse(None, "+979 123456789")
+                .unwrap()
+                .format()
+                .mode(Mode::Rfc3966)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn leading_zero() {
+        let cases = [
+            (
+                vec![
+                    (None, "+39 06 698 83461"),
+                    (Some(country::IT), "06 698 83461"),
+                    (Some(country::DE), "0039 06 698 83461"),
+                ],
+                [
+                    "+390669883461",
+                    "+39 06 6988 3461",
+                    "06 6988 3461",
+                    "tel:+39-06-6988-3461",
+                ],
+            ),
+            (
+                vec![
+                    (None, "+225 01 23 45 67 89"),
+                    (Some(country::CI), "01 23 45 67 89"),
+                    (Some(country::FR), "00225 01 23 45 67 89"),
+                ],
+                [
+                    "+2250123456789",
+                    "+225 01 23 45 6789",
+                    "01 23 45 6789",
+                    "tel:+225-01-23-45-6789",
+                ],
+            ),
+            (
+                vec![
+                    (None, "+241 06 03 12 34"),
+                    (Some(country::GA), "06 03 12 34"),
+                    (Some(country::DE), "00241 06 03 12 34"),
+                ],
+                [
+                    "+24106031234",
+                    "+241 06 03 12 34",
+                    "06 03 12 34",
+                    "tel:+241-06-03-12-34",
+                ],
+            ),
+        ];
+
+        for (inputs, outputs) in cases {
+            for (country, input) in inputs {
+                let number = parser::parse(country, input).unwrap();
+
+                for (mode, output) in [
+                    Mode::E164,
+                    Mode::International,
+                    Mode::National,
+                    Mode::Rfc3966,
+                ]
+                .into_iter()
+                .zip(outputs)
+                {
+                    assert_eq!(output, number.format().mode(mode).to_string(), "{}", input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_country() {
+        let number = parser::parse(None, "+44 20 7031 3000").unwrap();
+
+        for (country, output) in [
+            (country::US, "011 44 20 7031 3000"),
+            (country::GB, "020 7031 3000"),
+            (country::GG, "020 7031 3000"),
+            (country::NZ, "00 44 20 7031 3000"),
+            (country::BR, "+44 20 7031 3000"),
+        ] {
+            assert_eq!(
+                output,
+                number
+                    .format()
+                    .mode(Mode::International)
+                    .calling_from(country)
+                    .to_string()
+            );
+        }
+
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+
+        assert_eq!(
+            "1 (650) 253-0000",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::CA)
+                .to_string()
+        );
+
+        // Calling from or to non-geographical entities, by passing back the
+        // region of the other number.
+        let satellite = parser::parse(None, "+870 301234567").unwrap();
+        let freephone = parser::parse(None, "+800 1234 5678").unwrap();
+
+        for (number, from, output) in [
+            (&number, &satellite, "+1 650-253-0000"),
+            (&freephone, &number, "011 800 1234 5678"),
+            (&freephone, &satellite, "+800 1234 5678"),
+        ] {
+            assert_eq!(
+                output,
+                number
+                    .format()
+                    .mode(Mode::International)
+                    .calling_from(from.country().id())
+                    .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn keep_alpha() {
+        let number = parser::parse_and_keep_raw_input(Some(country::US), "1-800-FLOWERS").unwrap();
+
+        assert_eq!(
+            "1 800-FLOWERS",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::US)
+                .keep_alpha(true)
+                .to_string()
+        );
+
+        assert_eq!(
+            "1 (800) 356-9377",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::US)
+                .to_string()
+        );
+
+        assert_eq!(
+            "00 1 800-356-9377",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::DE)
+                .keep_alpha(true)
+                .to_string()
+        );
+
+        let number =
+            parser::parse_and_keep_raw_input(Some(country::GB), "+44 800 FLOWERS").unwrap();
+
+        assert_eq!(
+            "0800 FLOWERS",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::GB)
+                .keep_alpha(true)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn e164() {
+        // The E.164 format skips the metadata, make sure it still has the
+        // digits of the international format for every example number.
+        for region in DATABASE.supported_regions() {
+            for kind in [
+                Type::FixedLine,
+                Type::Mobile,
+                Type::TollFree,
+                Type::PremiumRate,
+                Type::SharedCost,
+                Type::PersonalNumber,
+                Type::Voip,
+                Type::Pager,
+                Type::Uan,
+                Type::Voicemail,
+            ] {
+                let number = match example::example_number_for_type(region, kind) {
+                    Some(number) => number,
+                    None => continue,
+                };
+
+                let international = number.format().mode(Mode::International).to_string();
+                let digits = international
+                    .chars()
+                    .filter(|c| c.is_ascii_digit())
+                    .collect::<String>();
+
+                assert_eq!(
+                    format!("+{}", digits),
+                    number.format().mode(Mode::E164).to_string(),
+                    "{}",
+                    international
+                );
+            }
+        }
+
+        // Even without metadata for the country code.
+        let number = PhoneNumber {
+            code: country::Code {
+                value: 999,
+                source: country::Source::Plus,
+            },
+            national: NationalNumber {
+                value: 1234,
+                zeros: 2,
+            },
+            extras: None,
+        };
+        assert_eq!("+999001234", number.format().mode(Mode::E164).to_string());
+    }
+
+    #[test]
+    fn selection() {
+        // The formats used to be picked by trying all of them in order.
+        fn linear<'a>(number: &str, formats: &'a [Format]) -> Option<&'a Format> {
+            formats.iter().find(|format| {
+                let leading = format.leading_digits();
+
+                leading.is_empty()
+                    || leading
+                        .last()
+                        .unwrap()
+                        .find(number)
+                        .map(|m| m.start() == 0)
+                        .unwrap_or(false)
+                        && format
+                            .pattern()
+                            .find(number)
+                            .map(|m| m.start() == 0 && m.end() == number.len())
+                            .unwrap_or(false)
+            })
+        }
+
+        let mut checked = 0;
+
+        for code in DATABASE.supported_calling_codes() {
+            for meta in DATABASE.by_code(&code).unwrap() {
+                let desc = &meta.descriptors;
+                let examples = [
+                    Some(&desc.general),
+                    desc.fixed_line.as_ref(),
+                    desc.mobile.as_ref(),
+                    desc.toll_free.as_ref(),
+                    desc.premium_rate.as_ref(),
+                    desc.shared_cost.as_ref(),
+                    desc.personal_number.as_ref(),
+                    desc.voip.as_ref(),
+                    desc.pager.as_ref(),
+                    desc.uan.as_ref(),
+                    desc.emergency.as_ref(),
+                    desc.voicemail.as_ref(),
+                    desc.short_code.as_ref(),
+                    desc.standard_rate.as_ref(),
+                    desc.carrier.as_ref(),
+                    desc.no_international.as_ref(),
+                    desc.sms_services.as_ref(),
+                ]
+                .into_iter()
+                .flatten()
+                .filter_map(|d| d.example());
+
+                for example in examples {
+                    for formats in [meta.formats(), meta.international_formats()] {
+                        let index = |format: Option<&Format>| {
+                            format.map(|f| formats.iter().position(|o| std::ptr::eq(f, o)))
+                        };
+
+                        assert_eq!(
+                            index(linear(example, formats)),
+                            index(formatter::formatter(example, formats)),
+                            "{} {}",
+                            meta.id(),
+                            example
+                        );
+                        checked += 1;
+                    }
+                }
+            }
+        }
+
+        assert!(checked > 1000);
+    }
+
+    #[test]
+    fn options() {
+        let number = parser::parse(None, "+33 1 23 45 67 89 ext. 12").unwrap();
+        let fr = FormatOptions {
+            extension_prefix: Some(" poste "),
+            use_nbsp: true,
+            ..Default::default()
+        };
+        let de = FormatOptions {
+            group_separator: '\u{202f}',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "01 23 45 67 89 ext. 12",
+            number.format().mode(Mode::National).to_string()
+        );
+        assert_eq!(
+            "01\u{a0}23\u{a0}45\u{a0}67\u{a0}89\u{a0}poste\u{a0}12",
+            number.format().mode(Mode::National).options(fr).to_string()
+        );
+        assert_eq!(
+            "+33\u{a0}1\u{a0}23\u{a0}45\u{a0}67\u{a0}89\u{a0}poste\u{a0}12",
+            number
+                .format()
+                .mode(Mode::International)
+                .options(fr)
+                .to_string()
+        );
+        assert_eq!(
+            "+33\u{202f}1\u{202f}23\u{202f}45\u{202f}67\u{202f}89 ext. 12",
+            number
+                .format()
+                .mode(Mode::International)
+                .options(de)
+                .to_string()
+        );
+
+        // The defaults change nothing, and other punctuation is kept.
+        let number = parser::parse(Some(country::US), "+1 6502530000").unwrap();
+        assert_eq!(
+            "(650) 253-0000",
+            number
+                .format()
+                .mode(Mode::National)
+                .options(FormatOptions::default())
+                .to_string()
+        );
+        assert_eq!(
+            "(650)\u{202f}253-0000",
+            number.format().mode(Mode::National).options(de).to_string()
+        );
+
+        // Calling from the same country code or another country.
+        assert_eq!(
+            "1\u{202f}(650)\u{202f}253-0000",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::CA)
+                .options(de)
+                .to_string()
+        );
+        assert_eq!(
+            "00\u{a0}1\u{a0}650-253-0000",
+            number
+                .format()
+                .mode(Mode::International)
+                .calling_from(country::DE)
+                .options(fr)
+                .to_string()
+        );
+
+        // Only the human readable modes are affected.
+        for mode in [Mode::E164, Mode::Rfc3966] {
+            assert_eq!(
+                number.format().mode(mode).to_string(),
+                number.format().mode(mode).options(fr).to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn parts() {
+        use crate::formatter::SegmentKind::*;
+
+        fn segments(formatter: formatter::Formatter) -> Vec<(SegmentKind, String)> {
+            let parts = formatter.parts();
+            assert_eq!(formatter.to_string(), parts.to_string());
+
+            parts
+                .segments()
+                .iter()
+                .map(|s| (s.kind(), s.text().into()))
+                .collect()
+        }
+
+        fn expected(segments: &[(SegmentKind, &str)]) -> Vec<(SegmentKind, String)> {
+            segments.iter().map(|&(k, t)| (k, t.into())).collect()
+        }
+
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+        assert_eq!(
+            expected(&[
+                (Plus, "+"),
+                (CountryCode, "1"),
+                (Separator, " "),
+                (Group, "650"),
+                (Separator, "-"),
+                (Group, "253"),
+                (Separator, "-"),
+                (Group, "0000"),
+            ]),
+            segments(number.format().mode(Mode::International))
+        );
+        assert_eq!(
+            expected(&[
+                (InternationalPrefix, "00"),
+                (Separator, " "),
+                (CountryCode, "1"),
+                (Separator, " "),
+                (Group, "650"),
+                (Separator, "-"),
+                (Group, "253"),
+                (Separator, "-"),
+                (Group, "0000"),
+            ]),
+            segments(
+                number
+                    .format()
+                    .mode(Mode::International)
+                    .calling_from(country::DE)
+            )
+        );
+        assert_eq!(
+            expected(&[(Plus, "+"), (CountryCode, "1"), (Group, "6502530000")]),
+            segments(number.format().mode(Mode::E164))
+        );
+
+        let number = parser::parse(Some(country::DE), "030 123456 ext. 12").unwrap();
+        assert_eq!(
+            expected(&[
+                (NationalPrefix, "0"),
+                (Group, "30"),
+                (Separator, " "),
+                (Group, "123456"),
+                (ExtensionMarker, " ext. "),
+                (Extension, "12"),
+            ]),
+            segments(number.format().mode(Mode::National))
+        );
+        assert_eq!(
+            expected(&[
+                (Scheme, "tel:"),
+                (Plus, "+"),
+                (CountryCode, "49"),
+                (Separator, "-"),
+                (Group, "30"),
+                (Separator, "-"),
+                (Group, "123456"),
+                (ExtensionMarker, ";ext="),
+                (Extension, "12"),
+            ]),
+            segments(number.format().mode(Mode::Rfc3966))
+        );
+        assert_eq!(
+            expected(&[
+                (NationalPrefix, "0"),
+                (Group, "30"),
+                (Separator, "\u{a0}"),
+                (Group, "123456"),
+                (ExtensionMarker, "\u{a0}poste\u{a0}"),
+                (Extension, "12"),
+            ]),
+            segments(number.format().mode(Mode::National).options(FormatOptions {
+                extension_prefix: Some(" poste "),
+                use_nbsp: true,
+                ..Default::default()
+            }))
+        );
+
+        // The national prefix inside the parentheses of the first group.
+        let number = parser::parse(None, "+36 1 234 5678").unwrap();
+        assert_eq!(
+            expected(&[
+                (Separator, "("),
+                (NationalPrefix, "06"),
+                (Separator, " "),
+                (Group, "1"),
+                (Separator, ") "),
+                (Group, "234"),
+                (Separator, " "),
+                (Group, "5678"),
+            ]),
+            segments(number.format().mode(Mode::National))
+        );
+
+        // The groups are those of the pattern applied to every example.
+        for code in DATABASE.supported_calling_codes() {
+            let meta = DATABASE.by_code(&code).unwrap()[0];
+            let formats = if meta.international_formats().is_empty() {
+                meta.formats()
+            } else {
+                meta.international_formats()
+            };
+
+            // Non-geographical entities have no region.
+            for region in DATABASE.region(&code).unwrap() {
+                if region == consts::REGION_CODE_FOR_NON_GEO_ENTITY {
+                    continue;
+                }
+
+                let country = region.parse::<country::Id>().unwrap();
+                let number = example::example_number(country)
+                    .unwrap_or_else(|| panic!("no example for {:?}", country));
+
+                let national = number.national().to_string();
+                let format = match formatter::formatter(&national, formats) {
+                    Some(format) => format,
+                    None => continue,
+                };
+
+                assert_eq!(
+                    format!(
+                        "+{} {}",
+                        code,
+                        format.pattern().replace(&national, format.format())
+                    ),
+                    number.format().mode(Mode::International).to_string()
+                );
+
+                for mode in [
+                    Mode::E164,
+                    Mode::International,
+                    Mode::National,
+                    Mode::Rfc3966,
+                ] {
+                    segments(number.format().mode(mode));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn append() {
+        let numbers = [
+            "+1 650 253 0000 ext. 123",
+            "+39 02 3661 8300",
+            "+800 1234 5678",
+        ]
+        .iter()
+        .map(|input| parser::parse(None, input).unwrap())
+        .collect::<Vec<_>>();
+
+        for &mode in &[
+            Mode::E164,
+            Mode::International,
+            Mode::National,
+            Mode::Rfc3966,
+        ] {
+            let mut string = String::from("numbers:");
+            let mut bytes = b"numbers:".to_vec();
+            let mut expected = String::from("numbers:");
+
+            for number in &numbers {
+                number.format_append(mode, &mut string);
+                number.format_append_bytes(mode, &mut bytes);
+                expected.push_str(&number.format().mode(mode).to_string());
+            }
+
+            assert_eq!(expected, string);
+            assert_eq!(expected.as_bytes(), &bytes[..]);
+        }
+
+        let mut bytes = Vec::new();
+        numbers[0].format_append_bytes(Mode::E164, &mut bytes);
+        assert_eq!(b"+16502530000", &bytes[..]);
+
+        // Separators outside of ASCII are written in UTF-8.
+        let options = FormatOptions {
+            group_separator: '\u{202F}',
+            ..FormatOptions::default()
+        };
+
+        let mut bytes = Vec::new();
+        numbers[1]
+            .format()
+            .mode(Mode::International)
+            .options(options)
+            .append_bytes(&mut bytes);
+        assert_eq!(
+            "+39\u{202F}02\u{202F}3661\u{202F}8300",
+            String::from_utf8(bytes).unwrap()
+        );
+    }
 }
@@ -0,0 +1,387 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline geocoding, describing the area a phone number belongs to.
+
+use bincode::Options;
+use once_cell::sync::Lazy;
+
+use crate::consts;
+use crate::country;
+use crate::metadata::{self, Database};
+use crate::phone_number::{PhoneNumber, Type};
+use crate::prefix_map::{self, PrefixMaps};
+
+const GEOCODING: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/geocoding.bin"));
+
+/// The Google provided area descriptions, by language and country calling
+/// code.
+static DEFAULT: Lazy<PrefixMaps> = Lazy::new(|| {
+    bincode::options()
+        .with_varint_encoding()
+        .deserialize(GEOCODING)
+        .unwrap()
+});
+
+/// Get a description of the area the number belongs to in the given language,
+/// like "en" or "de", falling back to English.
+///
+/// When the number is dialled from another region than its own, or its area is
+/// unknown, the name of its country is given instead. Country names are only
+/// available in English.
+pub fn description_for_number(
+    number: &PhoneNumber,
+    lang: &str,
+    from: Option<country::Id>,
+) -> Option<String> {
+    description_for_number_with(&metadata::global(), number, lang, from)
+}
+
+/// Get a description of the area the number belongs to in the given language
+/// with the given `Database`.
+pub fn description_for_number_with(
+    database: &Database,
+    number: &PhoneNumber,
+    lang: &str,
+    from: Option<country::Id>,
+) -> Option<String> {
+    if number.number_type_with(database) == Type::Unknown {
+        return None;
+    }
+
+    // Non-geographical entities have neither an area nor a country.
+    let region = number
+        .metadata(database)
+        .map(|meta| meta.id())
+        .filter(|&id| id != consts::REGION_CODE_FOR_NON_GEO_ENTITY)?
+        .parse::<country::Id>()
+        .ok()?;
+
+    if !number.is_geographical_with(database) || from.map(|f| f != region).unwrap_or(false) {
+        return Some(country_name(region).into());
+    }
+
+    let code = number.code().value();
+    let area = prefix_map::describe(
+        &DEFAULT,
+        lang,
+        code,
+        &format!("{}{}", code, number.national()),
+    );
+
+    Some(area.unwrap_or_else(|| country_name(region)).into())
+}
+
+/// The English name of the country.
+fn country_name(country: country::Id) -> &'static str {
+    use crate::country::Id::*;
+
+    match country {
+        AC => "Ascension Island",
+        AD => "Andorra",
+        AE => "United Arab Emirates",
+        AF => "Afghanistan",
+        AG => "Antigua & Barbuda",
+        AI => "Anguilla",
+        AL => "Albania",
+        AM => "Armenia",
+        AO => "Angola",
+        AR => "Argentina",
+        AS => "American Samoa",
+        AT => "Austria",
+        AU => "Australia",
+        AW => "Aruba",
+        AX => "Åland Islands",
+        AZ => "Azerbaijan",
+        BA => "Bosnia & Herzegovina",
+        BB => "Barbados",
+        BD => "Bangladesh",
+        BE => "Belgium",
+        BF => "Burkina Faso",
+        BG => "Bulgaria",
+        BH => "Bahrain",
+        BI => "Burundi",
+        BJ => "Benin",
+        BL => "St. Barthélemy",
+        BM => "Bermuda",
+        BN => "Brunei",
+        BO => "Bolivia",
+        BQ => "Caribbean Netherlands",
+        BR => "Brazil",
+        BS => "Bahamas",
+        BT => "Bhutan",
+        BW => "Botswana",
+        BY => "Belarus",
+        BZ => "Belize",
+        CA => "Canada",
+        CC => "Cocos (Keeling) Islands",
+        CD => "Congo - Kinshasa",
+        CF => "Central African Republic",
+        CG => "Congo - Brazzaville",
+        CH => "Switzerland",
+        CI => "Côte d’Ivoire",
+        CK => "Cook Islands",
+        CL => "Chile",
+        CM => "Cameroon",
+        CN => "China",
+        CO => "Colombia",
+        CR => "Costa Rica",
+        CU => "Cuba",
+        CV => "Cape Verde",
+        CW => "Curaçao",
+        CX => "Christmas Island",
+        CY => "Cyprus",
+        CZ => "Czechia",
+        DE => "Germany",
+        DJ => "Djibouti",
+        DK => "Denmark",
+        DM => "Dominica",
+        DO => "Dominican Republic",
+        DZ => "Algeria",
+        EC => "Ecuador",
+        EE => "Estonia",
+        EG => "Egypt",
+        EH => "Western Sahara",
+        ER => "Eritrea",
+        ES => "Spain",
+        ET => "Ethiopia",
+        FI => "Finland",
+        FJ => "Fiji",
+        FK => "Falkland Islands",
+        FM => "Micronesia",
+        FO => "Faroe Islands",
+        FR => "France",
+        GA => "Gabon",
+        GB => "United Kingdom",
+        GD => "Grenada",
+        GE => "Georgia",
+        GF => "French Guiana",
+        GG => "Guernsey",
+        GH => "Ghana",
+        GI => "Gibraltar",
+        GL => "Greenland",
+        GM => "Gambia",
+        GN => "Guinea",
+        GP => "Guadeloupe",
+        GQ => "Equatorial Guinea",
+        GR => "Greece",
+        GT => "Guatemala",
+        GU => "Guam",
+        GW => "Guinea-Bissau",
+        GY => "Guyana",
+        HK => "Hong Kong",
+        HN => "Honduras",
+        HR => "Croatia",
+        HT => "Haiti",
+        HU => "Hungary",
+        ID => "Indonesia",
+        IE => "Ireland",
+        IL => "Israel",
+        IM => "Isle of Man",
+        IN => "India",
+        IO => "British Indian Ocean Territory",
+        IQ => "Iraq",
+        IR => "Iran",
+        IS => "Iceland",
+        IT => "Italy",
+        JE => "Jersey",
+        JM => "Jamaica",
+        JO => "Jordan",
+        JP => "Japan",
+        KE => "Kenya",
+        KG => "Kyrgyzstan",
+        KH => "Cambodia",
+        KI => "Kiribati",
+        KM => "Comoros",
+        KN => "St. Kitts & Nevis",
+        KP => "North Korea",
+        KR => "South Korea",
+        KW => "Kuwait",
+        KY => "Cayman Islands",
+        KZ => "Kazakhstan",
+        LA => "Laos",
+        LB => "Lebanon",
+        LC => "St. Lucia",
+        LI => "Liechtenstein",
+        LK => "Sri Lanka",
+        LR => "Liberia",
+        LS => "Lesotho",
+        LT => "Lithuania",
+        LU => "Luxembourg",
+        LV => "Latvia",
+        LY => "Libya",
+        MA => "Morocco",
+        MC => "Monaco",
+        MD => "Moldova",
+        ME => "Montenegro",
+        MF => "St. Martin",
+        MG => "Madagascar",
+        MH => "Marshall Islands",
+        MK => "North Macedonia",
+        ML => "Mali",
+        MM => "Myanmar (Burma)",
+        MN => "Mongolia",
+        MO => "Macao",
+        MP => "Northern Mariana Islands",
+        MQ => "Martinique",
+        MR => "Mauritania",
+        MS => "Montserrat",
+        MT => "Malta",
+        MU => "Mauritius",
+        MV => "Maldives",
+        MW => "Malawi",
+        MX => "Mexico",
+        MY => "Malaysia",
+        MZ => "Mozambique",
+        NA => "Namibia",
+        NC => "New Caledonia",
+        NE => "Niger",
+        NF => "Norfolk Island",
+        NG => "Nigeria",
+        NI => "Nicaragua",
+        NL => "Netherlands",
+        NO => "Norway",
+        NP => "Nepal",
+        NR => "Nauru",
+        NU => "Niue",
+        NZ => "New Zealand",
+        OM => "Oman",
+        PA => "Panama",
+        PE => "Peru",
+        PF => "French Polynesia",
+        PG => "Papua New Guinea",
+        PH => "Philippines",
+        PK => "Pakistan",
+        PL => "Poland",
+        PM => "St. Pierre & Miquelon",
+        PR => "Puerto Rico",
+        PS => "Palestinian Territories",
+        PT => "Portugal",
+        PW => "Palau",
+        PY => "Paraguay",
+        QA => "Qatar",
+        RE => "Réunion",
+        RO => "Romania",
+        RS => "Serbia",
+        RU => "Russia",
+        RW => "Rwanda",
+        SA => "Saudi Arabia",
+        SB => "Solomon Islands",
+        SC => "Seychelles",
+        SD => "Sudan",
+        SE => "Sweden",
+        SG => "Singapore",
+        SH => "St. Helena",
+        SI => "Slovenia",
+        SJ => "Svalbard & Jan Mayen",
+        SK => "Slovakia",
+        SL => "Sierra Leone",
+        SM => "San Marino",
+        SN => "Senegal",
+        SO => "Somalia",
+        SR => "Suriname",
+        SS => "South Sudan",
+        ST => "São Tomé & Príncipe",
+        SV => "El Salvador",
+        SX => "Sint Maarten",
+        SY => "Syria",
+        SZ => "Eswatini",
+        TA => "Tristan da Cunha",
+        TC => "Turks & Caicos Islands",
+        TD => "Chad",
+        TG => "Togo",
+        TH => "Thailand",
+        TJ => "Tajikistan",
+        TK => "Tokelau",
+        TL => "Timor-Leste",
+        TM => "Turkmenistan",
+        TN => "Tunisia",
+        TO => "Tonga",
+        TR => "Turkey",
+        TT => "Trinidad & Tobago",
+        TV => "Tuvalu",
+        TW => "Taiwan",
+        TZ => "Tanzania",
+        UA => "Ukraine",
+        UG => "Uganda",
+        US => "United States",
+        UY => "Uruguay",
+        UZ => "Uzbekistan",
+        VA => "Vatican City",
+        VC => "St. Vincent & Grenadines",
+        VE => "Venezuela",
+        VG => "British Virgin Islands",
+        VI => "U.S. Virgin Islands",
+        VN => "Vietnam",
+        VU => "Vanuatu",
+        WF => "Wallis & Futuna",
+        WS => "Samoa",
+        XK => "Kosovo",
+        YE => "Yemen",
+        YT => "Mayotte",
+        ZA => "South Africa",
+        ZM => "Zambia",
+        ZW => "Zimbabwe",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::geocoder;
+    use crate::parser;
+
+    #[test]
+    fn description_for_number() {
+        let number = parser::parse(None, "+49 30 901820").unwrap();
+        assert_eq!(
+            Some("Berlin".into()),
+            geocoder::description_for_number(&number, "en", None)
+        );
+        assert_eq!(
+            Some("Berlin".into()),
+            geocoder::description_for_number(&number, "en", Some(country::DE))
+        );
+        assert_eq!(
+            Some("Germany".into()),
+            geocoder::description_for_number(&number, "en", Some(country::US))
+        );
+
+        let number = parser::parse(None, "+1 650 222 0000").unwrap();
+        assert_eq!(
+            Some("California".into()),
+            geocoder::description_for_number(&number, "en", None)
+        );
+
+        // The longest prefix wins.
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+        assert_eq!(
+            Some("Mountain View, CA".into()),
+            geocoder::description_for_number(&number, "en", None)
+        );
+
+        // Mobile numbers aren't tied to an area in Germany.
+        let number = parser::parse(None, "+49 1512 3456789").unwrap();
+        assert_eq!(
+            Some("Germany".into()),
+            geocoder::description_for_number(&number, "en", None)
+        );
+
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert_eq!(None, geocoder::description_for_number(&number, "en", None));
+
+        let number = parser::parse(None, "+49 30 1").unwrap();
+        assert_eq!(None, geocoder::description_for_number(&number, "en", None));
+    }
+}
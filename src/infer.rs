@@ -0,0 +1,115 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::country;
+use crate::metadata::{self, Database, Metadata};
+use crate::parser::helper;
+
+/// Infer the regions a partially typed international number can still
+/// belong to, like "+4" or "+1 6", for example to show a flag while typing.
+///
+/// While the country code is incomplete, the regions of every country code it
+/// can become are returned in the order of the codes as typed, so "+4" lists
+/// the regions of 41 before those of 420 and 43. Once it's complete, the
+/// regions sharing it are narrowed down by the digits typed after it, with
+/// the main region first.
+///
+/// The result is empty if no number can start like this, or it's not an
+/// international number.
+pub fn infer_regions<S: AsRef<str>>(partial: S) -> Vec<country::Id> {
+    metadata::with_global(|database| infer_regions_with(database, partial)).unwrap_or_default()
+}
+
+/// Infer the regions a partially typed international number can still
+/// belong to with the given `Database`.
+pub fn infer_regions_with<S: AsRef<str>>(database: &Database, partial: S) -> Vec<country::Id> {
+    let digits = match helper::digits(partial.as_ref()) {
+        Some((true, digits)) => digits,
+        _ => return Vec::new(),
+    };
+
+    if let Some((metas, national)) = helper::split_country_code(database, &digits) {
+        return metas
+            .into_iter()
+            .filter(|meta| national.is_empty() || can_start(meta, national))
+            .filter_map(|meta| meta.id().parse().ok())
+            .collect();
+    }
+
+    let mut codes = database
+        .iter_calling_codes()
+        .map(|(code, regions)| (code.to_string(), regions))
+        .filter(|(code, _)| code.starts_with(&digits))
+        .collect::<Vec<_>>();
+
+    codes.sort_by(|a, b| a.0.cmp(&b.0));
+    codes
+        .into_iter()
+        .flat_map(|(_, regions)| regions.iter().copied())
+        .collect()
+}
+
+/// Check if numbers of the region can start with the national digits, by the
+/// leading digits of the region if it has any.
+fn can_start(meta: &Metadata, national: &str) -> bool {
+    let pattern = meta
+        .leading_digits()
+        .unwrap_or_else(|| meta.descriptors().general().national_number());
+
+    pattern.prefix_of_match(national)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country::*;
+    use crate::infer::infer_regions;
+
+    #[test]
+    fn incomplete() {
+        assert_eq!(
+            vec![RO, CH, CZ, SK, LI, AT, GB, GG, IM, JE, DK, SE, NO, SJ, PL, DE],
+            infer_regions("+4")
+        );
+        assert_eq!(vec![CZ, SK, LI], infer_regions("+42"));
+        assert_eq!(vec![TJ, TM, AZ, GE, KG, UZ], infer_regions("\u{FF0B}99"));
+    }
+
+    #[test]
+    fn complete() {
+        assert_eq!(vec![DE], infer_regions("+49"));
+        assert_eq!(vec![DE], infer_regions("+49 30 1234"));
+        assert_eq!(vec![GB, GG, IM, JE], infer_regions("+44"));
+        assert_eq!(vec![GB, GG], infer_regions("+44 1481"));
+        assert_eq!(vec![GB], infer_regions("+44 20"));
+        assert_eq!(vec![RU], infer_regions("+7 9"));
+        assert_eq!(vec![KZ], infer_regions("+7 7"));
+
+        // Narrowed down by the area codes of the NANPA countries.
+        assert_eq!(vec![US, AS, CA, GU, JM, MP, MS, TC], infer_regions("+1 6"));
+        assert_eq!(vec![US, AG, CA], infer_regions("+1 (268"));
+        assert_eq!(vec![US, AG, CA], infer_regions("+1 (268) 46"));
+        assert_eq!(vec![US, CA, DO], infer_regions("+1 800"));
+        assert_eq!(vec![US, CA], infer_regions("+1 8005"));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(infer_regions("+999").is_empty());
+        assert!(infer_regions("+0").is_empty());
+        assert!(infer_regions("+1 0").is_empty());
+        assert!(infer_regions("49").is_empty());
+        assert!(infer_regions("+4a").is_empty());
+        assert!(infer_regions("").is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::consts;
+use crate::country;
+use crate::formatter::Mode;
+use crate::matcher::{self, MatchType};
+use crate::metadata::{self, Database, Format};
+use crate::parser::helper::AsCharExt;
+use crate::phone_number::PhoneNumber;
+
+/// How strictly a phone number found in some text has to match the way it was
+/// written there, from most to least lenient.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Leniency {
+    /// The number has to be possible, but not necessarily valid.
+    Possible,
+
+    /// The number has to be valid, and any "x" in the text has to introduce its
+    /// extension or carrier code.
+    Valid,
+
+    /// The number has to be valid, and the digits of each group of its usual
+    /// formatting have to be written together, for example "650 2530000" but
+    /// not "65 02 53 00 00".
+    ///
+    /// Alternate formats commonly used in the region are accepted as well.
+    StrictGrouping,
+
+    /// The number has to be valid, and written with exactly the groups of its
+    /// usual formatting, for example "650 253 0000" but not "650 2530000".
+    ///
+    /// Alternate formats commonly used in the region are accepted as well.
+    ExactGrouping,
+}
+
+impl Leniency {
+    /// Check if the number, parsed from the given candidate text, is accepted.
+    pub fn verify(self, number: &PhoneNumber, candidate: &str) -> bool {
+        self.verify_with(&metadata::global(), number, candidate)
+    }
+
+    /// Check if the number, parsed from the given candidate text, is accepted
+    /// with the given `Database`.
+    pub fn verify_with(self, database: &Database, number: &PhoneNumber, candidate: &str) -> bool {
+        match self {
+            Leniency::Possible => number.is_possible_with(database),
+
+            Leniency::Valid => {
+                number.is_valid_with(database) && only_valid_x(database, number, candidate)
+            }
+
+            Leniency::StrictGrouping => {
+                number.is_valid_with(database)
+                    && only_valid_x(database, number, candidate)
+                    && !more_than_one_slash(number, candidate)
+                    && grouping(database, number, candidate, remain_grouped)
+            }
+
+            Leniency::ExactGrouping => {
+                number.is_valid_with(database)
+                    && only_valid_x(database, number, candidate)
+                    && !more_than_one_slash(number, candidate)
+                    && grouping(database, number, candidate, exactly_present)
+            }
+        }
+    }
+}
+
+/// Check that every "x" in the candidate introduces either the extension, or
+/// as "xx" a carrier code followed by the number.
+fn only_valid_x(database: &Database, number: &PhoneNumber, candidate: &str) -> bool {
+    let chars = candidate.char_indices().collect::<Vec<_>>();
+    let mut index = 0;
+
+    while index + 1 < chars.len() {
+        let (offset, c) = chars[index];
+
+        if c == 'x' || c == 'X' {
+            let (next_offset, next) = chars[index + 1];
+
+            if next == 'x' || next == 'X' {
+                index += 1;
+
+                if matcher::is_number_match_with(database, number, &candidate[next_offset..])
+                    != MatchType::NsnMatch
+                {
+                    return false;
+                }
+            } else if digits(&candidate[offset..])
+                != number.extension().map(|e| e.as_ref()).unwrap_or("")
+            {
+                return false;
+            }
+        }
+
+        index += 1;
+    }
+
+    true
+}
+
+/// Check if the national number is split up by more than one slash, which is
+/// usually the sign of two numbers written together.
+fn more_than_one_slash(number: &PhoneNumber, candidate: &str) -> bool {
+    let first = match candidate.find('/') {
+        Some(index) => index,
+        None => return false,
+    };
+
+    let second = match candidate[first + 1..].find('/') {
+        Some(index) => first + 1 + index,
+        None => return false,
+    };
+
+    let with_code = matches!(
+        number.country().source(),
+        country::Source::Plus | country::Source::Number
+    );
+
+    // A slash right after the country calling code doesn't count.
+    if with_code && digits(&candidate[..first]) == number.code().value().to_string() {
+        return candidate[second + 1..].contains('/');
+    }
+
+    true
+}
+
+/// Check the grouping of the candidate against the usual formatting of the
+/// number, and then against the alternate formats.
+fn grouping(
+    database: &Database,
+    number: &PhoneNumber,
+    candidate: &str,
+    check: fn(&Database, &PhoneNumber, &str, &[&str]) -> bool,
+) -> bool {
+    let candidate = candidate
+        .chars()
+        .map(|c| c.as_dec_digit().unwrap_or(c))
+        .collect::<String>();
+
+    let formatted = number.format_with(database).mode(Mode::Rfc3966).to_string();
+
+    if check(database, number, &candidate, &groups(&formatted)) {
+        return true;
+    }
+
+    let national = number.national().to_string();
+    let formats = match database.alternate_formats(number.code().value()) {
+        Some(formats) => formats,
+        None => return false,
+    };
+
+    formats
+        .iter()
+        .filter(|format| leading(format, &national))
+        .any(|format| {
+            let formatted = number
+                .format_with(database)
+                .with(format)
+                .mode(Mode::Rfc3966)
+                .to_string();
+
+            check(database, number, &candidate, &groups(&formatted))
+        })
+}
+
+/// Check if the format applies to the national number, only looking at the
+/// most specific leading digits pattern.
+fn leading(format: &Format, national: &str) -> bool {
+    format
+        .leading_digits()
+        .first()
+        .map(|pattern| {
+            pattern
+                .find(national)
+                .map(|m| m.start() == 0)
+                .unwrap_or(false)
+        })
+        .unwrap_or(true)
+}
+
+/// Get the groups of the national number from its RFC3966 formatting.
+fn groups(formatted: &str) -> Vec<&str> {
+    let end = formatted.find(';').unwrap_or(formatted.len());
+    let start = formatted.find('-').map(|i| i + 1).unwrap_or(end);
+
+    formatted[start..end].split('-').collect()
+}
+
+/// Only keep the digits of the value.
+fn digits(value: &str) -> String {
+    value.chars().filter_map(|c| c.as_dec_digit()).collect()
+}
+
+/// Check that the digits of each group are written together, in order.
+fn remain_grouped(
+    database: &Database,
+    number: &PhoneNumber,
+    candidate: &str,
+    groups: &[&str],
+) -> bool {
+    let mut from = 0;
+
+    if number.country().source() != country::Source::Default {
+        let code = number.code().value().to_string();
+        from = candidate.find(&code).map(|i| i + code.len()).unwrap_or(0);
+    }
+
+    for (i, group) in groups.iter().enumerate() {
+        from = match candidate[from..].find(group) {
+            Some(index) => from + index + group.len(),
+            None => return false,
+        };
+
+        // The national prefix may have been glued to the first group.
+        if i == 0 && from < candidate.len() {
+            let prefix = database
+                .main_by_code(number.code().value())
+                .and_then(|meta| meta.national_prefix());
+
+            if prefix.is_some() && candidate[from..].starts_with(|c: char| c.is_ascii_digit()) {
+                return number
+                    .national()
+                    .to_string()
+                    .starts_with(&candidate[from - group.len()..]);
+            }
+        }
+    }
+
+    candidate[from..].contains(number.extension().map(|e| e.as_ref()).unwrap_or(""))
+}
+
+/// Check that the candidate is written with exactly the groups.
+fn exactly_present(
+    _database: &Database,
+    number: &PhoneNumber,
+    candidate: &str,
+    groups: &[&str],
+) -> bool {
+    let mut candidate = consts::NON_DIGITS.split(candidate).collect::<Vec<_>>();
+
+    // Like the upstream implementation, ignore a trailing empty group.
+    if candidate.last() == Some(&"") {
+        candidate.pop();
+    }
+
+    if candidate.len() <= 1 {
+        return true;
+    }
+
+    let national = number.national().to_string();
+    let mut index = candidate.len() as isize - if number.extension().is_some() { 2 } else { 1 };
+
+    if index >= 0 && candidate[index as usize].contains(&national) {
+        return true;
+    }
+
+    for group in groups.iter().skip(1).rev() {
+        if index < 0 {
+            break;
+        }
+
+        if candidate[index as usize] != *group {
+            return false;
+        }
+
+        index -= 1;
+    }
+
+    index >= 0 && candidate[index as usize].ends_with(groups[0])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::leniency::Leniency;
+    use crate::metadata::DATABASE;
+    use crate::parser;
+
+    fn verify(leniency: Leniency, country: country::Id, candidate: &str) -> bool {
+        let number = parser::parse(Some(country), candidate).unwrap();
+        leniency.verify(&number, candidate)
+    }
+
+    #[test]
+    fn valid() {
+        assert!(verify(Leniency::Possible, country::US, "+1 650 253 0000"));
+        assert!(verify(Leniency::Valid, country::US, "+1 650 253 0000"));
+        assert!(verify(Leniency::Valid, country::US, "650 253 0000 x 123"));
+
+        // Possible but not valid.
+        assert!(verify(Leniency::Possible, country::US, "+1 123 456 7890"));
+        assert!(!verify(Leniency::Valid, country::US, "+1 123 456 7890"));
+    }
+
+    #[test]
+    fn strict_grouping() {
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::US,
+            "(650) 2530000"
+        ));
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::US,
+            "650-253-0000"
+        ));
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::US,
+            "+1 650 253 0000"
+        ));
+        assert!(verify(Leniency::StrictGrouping, country::US, "6502530000"));
+
+        // The area code is split up.
+        assert!(!verify(
+            Leniency::StrictGrouping,
+            country::US,
+            "65 02 53 00 00"
+        ));
+        assert!(!verify(
+            Leniency::StrictGrouping,
+            country::US,
+            "650/253/0000"
+        ));
+    }
+
+    #[test]
+    fn exact_grouping() {
+        assert!(verify(Leniency::ExactGrouping, country::US, "650 253 0000"));
+        assert!(verify(
+            Leniency::ExactGrouping,
+            country::US,
+            "650-253-0000 ext. 503"
+        ));
+        assert!(verify(
+            Leniency::ExactGrouping,
+            country::US,
+            "+1 650-253-0000"
+        ));
+        assert!(verify(Leniency::ExactGrouping, country::US, "6502530000"));
+
+        assert!(!verify(Leniency::ExactGrouping, country::US, "650 2530000"));
+        assert!(!verify(
+            Leniency::ExactGrouping,
+            country::US,
+            "65 02 53 00 00"
+        ));
+    }
+
+    #[test]
+    fn alternate_formats() {
+        let candidate = "+49 89 2 1234 567";
+        let number = parser::parse(None, candidate).unwrap();
+        assert!(!Leniency::StrictGrouping.verify_with(&DATABASE, &number, candidate));
+
+        let database = DATABASE
+            .clone()
+            .with_alternate_xml(
+                r#"
+                <phoneNumberMetadata>
+                  <territories>
+                    <territory countryCode="49">
+                      <availableFormats>
+                        <numberFormat pattern="(\d{2})(\d)(\d{4})(\d{3})">
+                          <leadingDigits>89</leadingDigits>
+                          <format>$1 $2 $3 $4</format>
+                        </numberFormat>
+                      </availableFormats>
+                    </territory>
+                  </territories>
+                </phoneNumberMetadata>
+                "#
+                .as_bytes(),
+            )
+            .unwrap();
+
+        assert!(Leniency::StrictGrouping.verify_with(&database, &number, candidate));
+        assert!(Leniency::ExactGrouping.verify_with(&database, &number, candidate));
+    }
+
+    #[cfg(feature = "alternate-formats")]
+    #[test]
+    fn bundled_alternate_formats() {
+        // Neither is the usual "89 21234567", but both are common.
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::DE,
+            "+49 89-21 23 45 67"
+        ));
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::DE,
+            "+49 89 212 345 67"
+        ));
+        assert!(verify(
+            Leniency::ExactGrouping,
+            country::DE,
+            "+49 89 212 345 67"
+        ));
+        assert!(verify(
+            Leniency::ExactGrouping,
+            country::DE,
+            "030 12 34 56 78"
+        ));
+        assert!(verify(
+            Leniency::StrictGrouping,
+            country::DE,
+            "0900-1 123 123"
+        ));
+
+        assert!(!verify(
+            Leniency::StrictGrouping,
+            country::DE,
+            "+49 8 92 12 34 56 7"
+        ));
+    }
+}
@@ -14,20 +14,17 @@
 
 #![recursion_limit = "1024"]
 
-#[macro_use]
-extern crate lazy_static;
-
 extern crate nom;
 extern crate thiserror;
 
 extern crate either;
 extern crate fnv;
 extern crate itertools;
+extern crate once_cell;
 extern crate quick_xml as xml;
 extern crate regex;
-extern crate regex_cache;
+extern crate regex_syntax;
 
-extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate bincode;
@@ -41,17 +38,22 @@ doctest!("../README.md");
 
 /// Errors for various parts of the crate.
 mod error;
-pub use crate::error::{Metadata as MetadataError, Parse as ParseError};
+pub use crate::error::{
+    Build as BuildError, Extension as ExtensionError, Metadata as MetadataError, Name as NameError,
+    Parse as ParseError,
+};
 
 /// Phone number metadata, containing patterns, formatting and other useful
 /// data about countries and phone numbers.
 pub mod metadata;
+#[cfg(feature = "test-metadata")]
+pub use crate::metadata::test_metadata;
 pub use crate::metadata::Metadata;
 
 /// Country related types.
 pub mod country;
 
-mod consts;
+pub mod consts;
 
 mod national_number;
 pub use crate::national_number::NationalNumber;
@@ -59,17 +61,93 @@ pub use crate::national_number::NationalNumber;
 mod extension;
 pub use crate::extension::Extension;
 
-mod carrier;
+pub mod carrier;
 pub use crate::carrier::Carrier;
 
+#[cfg(feature = "geocoding")]
+pub mod geocoder;
+
+#[cfg(any(feature = "carrier", feature = "geocoding"))]
+mod prefix_map;
+
 mod phone_number;
-pub use crate::phone_number::{PhoneNumber, Type};
+pub use crate::phone_number::{Components, PhoneNumber, PhoneNumberBuilder, Redacted, Type};
 
 mod parser;
-pub use crate::parser::{parse, parse_with};
+pub use crate::parser::{
+    parse, parse_and_keep_raw_input, parse_and_keep_raw_input_with, parse_e164, parse_e164_with,
+    parse_with, parse_with_candidates, parse_with_candidates_with, parse_with_policy,
+    parse_with_policy_with, CandidateFailure, CandidatesError,
+};
 
 mod formatter;
-pub use crate::formatter::{format, format_with, Formatter, Mode};
+pub use crate::formatter::{
+    format, format_with, FormatOptions, FormattedNumber, Formatter, Mode, Segment, SegmentKind,
+};
+
+mod example;
+pub use crate::example::{
+    example_number, example_number_for_type, example_number_for_type_with, example_number_with,
+    invalid_example_number, invalid_example_number_with, random_number, random_number_for_region,
+    random_number_for_region_with, random_number_for_type, random_number_for_type_with,
+    random_number_with,
+};
+
+mod matcher;
+pub use crate::matcher::{
+    is_number_match, is_number_match_with, is_number_match_with_policy,
+    is_number_match_with_policy_with, MatchType, NumberOrStr,
+};
+
+mod leniency;
+pub use crate::leniency::Leniency;
+
+mod policy;
+pub use crate::policy::{ExtensionPolicy, PolicyViolation, ValidationPolicy};
+
+mod canonical;
+pub use crate::canonical::{canonicalize, canonicalize_with, Canonical, CanonicalReport};
+
+mod dial;
+pub use crate::dial::{DialError, DialPlan};
+
+mod lookup;
+pub use crate::lookup::LookupKeys;
+
+mod infer;
+pub use crate::infer::{infer_regions, infer_regions_with};
+
+mod assess;
+pub use crate::assess::{assess, assess_with, Assessment, Verdict};
+
+mod validated;
+pub use crate::validated::{ValidatedPhoneNumber, ValidationError};
 
 mod validator;
-pub use crate::validator::{is_valid, is_valid_with, is_viable, Validation};
+pub use crate::validator::{
+    is_alpha_number, is_e164, is_e164_with, is_possible, is_possible_for_type,
+    is_possible_for_type_with, is_possible_for_type_with_reason,
+    is_possible_for_type_with_reason_with, is_possible_str, is_possible_str_with, is_possible_with,
+    is_possible_with_reason, is_possible_with_reason_with, is_valid, is_valid_for_region,
+    is_valid_for_region_with, is_valid_with, is_viable, validate, validate_with, Validation,
+    ValidationReport,
+};
+
+pub mod shortnumber;
+
+/// Serializing phone numbers as strings.
+pub mod serde;
+
+/// JavaScript bindings for WebAssembly.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Parallel validation of many numbers at once.
+#[cfg(feature = "rayon")]
+pub mod bulk;
+
+/// Conversion to and from the libphonenumber protobuf message.
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "proto")]
+pub use crate::error::Proto as ProtoError;
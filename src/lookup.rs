@@ -0,0 +1,246 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::metadata::Database;
+use crate::parser::{self, helper};
+use crate::phone_number::PhoneNumber;
+
+/// The number of trailing digits matched when nothing else is known, like
+/// for local numbers dialled without an area code.
+const SHORT_SUFFIX: usize = 7;
+
+/// The number of trailing digits matched for numbers dialled with their area
+/// code.
+const LONG_SUFFIX: usize = 10;
+
+/// The keys to find a stored phone number by, in a contact search, like
+/// Android's phone lookup.
+///
+/// A dialled or typed number, normalized to digits, can be looked up by the
+/// key it's long enough for: the E.164 format when it's in the international
+/// format, the national significant number, or the last 10 or 7 digits.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LookupKeys {
+    /// The number in the E.164 format, like "+46701234567".
+    pub e164: String,
+
+    /// The national significant number, without the national prefix, like
+    /// "701234567".
+    pub national: String,
+
+    /// The last 10 digits of the E.164 format, like "6701234567", or all of
+    /// them for shorter numbers.
+    pub last_10: String,
+
+    /// The last 7 digits of the E.164 format, like "1234567", or all of them
+    /// for shorter numbers.
+    pub last_7: String,
+}
+
+impl LookupKeys {
+    /// Iterate over the keys, from the most to the least specific one.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        [&self.e164, &self.national, &self.last_10, &self.last_7]
+            .into_iter()
+            .map(|key| key.as_str())
+    }
+}
+
+/// The lookup keys of the number, see `PhoneNumber::lookup_keys`.
+pub fn lookup_keys(number: &PhoneNumber) -> LookupKeys {
+    let national = number.national().to_string();
+    let e164 = format!("+{}{}", number.code().value(), national);
+    let suffix = |length: usize| e164[1 + (e164.len() - 1).saturating_sub(length)..].to_owned();
+
+    LookupKeys {
+        last_10: suffix(LONG_SUFFIX),
+        last_7: suffix(SHORT_SUFFIX),
+        national,
+        e164,
+    }
+}
+
+/// Check whether the dialled string reaches the number, see
+/// `PhoneNumber::matches_dialed_string`.
+pub fn matches_dialed_string_with(database: &Database, number: &PhoneNumber, dialed: &str) -> bool {
+    let (plus, digits) = match helper::digits(dialed) {
+        Some(found) if !found.1.is_empty() => found,
+        _ => return false,
+    };
+
+    let code = number.code().value().to_string();
+    let national = number.national().to_string();
+
+    if plus {
+        return digits.strip_prefix(code.as_str()) == Some(national.as_str());
+    }
+
+    // Local numbers dialled without their area code.
+    if digits.len() <= national.len() {
+        return digits.len() >= SHORT_SUFFIX.min(national.len()) && national.ends_with(&digits);
+    }
+
+    let rest = match digits.strip_suffix(national.as_str()) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    // The country calling code without the plus sign.
+    if rest == code {
+        return true;
+    }
+
+    // The national prefix, maybe with a carrier code, like "0 15" in Brazil,
+    // is what parsing the number in its regions strips.
+    let national_prefix = database
+        .by_code(&number.code().value())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|meta| meta.id().parse().ok())
+        .filter_map(|id| parser::parse_with(database, Some(id), &digits).ok())
+        .any(|parsed| {
+            parsed.code().value() == number.code().value() && parsed.national() == number.national()
+        });
+
+    if national_prefix {
+        return true;
+    }
+
+    // The international prefix of any region followed by the country calling
+    // code, like "011 46" from the US.
+    rest.strip_suffix(code.as_str())
+        .filter(|idd| !idd.is_empty())
+        .map(|idd| {
+            database.iter_regions().any(|(_, meta)| {
+                meta.international_prefix()
+                    .and_then(|prefix| prefix.find(idd))
+                    .map(|m| m.start() == 0 && m.end() == idd.len())
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::parser;
+
+    #[test]
+    fn keys() {
+        let number = parser::parse(None, "+46 70 123 45 67").unwrap();
+        let keys = number.lookup_keys();
+        assert_eq!("+46701234567", keys.e164);
+        assert_eq!("701234567", keys.national);
+        assert_eq!("6701234567", keys.last_10);
+        assert_eq!("1234567", keys.last_7);
+        assert_eq!(
+            vec!["+46701234567", "701234567", "6701234567", "1234567"],
+            keys.iter().collect::<Vec<_>>()
+        );
+
+        let number = parser::parse(Some(country::US), "(650) 253-0000").unwrap();
+        let keys = number.lookup_keys();
+        assert_eq!("+16502530000", keys.e164);
+        assert_eq!("6502530000", keys.national);
+        assert_eq!("6502530000", keys.last_10);
+        assert_eq!("2530000", keys.last_7);
+
+        // The leading zeros are part of the national significant number.
+        let number = parser::parse(Some(country::IT), "02 3661 8300").unwrap();
+        assert_eq!("0236618300", number.lookup_keys().national);
+
+        // Numbers shorter than the suffixes.
+        let number = parser::parse(None, "+882 3421234").unwrap();
+        let keys = number.lookup_keys();
+        assert_eq!("8823421234", keys.last_10);
+        assert_eq!("3421234", keys.last_7);
+
+        let number = parser::parse(None, "+49 30 1234").unwrap();
+        let keys = number.lookup_keys();
+        assert_eq!("49301234", keys.last_10);
+        assert_eq!("9301234", keys.last_7);
+    }
+
+    #[test]
+    fn sweden() {
+        let number = parser::parse(None, "+46 70 123 45 67").unwrap();
+
+        for dialed in [
+            "+46701234567",
+            "+46 70-123 45 67",
+            "0701234567",
+            "070-123 45 67",
+            "701234567",
+            "46701234567",
+            "0046701234567",
+            "011 46 70 123 45 67",
+            "1234567",
+        ] {
+            assert!(number.matches_dialed_string(dialed), "{}", dialed);
+        }
+
+        for dialed in [
+            "",
+            "+",
+            "+4670123456",
+            "+47701234567",
+            "9701234567",
+            "0701234568",
+            "234567",
+            "4646701234567",
+            "070 123 45 6a",
+        ] {
+            assert!(!number.matches_dialed_string(dialed), "{}", dialed);
+        }
+    }
+
+    #[test]
+    fn united_states() {
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+
+        for dialed in [
+            "+1 650 253 0000",
+            "(650) 253-0000",
+            "1 650 253 0000",
+            "1-650-253-0000",
+            "253-0000",
+            "00 1 650 253 0000",
+        ] {
+            assert!(number.matches_dialed_string(dialed), "{}", dialed);
+        }
+
+        for dialed in ["+1 650 253 0001", "2 650 253 0000", "53-0000"] {
+            assert!(!number.matches_dialed_string(dialed), "{}", dialed);
+        }
+    }
+
+    #[test]
+    fn brazil() {
+        let number = parser::parse(None, "+55 11 91234 5678").unwrap();
+
+        for dialed in [
+            "11 91234-5678",
+            "0 11 91234-5678",
+            // Dialled with the carrier selection code "15".
+            "0 15 11 91234-5678",
+            "5511912345678",
+        ] {
+            assert!(number.matches_dialed_string(dialed), "{}", dialed);
+        }
+
+        // The carrier code only goes after the national prefix.
+        assert!(!number.matches_dialed_string("15 11 91234-5678"));
+    }
+}
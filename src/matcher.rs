@@ -0,0 +1,564 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+use std::str;
+
+use crate::country;
+use crate::error;
+use crate::formatter::Mode;
+use crate::metadata::{self, Database};
+use crate::national_number::NationalNumber;
+use crate::parser;
+use crate::phone_number::PhoneNumber;
+use crate::policy::ExtensionPolicy;
+
+/// The most digits of a national number, with any number of leading zeros.
+const NATIONAL_DIGITS: usize = u8::MAX as usize + 20;
+
+/// How closely two phone numbers match, from worst to best.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MatchType {
+    /// Either number could not be parsed, or its extension didn't pass the
+    /// `ExtensionPolicy`.
+    NotANumber,
+
+    /// The numbers are different.
+    NoMatch,
+
+    /// One national significant number is a suffix of the other, for example
+    /// "345 6789" and "+1 345 6789".
+    ShortNsnMatch,
+
+    /// The national significant numbers are the same, but at least one of the
+    /// numbers has no country calling code, for example "+1 345 657 1234" and
+    /// "345 657 1234", or only one of them has an extension, for example
+    /// "+1 345 657 1234" and "+1 345 657 1234 ext. 12".
+    NsnMatch,
+
+    /// The country calling code, national significant number and extension
+    /// are the same.
+    ExactMatch,
+}
+
+/// Either an already parsed phone number or a string to parse.
+#[derive(Copy, Clone, Debug)]
+pub enum NumberOrStr<'a> {
+    /// An already parsed phone number.
+    Number(&'a PhoneNumber),
+
+    /// A string to parse without a default country.
+    Str(&'a str),
+}
+
+impl<'a> From<&'a PhoneNumber> for NumberOrStr<'a> {
+    fn from(number: &'a PhoneNumber) -> Self {
+        NumberOrStr::Number(number)
+    }
+}
+
+impl<'a> From<&'a str> for NumberOrStr<'a> {
+    fn from(string: &'a str) -> Self {
+        NumberOrStr::Str(string)
+    }
+}
+
+impl<'a> From<&'a String> for NumberOrStr<'a> {
+    fn from(string: &'a String) -> Self {
+        NumberOrStr::Str(string)
+    }
+}
+
+/// Check how closely two phone numbers match.
+///
+/// Strings are parsed without a default country; when only one of the numbers
+/// has a country calling code, the other one is parsed in its region.
+pub fn is_number_match<'a, 'b, A, B>(a: A, b: B) -> MatchType
+where
+    A: Into<NumberOrStr<'a>>,
+    B: Into<NumberOrStr<'b>>,
+{
+    is_number_match_with(&metadata::global(), a, b)
+}
+
+/// Check how closely two phone numbers match with the given `Database`.
+pub fn is_number_match_with<'a, 'b, A, B>(database: &Database, a: A, b: B) -> MatchType
+where
+    A: Into<NumberOrStr<'a>>,
+    B: Into<NumberOrStr<'b>>,
+{
+    helper(database, ExtensionPolicy::Ignore, a.into(), b.into())
+}
+
+/// Check how closely two phone numbers match, when both their extensions pass
+/// the `ExtensionPolicy`, otherwise they're `MatchType::NotANumber`.
+pub fn is_number_match_with_policy<'a, 'b, A, B>(a: A, b: B, policy: ExtensionPolicy) -> MatchType
+where
+    A: Into<NumberOrStr<'a>>,
+    B: Into<NumberOrStr<'b>>,
+{
+    is_number_match_with_policy_with(&metadata::global(), a, b, policy)
+}
+
+/// Check how closely two phone numbers match with the given `Database`, when
+/// both their extensions pass the `ExtensionPolicy`.
+pub fn is_number_match_with_policy_with<'a, 'b, A, B>(
+    database: &Database,
+    a: A,
+    b: B,
+    policy: ExtensionPolicy,
+) -> MatchType
+where
+    A: Into<NumberOrStr<'a>>,
+    B: Into<NumberOrStr<'b>>,
+{
+    helper(database, policy, a.into(), b.into())
+}
+
+fn helper(
+    database: &Database,
+    policy: ExtensionPolicy,
+    a: NumberOrStr,
+    b: NumberOrStr,
+) -> MatchType {
+    match (a, b) {
+        (NumberOrStr::Number(a), NumberOrStr::Number(b)) => numbers(database, policy, a, b),
+
+        (NumberOrStr::Number(a), NumberOrStr::Str(b))
+        | (NumberOrStr::Str(b), NumberOrStr::Number(a)) => {
+            number_and_string(database, policy, a, b)
+        }
+
+        (NumberOrStr::Str(a), NumberOrStr::Str(b)) => strings(database, policy, a, b),
+    }
+}
+
+fn strings(database: &Database, policy: ExtensionPolicy, a: &str, b: &str) -> MatchType {
+    match parser::parse_with(database, None, a) {
+        Ok(a) => return number_and_string(database, policy, &a, b),
+        Err(error::Parse::InvalidCountryCode) => (),
+        Err(_) => return MatchType::NotANumber,
+    }
+
+    match parser::parse_with(database, None, b) {
+        Ok(b) => return number_and_string(database, policy, &b, a),
+        Err(error::Parse::InvalidCountryCode) => (),
+        Err(_) => return MatchType::NotANumber,
+    }
+
+    match (
+        parser::parse_unchecked_with(database, a),
+        parser::parse_unchecked_with(database, b),
+    ) {
+        (Ok(a), Ok(b)) => numbers(database, policy, &a, &b),
+        _ => MatchType::NotANumber,
+    }
+}
+
+fn number_and_string(
+    database: &Database,
+    policy: ExtensionPolicy,
+    a: &PhoneNumber,
+    b: &str,
+) -> MatchType {
+    match parser::parse_with(database, None, b) {
+        Ok(b) => return numbers(database, policy, a, &b),
+        Err(error::Parse::InvalidCountryCode) => (),
+        Err(_) => return MatchType::NotANumber,
+    }
+
+    // The string has no country calling code, so parse it in the region of
+    // the number, which can at best make it a national match.
+    let region = database
+        .region(&a.code().value())
+        .and_then(|r| r.into_iter().next())
+        .and_then(|r| r.parse::<country::Id>().ok());
+
+    if let Some(region) = region {
+        match parser::parse_with(database, Some(region), b) {
+            Ok(b) => match numbers(database, policy, a, &b) {
+                MatchType::ExactMatch => MatchType::NsnMatch,
+                other => other,
+            },
+
+            Err(_) => MatchType::NotANumber,
+        }
+    } else {
+        match parser::parse_unchecked_with(database, b) {
+            Ok(b) => numbers(database, policy, a, &b),
+            Err(_) => MatchType::NotANumber,
+        }
+    }
+}
+
+fn numbers(
+    database: &Database,
+    policy: ExtensionPolicy,
+    a: &PhoneNumber,
+    b: &PhoneNumber,
+) -> MatchType {
+    if policy.check(a.extension()).is_err() || policy.check(b.extension()).is_err() {
+        return MatchType::NotANumber;
+    }
+
+    if let (Some(x), Some(y)) = (a.extension(), b.extension()) {
+        if x != y {
+            return MatchType::NoMatch;
+        }
+    }
+
+    let a_code = a.code().value();
+    let b_code = b.code().value();
+
+    let national = a.national() == b.national()
+        || a_code == b_code
+            && matches!(
+                (current(database, a), current(database, b)),
+                (Some(x), Some(y)) if x == y
+            );
+    // Different extensions were ruled out above, an extension on only one
+    // side is taken as unknown on the other one.
+    let same = national && a.extension() == b.extension();
+
+    if a_code != 0 && b_code != 0 {
+        if a_code == b_code && same {
+            MatchType::ExactMatch
+        } else if a_code == b_code && national {
+            MatchType::NsnMatch
+        } else if a_code == b_code && is_suffix(a, b) {
+            MatchType::ShortNsnMatch
+        } else {
+            MatchType::NoMatch
+        }
+    } else if national {
+        MatchType::NsnMatch
+    } else if is_suffix(a, b) {
+        MatchType::ShortNsnMatch
+    } else {
+        MatchType::NoMatch
+    }
+}
+
+/// The national number as it's parsed now, numbers stored before a dialling
+/// plan change may still have digits since dropped, like the "1" Mexican
+/// mobile numbers had after the country code.
+fn current(database: &Database, number: &PhoneNumber) -> Option<NationalNumber> {
+    if number.code().value() == 0 {
+        return None;
+    }
+
+    // Only a national prefix for parsing at the start of the national number
+    // can change it, so there's nothing to parse again for most numbers.
+    let mut buffer = [0u8; NATIONAL_DIGITS];
+    let national = {
+        let mut cursor = &mut buffer[..];
+        write!(cursor, "{}", number.national()).unwrap();
+        let length = NATIONAL_DIGITS - cursor.len();
+
+        str::from_utf8(&buffer[..length]).unwrap()
+    };
+
+    let changes = database
+        .iter_by_code(number.code().value())
+        .filter_map(|meta| meta.national_prefix_for_parsing())
+        .any(|prefix| prefix.find(national).map_or(false, |m| m.start() == 0));
+
+    if !changes {
+        return Some(*number.national());
+    }
+
+    let e164 = number.format_with(database).mode(Mode::E164).to_string();
+    parser::parse_with(database, None, &e164)
+        .ok()
+        .map(|number| *number.national())
+}
+
+/// Check if either national number is a suffix of the other one, ignoring
+/// leading zeros.
+fn is_suffix(a: &PhoneNumber, b: &PhoneNumber) -> bool {
+    // Whether the decimal digits of `y` are the last ones of `x`.
+    fn ends_with(x: u64, y: u64) -> bool {
+        let mut modulus = 10u64;
+
+        while modulus <= y {
+            modulus = match modulus.checked_mul(10) {
+                Some(modulus) => modulus,
+                None => return x == y,
+            };
+        }
+
+        x % modulus == y
+    }
+
+    let a = a.national().value();
+    let b = b.national().value();
+
+    ends_with(a, b) || ends_with(b, a)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::matcher::{self, MatchType};
+    use crate::national_number::NationalNumber;
+    use crate::parser;
+    use crate::phone_number::PhoneNumber;
+    use crate::policy::ExtensionPolicy;
+
+    #[test]
+    fn exact() {
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+64 3 331 6005", "+64 03 331 6005")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+800 1234 5678", "+80012345678")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+64 03 331-6005", "+64 03331 6005")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+643 331-6005", "+64033316005")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+64 3 331-6005", "tel:+64-3-331-6005;isub=123")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+64 3 331-6005 extn 1234", "+6433316005#1234")
+        );
+
+        let number = parser::parse(None, "+64 3 331 6005").unwrap();
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match(&number, "+6433316005")
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match(&number, &number)
+        );
+    }
+
+    #[test]
+    fn legacy() {
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match("+52 1 55 1234 5678", "+52 55 1234 5678")
+        );
+
+        // A Mexican mobile number stored before the "1" after the country
+        // code was dropped.
+        let stored = PhoneNumber {
+            code: country::Code {
+                value: 52,
+                source: country::Source::Plus,
+            },
+            national: NationalNumber {
+                value: 15512345678,
+                zeros: 0,
+            },
+            extras: None,
+        };
+        let number = parser::parse(None, "+52 55 1234 5678").unwrap();
+
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match(&stored, &number)
+        );
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match(&stored, "+52 55 1234 5678")
+        );
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match(&stored, "55 1234 5678")
+        );
+        assert_eq!(
+            MatchType::NoMatch,
+            matcher::is_number_match(&stored, "+52 55 1234 5679")
+        );
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(
+            MatchType::NoMatch,
+            matcher::is_number_match("+64 3 331 6005", "+64 3 331 6006")
+        );
+        assert_eq!(
+            MatchType::NoMatch,
+            matcher::is_number_match("+800 1234 5678", "+1 800 1234 5678")
+        );
+        assert_eq!(
+            MatchType::NoMatch,
+            matcher::is_number_match("+64 3 331-6005 extn 1234", "+6433316005#1235")
+        );
+        assert_eq!(
+            MatchType::NoMatch,
+            matcher::is_number_match("03 331 6005", "03 331 6006")
+        );
+    }
+
+    #[test]
+    fn nsn() {
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("+64 3 331-6005", "03 331 6005")
+        );
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("+1 650 555 0100", "650 555 0100")
+        );
+
+        let number = parser::parse(Some(country::US), "650 555 0100").unwrap();
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match(&number, "650 555 0100")
+        );
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("6505550100x1234", "650 555 0100x1234")
+        );
+
+        // An extension on only one side still leaves the numbers matching.
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("+1 650 555 0100", "6505550100x1234")
+        );
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("+1 650 555 0100", "+1 650 555 0100 ext. 1234")
+        );
+        assert_eq!(
+            MatchType::NsnMatch,
+            matcher::is_number_match("650 555 0100 ext. 1234", "650 555 0100")
+        );
+    }
+
+    #[test]
+    fn short_nsn() {
+        assert_eq!(
+            MatchType::ShortNsnMatch,
+            matcher::is_number_match("+64 3 331-6005", "331 6005")
+        );
+        assert_eq!(
+            MatchType::ShortNsnMatch,
+            matcher::is_number_match("3 331-6005", "331 6005")
+        );
+        assert_eq!(
+            MatchType::ShortNsnMatch,
+            matcher::is_number_match("+1 234 345 6789", "345 6789")
+        );
+
+        // The leading zero is part of the national number.
+        assert_eq!(
+            MatchType::ShortNsnMatch,
+            matcher::is_number_match("3 331-6005", "03 331 6005")
+        );
+
+        // With an extension on only one side.
+        assert_eq!(
+            MatchType::ShortNsnMatch,
+            matcher::is_number_match("+1 234 345 6789 ext. 12", "345 6789")
+        );
+    }
+
+    #[test]
+    fn not_a_number() {
+        assert_eq!(
+            MatchType::NotANumber,
+            matcher::is_number_match("+64 3 331-6005", "abc")
+        );
+        assert_eq!(
+            MatchType::NotANumber,
+            matcher::is_number_match("abc", "abc")
+        );
+    }
+
+    #[test]
+    fn policy() {
+        let mut number = parser::parse(None, "+1 650 253 0000").unwrap();
+        number.set_extension("1234567890123456789012345").unwrap();
+        let same = number.clone();
+        let plain = parser::parse(None, "+1 650 253 0000").unwrap();
+
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match(&number, &same)
+        );
+
+        for (policy, exact, nsn) in [
+            (
+                ExtensionPolicy::Ignore,
+                MatchType::ExactMatch,
+                MatchType::NsnMatch,
+            ),
+            (
+                ExtensionPolicy::RequireAbsent,
+                MatchType::NotANumber,
+                MatchType::NotANumber,
+            ),
+            (
+                ExtensionPolicy::ValidateLength(6),
+                MatchType::NotANumber,
+                MatchType::NotANumber,
+            ),
+            (
+                ExtensionPolicy::ValidateLength(25),
+                MatchType::ExactMatch,
+                MatchType::NsnMatch,
+            ),
+        ] {
+            assert_eq!(
+                exact,
+                matcher::is_number_match_with_policy(&number, &same, policy),
+                "{:?}",
+                policy
+            );
+            assert_eq!(
+                nsn,
+                matcher::is_number_match_with_policy(&plain, &number, policy),
+                "{:?}",
+                policy
+            );
+            assert_eq!(
+                nsn,
+                matcher::is_number_match_with_policy(&number, "+1 650 253 0000", policy),
+                "{:?}",
+                policy
+            );
+
+            // Numbers without an extension pass any policy.
+            assert_eq!(
+                MatchType::ExactMatch,
+                matcher::is_number_match_with_policy(&plain, "+1 650 253 0000", policy),
+                "{:?}",
+                policy
+            );
+        }
+
+        assert_eq!(
+            MatchType::NotANumber,
+            matcher::is_number_match_with_policy(
+                "+1 650 253 0000 ext. 1234",
+                "650 253 0000",
+                ExtensionPolicy::RequireAbsent
+            )
+        );
+    }
+}
@@ -13,36 +13,181 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::fs::File;
 use std::hash::Hash;
-use std::io::{BufReader, Cursor};
+use std::io::{BufReader, Cursor, Read};
+use std::mem;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
 
-use bincode;
-use bincode::Options;
 use fnv::FnvHashMap;
-use regex_cache::{CachedRegex, CachedRegexBuilder, RegexCache};
+use once_cell::sync::{Lazy, OnceCell};
 
+use crate::consts;
+use crate::country;
 use crate::error;
-use crate::metadata::loader;
+use crate::metadata::{loader, LazyRegex};
+use crate::phone_number::Type;
 
 const DATABASE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/database.bin"));
 
-lazy_static! {
-    /// The Google provided metadata database, used as default.
-    pub static ref DEFAULT: Database =
-        Database::from(bincode::options()
-        .with_varint_encoding().deserialize(DATABASE).unwrap()).unwrap();
+/// The version of the upstream libphonenumber metadata bundled with the crate.
+pub const BUNDLED_VERSION: &str = env!("PHONENUMBER_METADATA_VERSION");
+
+/// The version of databases not loaded from the binary format, since the XML
+/// metadata doesn't carry one.
+const UNKNOWN_VERSION: &str = "unknown";
+
+/// The types of numbers regions can have.
+const TYPES: &[Type] = &[
+    Type::FixedLine,
+    Type::Mobile,
+    Type::TollFree,
+    Type::PremiumRate,
+    Type::SharedCost,
+    Type::PersonalNumber,
+    Type::Voip,
+    Type::Pager,
+    Type::Uan,
+    Type::Voicemail,
+];
+
+/// The embedded metadata, decoded once for both `DEFAULT` and the global
+/// database it starts out as, and kept loaded after `set_global`.
+static EMBEDDED: Lazy<Result<Arc<Database>, error::Metadata>> = Lazy::new(|| {
+    Database::from_bytes(DATABASE)
+        .map(Arc::new)
+        .map_err(|err| error::Metadata::Corrupt(err.to_string()))
+});
+
+/// The Google provided metadata database, used as default.
+///
+/// It shares the metadata with the global database as long as that wasn't
+/// replaced with `set_global`, and is empty if the embedded metadata is
+/// corrupt, see `try_global` for the error.
+pub static DEFAULT: Lazy<Database> = Lazy::new(|| {
+    EMBEDDED
+        .as_ref()
+        .map_or_else(|_| Database::empty(), |database| Database::clone(database))
+});
+
+/// The database used by the functions not taking one explicitly.
+static GLOBAL: OnceCell<RwLock<Arc<Database>>> = OnceCell::new();
+
+/// Bumped every time the global database is replaced, so threads know when
+/// their copy is out of date.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The global database as last seen by this thread, with its generation,
+    /// so getting it doesn't touch the lock shared by all threads.
+    static LOCAL: RefCell<Option<(usize, Arc<Database>)>> = const { RefCell::new(None) };
+}
+
+/// Get the database used by the functions not taking one explicitly, which is
+/// the Google provided one unless replaced with `set_global`.
+///
+/// If the embedded metadata is corrupt and nothing replaced it, this is an
+/// empty database, so the functions using it know no numbers instead of
+/// panicking, see `try_global` for the error.
+pub fn global() -> Arc<Database> {
+    try_global().unwrap_or_else(|_| Arc::new(Database::empty()))
+}
+
+/// Get the database used by the functions not taking one explicitly, failing
+/// if the embedded metadata is corrupt.
+pub fn try_global() -> Result<Arc<Database>, error::Metadata> {
+    with_global(Arc::clone)
+}
+
+/// Call the function with the database used by the functions not taking one
+/// explicitly, without taking a reference to it.
+pub(crate) fn with_global<T, F>(body: F) -> Result<T, error::Metadata>
+where
+    F: FnOnce(&Arc<Database>) -> T,
+{
+    let mut body = Some(body);
+    let result = LOCAL.try_with(|local| {
+        let generation = GENERATION.load(Ordering::Acquire);
+
+        if !matches!(&*local.borrow(), Some((seen, _)) if *seen == generation) {
+            let database = shared()?;
+
+            match local.try_borrow_mut() {
+                Ok(mut local) => *local = Some((generation, database)),
+                // Called again from the body, which keeps the previous one.
+                Err(_) => return Ok(body.take().unwrap()(&database)),
+            }
+        }
+
+        let local = local.borrow();
+        Ok(body.take().unwrap()(&local.as_ref().unwrap().1))
+    });
+
+    match result {
+        Ok(result) => result,
+        // The thread is going away, so there's nothing to keep.
+        Err(_) => shared().map(|database| body.take().unwrap()(&database)),
+    }
+}
+
+/// Get the global database from the lock shared by all threads.
+fn shared() -> Result<Arc<Database>, error::Metadata> {
+    let global = GLOBAL.get_or_try_init(|| {
+        EMBEDDED
+            .as_ref()
+            .map(|database| RwLock::new(database.clone()))
+            .map_err(Clone::clone)
+    })?;
+
+    Ok(global
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone())
+}
+
+/// Replace the database used by the functions not taking one explicitly, for
+/// example after updating the metadata at runtime.
+///
+/// The swap is atomic, calls already in progress keep using the previous
+/// database until they're done, and threads which used it keep it until their
+/// next call.
+pub fn set_global(database: Database) {
+    // The embedded metadata doesn't have to be loaded when it's never used.
+    let lock = match GLOBAL.set(RwLock::new(Arc::new(database))) {
+        Ok(()) => {
+            GENERATION.fetch_add(1, Ordering::Release);
+            return;
+        }
+
+        Err(lock) => lock,
+    };
+
+    // Drop the previous database outside of the lock.
+    let _previous = mem::replace(
+        &mut *GLOBAL
+            .get()
+            .unwrap()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner),
+        lock.into_inner().unwrap_or_else(PoisonError::into_inner),
+    );
+
+    GENERATION.fetch_add(1, Ordering::Release);
 }
 
 /// Representation of a database of metadata for phone number.
 #[derive(Clone, Debug)]
 pub struct Database {
-    cache: Arc<Mutex<RegexCache>>,
+    version: String,
     by_id: FnvHashMap<String, Arc<super::Metadata>>,
     by_code: FnvHashMap<u16, Vec<Arc<super::Metadata>>>,
     regions: FnvHashMap<u16, Vec<String>>,
+    countries: FnvHashMap<u16, Vec<country::Id>>,
+    short: FnvHashMap<String, Arc<super::Metadata>>,
+    alternate: FnvHashMap<u16, Vec<super::Format>>,
 }
 
 impl Database {
@@ -56,150 +201,213 @@ impl Database {
         Database::from(loader::load(Cursor::new(content.as_ref()))?)
     }
 
-    /// Create a database from a loaded database.
-    pub fn from(meta: Vec<loader::Metadata>) -> Result<Self, error::LoadMetadata> {
-        fn tranpose<T, E>(value: Option<Result<T, E>>) -> Result<Option<T>, E> {
-            match value {
-                None => Ok(None),
+    /// Load a database from upstream libphonenumber XML metadata, like
+    /// `PhoneNumberMetadata.xml`, read from the given reader.
+    #[deprecated(note = "use `Database::from_reader` instead")]
+    pub fn from_xml<R: Read>(reader: R) -> Result<Self, error::LoadMetadata> {
+        Database::from_reader(reader)
+    }
 
-                Some(Ok(value)) => Ok(Some(value)),
+    /// Load a database from upstream libphonenumber XML metadata, like
+    /// `PhoneNumberMetadata.xml`, streaming it from the given reader instead
+    /// of reading it all in memory first.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, error::LoadMetadata> {
+        Database::from(loader::load(BufReader::new(reader))?)
+    }
 
-                Some(Err(err)) => Err(err),
+    /// Load a database from upstream XML metadata streamed from the given
+    /// reader, keeping only the given regions.
+    ///
+    /// Other territories, including the non-geographical entities, are skipped
+    /// without being parsed or having their patterns compiled, numbers from
+    /// them are then unsupported.
+    pub fn from_reader_filtered<R: Read>(
+        reader: R,
+        regions: &[country::Id],
+    ) -> Result<Self, error::LoadMetadata> {
+        Database::from_reader_filtered_with_warnings(reader, regions, |_| ())
+    }
+
+    /// Load a database from upstream XML metadata streamed from the given
+    /// reader, keeping only the given regions, and calling `warn` with the ID
+    /// of every territory which isn't a known region.
+    ///
+    /// Unknown territories are skipped instead of failing the load.
+    pub fn from_reader_filtered_with_warnings<R: Read, F: FnMut(&str)>(
+        reader: R,
+        regions: &[country::Id],
+        mut warn: F,
+    ) -> Result<Self, error::LoadMetadata> {
+        let meta = loader::load_filtered(BufReader::new(reader), |id| {
+            match id.parse::<country::Id>() {
+                Ok(id) => regions.contains(&id),
+                Err(_) if id == consts::REGION_CODE_FOR_NON_GEO_ENTITY => false,
+                Err(_) => {
+                    warn(id);
+                    false
+                }
             }
+        })?;
+
+        Database::from(meta)
+    }
+
+    /// Load a database from the versioned binary encoding produced by
+    /// `Database::to_bytes`.
+    ///
+    /// The regular expressions are only compiled on first use, since they come
+    /// from an already loaded database.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, error::LoadMetadata> {
+        let bundle = loader::decode(bytes)?;
+        let interner = Interner::new(false);
+        let mut database = Database::build(bundle.metadata, &interner)?
+            .with_short_metadata(Database::metadata(bundle.short, &interner)?);
+        database.alternate = Database::alternate(bundle.alternate, &interner)?;
+        database.version = bundle.version;
+
+        Ok(database)
+    }
+
+    /// Encode the database in a compact versioned binary format, which can be
+    /// loaded back with `Database::from_bytes`.
+    ///
+    /// The encoding is stable, encoding the same database twice gives the same
+    /// bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, error::LoadMetadata> {
+        fn regex(value: &LazyRegex) -> String {
+            value.as_str().into()
         }
 
-        let cache = Arc::new(Mutex::new(RegexCache::new(100)));
-        let regex = |value: String| -> Result<CachedRegex, error::LoadMetadata> {
-            Ok(CachedRegexBuilder::new(cache.clone(), &value)
-                .ignore_whitespace(true)
-                .build()?)
-        };
+        fn descriptor(desc: &super::Descriptor) -> loader::Descriptor {
+            let national = desc.national_number.as_str();
 
-        let descriptor =
-            |desc: loader::Descriptor| -> Result<super::Descriptor, error::LoadMetadata> {
-                desc.national_number.as_ref().unwrap();
-                desc.national_number.as_ref().unwrap();
-
-                Ok(super::Descriptor {
-                    national_number: desc
-                        .national_number
-                        .ok_or_else(|| {
-                            error::LoadMetadata::from(error::Metadata::MissingValue {
-                                phase: "descriptor".into(),
-                                name: "national_number".into(),
-                            })
-                        })
-                        .and_then(regex)?,
-
-                    possible_length: desc.possible_length,
-                    possible_local_length: desc.possible_local_length,
-                    example: desc.example,
-                })
-            };
-
-        let format = |format: loader::Format| -> Result<super::Format, error::LoadMetadata> {
-            Ok(super::Format {
-                pattern: format
-                    .pattern
-                    .ok_or_else(|| {
-                        error::LoadMetadata::from(error::Metadata::MissingValue {
-                            phase: "format".into(),
-                            name: "pattern".into(),
-                        })
-                    })
-                    .and_then(regex)?,
-
-                format: format.format.ok_or_else(|| {
-                    error::LoadMetadata::from(error::Metadata::MissingValue {
-                        phase: "format".into(),
-                        name: "format".into(),
-                    })
-                })?,
-
-                leading_digits: format
-                    .leading_digits
-                    .into_iter()
-                    .map(&regex)
-                    .collect::<Result<_, _>>()?,
+            loader::Descriptor {
+                // Undo the anchoring added when loading.
+                national_number: Some(
+                    national
+                        .strip_prefix("^(?:")
+                        .and_then(|n| n.strip_suffix(")$"))
+                        .unwrap_or(national)
+                        .into(),
+                ),
+                possible_length: desc.possible_length.clone(),
+                possible_local_length: desc.possible_local_length.clone(),
+                example: desc.example.clone(),
+            }
+        }
 
-                national_prefix: format.national_prefix_formatting_rule,
-                national_prefix_optional: format.national_prefix_optional_when_formatting,
+        fn format(format: &super::Format) -> loader::Format {
+            loader::Format {
+                pattern: Some(regex(&format.pattern)),
+                format: Some(format.format.clone()),
+                leading_digits: format.leading_digits.iter().map(regex).collect(),
+                national_prefix_formatting_rule: format.national_prefix.clone(),
+                national_prefix_optional_when_formatting: format.national_prefix_optional,
+                domestic_carrier: format.domestic_carrier.clone(),
+            }
+        }
 
-                domestic_carrier: format.domestic_carrier,
-            })
-        };
+        fn metadata(meta: &super::Metadata) -> loader::Metadata {
+            let desc = &meta.descriptors;
 
-        let metadata = |meta: loader::Metadata| -> Result<super::Metadata, error::LoadMetadata> {
-            Ok(super::Metadata {
-                descriptors: super::Descriptors {
-                    general: descriptor(meta.general.ok_or_else(|| {
-                        error::LoadMetadata::from(error::Metadata::MissingValue {
-                            phase: "metadata".into(),
-                            name: "generalDesc".into(),
-                        })
-                    })?)?,
-
-                    fixed_line: tranpose(meta.fixed_line.map(descriptor))?,
-                    mobile: tranpose(meta.mobile.map(descriptor))?,
-                    toll_free: tranpose(meta.toll_free.map(descriptor))?,
-                    premium_rate: tranpose(meta.premium_rate.map(descriptor))?,
-                    shared_cost: tranpose(meta.shared_cost.map(descriptor))?,
-                    personal_number: tranpose(meta.personal_number.map(descriptor))?,
-                    voip: tranpose(meta.voip.map(descriptor))?,
-                    pager: tranpose(meta.pager.map(descriptor))?,
-                    uan: tranpose(meta.uan.map(descriptor))?,
-                    emergency: tranpose(meta.emergency.map(descriptor))?,
-                    voicemail: tranpose(meta.voicemail.map(descriptor))?,
-                    short_code: tranpose(meta.short_code.map(descriptor))?,
-                    standard_rate: tranpose(meta.standard_rate.map(descriptor))?,
-                    carrier: tranpose(meta.carrier.map(descriptor))?,
-                    no_international: tranpose(meta.no_international.map(descriptor))?,
-                },
-
-                id: meta.id.ok_or_else(|| {
-                    error::LoadMetadata::from(error::Metadata::MissingValue {
-                        phase: "metadata".into(),
-                        name: "id".into(),
-                    })
-                })?,
-
-                country_code: meta.country_code.ok_or_else(|| {
-                    error::LoadMetadata::from(error::Metadata::MissingValue {
-                        phase: "metadata".into(),
-                        name: "countryCode".into(),
-                    })
-                })?,
-
-                international_prefix: tranpose(meta.international_prefix.map(regex))?,
-                preferred_international_prefix: meta.preferred_international_prefix,
-                national_prefix: meta.national_prefix,
-                preferred_extension_prefix: meta.preferred_extension_prefix,
-                national_prefix_for_parsing: tranpose(meta.national_prefix_for_parsing.map(regex))?,
-                national_prefix_transform_rule: meta.national_prefix_transform_rule,
-
-                formats: meta
-                    .formats
-                    .into_iter()
-                    .map(&format)
-                    .collect::<Result<_, _>>()?,
-                international_formats: meta
-                    .international_formats
-                    .into_iter()
-                    .map(&format)
-                    .collect::<Result<_, _>>()?,
+            loader::Metadata {
+                general: Some(descriptor(&desc.general)),
+                fixed_line: desc.fixed_line.as_ref().map(descriptor),
+                mobile: desc.mobile.as_ref().map(descriptor),
+                toll_free: desc.toll_free.as_ref().map(descriptor),
+                premium_rate: desc.premium_rate.as_ref().map(descriptor),
+                shared_cost: desc.shared_cost.as_ref().map(descriptor),
+                personal_number: desc.personal_number.as_ref().map(descriptor),
+                voip: desc.voip.as_ref().map(descriptor),
+                pager: desc.pager.as_ref().map(descriptor),
+                uan: desc.uan.as_ref().map(descriptor),
+                emergency: desc.emergency.as_ref().map(descriptor),
+                voicemail: desc.voicemail.as_ref().map(descriptor),
+                short_code: desc.short_code.as_ref().map(descriptor),
+                standard_rate: desc.standard_rate.as_ref().map(descriptor),
+                carrier: desc.carrier.as_ref().map(descriptor),
+                no_international: desc.no_international.as_ref().map(descriptor),
+                sms_services: desc.sms_services.as_ref().map(descriptor),
+
+                id: Some(meta.id.clone()),
+                country_code: Some(meta.country_code),
+
+                international_prefix: meta.international_prefix.as_ref().map(regex),
+                preferred_international_prefix: meta.preferred_international_prefix.clone(),
+                national_prefix: meta.national_prefix.clone(),
+                preferred_extension_prefix: meta.preferred_extension_prefix.clone(),
+                national_prefix_for_parsing: meta.national_prefix_for_parsing.as_ref().map(regex),
+                national_prefix_transform_rule: meta.national_prefix_transform_rule.clone(),
+
+                formats: meta.formats.iter().map(format).collect(),
+                international_formats: meta.international_formats.iter().map(format).collect(),
 
                 main_country_for_code: meta.main_country_for_code,
-                leading_digits: tranpose(meta.leading_digits.map(regex))?,
+                leading_digits: meta.leading_digits.as_ref().map(regex),
                 mobile_number_portable: meta.mobile_number_portable,
+
+                defaults: Default::default(),
+            }
+        }
+
+        // Go through the calling codes in order, so the encoding doesn't
+        // depend on the hash map ordering, while keeping the order of the
+        // regions sharing a calling code.
+        let mut codes = self.by_code.keys().collect::<Vec<_>>();
+        codes.sort_unstable();
+
+        let meta = codes
+            .into_iter()
+            .flat_map(|code| &self.by_code[code])
+            .map(|meta| metadata(meta))
+            .collect::<Vec<_>>();
+
+        let mut ids = self.short.keys().collect::<Vec<_>>();
+        ids.sort_unstable();
+
+        let short = ids
+            .into_iter()
+            .map(|id| metadata(&self.short[id]))
+            .collect::<Vec<_>>();
+
+        let mut codes = self.alternate.keys().collect::<Vec<_>>();
+        codes.sort_unstable();
+
+        let alternate = codes
+            .into_iter()
+            .map(|code| loader::Metadata {
+                country_code: Some(*code),
+                formats: self.alternate[code].iter().map(format).collect(),
+                ..Default::default()
             })
-        };
+            .collect::<Vec<_>>();
+
+        loader::encode(&loader::Bundle {
+            version: self.version.clone(),
+            metadata: meta,
+            short,
+            alternate,
+        })
+    }
 
+    /// Create a database from a loaded database.
+    pub fn from(meta: Vec<loader::Metadata>) -> Result<Self, error::LoadMetadata> {
+        Database::build(meta, &Interner::new(true))
+    }
+
+    /// Create a database from already built metadata, for example made by hand
+    /// with `Metadata::builder`.
+    ///
+    /// When multiple entries share a country calling code, the one marked as
+    /// main country for it comes first.
+    pub fn from_metadata<I: IntoIterator<Item = super::Metadata>>(meta: I) -> Self {
         let mut by_id = FnvHashMap::default();
         let mut by_code = FnvHashMap::default();
         let mut regions = FnvHashMap::default();
+        let mut countries = FnvHashMap::default();
 
         for meta in meta {
-            let meta = Arc::new(metadata(meta)?);
+            let meta = Arc::new(meta);
 
             by_id.insert(meta.id.clone(), meta.clone());
 
@@ -214,19 +422,158 @@ impl Database {
                 by_code.push(meta.clone());
                 regions.push(meta.id.clone());
             }
+
+            // Non-geographical entities have no country ID.
+            if let Ok(id) = meta.id.parse::<country::Id>() {
+                let countries = countries.entry(meta.country_code).or_insert_with(Vec::new);
+
+                if meta.main_country_for_code {
+                    countries.insert(0, id);
+                } else {
+                    countries.push(id);
+                }
+            }
         }
 
-        Ok(Database {
-            cache: cache.clone(),
+        Database {
+            version: UNKNOWN_VERSION.into(),
             by_id,
             by_code,
             regions,
-        })
+            countries,
+            short: FnvHashMap::default(),
+            alternate: FnvHashMap::default(),
+        }
+    }
+
+    /// Create a database without any metadata.
+    fn empty() -> Self {
+        Database::from_metadata(std::iter::empty())
+    }
+
+    /// Add the short number metadata, like `ShortNumberMetadata.xml`, read from
+    /// the given reader.
+    ///
+    /// Short number territories don't carry a country calling code, so it's
+    /// taken from the database, and territories missing from it are skipped.
+    pub fn with_short_xml<R: Read>(self, reader: R) -> Result<Self, error::LoadMetadata> {
+        let mut short = loader::load(BufReader::new(reader))?;
+
+        for meta in &mut short {
+            if meta.country_code.is_none() {
+                meta.country_code = meta
+                    .id
+                    .as_ref()
+                    .and_then(|id| self.by_id(id.as_str()))
+                    .map(|m| m.country_code);
+            }
+        }
+
+        short.retain(|m| m.country_code.is_some());
+        let short = Database::metadata(short, &Interner::new(true))?;
+
+        Ok(self.with_short_metadata(short))
+    }
+
+    /// Add already built short number metadata, replacing any previous short
+    /// number metadata of the same regions.
+    pub fn with_short_metadata<I: IntoIterator<Item = super::Metadata>>(mut self, meta: I) -> Self {
+        self.short.extend(
+            meta.into_iter()
+                .map(|meta| (meta.id.clone(), Arc::new(meta))),
+        );
+
+        self
+    }
+
+    /// Add the alternate formats, like `PhoneNumberAlternateFormats.xml`, read
+    /// from the given reader.
+    pub fn with_alternate_xml<R: Read>(mut self, reader: R) -> Result<Self, error::LoadMetadata> {
+        let alternate =
+            Database::alternate(loader::load(BufReader::new(reader))?, &Interner::new(true))?;
+        self.alternate.extend(alternate);
+
+        Ok(self)
+    }
+
+    /// Build the loaded alternate formats by country calling code.
+    fn alternate(
+        meta: Vec<loader::Metadata>,
+        interner: &Interner,
+    ) -> Result<FnvHashMap<u16, Vec<super::Format>>, error::LoadMetadata> {
+        meta.into_iter()
+            .map(|meta| {
+                let code = meta
+                    .country_code
+                    .ok_or_else(|| missing("metadata", "countryCode"))?;
+
+                let formats = meta
+                    .formats
+                    .into_iter()
+                    .map(|value| format(value, interner))
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| err.territory(code.to_string()))?;
+
+                Ok((code, formats))
+            })
+            .collect()
+    }
+
+    /// Create a database from a loaded database, with the regular expressions
+    /// made by the given interner. They are only compiled on first use.
+    fn build(
+        meta: Vec<loader::Metadata>,
+        interner: &Interner,
+    ) -> Result<Self, error::LoadMetadata> {
+        Ok(Database::from_metadata(Database::metadata(meta, interner)?))
+    }
+
+    /// Build loaded metadata, keeping track of the territory causing errors.
+    fn metadata(
+        meta: Vec<loader::Metadata>,
+        interner: &Interner,
+    ) -> Result<Vec<super::Metadata>, error::LoadMetadata> {
+        meta.into_iter()
+            .map(|meta| {
+                let id = meta.id.clone().unwrap_or_else(|| "<unknown>".into());
+                metadata(meta, interner).map_err(|err| err.territory(id))
+            })
+            .collect()
+    }
+
+    /// Compile all the regular expressions, and work out the leading digits
+    /// each format applies to, right away instead of on first use, like at
+    /// startup, so no call has to wait for it.
+    ///
+    /// The expressions are shared between the regions, so each is compiled
+    /// only once.
+    pub fn compile(&self) {
+        let metadata = self.by_code.values().flatten().chain(self.short.values());
+
+        for regex in metadata.flat_map(|meta| meta.regexes()) {
+            regex.regex();
+        }
+
+        for regex in self.alternate.values().flatten().flat_map(|f| f.regexes()) {
+            regex.regex();
+        }
+
+        let formats = self
+            .by_code
+            .values()
+            .flatten()
+            .flat_map(|meta| meta.formats().iter().chain(meta.international_formats()))
+            .chain(self.alternate.values().flatten());
+
+        for leading in formats.filter_map(|f| f.leading_digits().last()) {
+            leading.prefixes();
+        }
     }
 
-    /// Get the regular expression cache.
-    pub fn cache(&self) -> Arc<Mutex<RegexCache>> {
-        self.cache.clone()
+    /// Get the version of the upstream libphonenumber metadata the database was
+    /// built from, or "unknown" when it was loaded from XML.
+    pub fn version(&self) -> &str {
+        &self.version
     }
 
     /// Get a metadata entry by country ID.
@@ -249,6 +596,25 @@ impl Database {
             .map(|m| m.iter().map(AsRef::as_ref).collect())
     }
 
+    /// Get the metadata entry of the main country for the given country code,
+    /// without collecting the others.
+    pub(crate) fn main_by_code(&self, code: u16) -> Option<&super::Metadata> {
+        self.by_code
+            .get(&code)
+            .and_then(|m| m.first())
+            .map(AsRef::as_ref)
+    }
+
+    /// Iterate over the metadata entries for the given country code, without
+    /// collecting them.
+    pub(crate) fn iter_by_code(&self, code: u16) -> impl Iterator<Item = &super::Metadata> {
+        self.by_code
+            .get(&code)
+            .into_iter()
+            .flatten()
+            .map(AsRef::as_ref)
+    }
+
     /// Get all country IDs corresponding to the given country code.
     pub fn region<Q>(&self, code: &Q) -> Option<Vec<&str>>
     where
@@ -259,4 +625,933 @@ impl Database {
             .get(code)
             .map(|m| m.iter().map(AsRef::as_ref).collect())
     }
+
+    /// Get the main region for the given country calling code, the one used
+    /// when formatting and parsing numbers with it.
+    pub fn main_region_for_code(&self, code: u16) -> Option<country::Id> {
+        self.countries.get(&code).and_then(|c| c.first()).copied()
+    }
+
+    /// Iterate over the metadata of all the regions, sorted by country ID.
+    ///
+    /// Non-geographical entities are left out, see
+    /// `Database::non_geographical`.
+    pub fn iter_regions(&self) -> impl Iterator<Item = (country::Id, &super::Metadata)> {
+        let mut regions = self
+            .by_id
+            .iter()
+            .filter_map(|(id, meta)| Some((id.parse().ok()?, meta.as_ref())))
+            .collect::<Vec<(country::Id, _)>>();
+
+        regions.sort_unstable_by_key(|&(id, _)| id);
+        regions.into_iter()
+    }
+
+    /// Iterate over the country calling codes of regions, sorted, with the
+    /// regions sharing each, the main region first.
+    ///
+    /// Calling codes only used by non-geographical entities are left out.
+    pub fn iter_calling_codes(&self) -> impl Iterator<Item = (u16, &[country::Id])> {
+        let mut codes = self
+            .countries
+            .iter()
+            .map(|(&code, countries)| (code, countries.as_slice()))
+            .collect::<Vec<_>>();
+
+        codes.sort_unstable_by_key(|&(code, _)| code);
+        codes.into_iter()
+    }
+
+    /// Get the short number metadata of the given region, with the short codes,
+    /// emergency numbers and other short numbers which can be dialled there.
+    pub fn short_metadata(&self, country: country::Id) -> Option<&super::Metadata> {
+        self.short.get(country.as_ref()).map(AsRef::as_ref)
+    }
+
+    /// Get the alternate formats commonly used when writing numbers with the
+    /// given country calling code, in addition to the ones in their metadata.
+    ///
+    /// The bundled database only has them with the `alternate-formats` feature.
+    pub fn alternate_formats(&self, code: u16) -> Option<&[super::Format]> {
+        self.alternate.get(&code).map(AsRef::as_ref)
+    }
+
+    /// Check if the region has implemented mobile number portability, which
+    /// makes the carrier of a mobile number unreliable.
+    pub fn is_mobile_number_portable_region(&self, country: country::Id) -> bool {
+        self.by_id(country.as_ref())
+            .map(|meta| meta.is_mobile_number_portable())
+            .unwrap_or(false)
+    }
+
+    /// Get the ranges of number prefixes, with the lengths of the numbers, the
+    /// numbers of the given type in the region start with, for example to
+    /// generate routing tables.
+    ///
+    /// Anything after a digit which can be any digit only counts towards the
+    /// lengths, so the table may cover more numbers than the pattern does.
+    pub fn prefix_table(
+        &self,
+        country: country::Id,
+        kind: Type,
+    ) -> Result<Vec<super::PrefixRange>, super::PrefixTableError> {
+        let meta = self
+            .by_id(country.as_ref())
+            .ok_or(super::PrefixTableError::UnknownRegion(country))?;
+
+        match meta.descriptors().get(kind) {
+            Some(descriptor) => super::prefix_table::build(descriptor),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the types of numbers the region has, like `Type::Pager`, in the
+    /// order of `Type`.
+    ///
+    /// `Type::FixedLineOrMobile` and `Type::Unknown` are never included, and
+    /// neither are the types of short numbers.
+    pub fn supported_types(&self, country: country::Id) -> Vec<Type> {
+        self.by_id(country.as_ref())
+            .map(supported_types)
+            .unwrap_or_default()
+    }
+
+    /// Get the types of numbers the non-geographical entity with the given
+    /// country calling code has.
+    pub fn supported_types_for_non_geo(&self, code: u16) -> Vec<Type> {
+        self.non_geographical(code)
+            .map(supported_types)
+            .unwrap_or_default()
+    }
+
+    /// Narrow down the regions sharing the country calling code to those the
+    /// national number starting with the given digits can belong to, like
+    /// "204" for Canada among the regions of "+1", with the main region
+    /// first.
+    ///
+    /// Regions whose leading digits match the start of the digits win over
+    /// all others. Otherwise the regions are kept whose leading digits, or
+    /// the pattern of one of their types of numbers when they have none, can
+    /// still match numbers starting with the digits. Non-geographical
+    /// entities aren't regions, so their country calling codes have none.
+    pub fn narrow_regions(&self, code: u16, national: &str) -> Vec<country::Id> {
+        let metas = self.by_code(&code).unwrap_or_default();
+        let id = |meta: &&super::Metadata| meta.id().parse().ok();
+
+        let leading = metas
+            .iter()
+            .filter(|meta| {
+                meta.leading_digits()
+                    .and_then(|pattern| pattern.find(national))
+                    .map(|found| found.start() == 0 && !national.is_empty())
+                    .unwrap_or(false)
+            })
+            .filter_map(id)
+            .collect::<Vec<_>>();
+
+        if !leading.is_empty() {
+            return leading;
+        }
+
+        metas
+            .iter()
+            .filter(|meta| match meta.leading_digits() {
+                Some(pattern) => pattern.prefix_of_match(national),
+                None => TYPES.iter().any(|&kind| {
+                    meta.descriptors()
+                        .get(kind)
+                        .map(|d| d.national_number().prefix_of_match(national))
+                        .unwrap_or(false)
+                }),
+            })
+            .filter_map(id)
+            .collect()
+    }
+
+    /// Get the metadata of the non-geographical entity, like a global network,
+    /// with the given country calling code.
+    ///
+    /// These all share the "001" ID, so they can't be looked up with `by_id`.
+    pub fn non_geographical(&self, code: u16) -> Option<&super::Metadata> {
+        self.main_by_code(code)
+            .filter(|meta| meta.id() == consts::REGION_CODE_FOR_NON_GEO_ENTITY)
+    }
+
+    /// Get the country calling codes of all the non-geographical entities
+    /// covered by the database, in no particular order.
+    pub fn supported_global_network_calling_codes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.supported_calling_codes()
+            .filter(move |&code| self.non_geographical(code).is_some())
+    }
+
+    /// Get all the regions covered by the database, in no particular order.
+    pub fn supported_regions(&self) -> impl Iterator<Item = country::Id> + '_ {
+        self.by_id
+            .keys()
+            .filter(|id| id.as_str() != consts::REGION_CODE_FOR_NON_GEO_ENTITY)
+            .filter_map(|id| id.parse().ok())
+    }
+
+    /// Get all the country calling codes covered by the database, including
+    /// non-geographical entities, in no particular order.
+    pub fn supported_calling_codes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.by_code.keys().cloned()
+    }
+}
+
+fn tranpose<T, E>(value: Option<Result<T, E>>) -> Result<Option<T>, E> {
+    match value {
+        None => Ok(None),
+
+        Some(Ok(value)) => Ok(Some(value)),
+
+        Some(Err(err)) => Err(err),
+    }
+}
+
+/// The types of numbers with a pattern that can match numbers, rather than
+/// the "NA" placeholder older metadata used for missing ones.
+fn supported_types(meta: &super::Metadata) -> Vec<Type> {
+    TYPES
+        .iter()
+        .copied()
+        .filter(|&kind| {
+            meta.descriptors()
+                .get(kind)
+                .map(|d| d.national_number().prefixes() != 0)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn missing(phase: &str, name: &str) -> error::LoadMetadata {
+    error::Metadata::MissingValue {
+        phase: phase.into(),
+        name: name.into(),
+    }
+    .into()
+}
+
+/// Shares the regular expressions, and so their compiled form, between all the
+/// metadata built with it, since many patterns are the same across regions.
+pub(super) struct Interner {
+    check: bool,
+    regexes: RefCell<FnvHashMap<String, LazyRegex>>,
+}
+
+impl Interner {
+    /// Create an interner, checking the syntax of new regular expressions if
+    /// asked to.
+    pub(super) fn new(check: bool) -> Self {
+        Interner {
+            check,
+            regexes: RefCell::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Get the regular expression with the given source.
+    fn regex(&self, value: String) -> Result<LazyRegex, error::LoadMetadata> {
+        if let Some(regex) = self.regexes.borrow().get(&value) {
+            return Ok(regex.clone());
+        }
+
+        let regex = if self.check {
+            LazyRegex::checked(value)?
+        } else {
+            LazyRegex::new(value)
+        };
+
+        self.regexes
+            .borrow_mut()
+            .insert(regex.as_str().into(), regex.clone());
+
+        Ok(regex)
+    }
+}
+
+/// Build a descriptor from a loaded one.
+pub(super) fn descriptor(
+    desc: loader::Descriptor,
+    interner: &Interner,
+) -> Result<super::Descriptor, error::LoadMetadata> {
+    Ok(super::Descriptor {
+        // National numbers have to match the whole pattern, not just a prefix
+        // of it.
+        national_number: desc
+            .national_number
+            .ok_or_else(|| missing("descriptor", "national_number"))
+            .map(|value| format!("^(?:{})$", value))
+            .and_then(|value| interner.regex(value))?,
+
+        possible_length: desc.possible_length,
+        possible_local_length: desc.possible_local_length,
+        example: desc.example,
+    })
+}
+
+/// Build a format from a loaded one.
+pub(super) fn format(
+    format: loader::Format,
+    interner: &Interner,
+) -> Result<super::Format, error::LoadMetadata> {
+    Ok(super::Format {
+        pattern: format
+            .pattern
+            .ok_or_else(|| missing("format", "pattern"))
+            .and_then(|value| interner.regex(value))?,
+
+        format: format.format.ok_or_else(|| missing("format", "format"))?,
+
+        leading_digits: format
+            .leading_digits
+            .into_iter()
+            .map(|value| interner.regex(value))
+            .collect::<Result<_, _>>()?,
+
+        national_prefix: format.national_prefix_formatting_rule,
+        national_prefix_optional: format.national_prefix_optional_when_formatting,
+
+        domestic_carrier: format.domestic_carrier,
+    })
+}
+
+/// Build metadata from loaded metadata.
+pub(super) fn metadata(
+    meta: loader::Metadata,
+    interner: &Interner,
+) -> Result<super::Metadata, error::LoadMetadata> {
+    let descriptor = |desc| descriptor(desc, interner);
+    let format = |value| format(value, interner);
+    let regex = |value| interner.regex(value);
+
+    Ok(super::Metadata {
+        descriptors: super::Descriptors {
+            general: descriptor(
+                meta.general
+                    .ok_or_else(|| missing("metadata", "generalDesc"))?,
+            )?,
+
+            fixed_line: tranpose(meta.fixed_line.map(descriptor))?,
+            mobile: tranpose(meta.mobile.map(descriptor))?,
+            toll_free: tranpose(meta.toll_free.map(descriptor))?,
+            premium_rate: tranpose(meta.premium_rate.map(descriptor))?,
+            shared_cost: tranpose(meta.shared_cost.map(descriptor))?,
+            personal_number: tranpose(meta.personal_number.map(descriptor))?,
+            voip: tranpose(meta.voip.map(descriptor))?,
+            pager: tranpose(meta.pager.map(descriptor))?,
+            uan: tranpose(meta.uan.map(descriptor))?,
+            emergency: tranpose(meta.emergency.map(descriptor))?,
+            voicemail: tranpose(meta.voicemail.map(descriptor))?,
+            short_code: tranpose(meta.short_code.map(descriptor))?,
+            standard_rate: tranpose(meta.standard_rate.map(descriptor))?,
+            carrier: tranpose(meta.carrier.map(descriptor))?,
+            no_international: tranpose(meta.no_international.map(descriptor))?,
+            sms_services: tranpose(meta.sms_services.map(descriptor))?,
+        },
+
+        id: meta.id.ok_or_else(|| missing("metadata", "id"))?,
+        country_code: meta
+            .country_code
+            .ok_or_else(|| missing("metadata", "countryCode"))?,
+
+        international_prefix: tranpose(meta.international_prefix.map(regex))?,
+        preferred_international_prefix: meta.preferred_international_prefix,
+        national_prefix: meta.national_prefix,
+        preferred_extension_prefix: meta.preferred_extension_prefix,
+        national_prefix_for_parsing: tranpose(meta.national_prefix_for_parsing.map(regex))?,
+        national_prefix_transform_rule: meta.national_prefix_transform_rule,
+
+        formats: meta
+            .formats
+            .into_iter()
+            .map(format)
+            .collect::<Result<_, _>>()?,
+        international_formats: meta
+            .international_formats
+            .into_iter()
+            .map(format)
+            .collect::<Result<_, _>>()?,
+
+        main_country_for_code: meta.main_country_for_code,
+        leading_digits: tranpose(meta.leading_digits.map(regex))?,
+        mobile_number_portable: meta.mobile_number_portable,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::country;
+    use crate::error;
+    use crate::formatter::Mode;
+    use crate::metadata::{self, loader, Database, DATABASE};
+    use crate::parser;
+    use crate::phone_number::Type;
+
+    const METADATA: &str = r#"
+        <phoneNumberMetadata>
+          <territories>
+            <territory id="AC" countryCode="247" internationalPrefix="00">
+              <generalDesc>
+                <nationalNumberPattern>(?:[01589]\d|[46])\d{4}</nationalNumberPattern>
+              </generalDesc>
+              <fixedLine>
+                <possibleLengths national="5"/>
+                <exampleNumber>62889</exampleNumber>
+                <nationalNumberPattern>6[2-467]\d{3}</nationalNumberPattern>
+              </fixedLine>
+              <mobile>
+                <possibleLengths national="5"/>
+                <exampleNumber>40123</exampleNumber>
+                <nationalNumberPattern>4\d{4}</nationalNumberPattern>
+              </mobile>
+            </territory>
+            <territory id="AD" countryCode="376" internationalPrefix="00">
+              <availableFormats>
+                <numberFormat pattern="(\d{3})(\d{3})">
+                  <leadingDigits>[135-9]</leadingDigits>
+                  <format>$1 $2</format>
+                </numberFormat>
+              </availableFormats>
+              <generalDesc>
+                <nationalNumberPattern>(?:1|6\d)\d{7}|[135-9]\d{5}</nationalNumberPattern>
+              </generalDesc>
+              <fixedLine>
+                <possibleLengths national="6"/>
+                <exampleNumber>712345</exampleNumber>
+                <nationalNumberPattern>[78]\d{5}</nationalNumberPattern>
+              </fixedLine>
+              <mobile>
+                <possibleLengths national="6,9"/>
+                <exampleNumber>312345</exampleNumber>
+                <nationalNumberPattern>690\d{6}|[356]\d{5}</nationalNumberPattern>
+              </mobile>
+            </territory>
+          </territories>
+        </phoneNumberMetadata>
+    "#;
+
+    #[test]
+    fn portable() {
+        assert!(DATABASE.is_mobile_number_portable_region(country::GB));
+        assert!(DATABASE.is_mobile_number_portable_region(country::US));
+        assert!(!DATABASE.is_mobile_number_portable_region(country::AD));
+    }
+
+    #[test]
+    fn supported() {
+        let meta = loader::load(BufReader::new(
+            File::open("assets/PhoneNumberMetadata.xml").unwrap(),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            meta.iter()
+                .filter(|m| m.id.as_deref() != Some("001"))
+                .count(),
+            DATABASE.supported_regions().count()
+        );
+
+        let mut codes = meta
+            .iter()
+            .filter_map(|m| m.country_code)
+            .collect::<Vec<_>>();
+        codes.sort_unstable();
+        codes.dedup();
+
+        let mut supported = DATABASE.supported_calling_codes().collect::<Vec<_>>();
+        supported.sort_unstable();
+
+        assert_eq!(codes, supported);
+        assert!(supported.contains(&800));
+        assert!(DATABASE.supported_regions().any(|c| c == country::VA));
+    }
+
+    #[test]
+    fn iter() {
+        let (code, countries) = DATABASE.iter_calling_codes().next().unwrap();
+        assert_eq!(1, code);
+        assert_eq!(country::US, countries[0]);
+        assert!(countries.contains(&country::CA));
+
+        assert_eq!(Some(country::US), DATABASE.main_region_for_code(1));
+        assert_eq!(Some(country::GB), DATABASE.main_region_for_code(44));
+        assert_eq!(Some(country::RU), DATABASE.main_region_for_code(7));
+        assert_eq!(None, DATABASE.main_region_for_code(800));
+        assert_eq!(None, DATABASE.main_region_for_code(999));
+
+        let codes = DATABASE
+            .iter_calling_codes()
+            .map(|(code, _)| code)
+            .collect::<Vec<_>>();
+        assert!(codes.windows(2).all(|w| w[0] < w[1]));
+        assert!(!codes.contains(&800));
+
+        let regions = DATABASE.iter_regions().collect::<Vec<_>>();
+        assert_eq!(DATABASE.supported_regions().count(), regions.len());
+        assert!(regions.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(regions.iter().all(|(id, meta)| id.as_ref() == meta.id()));
+        assert_eq!(country::AC, regions[0].0);
+
+        for (code, countries) in DATABASE.iter_calling_codes() {
+            for country in countries {
+                assert_eq!(
+                    code,
+                    DATABASE.by_id(country.as_ref()).unwrap().country_code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn from_reader() {
+        let database = Database::from_reader(METADATA.as_bytes()).unwrap();
+        assert_eq!(2, database.supported_regions().count());
+
+        let number = parser::parse_with(&database, Some(country::AD), "712 345").unwrap();
+        assert!(number.is_valid_with(&database));
+        assert_eq!(Type::FixedLine, number.number_type_with(&database));
+        assert_eq!(
+            "+376 712 345",
+            number
+                .format_with(&database)
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        let number = parser::parse_with(&database, None, "+247 40123").unwrap();
+        assert!(number.is_valid_with(&database));
+        assert_eq!(Type::Mobile, number.number_type_with(&database));
+
+        // Regions missing from the metadata are unsupported, rather than the
+        // numbers being invalid.
+        assert!(matches!(
+            parser::parse_with(&database, None, "+44 7912 345678"),
+            Err(error::Parse::UnsupportedRegion)
+        ));
+        assert!(matches!(
+            parser::parse_with(&database, Some(country::AD), "0044 7912 345678"),
+            Err(error::Parse::UnsupportedRegion)
+        ));
+        assert!(matches!(
+            parser::parse_with(&database, Some(country::DE), "030 901820"),
+            Err(error::Parse::UnsupportedRegion)
+        ));
+        assert!(matches!(
+            parser::parse_with(&database, None, "+999 1234 5678"),
+            Err(error::Parse::InvalidCountryCode)
+        ));
+    }
+
+    /// Only meaningful when built with a restricted set of regions, for
+    /// example with `PHONENUMBER_REGIONS=DE,FR cargo test restricted`.
+    #[test]
+    fn restricted() {
+        let regions = if let Some(regions) = option_env!("PHONENUMBER_REGIONS") {
+            regions
+                .split(',')
+                .filter_map(|id| id.trim().parse::<country::Id>().ok())
+                .collect::<Vec<_>>()
+        } else {
+            return;
+        };
+
+        let mut supported = DATABASE.supported_regions().collect::<Vec<_>>();
+        supported.sort_unstable_by_key(|c| c.as_ref().to_owned());
+        let mut expected = regions.clone();
+        expected.sort_unstable_by_key(|c| c.as_ref().to_owned());
+        assert_eq!(expected, supported);
+
+        for &(region, input) in &[
+            (country::DE, "+49 30 901820"),
+            (country::FR, "+33 1 23 45 67 89"),
+            (country::US, "+1 650 253 0000"),
+            (country::GB, "+44 7912 345678"),
+        ] {
+            let result = parser::parse(Some(region), input);
+
+            if regions.contains(&region) {
+                assert!(result.unwrap().is_valid(), "{}", input);
+            } else {
+                assert!(
+                    matches!(result, Err(error::Parse::UnsupportedRegion)),
+                    "{}",
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn from_reader_error() {
+        let error = Database::from_reader(
+            METADATA
+                .replace(
+                    r#"<nationalNumberPattern>4\d{4}"#,
+                    r#"<nationalNumberPattern>4\d{4"#,
+                )
+                .as_bytes(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("\"AC\""), "{}", error);
+
+        let error = Database::from_reader(METADATA.replace("</mobile>", "</fixedLine>").as_bytes())
+            .unwrap_err();
+        assert!(error.to_string().contains("\"AC\""), "{}", error);
+    }
+
+    #[test]
+    fn from_reader_upstream() {
+        let database =
+            Database::from_reader(File::open("assets/PhoneNumberMetadata.xml").unwrap()).unwrap();
+
+        for input in &[
+            "+1 650 253 0000",
+            "+44 7912 345678",
+            "+44 909 876 5432",
+            "+49 30 901820",
+            "+33 1 23 45 67 89",
+            "+39 06 6988 2100",
+            "+61 2 9374 4000",
+            "+800 1234 5678",
+            "+376 712 345",
+            "+44 791 234567",
+        ] {
+            let expected = parser::parse(None, input).unwrap();
+            let number = parser::parse_with(&database, None, input).unwrap();
+
+            assert_eq!(expected, number, "{}", input);
+            assert_eq!(expected.is_valid(), number.is_valid_with(&database));
+            assert_eq!(expected.number_type(), number.number_type_with(&database));
+            assert_eq!(
+                expected.format().mode(Mode::International).to_string(),
+                number
+                    .format_with(&database)
+                    .mode(Mode::International)
+                    .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn from_reader_filtered() {
+        let database = Database::from_reader_filtered(
+            File::open("assets/PhoneNumberMetadata.xml").unwrap(),
+            &[country::DE, country::CA, country::US],
+        )
+        .unwrap();
+
+        let mut regions = database
+            .supported_regions()
+            .map(|c| c.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        regions.sort_unstable();
+        assert_eq!(vec!["CA", "DE", "US"], regions);
+
+        let mut codes = database.supported_calling_codes().collect::<Vec<_>>();
+        codes.sort_unstable();
+        assert_eq!(vec![1, 49], codes);
+        assert_eq!(Some("US"), database.region(&1).map(|r| r[0]));
+
+        let number = parser::parse_with(&database, None, "+49 30 901820").unwrap();
+        assert!(number.is_valid_with(&database));
+
+        let number = parser::parse_with(&database, Some(country::CA), "613 555 0123").unwrap();
+        assert_eq!(Some(country::CA), number.country().id_with(&database));
+
+        for input in &["+44 7912 345678", "+800 1234 5678"] {
+            assert!(matches!(
+                parser::parse_with(&database, None, input),
+                Err(error::Parse::UnsupportedRegion)
+            ));
+        }
+    }
+
+    #[test]
+    fn from_reader_unknown() {
+        let xml = METADATA.replace(r#"id="AC""#, r#"id="XX""#);
+        let mut unknown = Vec::new();
+        let database = Database::from_reader_filtered_with_warnings(
+            xml.as_bytes(),
+            &[country::AC, country::AD],
+            |id| unknown.push(id.to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!(vec!["XX"], unknown);
+        assert_eq!(
+            vec![country::AD],
+            database.supported_regions().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        let bytes = DATABASE.to_bytes().unwrap();
+        let database = Database::from_bytes(&bytes).unwrap();
+        assert_eq!(bytes, database.to_bytes().unwrap());
+
+        assert_eq!(
+            DATABASE.supported_regions().count(),
+            database.supported_regions().count()
+        );
+        assert_eq!(DATABASE.region(&1).unwrap(), database.region(&1).unwrap());
+
+        for input in &["+1 650 253 0000", "+44 7912 345678", "+800 1234 5678"] {
+            let number = parser::parse_with(&database, None, input).unwrap();
+            assert!(number.is_valid_with(&database));
+            assert_eq!(
+                parser::parse(None, input).unwrap().number_type(),
+                number.number_type_with(&database)
+            );
+        }
+
+        // The short number metadata is part of the encoding.
+        assert!(Database::from_bytes(&DATABASE.to_bytes().unwrap())
+            .unwrap()
+            .short_metadata(country::US)
+            .is_some());
+    }
+
+    #[test]
+    fn bytes_error() {
+        let mut bytes = DATABASE.to_bytes().unwrap();
+        bytes[loader::MAGIC.len()] = bytes[loader::MAGIC.len()].wrapping_add(1);
+
+        assert!(matches!(
+            Database::from_bytes(&bytes),
+            Err(error::LoadMetadata::Metadata(
+                error::Metadata::VersionMismatch {
+                    expected: loader::VERSION,
+                    ..
+                }
+            ))
+        ));
+
+        assert!(matches!(
+            Database::from_bytes(b"<phoneNumberMetadata>"),
+            Err(error::LoadMetadata::Metadata(error::Metadata::InvalidMagic))
+        ));
+
+        assert!(matches!(
+            Database::from_bytes(b"PN"),
+            Err(error::LoadMetadata::Metadata(
+                error::Metadata::UnexpectedEof
+            ))
+        ));
+    }
+
+    #[test]
+    fn supported_types() {
+        assert_eq!(
+            vec![
+                Type::FixedLine,
+                Type::Mobile,
+                Type::TollFree,
+                Type::PremiumRate,
+                Type::PersonalNumber,
+            ],
+            DATABASE.supported_types(country::US)
+        );
+
+        let types = DATABASE.supported_types(country::DE);
+        assert!(types.contains(&Type::Pager));
+        assert!(types.contains(&Type::Voicemail));
+        assert!(!types.contains(&Type::Voip));
+
+        for id in DATABASE.supported_regions() {
+            let types = DATABASE.supported_types(id);
+            assert!(
+                types.contains(&Type::FixedLine) || types.contains(&Type::Mobile),
+                "{:?}",
+                id
+            );
+        }
+
+        assert_eq!(
+            vec![Type::TollFree],
+            DATABASE.supported_types_for_non_geo(800)
+        );
+        assert_eq!(
+            vec![Type::Mobile],
+            DATABASE.supported_types_for_non_geo(870)
+        );
+        assert!(DATABASE.supported_types_for_non_geo(49).is_empty());
+        assert!(DATABASE.supported_types_for_non_geo(999).is_empty());
+
+        // Older metadata has "NA" patterns for the types a region doesn't have.
+        let database = Database::parse(METADATA.replace(
+            "</mobile>",
+            "</mobile><pager><nationalNumberPattern>NA</nationalNumberPattern></pager>",
+        ))
+        .unwrap();
+        assert_eq!(
+            vec![Type::FixedLine, Type::Mobile],
+            database.supported_types(country::AC)
+        );
+        assert!(database.supported_types(country::US).is_empty());
+    }
+
+    #[test]
+    fn version() {
+        assert!(!metadata::BUNDLED_VERSION.is_empty());
+        assert!(metadata::BUNDLED_VERSION
+            .split('.')
+            .all(|part| part.parse::<u16>().is_ok()));
+
+        assert_eq!(metadata::BUNDLED_VERSION, DATABASE.version());
+
+        let database = Database::from_bytes(&DATABASE.to_bytes().unwrap()).unwrap();
+        assert_eq!(metadata::BUNDLED_VERSION, database.version());
+        assert!(format!("{:?}", database).contains(metadata::BUNDLED_VERSION));
+
+        let database = Database::from_reader(METADATA.as_bytes()).unwrap();
+        assert_eq!("unknown", database.version());
+    }
+
+    #[test]
+    fn narrow_regions() {
+        assert_eq!(vec![country::CA], DATABASE.narrow_regions(1, "204"));
+        assert_eq!(vec![country::PR], DATABASE.narrow_regions(1, "787"));
+        assert_eq!(vec![country::US], DATABASE.narrow_regions(1, "650"));
+        assert_eq!(vec![country::GG], DATABASE.narrow_regions(44, "1481"));
+        assert_eq!(vec![country::IM], DATABASE.narrow_regions(44, "7624"));
+
+        // Not enough digits to tell yet.
+        assert_eq!(
+            vec![country::US, country::CA],
+            DATABASE.narrow_regions(1, "20")
+        );
+        assert_eq!(
+            vec![country::GB, country::GG, country::IM, country::JE],
+            DATABASE.narrow_regions(44, "1")
+        );
+        assert_eq!(25, DATABASE.narrow_regions(1, "").len());
+
+        assert!(DATABASE.narrow_regions(800, "1").is_empty());
+        assert!(DATABASE.narrow_regions(999, "1").is_empty());
+    }
+
+    #[test]
+    fn non_geographical() {
+        let meta = DATABASE.non_geographical(800).unwrap();
+        assert_eq!("001", meta.id());
+        assert_eq!(800, meta.country_code());
+        assert!(DATABASE.non_geographical(44).is_none());
+        assert!(DATABASE.non_geographical(801).is_none());
+
+        let mut codes = DATABASE
+            .supported_global_network_calling_codes()
+            .collect::<Vec<_>>();
+        codes.sort_unstable();
+        assert_eq!(vec![800, 808, 870, 878, 881, 882, 883, 888, 979], codes);
+    }
+
+    #[test]
+    fn short_metadata() {
+        let emergency = DATABASE
+            .short_metadata(country::DE)
+            .and_then(|meta| meta.descriptors().emergency())
+            .unwrap();
+        assert!(emergency.is_match("110"));
+        assert!(emergency.is_match("112"));
+        assert!(!emergency.is_match("113"));
+
+        let meta = DATABASE.short_metadata(country::DE).unwrap();
+        assert_eq!(49, meta.country_code());
+        assert!(meta.descriptors().short_code().is_some());
+        assert!(meta.descriptors().toll_free().is_some());
+        assert!(DATABASE
+            .short_metadata(country::US)
+            .unwrap()
+            .descriptors()
+            .sms_services()
+            .is_some());
+
+        // The short number metadata survives the binary encoding.
+        let database = Database::from_bytes(&DATABASE.to_bytes().unwrap()).unwrap();
+        assert!(database
+            .short_metadata(country::DE)
+            .and_then(|meta| meta.descriptors().emergency())
+            .unwrap()
+            .is_match("112"));
+
+        let database = Database::parse(METADATA)
+            .unwrap()
+            .with_short_xml(
+                r#"
+                <phoneNumberMetadata>
+                  <territories>
+                    <territory id="AD">
+                      <generalDesc>
+                        <nationalNumberPattern>1\d\d</nationalNumberPattern>
+                      </generalDesc>
+                      <emergency>
+                        <possibleLengths national="3"/>
+                        <nationalNumberPattern>11[0268]</nationalNumberPattern>
+                      </emergency>
+                    </territory>
+                    <territory id="DE">
+                      <generalDesc>
+                        <nationalNumberPattern>1\d\d</nationalNumberPattern>
+                      </generalDesc>
+                    </territory>
+                  </territories>
+                </phoneNumberMetadata>
+                "#
+                .as_bytes(),
+            )
+            .unwrap();
+
+        let meta = database.short_metadata(country::AD).unwrap();
+        assert_eq!(376, meta.country_code());
+        assert!(meta.descriptors().emergency().unwrap().is_match("112"));
+        assert!(database.short_metadata(country::DE).is_none());
+        assert!(Database::parse(METADATA)
+            .unwrap()
+            .short_metadata(country::AD)
+            .is_none());
+    }
+
+    #[test]
+    fn compile() {
+        let database = Database::from_bytes(&DATABASE.to_bytes().unwrap()).unwrap();
+        let meta = database.by_id("DE").unwrap();
+        assert!(meta.regexes().any(|regex| !regex.is_compiled()));
+
+        database.compile();
+        assert!(meta.regexes().all(|regex| regex.is_compiled()));
+        assert!(database
+            .short_metadata(country::DE)
+            .unwrap()
+            .regexes()
+            .all(|regex| regex.is_compiled()));
+    }
+
+    #[test]
+    fn interned() {
+        let database = Database::from_bytes(&DATABASE.to_bytes().unwrap()).unwrap();
+
+        // Many regions format their numbers with the same patterns, which
+        // should all share one compiled expression.
+        let mut seen = std::collections::HashMap::new();
+        let mut shared = 0;
+
+        for region in database.supported_regions() {
+            let meta = database.by_id(region.as_ref()).unwrap();
+
+            for format in meta.formats() {
+                let pattern = format.pattern();
+
+                if let Some(other) = seen.insert(pattern.as_str().to_owned(), pattern.clone()) {
+                    assert!(std::ptr::eq(pattern.regex(), other.regex()));
+                    shared += 1;
+                }
+            }
+        }
+
+        assert!(shared > 100);
+    }
 }
@@ -12,12 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use regex_cache::CachedRegex;
+use crate::error;
+use crate::metadata::{database, loader, LazyRegex};
 
 /// Description of a phone number to parse.
 #[derive(Clone, Debug)]
 pub struct Descriptor {
-    pub(crate) national_number: CachedRegex,
+    pub(crate) national_number: LazyRegex,
 
     pub(crate) possible_length: Vec<u16>,
     pub(crate) possible_local_length: Vec<u16>,
@@ -26,10 +27,21 @@ pub struct Descriptor {
 }
 
 impl Descriptor {
+    /// Start building a descriptor by hand for the given national number
+    /// pattern, for example to make a small fixed database for tests.
+    pub fn builder<S: Into<String>>(national_number: S) -> DescriptorBuilder {
+        DescriptorBuilder(loader::Descriptor {
+            national_number: Some(national_number.into()),
+            ..Default::default()
+        })
+    }
+
     /// The national number is the pattern that a valid national significant
     /// number would match. This specifies information such as its total length
     /// and leading digits.
-    pub fn national_number(&self) -> &CachedRegex {
+    ///
+    /// The pattern is anchored, so it only matches whole national numbers.
+    pub fn national_number(&self) -> &LazyRegex {
         &self.national_number
     }
 
@@ -77,9 +89,35 @@ impl Descriptor {
             return false;
         }
 
-        self.national_number
-            .find(value)
-            .map(|m| m.start() == 0)
-            .unwrap_or(false)
+        self.national_number.is_match(value)
+    }
+}
+
+/// Builder for a `Descriptor`, created with `Descriptor::builder`.
+#[derive(Clone, Debug)]
+pub struct DescriptorBuilder(pub(crate) loader::Descriptor);
+
+impl DescriptorBuilder {
+    /// Set the lengths numbers matching the descriptor can be.
+    pub fn possible_length(mut self, lengths: &[u16]) -> Self {
+        self.0.possible_length = lengths.to_vec();
+        self
+    }
+
+    /// Set the lengths only local numbers matching the descriptor can be.
+    pub fn possible_local_length(mut self, lengths: &[u16]) -> Self {
+        self.0.possible_local_length = lengths.to_vec();
+        self
+    }
+
+    /// Set the example national significant number.
+    pub fn example<S: Into<String>>(mut self, example: S) -> Self {
+        self.0.example = Some(example.into());
+        self
+    }
+
+    /// Build the descriptor, checking the syntax of the pattern.
+    pub fn build(self) -> Result<Descriptor, error::LoadMetadata> {
+        database::descriptor(self.0, &database::Interner::new(true))
     }
 }
@@ -12,28 +12,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use regex_cache::CachedRegex;
+use crate::error;
+use crate::metadata::{database, loader, LazyRegex};
 
 /// Description of a phone number format.
 #[derive(Clone, Debug)]
 pub struct Format {
-    pub(crate) pattern: CachedRegex,
+    pub(crate) pattern: LazyRegex,
     pub(crate) format: String,
 
-    pub(crate) leading_digits: Vec<CachedRegex>,
+    pub(crate) leading_digits: Vec<LazyRegex>,
     pub(crate) national_prefix: Option<String>,
     pub(crate) national_prefix_optional: bool,
     pub(crate) domestic_carrier: Option<String>,
 }
 
 impl Format {
+    /// Start building a format by hand for numbers matching the given pattern,
+    /// formatted with the given replacement, like "$1 $2".
+    pub fn builder<P: Into<String>, F: Into<String>>(pattern: P, format: F) -> FormatBuilder {
+        FormatBuilder(loader::Format {
+            pattern: Some(pattern.into()),
+            format: Some(format.into()),
+            ..Default::default()
+        })
+    }
+
     /// A regex that is used to match the national (significant) number. For
     /// example, the pattern "(20)(\d{4})(\d{4})" will match number "2070313000",
     /// which is the national (significant) number for Google London.
     ///
     /// Note the presence of the parentheses, which are capturing groups what
     /// specifies the grouping of numbers.
-    pub fn pattern(&self) -> &CachedRegex {
+    pub fn pattern(&self) -> &LazyRegex {
         &self.pattern
     }
 
@@ -63,10 +74,15 @@ impl Format {
     ///
     /// In the case when only one formatting pattern exists, no
     /// leading_digits_pattern is needed.
-    pub fn leading_digits(&self) -> &[CachedRegex] {
+    pub fn leading_digits(&self) -> &[LazyRegex] {
         &self.leading_digits
     }
 
+    /// All the regular expressions of the format.
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        std::iter::once(&self.pattern).chain(&self.leading_digits)
+    }
+
     /// Specifies how the national prefix ($NP) together with the first group
     /// ($FG) in the national significant number should be formatted in the
     /// NATIONAL format when a national prefix exists for a certain country.
@@ -102,3 +118,39 @@ impl Format {
         self.domestic_carrier.as_ref().map(AsRef::as_ref)
     }
 }
+
+/// Builder for a `Format`, created with `Format::builder`.
+#[derive(Clone, Debug)]
+pub struct FormatBuilder(pub(crate) loader::Format);
+
+impl FormatBuilder {
+    /// Add a pattern the leading digits of numbers have to match to use the
+    /// format.
+    pub fn leading_digits<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.0.leading_digits.push(pattern.into());
+        self
+    }
+
+    /// Set the national prefix formatting rule, like "$NP$FG".
+    pub fn national_prefix<S: Into<String>>(mut self, rule: S) -> Self {
+        self.0.national_prefix_formatting_rule = Some(rule.into());
+        self
+    }
+
+    /// Set whether the national prefix is optional when formatting.
+    pub fn national_prefix_optional(mut self, value: bool) -> Self {
+        self.0.national_prefix_optional_when_formatting = value;
+        self
+    }
+
+    /// Set the carrier code formatting rule, like "$NP$CC $FG".
+    pub fn domestic_carrier<S: Into<String>>(mut self, rule: S) -> Self {
+        self.0.domestic_carrier = Some(rule.into());
+        self
+    }
+
+    /// Build the format, checking the syntax of the patterns.
+    pub fn build(self) -> Result<Format, error::LoadMetadata> {
+        database::format(self.0, &database::Interner::new(true))
+    }
+}
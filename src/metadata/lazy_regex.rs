@@ -0,0 +1,364 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+use regex::{Regex, RegexBuilder};
+use regex_automata::hybrid::dfa::DFA;
+use regex_automata::util::syntax;
+use regex_automata::{Anchored, Input};
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+/// A metadata regular expression, compiled on first use.
+///
+/// Compilation happens at most once, even when multiple threads use the
+/// expression for the first time concurrently. Clones share both the source
+/// and the compiled expression.
+#[derive(Clone)]
+pub struct LazyRegex(Arc<Inner>);
+
+struct Inner {
+    source: String,
+    regex: OnceCell<Regex>,
+    prefixes: OnceCell<u128>,
+}
+
+impl LazyRegex {
+    /// Create a regular expression to be compiled on first use.
+    ///
+    /// The syntax is not checked, an invalid expression panics on first use.
+    pub fn new<S: Into<String>>(source: S) -> Self {
+        LazyRegex(Arc::new(Inner {
+            source: source.into(),
+            regex: OnceCell::new(),
+            prefixes: OnceCell::new(),
+        }))
+    }
+
+    /// Create a regular expression to be compiled on first use, checking its
+    /// syntax right away.
+    pub fn checked<S: Into<String>>(source: S) -> Result<Self, regex::Error> {
+        let source = source.into();
+
+        regex_syntax::ParserBuilder::new()
+            .ignore_whitespace(true)
+            .build()
+            .parse(&source)
+            .map_err(|err| regex::Error::Syntax(err.to_string()))?;
+
+        Ok(LazyRegex::new(source))
+    }
+
+    /// The source of the regular expression.
+    pub fn as_str(&self) -> &str {
+        &self.0.source
+    }
+
+    /// Check if the regular expression has been compiled already.
+    pub fn is_compiled(&self) -> bool {
+        self.0.regex.get().is_some()
+    }
+
+    /// Get the compiled regular expression, compiling it if needed.
+    pub fn regex(&self) -> &Regex {
+        self.0.regex.get_or_init(|| build(&self.0.source).unwrap())
+    }
+
+    /// The two digit prefixes of the numbers the expression can match the
+    /// start of, bit `10 * a + b` standing for the prefix "ab".
+    ///
+    /// It's worked out from the syntax of the expression without compiling
+    /// it, and may include prefixes that can't actually match.
+    pub(crate) fn prefixes(&self) -> u128 {
+        *self.0.prefixes.get_or_init(|| {
+            regex_syntax::ParserBuilder::new()
+                .ignore_whitespace(true)
+                .build()
+                .parse(&self.0.source)
+                .map(|hir| prefixes(starts(&hir)))
+                .unwrap_or(ALL)
+        })
+    }
+
+    /// Check if the digits are the start of a string the expression matches,
+    /// or have a match at their start.
+    pub(crate) fn prefix_of_match(&self, digits: &str) -> bool {
+        self.walk(digits, true)
+    }
+
+    /// Check if the digits are the start of a string the expression matches,
+    /// so more digits can still make it match.
+    pub(crate) fn is_prefix(&self, digits: &str) -> bool {
+        self.walk(digits, false)
+    }
+
+    /// Run the digits through the expression, stopping at the first match of
+    /// their start if `early` is set.
+    fn walk(&self, digits: &str, early: bool) -> bool {
+        let bytes = digits.as_bytes();
+
+        if let [a, b, ..] = bytes {
+            if self.prefixes() & 1 << (10 * (a - b'0') + (b - b'0')) == 0 {
+                return false;
+            }
+        }
+
+        let dfa = match DFA::builder()
+            .syntax(syntax::Config::new().ignore_whitespace(true))
+            .build(self.as_str())
+        {
+            Ok(dfa) => dfa,
+            Err(_) => return true,
+        };
+
+        let mut cache = dfa.create_cache();
+        let input = Input::new(digits).anchored(Anchored::Yes);
+        let mut state = match dfa.start_state_forward(&mut cache, &input) {
+            Ok(state) => state,
+            Err(_) => return true,
+        };
+
+        for &byte in bytes {
+            state = match dfa.next_state(&mut cache, state, byte) {
+                Ok(state) => state,
+                Err(_) => return true,
+            };
+
+            // Matches are only seen one byte late, a match of the digits so
+            // far means the expression matches their start.
+            if early && state.is_match() {
+                return true;
+            }
+
+            if state.is_dead() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Every two digit prefix.
+const ALL: u128 = (1 << 100) - 1;
+
+/// A set of digit strings up to two digits long, as bits: the empty string,
+/// then the ten digits, then the hundred pairs of digits.
+type Starts = u128;
+
+const EMPTY: Starts = 1;
+
+fn decode(index: u32) -> Vec<u8> {
+    match index {
+        0 => vec![],
+        1..=10 => vec![index as u8 - 1],
+        _ => vec![(index as u8 - 11) / 10, (index as u8 - 11) % 10],
+    }
+}
+
+fn encode(digits: &[u8]) -> u32 {
+    match *digits {
+        [] => 0,
+        [a] => 1 + a as u32,
+        [a, b, ..] => 11 + 10 * a as u32 + b as u32,
+    }
+}
+
+fn iter(starts: Starts) -> impl Iterator<Item = u32> {
+    (0..111).filter(move |i| starts & (1 << i) != 0)
+}
+
+/// The starts of the strings made of one from `a` followed by one from `b`.
+fn concat(a: Starts, b: Starts) -> Starts {
+    let mut result = 0;
+
+    for x in iter(a) {
+        if x > 10 {
+            result |= 1 << x;
+            continue;
+        }
+
+        for y in iter(b) {
+            let mut digits = decode(x);
+            digits.extend(decode(y));
+            result |= 1 << encode(&digits);
+        }
+    }
+
+    result
+}
+
+/// The starts of the strings of digits the expression can match, anything
+/// else can't be part of a number and is dropped.
+fn starts(hir: &Hir) -> Starts {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => EMPTY,
+
+        HirKind::Literal(literal) => literal.0.iter().fold(EMPTY, |acc, &byte| {
+            concat(
+                acc,
+                if byte.is_ascii_digit() {
+                    1 << encode(&[byte - b'0'])
+                } else {
+                    0
+                },
+            )
+        }),
+
+        HirKind::Class(class) => (b'0'..=b'9')
+            .filter(|&digit| match class {
+                Class::Unicode(class) => class
+                    .iter()
+                    .any(|r| r.start() <= digit as char && digit as char <= r.end()),
+                Class::Bytes(class) => class.iter().any(|r| r.start() <= digit && digit <= r.end()),
+            })
+            .fold(0, |acc, digit| acc | 1 << encode(&[digit - b'0'])),
+
+        HirKind::Capture(capture) => starts(&capture.sub),
+
+        HirKind::Concat(subs) => subs.iter().fold(EMPTY, |acc, sub| concat(acc, starts(sub))),
+
+        HirKind::Alternation(subs) => subs.iter().fold(0, |acc, sub| acc | starts(sub)),
+
+        // Past two repetitions the starts don't change anymore.
+        HirKind::Repetition(repetition) => {
+            let sub = starts(&repetition.sub);
+            let last = repetition
+                .max
+                .unwrap_or(u32::MAX)
+                .min(repetition.min.max(2));
+
+            let mut current = EMPTY;
+            let mut result = 0;
+
+            for count in 0..=last {
+                if count >= repetition.min {
+                    result |= current;
+                }
+
+                current = concat(current, sub);
+            }
+
+            result
+        }
+    }
+}
+
+/// The two digit prefixes of the numbers starting with one of the strings.
+fn prefixes(starts: Starts) -> u128 {
+    iter(starts).fold(0, |acc, index| match *decode(index) {
+        [] => ALL,
+        [a] => acc | 0x3ff << (10 * a),
+        [a, b, ..] => acc | 1 << (10 * a + b),
+    })
+}
+
+/// All metadata patterns are written with insignificant whitespace.
+fn build(source: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(source).ignore_whitespace(true).build()
+}
+
+impl Deref for LazyRegex {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        self.regex()
+    }
+}
+
+impl fmt::Debug for LazyRegex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0.source, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    use crate::metadata::LazyRegex;
+
+    #[test]
+    fn lazy() {
+        let regex = LazyRegex::new(r"1 \d{3}");
+        assert!(!regex.is_compiled());
+        assert_eq!(r"1 \d{3}", regex.as_str());
+
+        assert!(regex.is_match("1234"));
+        assert!(regex.is_compiled());
+
+        assert!(LazyRegex::checked("1(").is_err());
+        assert!(!LazyRegex::checked("1").unwrap().is_compiled());
+    }
+
+    #[test]
+    fn prefixes() {
+        fn prefixes(source: &str) -> Vec<u8> {
+            let prefixes = LazyRegex::new(source).prefixes();
+            (0..100).filter(|i| prefixes & (1 << i) != 0).collect()
+        }
+
+        assert_eq!(vec![10, 11, 12], prefixes("1[0-2]"));
+        assert_eq!(
+            (10..20).chain(30..40).chain(50..100).collect::<Vec<_>>(),
+            prefixes("[135-9]")
+        );
+        assert_eq!(
+            [11, 12].iter().copied().chain(20..30).collect::<Vec<_>>(),
+            prefixes("1(?:1|2[2-4])|2")
+        );
+        assert_eq!(vec![30, 31, 33], prefixes("3 (?: [01] | 3 )"));
+        assert_eq!(vec![44, 45, 54, 55], prefixes("[45]{2,}"));
+        assert_eq!(
+            [17].iter().copied().chain(70..80).collect::<Vec<_>>(),
+            prefixes("1?7")
+        );
+        assert_eq!((0..100).collect::<Vec<_>>(), prefixes(r"\d*"));
+        assert!(prefixes("a1").is_empty());
+    }
+
+    #[test]
+    fn concurrent() {
+        let regex = Arc::new(LazyRegex::new(r"(?:[2-9]\d{2}){2}\d{4}"));
+        let barrier = Arc::new(Barrier::new(8));
+
+        let threads = (0..8)
+            .map(|_| {
+                let regex = regex.clone();
+                let barrier = barrier.clone();
+
+                thread::spawn(move || {
+                    barrier.wait();
+                    assert!(regex.is_match("6502530000"));
+                    assert!(!regex.is_match("1502530000"));
+
+                    regex.regex() as *const _ as usize
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let compiled = threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // Every thread got the one and only compiled expression.
+        assert!(compiled.iter().all(|&c| c == compiled[0]));
+        assert_eq!(compiled[0], regex.regex() as *const _ as usize);
+    }
+}
@@ -15,6 +15,8 @@
 use std::io::BufRead;
 use std::str;
 
+use bincode::Options;
+
 use crate::xml::events::attributes::Attribute;
 use crate::xml::events::{self, Event};
 use crate::xml::Reader;
@@ -47,6 +49,7 @@ pub struct Metadata {
     pub standard_rate: Option<Descriptor>,
     pub carrier: Option<Descriptor>,
     pub no_international: Option<Descriptor>,
+    pub sms_services: Option<Descriptor>,
 
     pub id: Option<String>,
     pub country_code: Option<u16>,
@@ -89,25 +92,109 @@ pub struct Descriptor {
     pub example: Option<String>,
 }
 
+/// Magic bytes at the start of binary metadata.
+pub const MAGIC: &[u8; 4] = b"PNMD";
+
+/// Version of the binary metadata encoding, to be bumped whenever the layout
+/// of the loaded metadata changes.
+pub const VERSION: u16 = 4;
+
+/// Everything kept in the binary format.
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct Bundle {
+    /// The upstream metadata version the metadata comes from.
+    pub version: String,
+
+    /// The main metadata.
+    pub metadata: Vec<Metadata>,
+
+    /// The short number metadata.
+    pub short: Vec<Metadata>,
+
+    /// The alternate formats, by country calling code, which may be empty.
+    pub alternate: Vec<Metadata>,
+}
+
+/// Encode loaded metadata in the versioned binary format.
+#[allow(unused)] // This is unused in the library
+pub fn encode(bundle: &Bundle) -> Result<Vec<u8>, error::LoadMetadata> {
+    let mut result = Vec::new();
+    result.extend_from_slice(MAGIC);
+    result.extend_from_slice(&VERSION.to_le_bytes());
+
+    bincode::options()
+        .with_varint_encoding()
+        .serialize_into(&mut result, bundle)?;
+
+    Ok(result)
+}
+
+/// Decode metadata in the versioned binary format.
+#[allow(unused)] // This is unused in the build script
+pub fn decode(bytes: &[u8]) -> Result<Bundle, error::LoadMetadata> {
+    if bytes.len() < MAGIC.len() + 2 {
+        return Err(error::Metadata::UnexpectedEof.into());
+    }
+
+    let (magic, bytes) = bytes.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return Err(error::Metadata::InvalidMagic.into());
+    }
+
+    let (version, bytes) = bytes.split_at(2);
+    let version = u16::from_le_bytes([version[0], version[1]]);
+    if version != VERSION {
+        return Err(error::Metadata::VersionMismatch {
+            expected: VERSION,
+            found: version,
+        }
+        .into());
+    }
+
+    Ok(bincode::options()
+        .with_varint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)?)
+}
+
 /// Load XML metadata from the given reader.
 pub fn load<R: BufRead>(reader: R) -> Result<Vec<Metadata>, error::LoadMetadata> {
-    metadata(&mut Reader::from_reader(reader))
+    load_filtered(reader, |_| true)
 }
 
-fn metadata<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<Metadata>, error::LoadMetadata> {
+/// Load XML metadata from the given reader, only parsing the territories whose
+/// ID passes the filter, the others are skipped while streaming.
+#[allow(unused)] // This is unused in the build script
+pub fn load_filtered<R: BufRead, F: FnMut(&str) -> bool>(
+    reader: R,
+    mut filter: F,
+) -> Result<Vec<Metadata>, error::LoadMetadata> {
+    metadata(&mut Reader::from_reader(reader), &mut filter)
+}
+
+fn metadata<R: BufRead>(
+    reader: &mut Reader<R>,
+    filter: &mut dyn FnMut(&str) -> bool,
+) -> Result<Vec<Metadata>, error::LoadMetadata> {
     let mut buffer = Vec::new();
     let mut result = Vec::new();
 
     loop {
         match reader.read_event_into(&mut buffer)? {
-            Event::Text(_) | Event::Comment(_) | Event::DocType(_) => (),
+            Event::Text(_) | Event::Comment(_) | Event::Decl(_) | Event::DocType(_) => (),
 
             Event::Start(ref e) => match e.name().into_inner() {
                 b"phoneNumberMetadata" => continue,
 
-                b"territories" => result.extend(territories(reader)?),
+                b"territories" => result.extend(territories(reader, filter)?),
 
-                name => ignore(reader, name)?,
+                name => {
+                    return Err(error::Metadata::UnhandledElement {
+                        phase: "metadata".into(),
+                        name: str::from_utf8(name)?.into(),
+                    }
+                    .into())
+                }
             },
 
             Event::End(ref e) if e.name().into_inner() != b"phoneNumberMetadata" => {
@@ -132,7 +219,10 @@ fn metadata<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<Metadata>, error::
     }
 }
 
-fn territories<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<Metadata>, error::LoadMetadata> {
+fn territories<R: BufRead>(
+    reader: &mut Reader<R>,
+    filter: &mut dyn FnMut(&str) -> bool,
+) -> Result<Vec<Metadata>, error::LoadMetadata> {
     let mut buffer = Vec::new();
     let mut result = Vec::new();
 
@@ -141,9 +231,30 @@ fn territories<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<Metadata>, erro
             Event::Text(_) | Event::Comment(_) => (),
 
             Event::Start(ref e) => match e.name().into_inner() {
-                b"territory" => result.push(territory(reader, e)?),
+                b"territory" => {
+                    // Keep the ID around so errors can point at the territory.
+                    let id = e
+                        .try_get_attribute("id")
+                        .ok()
+                        .flatten()
+                        .and_then(|attr| String::from_utf8(attr.value.into_owned()).ok())
+                        .unwrap_or_else(|| "<unknown>".into());
+
+                    if !filter(&id) {
+                        ignore(reader, b"territory")?;
+                        continue;
+                    }
+
+                    result.push(territory(reader, e).map_err(|err| err.territory(id))?)
+                }
 
-                name => ignore(reader, name)?,
+                name => {
+                    return Err(error::Metadata::UnhandledElement {
+                        phase: "territories".into(),
+                        name: str::from_utf8(name)?.into(),
+                    }
+                    .into())
+                }
             },
 
             Event::End(ref e) if e.name().into_inner() == b"territories" => return Ok(result),
@@ -223,7 +334,7 @@ fn territory<R: BufRead>(
 
             (name, value) => {
                 return Err(error::Metadata::UnhandledAttribute {
-                    phase: "format".into(),
+                    phase: "territory".into(),
                     name: name.into(),
                     value: value.into(),
                 }
@@ -265,6 +376,18 @@ fn territory<R: BufRead>(
 
                 name @ b"voicemail" => meta.voicemail = Some(descriptor(reader, &meta, name)?),
 
+                name @ b"shortCode" => meta.short_code = Some(descriptor(reader, &meta, name)?),
+
+                name @ b"standardRate" => {
+                    meta.standard_rate = Some(descriptor(reader, &meta, name)?)
+                }
+
+                name @ b"carrierSpecific" => meta.carrier = Some(descriptor(reader, &meta, name)?),
+
+                name @ b"smsServices" => meta.sms_services = Some(descriptor(reader, &meta, name)?),
+
+                name @ b"expandedEmergency" => ignore(reader, name)?,
+
                 name @ b"noInternationalDialling" => {
                     meta.no_international = Some(descriptor(reader, &meta, name)?)
                 }
@@ -285,7 +408,10 @@ fn territory<R: BufRead>(
                 }
             },
 
-            Event::End(ref e) if e.name().into_inner() == b"territory" => return Ok(meta),
+            Event::End(ref e) if e.name().into_inner() == b"territory" => {
+                lengths(&mut meta);
+                return Ok(meta);
+            }
 
             Event::End(ref e) => {
                 return Err(error::Metadata::MismatchedTag(
@@ -307,6 +433,54 @@ fn territory<R: BufRead>(
     }
 }
 
+/// The general descriptor has no lengths of its own in the XML, they are the
+/// union of the lengths of all the types of number in the territory.
+fn lengths(meta: &mut Metadata) {
+    let mut national = Vec::new();
+    let mut local = Vec::new();
+
+    for desc in [
+        &meta.fixed_line,
+        &meta.mobile,
+        &meta.toll_free,
+        &meta.premium_rate,
+        &meta.shared_cost,
+        &meta.personal_number,
+        &meta.voip,
+        &meta.pager,
+        &meta.uan,
+        &meta.voicemail,
+        &meta.emergency,
+        &meta.short_code,
+        &meta.standard_rate,
+        &meta.carrier,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|d| d.national_number.is_some())
+    {
+        national.extend(&desc.possible_length);
+        local.extend(&desc.possible_local_length);
+    }
+
+    national.sort_unstable();
+    national.dedup();
+
+    local.sort_unstable();
+    local.dedup();
+    local.retain(|l| !national.contains(l));
+
+    if let Some(general) = meta.general.as_mut() {
+        if general.possible_length.is_empty() {
+            general.possible_length = national;
+        }
+
+        if general.possible_local_length.is_empty() {
+            general.possible_local_length = local;
+        }
+    }
+}
+
 fn descriptor<R: BufRead>(
     reader: &mut Reader<R>,
     meta: &Metadata,
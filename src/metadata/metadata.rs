@@ -13,29 +13,32 @@
 // limitations under the License.
 
 use crate::{
-    metadata::{Descriptor, Format},
+    error,
+    metadata::{database, loader, Descriptor, DescriptorBuilder, Format, FormatBuilder, LazyRegex},
     phone_number::Type,
 };
-use regex_cache::CachedRegex;
 
 /// Phone number metadata.
+///
+/// The accessors are a stable read-only view of the metadata of a region, which
+/// can be used to drive custom formatting or input masks.
 #[derive(Clone, Debug)]
 pub struct Metadata {
     pub(crate) descriptors: Descriptors,
     pub(crate) id: String,
     pub(crate) country_code: u16,
 
-    pub(crate) international_prefix: Option<CachedRegex>,
+    pub(crate) international_prefix: Option<LazyRegex>,
     pub(crate) preferred_international_prefix: Option<String>,
     pub(crate) national_prefix: Option<String>,
     pub(crate) preferred_extension_prefix: Option<String>,
-    pub(crate) national_prefix_for_parsing: Option<CachedRegex>,
+    pub(crate) national_prefix_for_parsing: Option<LazyRegex>,
     pub(crate) national_prefix_transform_rule: Option<String>,
 
     pub(crate) formats: Vec<Format>,
     pub(crate) international_formats: Vec<Format>,
     pub(crate) main_country_for_code: bool,
-    pub(crate) leading_digits: Option<CachedRegex>,
+    pub(crate) leading_digits: Option<LazyRegex>,
     pub(crate) mobile_number_portable: bool,
 }
 
@@ -58,14 +61,40 @@ pub struct Descriptors {
     pub(crate) standard_rate: Option<Descriptor>,
     pub(crate) carrier: Option<Descriptor>,
     pub(crate) no_international: Option<Descriptor>,
+    pub(crate) sms_services: Option<Descriptor>,
 }
 
 impl Metadata {
+    /// Start building metadata by hand for the region with the given ID and
+    /// country calling code, for example to make a small fixed database for
+    /// tests, which doesn't change when the bundled metadata is updated.
+    ///
+    /// The general descriptor has to be set before building.
+    pub fn builder<S: Into<String>>(id: S, country_code: u16) -> MetadataBuilder {
+        MetadataBuilder(loader::Metadata {
+            id: Some(id.into()),
+            country_code: Some(country_code),
+            ..Default::default()
+        })
+    }
+
     /// Descriptors for the various types of phone number.
     pub fn descriptors(&self) -> &Descriptors {
         &self.descriptors
     }
 
+    /// The descriptor for the given type of phone number, if any, where
+    /// `Type::Unknown` gives the general descriptor.
+    pub fn descriptor(&self, kind: Type) -> Option<&Descriptor> {
+        self.descriptors.get(kind)
+    }
+
+    /// The lengths national significant numbers of any type can be in this
+    /// region, from smallest to biggest.
+    pub fn possible_lengths(&self) -> &[u16] {
+        self.descriptors.general.possible_length()
+    }
+
     /// The CLDR 2-letter representation of a country/region, with the exception
     /// of "country calling codes" used for non-geographical entities, such as
     /// Universal International Toll Free Number (+800). These are all given the
@@ -87,7 +116,7 @@ impl Metadata {
     /// by the country code for country B. Note that some countries may have more
     /// than one international prefix, and for those cases, a regular expression
     /// matching the international prefixes will be stored in this field.
-    pub fn international_prefix(&self) -> Option<&CachedRegex> {
+    pub fn international_prefix(&self) -> Option<&LazyRegex> {
         self.international_prefix.as_ref()
     }
 
@@ -132,7 +161,7 @@ impl Metadata {
     ///
     /// When it is missing from the XML file, this field inherits the value of
     /// national prefix, if that is present.
-    pub fn national_prefix_for_parsing(&self) -> Option<&CachedRegex> {
+    pub fn national_prefix_for_parsing(&self) -> Option<&LazyRegex> {
         self.national_prefix_for_parsing.as_ref()
     }
 
@@ -213,7 +242,7 @@ impl Metadata {
     /// It is used merely as a short-cut for working out which region a number
     /// comes from in the case that there is only one, so leading digit prefixes
     /// should not overlap.
-    pub fn leading_digits(&self) -> Option<&CachedRegex> {
+    pub fn leading_digits(&self) -> Option<&LazyRegex> {
         self.leading_digits.as_ref()
     }
 
@@ -224,6 +253,47 @@ impl Metadata {
     pub fn is_mobile_number_portable(&self) -> bool {
         self.mobile_number_portable
     }
+
+    /// All the regular expressions of the metadata.
+    pub(crate) fn regexes(&self) -> impl Iterator<Item = &LazyRegex> {
+        let desc = &self.descriptors;
+        let descriptors = [
+            Some(&desc.general),
+            desc.fixed_line.as_ref(),
+            desc.mobile.as_ref(),
+            desc.toll_free.as_ref(),
+            desc.premium_rate.as_ref(),
+            desc.shared_cost.as_ref(),
+            desc.personal_number.as_ref(),
+            desc.voip.as_ref(),
+            desc.pager.as_ref(),
+            desc.uan.as_ref(),
+            desc.emergency.as_ref(),
+            desc.voicemail.as_ref(),
+            desc.short_code.as_ref(),
+            desc.standard_rate.as_ref(),
+            desc.carrier.as_ref(),
+            desc.no_international.as_ref(),
+            desc.sms_services.as_ref(),
+        ];
+
+        self.international_prefix
+            .iter()
+            .chain(&self.national_prefix_for_parsing)
+            .chain(&self.leading_digits)
+            .chain(
+                descriptors
+                    .into_iter()
+                    .flatten()
+                    .map(|d| &d.national_number),
+            )
+            .chain(
+                self.formats
+                    .iter()
+                    .chain(&self.international_formats)
+                    .flat_map(Format::regexes),
+            )
+    }
 }
 
 impl Descriptors {
@@ -264,67 +334,278 @@ impl Descriptors {
         }
     }
 
+    /// The general descriptor, matching all valid numbers of the region.
     pub fn general(&self) -> &Descriptor {
         &self.general
     }
 
+    /// The descriptor for fixed-line numbers.
     pub fn fixed_line(&self) -> Option<&Descriptor> {
         self.fixed_line.as_ref()
     }
 
+    /// The descriptor for mobile numbers.
     pub fn mobile(&self) -> Option<&Descriptor> {
         self.mobile.as_ref()
     }
 
+    /// The descriptor for toll-free numbers.
     pub fn toll_free(&self) -> Option<&Descriptor> {
         self.toll_free.as_ref()
     }
 
+    /// The descriptor for premium rate numbers.
     pub fn premium_rate(&self) -> Option<&Descriptor> {
         self.premium_rate.as_ref()
     }
 
+    /// The descriptor for shared cost numbers.
     pub fn shared_cost(&self) -> Option<&Descriptor> {
         self.shared_cost.as_ref()
     }
 
+    /// The descriptor for personal numbers.
     pub fn personal_number(&self) -> Option<&Descriptor> {
         self.personal_number.as_ref()
     }
 
+    /// The descriptor for VoIP numbers.
     pub fn voip(&self) -> Option<&Descriptor> {
         self.voip.as_ref()
     }
 
+    /// The descriptor for pager numbers.
     pub fn pager(&self) -> Option<&Descriptor> {
         self.pager.as_ref()
     }
 
+    /// The descriptor for universal access numbers.
     pub fn uan(&self) -> Option<&Descriptor> {
         self.uan.as_ref()
     }
 
+    /// The descriptor for emergency numbers.
     pub fn emergency(&self) -> Option<&Descriptor> {
         self.emergency.as_ref()
     }
 
+    /// The descriptor for voicemail access numbers.
     pub fn voicemail(&self) -> Option<&Descriptor> {
         self.voicemail.as_ref()
     }
 
+    /// The descriptor for short codes.
     pub fn short_code(&self) -> Option<&Descriptor> {
         self.short_code.as_ref()
     }
 
+    /// The descriptor for standard rate short numbers.
     pub fn standard_rate(&self) -> Option<&Descriptor> {
         self.standard_rate.as_ref()
     }
 
+    /// The descriptor for carrier specific short numbers.
     pub fn carrier(&self) -> Option<&Descriptor> {
         self.carrier.as_ref()
     }
 
+    /// The descriptor for numbers which can't be dialled from abroad.
     pub fn no_international(&self) -> Option<&Descriptor> {
         self.no_international.as_ref()
     }
+
+    /// The descriptor for SMS service short numbers.
+    pub fn sms_services(&self) -> Option<&Descriptor> {
+        self.sms_services.as_ref()
+    }
+}
+
+/// Builder for `Metadata`, created with `Metadata::builder`.
+#[derive(Clone, Debug)]
+pub struct MetadataBuilder(loader::Metadata);
+
+impl MetadataBuilder {
+    /// Set the general descriptor, matching all valid numbers of the region.
+    pub fn general(mut self, descriptor: DescriptorBuilder) -> Self {
+        self.0.general = Some(descriptor.0);
+        self
+    }
+
+    /// Set the descriptor for the given phone number type, `Type::Unknown`
+    /// being the general descriptor.
+    pub fn descriptor(mut self, kind: Type, descriptor: DescriptorBuilder) -> Self {
+        let descriptor = Some(descriptor.0);
+
+        match kind {
+            Type::Unknown => self.0.general = descriptor,
+            Type::FixedLine | Type::FixedLineOrMobile => self.0.fixed_line = descriptor,
+            Type::Mobile => self.0.mobile = descriptor,
+            Type::TollFree => self.0.toll_free = descriptor,
+            Type::PremiumRate => self.0.premium_rate = descriptor,
+            Type::SharedCost => self.0.shared_cost = descriptor,
+            Type::PersonalNumber => self.0.personal_number = descriptor,
+            Type::Voip => self.0.voip = descriptor,
+            Type::Pager => self.0.pager = descriptor,
+            Type::Uan => self.0.uan = descriptor,
+            Type::Emergency => self.0.emergency = descriptor,
+            Type::Voicemail => self.0.voicemail = descriptor,
+            Type::ShortCode => self.0.short_code = descriptor,
+            Type::StandardRate => self.0.standard_rate = descriptor,
+            Type::Carrier => self.0.carrier = descriptor,
+            Type::NoInternational => self.0.no_international = descriptor,
+        }
+
+        self
+    }
+
+    /// Set the descriptor of the numbers for SMS services, for short number
+    /// metadata.
+    pub fn sms_services(mut self, descriptor: DescriptorBuilder) -> Self {
+        self.0.sms_services = Some(descriptor.0);
+        self
+    }
+
+    /// Set the international prefix pattern, like "00".
+    pub fn international_prefix<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.0.international_prefix = Some(pattern.into());
+        self
+    }
+
+    /// Set the preferred international prefix, when the pattern allows more.
+    pub fn preferred_international_prefix<S: Into<String>>(mut self, value: S) -> Self {
+        self.0.preferred_international_prefix = Some(value.into());
+        self
+    }
+
+    /// Set the national prefix, like "0".
+    pub fn national_prefix<S: Into<String>>(mut self, value: S) -> Self {
+        self.0.national_prefix = Some(value.into());
+        self
+    }
+
+    /// Set the preferred prefix for extensions, like " ext. ".
+    pub fn preferred_extension_prefix<S: Into<String>>(mut self, value: S) -> Self {
+        self.0.preferred_extension_prefix = Some(value.into());
+        self
+    }
+
+    /// Set the pattern of the national prefix to strip when parsing.
+    pub fn national_prefix_for_parsing<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.0.national_prefix_for_parsing = Some(pattern.into());
+        self
+    }
+
+    /// Set the rule to transform the national prefix with when parsing.
+    pub fn national_prefix_transform_rule<S: Into<String>>(mut self, rule: S) -> Self {
+        self.0.national_prefix_transform_rule = Some(rule.into());
+        self
+    }
+
+    /// Add a format for national numbers, which is also used for international
+    /// numbers unless international formats are added.
+    pub fn format(mut self, format: FormatBuilder) -> Self {
+        self.0.formats.push(format.0);
+        self
+    }
+
+    /// Add a format for international numbers.
+    pub fn international_format(mut self, format: FormatBuilder) -> Self {
+        self.0.international_formats.push(format.0);
+        self
+    }
+
+    /// Set whether the region is the main one for its country calling code.
+    pub fn main_country_for_code(mut self, value: bool) -> Self {
+        self.0.main_country_for_code = value;
+        self
+    }
+
+    /// Set the pattern of the leading digits of numbers from the region, when
+    /// it shares its country calling code with others.
+    pub fn leading_digits<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.0.leading_digits = Some(pattern.into());
+        self
+    }
+
+    /// Set whether the region has implemented mobile number portability.
+    pub fn mobile_number_portable(mut self, value: bool) -> Self {
+        self.0.mobile_number_portable = value;
+        self
+    }
+
+    /// Build the metadata, checking the syntax of all the patterns.
+    pub fn build(self) -> Result<Metadata, error::LoadMetadata> {
+        let id = self.0.id.clone().unwrap_or_default();
+        database::metadata(self.0, &database::Interner::new(true)).map_err(|err| err.territory(id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::error;
+    use crate::metadata::{Descriptor, Format, Metadata, DATABASE};
+    use crate::phone_number::Type;
+
+    #[test]
+    fn accessors() {
+        let meta = DATABASE.by_id(country::DE.as_ref()).unwrap();
+
+        assert_eq!("DE", meta.id());
+        assert_eq!(49, meta.country_code());
+        assert_eq!(Some("0"), meta.national_prefix());
+        assert_eq!(Some("00"), meta.international_prefix().map(|p| p.as_str()));
+        assert!(meta.possible_lengths().contains(&11));
+        assert_eq!(
+            meta.possible_lengths(),
+            meta.descriptors().general().possible_length()
+        );
+
+        assert!(!meta.formats().is_empty());
+        let format = &meta.formats()[0];
+        assert!(!format.pattern().as_str().is_empty());
+        assert!(format.format().contains("$1"));
+        assert!(!format.leading_digits().is_empty());
+
+        let mobile = meta.descriptor(Type::Mobile).unwrap();
+        assert!(mobile.is_match("15123456789"));
+        assert!(mobile.example().is_some());
+        assert!(meta.descriptor(Type::Voip).is_none());
+    }
+
+    #[test]
+    fn builder() {
+        let meta = Metadata::builder("AD", 376)
+            .international_prefix("00")
+            .format(Format::builder(r"(\d{3})(\d{3})", "$1 $2").leading_digits("[135-9]"))
+            .general(Descriptor::builder(r"[135-9]\d{5}").possible_length(&[6]))
+            .descriptor(
+                Type::FixedLine,
+                Descriptor::builder(r"[78]\d{5}").example("712345"),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!("AD", meta.id());
+        assert_eq!(376, meta.country_code());
+        assert_eq!(&[6], meta.possible_lengths());
+        assert_eq!("$1 $2", meta.formats()[0].format());
+        assert!(meta.descriptor(Type::FixedLine).unwrap().is_match("712345"));
+        assert!(!meta
+            .descriptor(Type::FixedLine)
+            .unwrap()
+            .is_match("7123456"));
+        assert!(meta.descriptor(Type::Mobile).is_none());
+
+        assert!(matches!(
+            Metadata::builder("AD", 376).build(),
+            Err(error::LoadMetadata::Territory { .. })
+        ));
+        assert!(Metadata::builder("AD", 376)
+            .general(Descriptor::builder("1("))
+            .build()
+            .is_err());
+        assert!(Format::builder("1(", "$1").build().is_err());
+        assert!(Descriptor::builder(r"\d{6}").build().is_ok());
+    }
 }
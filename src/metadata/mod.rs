@@ -12,18 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod lazy_regex;
+pub use self::lazy_regex::LazyRegex;
+
 mod format;
-pub use self::format::Format;
+pub use self::format::{Format, FormatBuilder};
 
 mod descriptor;
-pub use self::descriptor::Descriptor;
+pub use self::descriptor::{Descriptor, DescriptorBuilder};
 
 #[allow(clippy::module_inception)]
 mod metadata;
-pub use self::metadata::{Descriptors, Metadata};
+pub use self::metadata::{Descriptors, Metadata, MetadataBuilder};
+
+#[cfg(any(test, feature = "test-metadata"))]
+mod test_metadata;
+#[cfg(any(test, feature = "test-metadata"))]
+pub use self::test_metadata::test_metadata;
+
+mod prefix_table;
+pub use self::prefix_table::{PrefixRange, PrefixTableError};
 
 mod database;
-pub use self::database::{Database, DEFAULT as DATABASE};
+pub(crate) use self::database::with_global;
+pub use self::database::{
+    global, set_global, try_global, Database, BUNDLED_VERSION, DEFAULT as DATABASE,
+};
 
 /// XML loading helpers.
 pub mod loader;
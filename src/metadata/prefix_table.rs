@@ -0,0 +1,495 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+use thiserror::Error;
+
+use crate::country;
+use crate::metadata::Descriptor;
+
+/// All ten digits as a set.
+const ANY: u16 = 0x3ff;
+
+/// Alternatives aren't expanded any further past this many, the rest of the
+/// expression only counts towards the lengths then.
+const MAX_PATHS: usize = 4096;
+
+/// Prefixes are cut short when they'd expand to more ranges than this.
+const MAX_RANGES: usize = 1024;
+
+/// Reasons a prefix table can't be built.
+#[derive(Error, Clone, Eq, PartialEq, Debug)]
+pub enum PrefixTableError {
+    /// There is no metadata for the region.
+    #[error("no metadata for region {0:?}")]
+    UnknownRegion(country::Id),
+
+    /// The pattern can match numbers of any length, so it can't be turned
+    /// into ranges.
+    #[error("unsupported pattern: {0}")]
+    UnsupportedPattern(String),
+}
+
+/// Numbers starting with any prefix from `start` to `end`, which have the
+/// same number of digits, and a national significant number of the given
+/// lengths.
+///
+/// For example "8002" to "8009" with length 10 are the numbers from
+/// 8002000000 to 8009999999.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PrefixRange {
+    start: String,
+    end: String,
+    min_length: u16,
+    max_length: u16,
+}
+
+impl PrefixRange {
+    /// The lowest prefix of the range.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The highest prefix of the range.
+    pub fn end(&self) -> &str {
+        &self.end
+    }
+
+    /// The shortest length of numbers in the range.
+    pub fn min_length(&self) -> u16 {
+        self.min_length
+    }
+
+    /// The longest length of numbers in the range.
+    pub fn max_length(&self) -> u16 {
+        self.max_length
+    }
+
+    /// Check if the national significant number is in the range.
+    pub fn contains(&self, national: &str) -> bool {
+        let len = national.len() as u16;
+
+        len >= self.min_length
+            && len <= self.max_length
+            && national.len() >= self.start.len()
+            && (self.start.as_str()..=self.end.as_str()).contains(&&national[..self.start.len()])
+    }
+}
+
+/// The digits of a pattern as sets, until a wildcard makes anything after it
+/// only count for the length.
+#[derive(Clone, Default, Debug)]
+struct Path {
+    sets: Vec<u16>,
+    open: bool,
+    min: u16,
+    max: u16,
+}
+
+impl Path {
+    fn open(min: u16, max: u16) -> Self {
+        Path {
+            sets: Vec::new(),
+            open: true,
+            min,
+            max,
+        }
+    }
+
+    fn join(&self, other: &Path) -> Path {
+        let mut path = self.clone();
+
+        if path.open {
+            path.min += other.sets.len() as u16 + other.min;
+            path.max += other.sets.len() as u16 + other.max;
+        } else {
+            path.sets.extend_from_slice(&other.sets);
+            path.open = other.open;
+            path.min = other.min;
+            path.max = other.max;
+        }
+
+        path
+    }
+}
+
+/// Build the prefix table of the numbers matching the descriptor.
+pub(crate) fn build(descriptor: &Descriptor) -> Result<Vec<PrefixRange>, PrefixTableError> {
+    let pattern = descriptor.national_number().as_str();
+    let hir = regex_syntax::ParserBuilder::new()
+        .ignore_whitespace(true)
+        .build()
+        .parse(pattern)
+        .map_err(|_| PrefixTableError::UnsupportedPattern(pattern.into()))?;
+    let possible = descriptor.possible_length();
+
+    let mut ranges = Vec::new();
+
+    for mut path in expand(&hir)? {
+        // Keep the number of ranges in check by making the last digits count
+        // for the length only.
+        while path
+            .sets
+            .iter()
+            .rev()
+            .skip(1)
+            .try_fold(1usize, |n, set| n.checked_mul(set.count_ones() as usize))
+            .map(|n| n > MAX_RANGES)
+            .unwrap_or(true)
+        {
+            path.sets.pop();
+            path.min += 1;
+            path.max += 1;
+        }
+
+        let mut min = path.sets.len() as u16 + path.min;
+        let mut max = path.sets.len() as u16 + path.max;
+
+        if !possible.is_empty() {
+            match (
+                possible
+                    .iter()
+                    .copied()
+                    .filter(|l| (min..=max).contains(l))
+                    .min(),
+                possible
+                    .iter()
+                    .copied()
+                    .filter(|l| (min..=max).contains(l))
+                    .max(),
+            ) {
+                (Some(low), Some(high)) => {
+                    min = low;
+                    max = high;
+                }
+
+                _ => continue,
+            }
+        }
+
+        if max == 0 {
+            continue;
+        }
+
+        let (last, head) = match path.sets.split_last() {
+            Some((&last, head)) => (last, head),
+            None => (ANY, &[][..]),
+        };
+
+        let mut prefixes = vec![String::new()];
+        for &set in head {
+            prefixes = prefixes
+                .iter()
+                .flat_map(|prefix| digits(set).map(move |d| format!("{}{}", prefix, d)))
+                .collect();
+        }
+
+        for prefix in &prefixes {
+            for (low, high) in runs(last) {
+                ranges.push(PrefixRange {
+                    start: format!("{}{}", prefix, low),
+                    end: format!("{}{}", prefix, high),
+                    min_length: min,
+                    max_length: max,
+                });
+            }
+        }
+    }
+
+    ranges.sort();
+    ranges.dedup();
+
+    Ok(merge(ranges))
+}
+
+/// Merge ranges of the same numbers lengths which follow each other.
+fn merge(ranges: Vec<PrefixRange>) -> Vec<PrefixRange> {
+    let mut merged: Vec<PrefixRange> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        if let Some(last) = merged.last_mut() {
+            let len = range.start.len();
+
+            if last.start.len() == len
+                && last.min_length == range.min_length
+                && last.max_length == range.max_length
+                && last.start[..len - 1] == range.start[..len - 1]
+                && last.end.as_bytes()[len - 1] + 1 >= range.start.as_bytes()[len - 1]
+            {
+                if range.end > last.end {
+                    last.end = range.end;
+                }
+
+                continue;
+            }
+        }
+
+        merged.push(range);
+    }
+
+    merged
+}
+
+/// The digits in the set.
+fn digits(set: u16) -> impl Iterator<Item = u8> {
+    (0..10u8).filter(move |d| set & 1 << d != 0)
+}
+
+/// The runs of consecutive digits in the set.
+fn runs(set: u16) -> Vec<(u8, u8)> {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+
+    for d in digits(set) {
+        match runs.last_mut() {
+            Some((_, high)) if *high + 1 == d => *high = d,
+            _ => runs.push((d, d)),
+        }
+    }
+
+    runs
+}
+
+/// Expand the expression into paths, leaving out anything that can't be part
+/// of a number.
+fn expand(hir: &Hir) -> Result<Vec<Path>, PrefixTableError> {
+    Ok(match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => vec![Path::default()],
+
+        HirKind::Literal(literal) => {
+            if !literal.0.iter().all(u8::is_ascii_digit) {
+                return Ok(Vec::new());
+            }
+
+            let mut path = Path::default();
+            for &byte in literal.0.iter() {
+                path = path.join(&set(1 << (byte - b'0')));
+            }
+
+            vec![path]
+        }
+
+        HirKind::Class(class) => {
+            let digits = (0..10u8)
+                .filter(|&d| match class {
+                    Class::Unicode(class) => class
+                        .iter()
+                        .any(|r| r.start() <= (b'0' + d) as char && (b'0' + d) as char <= r.end()),
+                    Class::Bytes(class) => class
+                        .iter()
+                        .any(|r| r.start() <= b'0' + d && b'0' + d <= r.end()),
+                })
+                .fold(0, |acc, d| acc | 1 << d);
+
+            match digits {
+                0 => Vec::new(),
+                digits => vec![set(digits)],
+            }
+        }
+
+        HirKind::Capture(capture) => expand(&capture.sub)?,
+
+        HirKind::Concat(subs) => {
+            let mut paths = vec![Path::default()];
+
+            for sub in subs {
+                paths = join(&paths, sub, &expand(sub)?)?;
+            }
+
+            paths
+        }
+
+        HirKind::Alternation(subs) => {
+            let mut paths = Vec::new();
+
+            for sub in subs {
+                paths.extend(expand(sub)?);
+            }
+
+            paths
+        }
+
+        HirKind::Repetition(repetition) => {
+            let max = repetition
+                .max
+                .ok_or_else(|| PrefixTableError::UnsupportedPattern(hir.to_string()))?;
+            let parts = expand(&repetition.sub)?;
+
+            // Any digits only count for the length.
+            if let [part] = &*parts {
+                if part.open && part.sets.is_empty() {
+                    return Ok(vec![Path::open(
+                        part.min * repetition.min as u16,
+                        part.max * max as u16,
+                    )]);
+                }
+            }
+
+            let mut paths = Vec::new();
+            let mut repeated = vec![Path::default()];
+
+            for count in 0..=max {
+                if count >= repetition.min {
+                    paths.extend(repeated.iter().cloned());
+                }
+
+                if count < max {
+                    repeated = join(&repeated, &repetition.sub, &parts)?;
+                }
+            }
+
+            paths
+        }
+    })
+}
+
+/// A path of one digit out of the set, opened right away if it's any digit.
+fn set(digits: u16) -> Path {
+    if digits == ANY {
+        Path::open(1, 1)
+    } else {
+        Path {
+            sets: vec![digits],
+            ..Path::default()
+        }
+    }
+}
+
+/// Join each of the paths with each of the paths of the expression, only
+/// counting the length of the expression when that would be too many.
+fn join(paths: &[Path], hir: &Hir, parts: &[Path]) -> Result<Vec<Path>, PrefixTableError> {
+    if paths.len() * parts.len() <= MAX_PATHS {
+        return Ok(paths
+            .iter()
+            .flat_map(|path| parts.iter().map(move |part| path.join(part)))
+            .collect());
+    }
+
+    let properties = hir.properties();
+    let max = properties
+        .maximum_len()
+        .ok_or_else(|| PrefixTableError::UnsupportedPattern(hir.to_string()))?;
+    let open = Path::open(properties.minimum_len().unwrap_or(0) as u16, max as u16);
+
+    Ok(paths.iter().map(|path| path.join(&open)).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::metadata::prefix_table::PrefixTableError;
+    use crate::metadata::{Database, Descriptor, DATABASE};
+    use crate::phone_number::Type;
+
+    fn table(pattern: &str) -> Vec<(String, String, u16, u16)> {
+        super::build(&Descriptor::builder(pattern).build().unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.start.clone(), r.end.clone(), r.min_length, r.max_length))
+            .collect()
+    }
+
+    fn range(start: &str, end: &str, min: u16, max: u16) -> (String, String, u16, u16) {
+        (start.into(), end.into(), min, max)
+    }
+
+    #[test]
+    fn syntax() {
+        assert_eq!(vec![range("2", "9", 10, 10)], table(r"[2-9]\d{9}"));
+        assert_eq!(
+            vec![range("12", "12", 4, 4), range("34", "35", 3, 5)],
+            table(r"12\d\d|3(?:4|5)\d{1,3}")
+        );
+        assert_eq!(
+            vec![range("1", "1", 3, 3), range("12", "12", 4, 4)],
+            table(r"12?\d\d")
+        );
+        assert_eq!(vec![range("0", "9", 1, 3)], table(r"\d{1,3}"));
+        assert_eq!(
+            vec![range("13", "13", 2, 2), range("15", "19", 2, 2)],
+            table("1[35-9]")
+        );
+
+        // Anything but digits can't be part of a number.
+        assert_eq!(
+            vec![range("0", "0", 1, 1), range("2", "9", 1, 1)],
+            table(r"[^1]|a")
+        );
+        assert_eq!(vec![range("1", "1", 2, 2)], table(r"1."));
+
+        for pattern in &[r"1+", r"1*", r"1\d{2,}"] {
+            assert!(
+                matches!(
+                    super::build(&Descriptor::builder(*pattern).build().unwrap()),
+                    Err(PrefixTableError::UnsupportedPattern(_))
+                ),
+                "{}",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn us_toll_free() {
+        let table = DATABASE.prefix_table(country::US, Type::TollFree).unwrap();
+
+        for prefix in &["800", "833", "844", "855", "866", "877", "888"] {
+            let range = table
+                .iter()
+                .find(|r| r.start().starts_with(prefix))
+                .unwrap();
+
+            assert_eq!(format!("{}2", prefix), range.start());
+            assert_eq!(format!("{}9", prefix), range.end());
+            assert_eq!(10, range.min_length());
+            assert_eq!(10, range.max_length());
+        }
+
+        assert_eq!(7, table.len());
+        assert!(table.iter().any(|r| r.contains("8002345678")));
+        assert!(!table.iter().any(|r| r.contains("8001345678")));
+        assert!(!table.iter().any(|r| r.contains("800234567")));
+    }
+
+    #[test]
+    fn de_mobile() {
+        let table = DATABASE.prefix_table(country::DE, Type::Mobile).unwrap();
+
+        for prefix in &["15", "16", "17"] {
+            assert!(table.iter().any(|r| r.start().starts_with(prefix)));
+        }
+
+        for number in &["15123456789", "1601234567", "17012345678"] {
+            assert!(table.iter().any(|r| r.contains(number)), "{}", number);
+        }
+
+        assert!(!table.iter().any(|r| r.contains("30123456")));
+        assert!(!table.iter().any(|r| r.contains("1612345678")));
+    }
+
+    #[test]
+    fn every_pattern() {
+        for (id, meta) in DATABASE.iter_regions() {
+            for kind in &[Type::FixedLine, Type::Mobile, Type::TollFree, Type::Voip] {
+                if meta.descriptors().get(*kind).is_some() {
+                    assert!(DATABASE.prefix_table(id, *kind).is_ok(), "{:?}", id);
+                }
+            }
+        }
+
+        assert_eq!(
+            Err(PrefixTableError::UnknownRegion(country::AC)),
+            Database::from_metadata(None).prefix_table(country::AC, Type::Mobile)
+        );
+    }
+}
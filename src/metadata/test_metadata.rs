@@ -0,0 +1,207 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::metadata::{Database, Descriptor, Format, Metadata};
+use crate::phone_number::Type;
+
+/// Get a small hand-written database, modelled after the US and GB metadata
+/// libphonenumber uses for its own tests.
+///
+/// Unlike the bundled metadata it never changes, so tests relying on it don't
+/// break when the bundled metadata is updated.
+pub fn test_metadata() -> Database {
+    let us = Metadata::builder("US", 1)
+        .international_prefix("011")
+        .national_prefix("1")
+        .national_prefix_for_parsing("1")
+        .preferred_extension_prefix(" extn. ")
+        .main_country_for_code(true)
+        .format(Format::builder(r"(\d{3})(\d{4})", "$1 $2"))
+        .format(Format::builder(r"(\d{3})(\d{3})(\d{4})", "$1 $2 $3"))
+        .international_format(Format::builder(r"(\d{3})(\d{3})(\d{4})", "$1 $2 $3"))
+        .general(Descriptor::builder(r"[13-689]\d{9}|2[0-35-9]\d{8}").possible_length(&[10]))
+        .descriptor(
+            Type::FixedLine,
+            Descriptor::builder(r"[13-689]\d{9}|2[0-35-9]\d{8}")
+                .possible_length(&[10])
+                .possible_local_length(&[7])
+                .example("1234567890"),
+        )
+        .descriptor(
+            Type::Mobile,
+            Descriptor::builder(r"[13-689]\d{9}|2[0-35-9]\d{8}")
+                .possible_length(&[10])
+                .possible_local_length(&[7])
+                .example("1234567890"),
+        )
+        .descriptor(
+            Type::TollFree,
+            Descriptor::builder(r"8(?:00|66|77|88)\d{7}")
+                .possible_length(&[10])
+                .example("8002123456"),
+        )
+        .descriptor(
+            Type::PremiumRate,
+            Descriptor::builder(r"900\d{7}")
+                .possible_length(&[10])
+                .example("9002123456"),
+        )
+        .build()
+        .unwrap();
+
+    let gb = Metadata::builder("GB", 44)
+        .international_prefix("00")
+        .national_prefix("0")
+        .format(
+            Format::builder(r"(\d{2})(\d{4})(\d{4})", "$1 $2 $3")
+                .leading_digits("[1-59]|[78]0")
+                .national_prefix("($NP$FG)"),
+        )
+        .format(
+            Format::builder(r"(\d)(\d{3})(\d{3})(\d{3})", "$1 $2 $3 $4")
+                .leading_digits("6")
+                .national_prefix("($NP$FG)"),
+        )
+        .format(
+            Format::builder(r"(\d{4})(\d{3})(\d{3})", "$1 $2 $3")
+                .leading_digits("7[1-57-9]")
+                .national_prefix("($NP$FG)"),
+        )
+        .general(Descriptor::builder(r"\d{10}").possible_length(&[10]))
+        .descriptor(
+            Type::FixedLine,
+            Descriptor::builder(r"[1-6]\d{9}")
+                .possible_length(&[10])
+                .example("1212345678"),
+        )
+        .descriptor(
+            Type::Mobile,
+            Descriptor::builder(r"7[1-57-9]\d{8}")
+                .possible_length(&[10])
+                .example("7012345678"),
+        )
+        .descriptor(
+            Type::TollFree,
+            Descriptor::builder(r"80\d{8}")
+                .possible_length(&[10])
+                .example("8012345678"),
+        )
+        .descriptor(
+            Type::PremiumRate,
+            Descriptor::builder(r"9[018]\d{8}")
+                .possible_length(&[10])
+                .example("9187654321"),
+        )
+        .descriptor(
+            Type::PersonalNumber,
+            Descriptor::builder(r"70\d{8}")
+                .possible_length(&[10])
+                .example("7031231234"),
+        )
+        .descriptor(
+            Type::Voip,
+            Descriptor::builder(r"56\d{8}")
+                .possible_length(&[10])
+                .example("5612345678"),
+        )
+        .build()
+        .unwrap();
+
+    Database::from_metadata(vec![us, gb])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::formatter::Mode;
+    use crate::matcher::{self, MatchType};
+    use crate::metadata::test_metadata;
+    use crate::parser;
+    use crate::phone_number::Type;
+    use crate::validator;
+    use crate::ParseError;
+
+    #[test]
+    fn parse() {
+        let database = test_metadata();
+
+        let number = parser::parse_with(&database, Some(country::GB), "020 7031 3000").unwrap();
+        assert_eq!(44, number.code().value());
+        assert_eq!(2070313000, number.national().value());
+        assert!(number.is_valid_with(&database));
+        assert_eq!(Type::FixedLine, number.number_type_with(&database));
+
+        let number = parser::parse_with(&database, Some(country::US), "1 (650) 253-0000").unwrap();
+        assert_eq!(1, number.code().value());
+        assert_eq!(6502530000, number.national().value());
+        assert_eq!(Type::FixedLineOrMobile, number.number_type_with(&database));
+
+        assert!(matches!(
+            parser::parse_with(&database, Some(country::DE), "030 123456"),
+            Err(ParseError::UnsupportedRegion)
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let database = test_metadata();
+
+        let number = parser::parse_with(&database, None, "+44 7912 345678").unwrap();
+        assert_eq!(Type::Mobile, number.number_type_with(&database));
+        assert!(validator::is_valid_for_region_with(
+            &database,
+            &number,
+            country::GB
+        ));
+        assert!(!validator::is_valid_for_region_with(
+            &database,
+            &number,
+            country::US
+        ));
+
+        let number = parser::parse_with(&database, None, "+1 900 212 3456").unwrap();
+        assert_eq!(Type::PremiumRate, number.number_type_with(&database));
+
+        // Valid in the bundled metadata, but not in the test one.
+        let number = parser::parse_with(&database, None, "+44 845 464 7000").unwrap();
+        assert!(number.is_valid());
+        assert!(!number.is_valid_with(&database));
+    }
+
+    #[test]
+    fn format() {
+        let database = test_metadata();
+
+        let number = parser::parse_with(&database, Some(country::GB), "020 7031 3000").unwrap();
+        assert_eq!(
+            "(020) 7031 3000",
+            number
+                .format_with(&database)
+                .mode(Mode::National)
+                .to_string()
+        );
+        assert_eq!(
+            "+44 20 7031 3000",
+            number
+                .format_with(&database)
+                .mode(Mode::International)
+                .to_string()
+        );
+
+        assert_eq!(
+            MatchType::ExactMatch,
+            matcher::is_number_match_with(&database, &number, "+44 20 7031 3000")
+        );
+    }
+}
@@ -15,20 +15,18 @@
 use nom::{
     self,
     character::complete::*,
-    combinator::*,
     error::{make_error, ErrorKind},
-    multi::*,
     AsChar, IResult,
 };
 use std::borrow::Cow;
+use std::ops::Range;
 
 use fnv::FnvHashMap;
-use regex_cache::CachedRegex;
 
 use crate::consts;
 use crate::country;
 use crate::error;
-use crate::metadata::{Database, Metadata};
+use crate::metadata::{Database, LazyRegex, Metadata};
 use crate::phone_number::Type;
 use crate::validator;
 
@@ -101,7 +99,13 @@ pub fn star(i: &str) -> IResult<&str, char> {
 }
 
 pub fn ignore_plus(i: &str) -> IResult<&str, &str> {
-    recognize(many1(plus))(i)
+    let rest = i.trim_start_matches(|c: char| c.is_plus());
+
+    if rest.len() == i.len() {
+        Err(nom::Err::Error(make_error(i, ErrorKind::Many1)))
+    } else {
+        Ok((rest, &i[..i.len() - rest.len()]))
+    }
 }
 
 /// Attempts to extract a possible number from the string passed in. This
@@ -157,11 +161,11 @@ pub fn country_code<'a>(
             }
 
             // If the prefix was already extracted, check it is valid.
-            if number.prefix.is_some() {
-                let prefix = number.prefix.as_ref().unwrap().parse()?;
+            if let Some(prefix) = number.prefix.as_ref() {
+                let prefix = country_code_value(prefix)?;
 
-                if database.by_code(&prefix).is_none() {
-                    return Err(error::Parse::InvalidCountryCode);
+                if database.main_by_code(prefix).is_none() {
+                    return Err(unknown_code(prefix));
                 } else {
                     return Ok(number);
                 }
@@ -173,27 +177,36 @@ pub fn country_code<'a>(
                 }
 
                 // Try to find the first available country code.
-                for len in 1..consts::MAX_LENGTH_FOR_COUNTRY_CODE + 1 {
-                    let code = number.national[..len].parse().unwrap();
+                let mut codes = (1..consts::MAX_LENGTH_FOR_COUNTRY_CODE + 1).filter_map(|len| {
+                    Some((len, number.national.get(..len)?.parse::<u16>().ok()?))
+                });
 
-                    if database.by_code(&code).is_some() {
-                        number.national = trim(number.national, len);
-                        number.prefix = Some(code.to_string().into());
+                for (len, code) in codes.clone() {
+                    if database.main_by_code(code).is_some() {
+                        let (prefix, national) = split(number.national, len);
+                        number.prefix = Some(prefix);
+                        number.national = national;
 
                         return Ok(number);
                     }
                 }
+
+                if codes.any(|(_, code)| consts::CALLING_CODES.contains(&code)) {
+                    return Err(error::Parse::UnsupportedRegion);
+                }
             }
         }
 
         country::Source::Default => {
             if let Some(country) = country {
-                let meta = database.by_id(country.as_ref()).unwrap();
+                let meta = database
+                    .by_id(country.as_ref())
+                    .ok_or(error::Parse::UnsupportedRegion)?;
                 let code = meta.country_code.to_string();
 
                 if number.national.starts_with(&code)
                     && (!meta.descriptors().general().is_match(&number.national)
-                        || !validator::length(meta, &number, Type::Unknown).is_possible())
+                        || !validator::length(meta, &number.national, Type::Unknown).is_possible())
                 {
                     number.country = country::Source::Number;
                     number.national = trim(number.national, code.len());
@@ -209,12 +222,46 @@ pub fn country_code<'a>(
     Err(error::Parse::InvalidCountryCode)
 }
 
+/// Get the error for a country code missing from the database, telling apart
+/// the ones that exist but were left out of it.
+fn unknown_code(code: u16) -> error::Parse {
+    if consts::CALLING_CODES.contains(&code) {
+        error::Parse::UnsupportedRegion
+    } else {
+        error::Parse::InvalidCountryCode
+    }
+}
+
+/// Convert the digits of a country calling code, which never start with a
+/// zero or have more than `consts::MAX_LENGTH_FOR_COUNTRY_CODE` digits.
+pub fn country_code_value(prefix: &str) -> Result<u16, error::Parse> {
+    if prefix.is_empty()
+        || prefix.len() > consts::MAX_LENGTH_FOR_COUNTRY_CODE
+        || prefix.starts_with('0')
+        || !prefix.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(error::Parse::InvalidCountryCode);
+    }
+
+    Ok(prefix.parse()?)
+}
+
+/// Convert the digits of a national significant number, failing with
+/// `TooLong` before the conversion could overflow.
+pub fn national_value(national: &str) -> Result<u64, error::Parse> {
+    if national.len() > consts::MAX_LENGTH_FOR_NSN {
+        return Err(error::Parse::TooLong);
+    }
+
+    Ok(national.parse()?)
+}
+
 /// Strip the IDD from a `Number`, update the country code source, and
 /// normalize it.
 ///
 /// Note that since the IDD comes from a passed default region, we can find the
 /// country code from the given default if the country source is from the IDD.
-pub fn international_prefix<'a>(idd: Option<&CachedRegex>, mut number: Number<'a>) -> Number<'a> {
+pub fn international_prefix<'a>(idd: Option<&LazyRegex>, mut number: Number<'a>) -> Number<'a> {
     // If there's a prefix already, i.e. RFC3966, just change the country source.
     if number.prefix.is_some() {
         number.country = country::Source::Plus;
@@ -266,71 +313,60 @@ pub fn international_prefix<'a>(idd: Option<&CachedRegex>, mut number: Number<'a
 }
 
 /// Strip national prefix and extract carrier.
-pub fn national_number<'a>(meta: &Metadata, mut number: Number<'a>) -> Number<'a> {
+/// Strip the national prefix from the national number, returning the
+/// stripped number and the carrier code if anything was stripped.
+pub fn national_number<'a>(
+    meta: &Metadata,
+    national: &Cow<'a, str>,
+) -> Option<(Cow<'a, str>, Option<Cow<'a, str>>)> {
     let transform = meta.national_prefix_transform_rule.as_ref();
     let parsing = if let Some(re) = meta.national_prefix_for_parsing.as_ref() {
         re
     } else {
-        if let Some(prefix) = meta.national_prefix.as_ref() {
-            if number.national.starts_with(prefix) {
-                number.national = trim(number.national, prefix.len());
-            }
+        let prefix = meta.national_prefix.as_ref()?;
+
+        if national.starts_with(prefix) {
+            return Some((slice(national, prefix.len()..national.len()), None));
         }
 
-        return number;
+        return None;
     };
 
-    let index = parsing.find(&number.national).map(|m| (m.start(), m.end()));
-
-    if index.is_none() {
-        return number;
-    }
-
-    let (start, end) = index.unwrap();
+    let (start, end) = parsing.find(national).map(|m| (m.start(), m.end()))?;
     if start != 0 {
-        return number;
+        return None;
     }
 
-    let viable = meta.descriptors.general.is_match(&number.national);
-    let groups = parsing.captures_len();
+    let viable = meta.descriptors.general.is_match(national);
+    // The whole match is the implicit first group, and not a carrier code.
+    let groups = parsing.captures_len() - 1;
 
-    let (first, last) = parsing
-        .captures(&number.national)
-        .map(|c| {
-            (
-                c.get(1).map(|m| m.as_str().to_owned()),
-                c.get(c.len() - 1).map(|m| m.as_str().to_owned()),
-            )
-        })
-        .unwrap();
+    let captures = parsing.captures(national)?;
+    let first = captures.get(1).map(|m| m.range());
+    let last = captures.get(captures.len() - 1).map(|m| m.range());
 
     if transform.is_none() || last.is_none() {
-        if viable && !meta.descriptors.general.is_match(&number.national[start..]) {
-            return number;
+        if viable && !meta.descriptors.general.is_match(&national[start..]) {
+            return None;
         }
 
-        number.carrier = last.filter(|_| groups > 0).map(Into::into);
-
-        number.national = trim(number.national, end);
+        Some((
+            slice(national, end..national.len()),
+            last.filter(|_| groups > 0).map(|r| slice(national, r)),
+        ))
     } else if let Some(transform) = transform {
-        let transformed = parsing.replace(&number.national, transform).into_owned();
+        let transformed = parsing.replace(national, transform).into_owned();
 
         if viable && !meta.descriptors.general.is_match(&transformed) {
-            return number;
+            return None;
         }
 
-        number.carrier = Some(first.unwrap().into());
-        number.national = transformed.into();
+        Some((transformed.into(), first.map(|r| slice(national, r))))
+    } else {
+        None
     }
-
-    number
 }
 
-/// Normalize a given `Number`, replacing the characters matching the mappings
-/// and converting any Unicode non-decimal digits into their decimal
-/// counterpart.
-///
-/// Note if the `Number` is already normalized it does not get modified.
 pub fn normalize<'a>(mut number: Number<'a>, mappings: &FnvHashMap<char, char>) -> Number<'a> {
     fn act<'a>(value: Cow<'a, str>, mappings: &FnvHashMap<char, char>) -> Cow<'a, str> {
         let mut owned = None;
@@ -339,7 +375,8 @@ pub fn normalize<'a>(mut number: Number<'a>, mappings: &FnvHashMap<char, char>)
 
             while let Some((start, ch)) = chars.next() {
                 if !ch.is_dec_digit() {
-                    let mut string = String::from(&value[..start]);
+                    let mut string = String::with_capacity(value.len());
+                    string.push_str(&value[..start]);
 
                     if let Some(ch) = ch.as_dec_digit() {
                         string.push(ch);
@@ -370,6 +407,46 @@ pub fn normalize<'a>(mut number: Number<'a>, mappings: &FnvHashMap<char, char>)
     number
 }
 
+/// Normalize the value into the buffer like `normalize` does, but keeping any
+/// leading plus signs, or borrow it if it's normalized already.
+pub fn normalize_into<'a>(
+    value: Cow<'a, str>,
+    mappings: &FnvHashMap<char, char>,
+    buffer: &'a mut String,
+) -> Cow<'a, str> {
+    let plus = value.len() - value.trim_start_matches(|c: char| c.is_plus()).len();
+
+    if value[plus..].chars().all(|c| c.is_dec_digit()) {
+        return value;
+    }
+
+    buffer.clear();
+    buffer.push_str(&value[..plus]);
+
+    for ch in value[plus..].chars() {
+        if let Some(ch) = ch.as_dec_digit() {
+            buffer.push(ch);
+        } else if let Some(&ch) = mappings.get(&ch) {
+            buffer.push(ch);
+        }
+    }
+
+    Cow::Borrowed(buffer.as_str())
+}
+
+/// Get a part of the value, borrowing it if possible.
+pub fn slice<'a>(value: &Cow<'a, str>, range: Range<usize>) -> Cow<'a, str> {
+    match value {
+        Cow::Borrowed(value) => Cow::Borrowed(&value[range]),
+        Cow::Owned(value) => Cow::Owned(value[range].to_owned()),
+    }
+}
+
+/// Split the value in two at the given index, borrowing it if possible.
+pub fn split(value: Cow<str>, at: usize) -> (Cow<str>, Cow<str>) {
+    (slice(&value, 0..at), trim(value, at))
+}
+
 pub fn trim(value: Cow<str>, start: usize) -> Cow<str> {
     match value {
         Cow::Borrowed(value) => Cow::Borrowed(&value[start..]),
@@ -381,7 +458,7 @@ pub fn trim(value: Cow<str>, start: usize) -> Cow<str> {
     }
 }
 
-#[allow(clippy::wrong_self_convention)]
+#[allow(clippy::wrong_self_convention, unused)]
 pub trait AsCharExt {
     fn is_wide_digit(self) -> bool;
     fn is_punctuation(self) -> bool;
@@ -461,13 +538,46 @@ impl<T: AsChar> AsCharExt for T {
     }
 }
 
+/// The decimal digits of partially typed or dialled input, and whether it
+/// starts with a plus sign.
+///
+/// Punctuation is skipped, while any other character that isn't a digit makes
+/// the input not a number at all.
+pub fn digits(input: &str) -> Option<(bool, String)> {
+    let input = input.trim();
+    let plus = input.starts_with(|c: char| c.is_plus());
+
+    input
+        .chars()
+        .skip(plus as usize)
+        .filter(|c| !c.is_punctuation())
+        .map(|c| c.as_dec_digit())
+        .collect::<Option<String>>()
+        .map(|digits| (plus, digits))
+}
+
+/// Split the complete country calling code off the digits of an
+/// international number, with the metadata of the regions sharing it and the
+/// digits after it.
+pub fn split_country_code<'a, 'd>(
+    database: &'d Database,
+    digits: &'a str,
+) -> Option<(Vec<&'d Metadata>, &'a str)> {
+    // Country codes are prefix free, so at most one of them is complete.
+    (1..=consts::MAX_LENGTH_FOR_COUNTRY_CODE.min(digits.len())).find_map(|len| {
+        let code = digits[..len].parse::<u16>().ok()?;
+
+        database.by_code(&code).map(|metas| (metas, &digits[len..]))
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use regex_cache::CachedRegex;
+    use crate::metadata::LazyRegex;
 
     use crate::consts;
     use crate::country;
-    use crate::metadata::DATABASE;
+    use crate::metadata::{Database, DATABASE};
     use crate::parser::helper;
     use crate::parser::helper::*;
 
@@ -722,6 +832,31 @@ mod test {
         );
     }
 
+    #[test]
+    fn normalize_into() {
+        let mut buffer = String::new();
+
+        // Keeps leading pluses.
+        assert_eq!(
+            "+\u{FF0B}034562",
+            helper::normalize_into(
+                "+\u{FF0B}034-56&+#2".into(),
+                &consts::ALPHA_PHONE_MAPPINGS,
+                &mut buffer
+            )
+        );
+
+        // Borrows normalized values.
+        assert!(matches!(
+            helper::normalize_into(
+                "+4930123456".into(),
+                &consts::ALPHA_PHONE_MAPPINGS,
+                &mut buffer
+            ),
+            Cow::Borrowed("+4930123456")
+        ));
+    }
+
     #[test]
     fn international_prefix() {
         assert_eq!(
@@ -732,7 +867,7 @@ mod test {
                 ..Default::default()
             },
             helper::international_prefix(
-                Some(&CachedRegex::new(DATABASE.cache(), "00[39]").unwrap()),
+                Some(&LazyRegex::new("00[39]")),
                 Number {
                     national: "0034567700-3898003".into(),
 
@@ -749,7 +884,7 @@ mod test {
                 ..Default::default()
             },
             helper::international_prefix(
-                Some(&CachedRegex::new(DATABASE.cache(), "00[39]").unwrap()),
+                Some(&LazyRegex::new("00[39]")),
                 Number {
                     national: "00945677003898003".into(),
 
@@ -766,7 +901,7 @@ mod test {
                 ..Default::default()
             },
             helper::international_prefix(
-                Some(&CachedRegex::new(DATABASE.cache(), "00[39]").unwrap()),
+                Some(&LazyRegex::new("00[39]")),
                 Number {
                     national: "00 9 45677003898003".into(),
 
@@ -782,7 +917,7 @@ mod test {
                 ..Default::default()
             },
             helper::international_prefix(
-                Some(&CachedRegex::new(DATABASE.cache(), "00[39]").unwrap()),
+                Some(&LazyRegex::new("00[39]")),
                 Number {
                     national: "45677003898003".into(),
 
@@ -799,7 +934,7 @@ mod test {
                 ..Default::default()
             },
             helper::international_prefix(
-                Some(&CachedRegex::new(DATABASE.cache(), "00[39]").unwrap()),
+                Some(&LazyRegex::new("00[39]")),
                 Number {
                     national: "+45677003898003".into(),
 
@@ -808,4 +943,32 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn national_number() {
+        // The carrier code is the first group, which doesn't take part in the
+        // match when the prefix is transformed here.
+        let database = Database::parse(
+            r#"
+            <phoneNumberMetadata>
+              <territories>
+                <territory id="DE" countryCode="49" nationalPrefix="0"
+                           nationalPrefixForParsing="0(?:(1\d)|(2))"
+                           nationalPrefixTransformRule="$2">
+                  <generalDesc>
+                    <nationalNumberPattern>2?\d{6}</nationalNumberPattern>
+                  </generalDesc>
+                </territory>
+              </territories>
+            </phoneNumberMetadata>
+            "#,
+        )
+        .unwrap();
+        let meta = database.by_id("DE").unwrap();
+
+        assert_eq!(
+            Some(("2123456".into(), None)),
+            helper::national_number(meta, &"02123456".into())
+        );
+    }
 }
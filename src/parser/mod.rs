@@ -12,14 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// Parsing runs on untrusted input, where a panic is a bug.
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+
+use std::cell::RefCell;
+
+use thiserror::Error;
+
 use crate::carrier::Carrier;
 use crate::consts;
 use crate::country;
 use crate::error;
 use crate::extension::Extension;
-use crate::metadata::{Database, DATABASE};
+use crate::metadata::{self, Database};
 use crate::national_number::NationalNumber;
-use crate::phone_number::{PhoneNumber, Type};
+use crate::phone_number::{Extras, PhoneNumber, Type};
+use crate::policy::{ExtensionPolicy, PolicyViolation};
 use crate::validator::{self, Validation};
 
 use nom::{branch::alt, IResult};
@@ -35,7 +43,7 @@ pub fn parse<S: AsRef<str>>(
     country: Option<country::Id>,
     string: S,
 ) -> Result<PhoneNumber, error::Parse> {
-    parse_with(&DATABASE, country, string)
+    metadata::with_global(|database| parse_with(database, country, string))?
 }
 
 /// Parse a phone number using a specific `Database`.
@@ -44,29 +52,335 @@ pub fn parse_with<S: AsRef<str>>(
     country: Option<country::Id>,
     string: S,
 ) -> Result<PhoneNumber, error::Parse> {
-    fn phone_number(i: &str) -> IResult<&str, helper::Number> {
+    parse_helper(database, country, string.as_ref(), false, true)
+}
+
+/// Parse a phone number, failing with `error::Extension::NotAllowed` or
+/// `error::Extension::TooLong` when its extension doesn't pass the
+/// `ExtensionPolicy`.
+pub fn parse_with_policy<S: AsRef<str>>(
+    country: Option<country::Id>,
+    string: S,
+    policy: ExtensionPolicy,
+) -> Result<PhoneNumber, error::Parse> {
+    metadata::with_global(|database| parse_with_policy_with(database, country, string, policy))?
+}
+
+/// Parse a phone number using a specific `Database`, checking its extension
+/// against the `ExtensionPolicy` like `parse_with_policy`.
+pub fn parse_with_policy_with<S: AsRef<str>>(
+    database: &Database,
+    country: Option<country::Id>,
+    string: S,
+    policy: ExtensionPolicy,
+) -> Result<PhoneNumber, error::Parse> {
+    let number = parse_with(database, country, string)?;
+
+    policy
+        .check(number.extension())
+        .map_err(|violation| match violation {
+            PolicyViolation::ExtensionTooLong(length) => error::Extension::TooLong(length),
+            _ => error::Extension::NotAllowed,
+        })?;
+
+    Ok(number)
+}
+
+/// Parse a phone number, keeping the raw input around for formatting.
+pub fn parse_and_keep_raw_input<S: AsRef<str>>(
+    country: Option<country::Id>,
+    string: S,
+) -> Result<PhoneNumber, error::Parse> {
+    metadata::with_global(|database| parse_and_keep_raw_input_with(database, country, string))?
+}
+
+/// Parse a phone number using a specific `Database`, keeping the raw input
+/// around for formatting.
+pub fn parse_and_keep_raw_input_with<S: AsRef<str>>(
+    database: &Database,
+    country: Option<country::Id>,
+    string: S,
+) -> Result<PhoneNumber, error::Parse> {
+    parse_helper(database, country, string.as_ref(), true, true)
+}
+
+/// Parse a phone number in the strict E.164 format, a plus sign followed by
+/// at most 15 digits, like "+4930123456".
+///
+/// Unlike `parse` nothing is cleaned up, spaces, punctuation, a missing plus
+/// sign or a trunk zero after the country code are errors. As E.164 strings
+/// have no extension, the numbers pass any `ExtensionPolicy`.
+pub fn parse_e164<S: AsRef<str>>(string: S) -> Result<PhoneNumber, error::Parse> {
+    metadata::with_global(|database| parse_e164_with(database, string))?
+}
+
+/// Parse a phone number in the strict E.164 format using a specific
+/// `Database`.
+pub fn parse_e164_with<S: AsRef<str>>(
+    database: &Database,
+    string: S,
+) -> Result<PhoneNumber, error::Parse> {
+    let (code, national) = e164(database, string.as_ref())?;
+
+    Ok(PhoneNumber {
+        code: country::Code {
+            value: code,
+            source: country::Source::Plus,
+        },
+
+        national: NationalNumber {
+            value: helper::national_value(national)?,
+            zeros: national
+                .bytes()
+                .take_while(|&b| b == b'0')
+                .count()
+                .min(national.len() - 1) as u8,
+        },
+
+        extras: None,
+    })
+}
+
+/// Why parsing a number in a candidate region didn't give a valid number.
+#[derive(Error, Clone, Debug)]
+pub enum CandidateFailure {
+    /// The number couldn't be parsed.
+    #[error("{0}")]
+    Parse(#[from] error::Parse),
+
+    /// The number has a length no number of the region has.
+    #[error("impossible number: {0:?}")]
+    Impossible(Validation),
+
+    /// The number has a possible length, but isn't valid.
+    #[error("invalid number")]
+    Invalid,
+}
+
+/// None of the candidate regions gave a valid number.
+#[derive(Error, Clone, Debug)]
+#[error("no valid number in any of the candidate regions")]
+pub struct CandidatesError {
+    failures: Vec<(country::Id, CandidateFailure)>,
+}
+
+impl CandidatesError {
+    /// Why each candidate region failed, in the order they were tried.
+    pub fn failures(&self) -> &[(country::Id, CandidateFailure)] {
+        &self.failures
+    }
+}
+
+/// Parse a phone number written the way it's dialled in one of several
+/// candidate regions, like the country of the SIM card, of the IP address and
+/// of the postal address, in order of preference.
+///
+/// The first candidate giving a valid number wins, possible numbers aren't
+/// enough. A number in the international format is the same for every
+/// candidate, so the first one is returned when it's valid.
+pub fn parse_with_candidates<S: AsRef<str>>(
+    candidates: &[country::Id],
+    string: S,
+) -> Result<(country::Id, PhoneNumber), CandidatesError> {
+    metadata::with_global(|database| parse_with_candidates_with(database, candidates, string))
+        .unwrap_or_else(|err| {
+            Err(CandidatesError {
+                failures: candidates
+                    .iter()
+                    .map(|&id| (id, CandidateFailure::Parse(err.clone().into())))
+                    .collect(),
+            })
+        })
+}
+
+/// Parse a phone number written the way it's dialled in one of several
+/// candidate regions using a specific `Database`.
+pub fn parse_with_candidates_with<S: AsRef<str>>(
+    database: &Database,
+    candidates: &[country::Id],
+    string: S,
+) -> Result<(country::Id, PhoneNumber), CandidatesError> {
+    let mut failures = Vec::new();
+
+    for &id in candidates {
+        let number = match parse_with(database, Some(id), string.as_ref()) {
+            Ok(number) => number,
+            Err(err) => {
+                failures.push((id, err.into()));
+                continue;
+            }
+        };
+
+        if validator::is_valid_with(database, &number) {
+            return Ok((id, number));
+        }
+
+        let failure = match validator::is_possible_with_reason_with(database, &number) {
+            Validation::IsPossible | Validation::IsPossibleLocalOnly => CandidateFailure::Invalid,
+            reason => CandidateFailure::Impossible(reason),
+        };
+
+        failures.push((id, failure));
+    }
+
+    Err(CandidatesError { failures })
+}
+
+/// Split a number in the strict E.164 format into its country code and
+/// national number, without allocating.
+pub(crate) fn e164<'a>(
+    database: &Database,
+    string: &'a str,
+) -> Result<(u16, &'a str), error::Parse> {
+    let digits = string.strip_prefix('+').ok_or(error::Parse::NotE164)?;
+
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error::Parse::NotE164);
+    }
+
+    if digits.len() > consts::MAX_LENGTH_FOR_E164 {
+        return Err(error::Parse::TooLong);
+    }
+
+    // No country code starts with a zero, or is the prefix of another.
+    if digits.starts_with('0') {
+        return Err(error::Parse::InvalidCountryCode);
+    }
+
+    let codes = (1..=consts::MAX_LENGTH_FOR_COUNTRY_CODE.min(digits.len()))
+        .filter_map(|len| Some((len, digits[..len].parse::<u16>().ok()?)));
+
+    let (len, code, meta) = match codes
+        .clone()
+        .find_map(|(len, code)| database.main_by_code(code).map(|meta| (len, code, meta)))
+    {
+        Some(found) => found,
+
+        None if codes
+            .clone()
+            .any(|(_, code)| consts::CALLING_CODES.contains(&code)) =>
+        {
+            return Err(error::Parse::UnsupportedRegion)
+        }
+
+        None => return Err(error::Parse::InvalidCountryCode),
+    };
+
+    let national = &digits[len..];
+
+    if national.len() < consts::MIN_LENGTH_FOR_NSN {
+        return Err(error::Parse::TooShortNsn);
+    }
+
+    // Only a trunk zero left in by mistake, unless numbers of the country can
+    // start with a zero like in Italy.
+    if national.starts_with('0')
+        && meta.descriptors().general().national_number().prefixes() & 0x3ff == 0
+    {
+        return Err(error::Parse::NotE164);
+    }
+
+    Ok((code, national))
+}
+
+/// Parse a phone number using a specific `Database`, leaving the country code
+/// unset instead of failing when there's no way to find it.
+pub(crate) fn parse_unchecked_with(
+    database: &Database,
+    string: &str,
+) -> Result<PhoneNumber, error::Parse> {
+    parse_helper(database, None, string, false, false)
+}
+
+/// Scratch buffers bigger than this aren't kept around after parsing.
+const MAX_SCRATCH: usize = 1024;
+
+thread_local! {
+    /// Scratch buffer for the normalized digits, reused across calls.
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+fn parse_helper(
+    database: &Database,
+    country: Option<country::Id>,
+    string: &str,
+    keep: bool,
+    check: bool,
+) -> Result<PhoneNumber, error::Parse> {
+    SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        let result = parse_into(database, country, string, keep, check, &mut scratch);
+
+        if scratch.capacity() > MAX_SCRATCH {
+            *scratch = String::new();
+        }
+
+        result
+    })
+}
+
+fn parse_into(
+    database: &Database,
+    country: Option<country::Id>,
+    string: &str,
+    keep: bool,
+    check: bool,
+    scratch: &mut String,
+) -> Result<PhoneNumber, error::Parse> {
+    fn phone_number(i: &str) -> IResult<&str, helper::Number<'_>> {
         parse! { i => alt((rfc3966::phone_number, natural::phone_number)) }
     }
 
+    if string.len() > consts::MAX_INPUT_STRING_LENGTH {
+        return Err(error::Parse::TooLong);
+    }
+
     // Try to parse the number as RFC3966 or natural language.
-    let (_, mut number) = phone_number(string.as_ref()).or(Err(error::Parse::NoNumber))?;
+    let (_, mut number) = phone_number(string).or(Err(error::Parse::NoNumber))?;
+
+    // Letters only stand for digits in vanity numbers, with at least three of
+    // them, otherwise they're dropped like the punctuation in "(0xx)".
+    let mappings = if number.national.bytes().any(|b| b.is_ascii_alphabetic())
+        && consts::VALID_ALPHA_PHONE.is_match(&number.national)
+    {
+        &*consts::ALPHA_PHONE_MAPPINGS
+    } else {
+        &*consts::ASCII_MAPPINGS
+    };
+    number.national = helper::normalize_into(number.national, mappings, scratch);
 
     // Normalize the number and extract country code.
-    number = helper::country_code(database, country, number)?;
+    number = match helper::country_code(database, country, number.clone()) {
+        Err(error::Parse::InvalidCountryCode)
+            if !check && number.country == country::Source::Default =>
+        {
+            helper::normalize(number, &consts::ALPHA_PHONE_MAPPINGS)
+        }
 
-    // Extract carrier and strip national prefix if present.
-    if let Some(meta) = country.and_then(|c| database.by_id(c.as_ref())) {
-        let mut potential = helper::national_number(meta, number.clone());
+        result => result?,
+    };
 
-        // Strip national prefix if present.
-        if let Some(prefix) = meta.national_prefix.as_ref() {
-            if potential.national.starts_with(prefix) {
-                potential.national = helper::trim(potential.national, prefix.len());
-            }
-        }
+    // Extract carrier and strip national prefix if present, using the metadata
+    // of the country the number belongs to rather than the default one.
+    let meta = match number.country {
+        country::Source::Default => country.and_then(|c| database.by_id(c.as_ref())),
 
-        if validator::length(meta, &potential, Type::Unknown) != Validation::TooShort {
-            number = potential;
+        _ => number
+            .prefix
+            .as_ref()
+            .and_then(|p| p.parse().ok())
+            .and_then(|code| database.main_by_code(code)),
+    };
+
+    if let Some(meta) = meta {
+        if let Some((national, carrier)) = helper::national_number(meta, &number.national) {
+            if !matches!(
+                validator::length(meta, &national, Type::Unknown),
+                Validation::TooShort | Validation::IsPossibleLocalOnly | Validation::InvalidLength
+            ) {
+                number.national = national;
+                number.carrier = carrier;
+            }
         }
     }
 
@@ -74,32 +388,51 @@ pub fn parse_with<S: AsRef<str>>(
         return Err(error::Parse::TooShortNsn);
     }
 
-    if number.national.len() > consts::MAX_LENGTH_FOR_NSN {
-        return Err(error::Parse::TooLong);
-    }
-
     Ok(PhoneNumber {
         code: country::Code {
-            value: number.prefix.map(|p| p.parse()).unwrap_or(Ok(0))?,
+            value: number
+                .prefix
+                .as_deref()
+                .map(helper::country_code_value)
+                .transpose()?
+                .unwrap_or(0),
             source: number.country,
         },
 
         national: NationalNumber {
-            value: number.national.parse()?,
-            zeros: number.national.chars().take_while(|&c| c == '0').count() as u8,
+            value: helper::national_value(&number.national)?,
+            // At least one digit is kept in the value, so "000" is two leading
+            // zeros followed by a zero.
+            zeros: number
+                .national
+                .chars()
+                .take_while(|&c| c == '0')
+                .count()
+                .min(number.national.len() - 1) as u8,
         },
 
-        extension: number.extension.map(|s| Extension(s.into_owned())),
-        carrier: number.carrier.map(|s| Carrier(s.into_owned())),
+        extras: Extras::new(
+            number
+                .extension
+                .map(|s| Extension::new(s.into_owned()))
+                .transpose()?,
+            number.carrier.map(|s| Carrier(s.into())),
+            if keep { Some(string.into()) } else { None },
+        ),
     })
 }
 
 #[cfg(test)]
 mod test {
+    use crate::consts;
     use crate::country;
+    use crate::error;
+    use crate::formatter::Mode;
     use crate::national_number::NationalNumber;
     use crate::parser;
-    use crate::phone_number::PhoneNumber;
+    use crate::phone_number::{Extras, PhoneNumber, Type};
+    use crate::policy::ExtensionPolicy;
+    use crate::validator::{self, Validation};
 
     #[test]
     fn parse() {
@@ -114,8 +447,7 @@ mod test {
                 zeros: 0,
             },
 
-            extension: None,
-            carrier: None,
+            extras: None,
         };
 
         number.code.source = country::Source::Default;
@@ -205,8 +537,7 @@ mod test {
                 zeros: 0,
             },
 
-            extension: None,
-            carrier: None,
+            extras: None,
         };
 
         assert_eq!(
@@ -226,8 +557,7 @@ mod test {
                     zeros: 0,
                 },
 
-                extension: None,
-                carrier: None,
+                extras: None,
             },
             parser::parse(Some(country::DE), "301/23456").unwrap()
         );
@@ -244,8 +574,7 @@ mod test {
                     zeros: 0,
                 },
 
-                extension: None,
-                carrier: None,
+                extras: None,
             },
             parser::parse(Some(country::JP), "+81 *2345").unwrap()
         );
@@ -262,8 +591,7 @@ mod test {
                     zeros: 0,
                 },
 
-                extension: None,
-                carrier: None,
+                extras: None,
             },
             parser::parse(Some(country::NZ), "12").unwrap()
         );
@@ -280,10 +608,465 @@ mod test {
                     zeros: 0,
                 },
 
-                extension: None,
-                carrier: Some("12".into()),
+                extras: Extras::new(None, Some("12".into()), None),
             },
             parser::parse(Some(country::BR), "012 3121286979").unwrap()
         );
     }
+
+    #[test]
+    fn ar() {
+        // Mobile numbers are dialled with "15" after the area code nationally,
+        // and with a "9" before it internationally.
+        for &(input, national) in &[
+            ("+54 9 343 555 1212", 93435551212),
+            ("0343 15 555 1212", 93435551212),
+            ("+54 9 3715 65 4320", 93715654320),
+            ("03715 15 65 4320", 93715654320),
+            ("911 876 54321", 91187654321),
+            ("011 15 8765 4321", 91187654321),
+            ("+54 11 15 8765 4321", 91187654321),
+            ("+54 11 8765 4321", 1187654321),
+            ("011 8765 4321", 1187654321),
+            ("+54 3715 65 4321", 3715654321),
+            ("03715 65 4321", 3715654321),
+            ("023 1234 0000", 2312340000),
+            ("+54 23 1234 0000", 2312340000),
+            ("01187654321", 1187654321),
+            ("(0) 1187654321", 1187654321),
+            ("0 1187654321", 1187654321),
+            ("(0xx) 1187654321", 1187654321),
+        ] {
+            let number = parser::parse(Some(country::AR), input).unwrap();
+            assert_eq!(54, number.country().code(), "{}", input);
+            assert_eq!(national, number.national().value(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn mx() {
+        // Mobile numbers used to be dialled with a "1" after the country code,
+        // which is still found in stored numbers.
+        let number = parser::parse(None, "+52 55 1234 5678").unwrap();
+        assert_eq!(number, parser::parse(None, "+52 1 55 1234 5678").unwrap());
+        assert_eq!(number, parser::parse(None, "+5215512345678").unwrap());
+        assert_eq!(None, number.carrier());
+        assert_eq!(5512345678, number.national().value());
+
+        for input in &["044 55 1234 5678", "045 55 1234 5678", "55 1234 5678"] {
+            assert_eq!(
+                number.national(),
+                parser::parse(Some(country::MX), input).unwrap().national(),
+                "{}",
+                input
+            );
+        }
+
+        // Mobile and fixed line numbers can't be told apart anymore.
+        for input in &[
+            "+52 1 55 1234 5678",
+            "+52 55 1234 5678",
+            "+52 1 222 123 4567",
+            "+52 222 123 4567",
+            "+52 55 5342 8400",
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            assert!(number.is_valid(), "{}", input);
+            assert_eq!(Type::FixedLineOrMobile, number.number_type(), "{}", input);
+        }
+
+        // A Mexico City landline, with the former national prefix.
+        let number = parser::parse(Some(country::MX), "01 55 5342 8400").unwrap();
+        assert_eq!(5553428400, number.national().value());
+        assert_eq!(
+            "55 5342 8400",
+            number.format().mode(Mode::National).to_string()
+        );
+        assert_eq!(
+            "+52 55 5342 8400",
+            number.format().mode(Mode::International).to_string()
+        );
+    }
+
+    #[test]
+    fn jp() {
+        // The international prefix 010 starts with the national prefix.
+        let number = parser::parse(Some(country::JP), "010 44 20 7031 3000").unwrap();
+        assert_eq!(country::Source::Idd, number.country().source());
+        assert_eq!(
+            parser::parse(None, "+44 20 7031 3000").unwrap().national(),
+            number.national()
+        );
+        assert_eq!(Type::FixedLine, number.number_type());
+
+        let number = parser::parse(Some(country::JP), "010 81 3 1234 5678").unwrap();
+        assert_eq!(81, number.country().code());
+        assert_eq!(312345678, number.national().value());
+
+        for &(input, kind, national, international) in &[
+            (
+                "0120-123-456",
+                Type::TollFree,
+                "0120-123-456",
+                "+81 120-123-456",
+            ),
+            (
+                "0120 123 456",
+                Type::TollFree,
+                "0120-123-456",
+                "+81 120-123-456",
+            ),
+            (
+                "0800-123-4567",
+                Type::TollFree,
+                "0800-123-4567",
+                "+81 800-123-4567",
+            ),
+            (
+                "03-1234-5678",
+                Type::FixedLine,
+                "03-1234-5678",
+                "+81 3-1234-5678",
+            ),
+            (
+                "+81 3 1234 5678",
+                Type::FixedLine,
+                "03-1234-5678",
+                "+81 3-1234-5678",
+            ),
+        ] {
+            let number = parser::parse(Some(country::JP), input).unwrap();
+            assert!(number.is_valid(), "{}", input);
+            assert_eq!(kind, number.number_type(), "{}", input);
+            assert_eq!(
+                national,
+                number.format().mode(Mode::National).to_string(),
+                "{}",
+                input
+            );
+            assert_eq!(
+                international,
+                number.format().mode(Mode::International).to_string(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn gb_extension() {
+        // The trunk zero written in parentheses after the country code.
+        for input in &[
+            "+44 (0) 20 7946 0958 ext 21",
+            "+44 (0) 20 7946 0958 x21",
+            "+44 (0) 20 7946 0958;ext=21",
+            "+44(0)2079460958x21",
+            "0044 (0) 20 7946 0958 ext. 21",
+            "tel:+44-20-7946-0958;ext=21",
+        ] {
+            let number = parser::parse(Some(country::GB), input).unwrap();
+            assert!(number.is_valid(), "{}", input);
+            assert_eq!(Type::FixedLine, number.number_type(), "{}", input);
+            assert_eq!(2079460958, number.national().value(), "{}", input);
+            assert_eq!(0, number.national().zeros(), "{}", input);
+            assert_eq!(Some("21"), number.extension().map(|e| &**e), "{}", input);
+            assert_eq!(
+                "020 7946 0958 x21",
+                number.format().mode(Mode::National).to_string(),
+                "{}",
+                input
+            );
+            assert_eq!(
+                "tel:+44-20-7946-0958;ext=21",
+                number.format().mode(Mode::Rfc3966).to_string(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn leading_zero() {
+        let number = NationalNumber {
+            value: 669883461,
+            zeros: 1,
+        };
+
+        for (country, string) in [
+            (None, "+39 06 698 83461"),
+            (Some(country::IT), "06 698 83461"),
+            (Some(country::IT), "39 06 698 83461"),
+            (Some(country::IT), "tel:06-698-83461;phone-context=+39"),
+            (Some(country::US), "011 39 06 698 83461"),
+            (Some(country::DE), "0039 06 698 83461"),
+        ] {
+            assert_eq!(
+                number,
+                *parser::parse(country, string).unwrap().national(),
+                "{}",
+                string
+            );
+        }
+
+        assert_eq!(
+            NationalNumber { value: 0, zeros: 2 },
+            *parser::parse(None, "+39 000").unwrap().national()
+        );
+
+        // The national prefix is stripped only once.
+        assert_eq!(
+            NationalNumber {
+                value: 8005553535,
+                zeros: 0,
+            },
+            *parser::parse(Some(country::RU), "8 800 555 35 35")
+                .unwrap()
+                .national()
+        );
+    }
+
+    #[test]
+    fn e164() {
+        for (input, code, value, zeros) in [
+            ("+4930123456", 49, 30123456, 0),
+            ("+390669883461", 39, 669883461, 1),
+            ("+16502530000", 1, 6502530000, 0),
+            ("+80012345678", 800, 12345678, 0),
+            ("+861234567890123", 86, 1234567890123, 0),
+        ] {
+            let number = parser::parse_e164(input).unwrap();
+            assert_eq!(code, number.code().value(), "{}", input);
+            assert_eq!(country::Source::Plus, number.code().source(), "{}", input);
+            assert_eq!(value, number.national().value(), "{}", input);
+            assert_eq!(zeros, number.national().zeros(), "{}", input);
+            assert_eq!(None, number.extension(), "{}", input);
+            assert_eq!(input, number.format().mode(Mode::E164).to_string());
+            assert!(validator::is_e164(input), "{}", input);
+        }
+
+        for (input, err) in [
+            ("4930123456", error::Parse::NotE164),
+            ("+49 30 123456", error::Parse::NotE164),
+            ("+49-30-123456", error::Parse::NotE164),
+            ("+49(30)123456", error::Parse::NotE164),
+            ("++4930123456", error::Parse::NotE164),
+            ("+4930123456 ", error::Parse::NotE164),
+            ("+4930123456x12", error::Parse::NotE164),
+            ("\u{ff0b}4930123456", error::Parse::NotE164),
+            ("+\u{ff14}\u{ff19}30123456", error::Parse::NotE164),
+            ("tel:+4930123456", error::Parse::NotE164),
+            ("+", error::Parse::NotE164),
+            ("+4903012345", error::Parse::NotE164),
+            ("+4402079460958", error::Parse::NotE164),
+            ("+004930123456", error::Parse::InvalidCountryCode),
+            ("+999123456", error::Parse::InvalidCountryCode),
+            ("+4912345678901234", error::Parse::TooLong),
+            ("+491", error::Parse::TooShortNsn),
+        ] {
+            assert_eq!(err, parser::parse_e164(input).unwrap_err(), "{}", input);
+            assert!(!validator::is_e164(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn candidates() {
+        // Valid in both, the order of the candidates decides.
+        let (id, number) =
+            parser::parse_with_candidates(&[country::FR, country::NL], "06 12 34 56 78").unwrap();
+        assert_eq!(country::FR, id);
+        assert_eq!(33, number.code().value());
+        assert_eq!(612345678, number.national().value());
+
+        let (id, number) =
+            parser::parse_with_candidates(&[country::NL, country::FR], "06 12 34 56 78").unwrap();
+        assert_eq!(country::NL, id);
+        assert_eq!(31, number.code().value());
+
+        // Only the second candidate gives a valid number.
+        let (id, number) =
+            parser::parse_with_candidates(&[country::GB, country::FR], "06 12 34 56 78").unwrap();
+        assert_eq!(country::FR, id);
+        assert!(number.is_valid());
+
+        let err = parser::parse_with_candidates(&[country::GB, country::US], "06 12 34 56 78")
+            .unwrap_err();
+        assert_eq!(2, err.failures().len());
+        assert_eq!(country::GB, err.failures()[0].0);
+        assert!(matches!(
+            err.failures()[0].1,
+            parser::CandidateFailure::Invalid
+        ));
+        assert_eq!(country::US, err.failures()[1].0);
+
+        let err = parser::parse_with_candidates(&[country::DE], "030 12345678901234").unwrap_err();
+        assert!(matches!(
+            err.failures(),
+            [(
+                country::DE,
+                parser::CandidateFailure::Impossible(Validation::TooLong)
+            )]
+        ));
+
+        let err = parser::parse_with_candidates(&[country::DE], "abc").unwrap_err();
+        assert!(matches!(
+            err.failures(),
+            [(
+                country::DE,
+                parser::CandidateFailure::Parse(error::Parse::NoNumber)
+            )]
+        ));
+
+        assert!(parser::parse_with_candidates(&[], "06 12 34 56 78")
+            .unwrap_err()
+            .failures()
+            .is_empty());
+    }
+
+    #[test]
+    fn uris() {
+        // Only URIs, or numbers with a phone context, are parsed as RFC3966,
+        // a plain number with spaces used to be taken for one and fail.
+        for input in [
+            "+64 3 331-6005",
+            "+64 3 331.6005",
+            "tel:+64-3-331-6005",
+            "TEL:+64-3-331-6005",
+            "33316005;phone-context=+64",
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            assert_eq!(64, number.code().value(), "{}", input);
+            assert_eq!(33316005, number.national().value(), "{}", input);
+        }
+
+        let number = parser::parse(None, "+1 650 253-0000").unwrap();
+        assert_eq!(6502530000, number.national().value());
+
+        assert_eq!(
+            error::Parse::NoNumber,
+            parser::parse(None, "abc").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn policy() {
+        for (policy, result) in [
+            (ExtensionPolicy::Ignore, Ok(())),
+            (
+                ExtensionPolicy::RequireAbsent,
+                Err(error::Parse::InvalidExtension(error::Extension::NotAllowed)),
+            ),
+            (
+                ExtensionPolicy::ValidateLength(6),
+                Err(error::Parse::InvalidExtension(error::Extension::TooLong(7))),
+            ),
+            (ExtensionPolicy::ValidateLength(7), Ok(())),
+        ] {
+            assert_eq!(
+                result,
+                parser::parse_with_policy(None, "+1 650 253 0000 ext. 1234567", policy)
+                    .map(|number| assert_eq!("1234567", &**number.extension().unwrap())),
+                "{:?}",
+                policy
+            );
+
+            // Numbers without an extension pass any policy.
+            assert!(parser::parse_with_policy(None, "+1 650 253 0000", policy).is_ok());
+
+            // The parser doesn't take extensions of 25 digits to begin with.
+            assert_eq!(
+                error::Parse::TooLong,
+                parser::parse_with_policy(
+                    None,
+                    "+1 650 253 0000 ext. 1234567890123456789012345",
+                    policy
+                )
+                .unwrap_err()
+            );
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let digits = |len: usize| "1234567890".repeat(2)[..len].to_owned();
+
+        let longest = parser::parse(Some(country::DE), digits(consts::MAX_LENGTH_FOR_NSN)).unwrap();
+        assert_eq!(
+            consts::MAX_LENGTH_FOR_NSN,
+            longest.national().to_string().len()
+        );
+        assert_eq!(
+            error::Parse::TooLong,
+            parser::parse(Some(country::DE), digits(consts::MAX_LENGTH_FOR_NSN + 1)).unwrap_err()
+        );
+
+        assert!(parser::parse(None, format!("+49{}", digits(consts::MIN_LENGTH_FOR_NSN))).is_ok());
+        assert_eq!(
+            error::Parse::TooShortNsn,
+            parser::parse(
+                None,
+                format!("+49{}", digits(consts::MIN_LENGTH_FOR_NSN - 1))
+            )
+            .unwrap_err()
+        );
+
+        let e164 = format!("+49{}", digits(consts::MAX_LENGTH_FOR_E164 - 2));
+        assert!(parser::parse_e164(&e164).is_ok());
+        assert_eq!(
+            error::Parse::TooLong,
+            parser::parse_e164(format!("{}0", e164)).unwrap_err()
+        );
+
+        // Padding doesn't change the number, until the input is too long.
+        let padded = |len: usize| format!("{:<1$}", "+49 30 123456", len);
+        assert!(parser::parse(None, padded(consts::MAX_INPUT_STRING_LENGTH)).is_ok());
+        assert_eq!(
+            error::Parse::TooLong,
+            parser::parse(None, padded(consts::MAX_INPUT_STRING_LENGTH + 1)).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        for &len in &[18, 19, 25] {
+            let digits = "9".repeat(len);
+            let inputs = [
+                (None, format!("+49 {}", digits)),
+                (None, format!("+1 {}", digits)),
+                (None, format!("tel:{};phone-context=+49", digits)),
+                (Some(country::DE), format!("0{}", digits)),
+                (Some(country::DE), format!("0049 {}", digits)),
+                (Some(country::US), digits.clone()),
+            ];
+
+            for (region, input) in &inputs {
+                assert_eq!(
+                    error::Parse::TooLong,
+                    parser::parse(*region, input).unwrap_err(),
+                    "{}",
+                    input
+                );
+            }
+
+            assert_eq!(
+                error::Parse::TooLong,
+                parser::parse_e164(format!("+49{}", digits)).unwrap_err()
+            );
+        }
+
+        // Up to the limit the digits are kept as they are.
+        let digits = "9".repeat(consts::MAX_LENGTH_FOR_NSN);
+        let number = parser::parse(Some(country::DE), format!("0{}", digits)).unwrap();
+        assert_eq!(digits, number.national().to_string());
+
+        // Country codes too long for one, or with a leading zero, aren't read
+        // as another one.
+        for context in &["+99999999", "+065", "example.com"] {
+            assert_eq!(
+                error::Parse::InvalidCountryCode,
+                parser::parse(None, format!("tel:30123456;phone-context={}", context)).unwrap_err(),
+                "{}",
+                context
+            );
+        }
+    }
 }
@@ -17,22 +17,33 @@ use nom::IResult;
 use crate::consts;
 use crate::parser::helper::*;
 
-pub fn phone_number(i: &str) -> IResult<&str, Number> {
+pub fn phone_number(i: &str) -> IResult<&str, Number<'_>> {
     let (_, i) = extract(i)?;
-    let extension = consts::EXTN_PATTERN.captures(i);
+    // Only look for the digits if there's an extension at all, since it
+    // needs allocating.
+    let extension = if consts::EXTN_PATTERN.is_match(i) {
+        consts::EXTN_PATTERN.captures(i)
+    } else {
+        None
+    };
 
     Ok((
         "",
         Number {
             national: extension
                 .as_ref()
-                .map(|c| &i[..c.get(0).unwrap().start()])
+                .and_then(|c| c.get(0))
+                .map(|m| &i[..m.start()])
                 .unwrap_or(i)
                 .into(),
 
+            // The digits are in whichever group of the alternatives matched,
+            // and an extension prefix without digits is dropped.
             extension: extension
                 .as_ref()
-                .map(|c| c.get(2).unwrap().as_str())
+                .and_then(|c| c.iter().skip(1).flatten().next())
+                .map(|m| m.as_str())
+                .filter(|e| !e.is_empty())
                 .map(Into::into),
 
             ..Default::default()
@@ -56,5 +67,24 @@ mod test {
                 ..Default::default()
             }
         );
+
+        assert_eq!(
+            natural::phone_number("+46701234567;ext=123").unwrap().1,
+            Number {
+                national: "+46701234567".into(),
+                extension: Some("123".into()),
+
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            natural::phone_number("650 253 0000 ext.").unwrap().1,
+            Number {
+                national: "650 253 0000".into(),
+
+                ..Default::default()
+            }
+        );
     }
 }
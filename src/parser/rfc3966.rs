@@ -25,7 +25,15 @@ use nom::{
 
 use crate::parser::helper::*;
 
-pub fn phone_number(i: &str) -> IResult<&str, Number> {
+pub fn phone_number(i: &str) -> IResult<&str, Number<'_>> {
+    // Anything that isn't a URI or has no phone context is left to the natural
+    // language parser.
+    if !i.get(..4).map_or(false, |s| s.eq_ignore_ascii_case("tel:"))
+        && !i.contains(";phone-context=")
+    {
+        return Err(nom::Err::Error(make_error(i, ErrorKind::Tag)));
+    }
+
     parse! { i =>
         opt(tag_no_case("Tel:"));
         let prefix = opt(prefix);
@@ -44,7 +52,8 @@ pub fn phone_number(i: &str) -> IResult<&str, Number> {
                     params
                         .as_ref()
                         .and_then(|m| m.get("phone-context"))
-                        .map(|&s| if s.as_bytes()[0] == b'+' { &s[1..] } else { s })
+                        .filter(|s| !s.is_empty())
+                        .map(|&s| s.strip_prefix('+').unwrap_or(s))
                 })
                 .map(|cs| cs.into()),
 
@@ -164,5 +173,22 @@ mod test {
                 ..Default::default()
             }
         );
+
+        // An empty phone context is no country code.
+        assert_eq!(
+            rfc3966::phone_number("tel:2034567890;phone-context=")
+                .unwrap()
+                .1,
+            Number {
+                national: "2034567890".into(),
+
+                ..Default::default()
+            }
+        );
+
+        // Plain numbers with separators are not RFC3966.
+        assert!(rfc3966::phone_number("+1 650 253-0000").is_err());
+        assert!(rfc3966::phone_number("+64 3 331.6005").is_err());
+        assert!(rfc3966::phone_number("abc").is_err());
     }
 }
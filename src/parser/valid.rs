@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::parser::helper::*;
-use nom::{branch::*, combinator::*, multi::*, IResult};
+use nom::{branch::*, combinator::*, multi::*, sequence::*, IResult};
 
 pub fn phone_number(i: &str) -> IResult<&str, &str> {
     parse! { i => recognize(alt((short, long))) }
@@ -31,9 +31,7 @@ fn short(i: &str) -> IResult<&str, ()> {
 fn long(i: &str) -> IResult<&str, ()> {
     parse! { i =>
         many0(plus);
-        many0(alt((punctuation, star)));
-        count(digit, 3);
-        many0(digit);
+        count(pair(many0(alt((punctuation, star))), digit), 3);
         many0(alt((punctuation, star, digit, alpha)));
         ieof;
     };
@@ -61,5 +59,8 @@ mod test {
         assert!(phone_number("08-PIZZA").is_err());
         assert!(phone_number("8-PIZZA").is_err());
         assert!(phone_number("12. March").is_err());
+        // Punctuation can be between the first digits.
+        assert!(phone_number("+1 800 six-flags").is_ok());
+        assert!(phone_number("+1 650 253 0000").is_ok());
     }
 }
@@ -13,22 +13,39 @@
 // limitations under the License.
 
 use either::*;
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
 use std::ops::Deref;
 use std::str::FromStr;
 
 use crate::carrier::Carrier;
+use crate::consts;
 use crate::country;
+use crate::dial::{self, DialError, DialPlan};
 use crate::error;
 use crate::extension::Extension;
 use crate::formatter;
-use crate::metadata::{Database, Metadata, DATABASE};
+use crate::lookup::{self, LookupKeys};
+use crate::metadata::{self, Database, Metadata};
 use crate::national_number::NationalNumber;
 use crate::parser;
+use crate::validated::{ValidatedPhoneNumber, ValidationError};
 use crate::validator;
 
 /// A phone number.
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+///
+/// Two numbers are equal, and hash the same, when they have the same country
+/// calling code, national number, leading zeros and extension, so the same
+/// number parsed from different strings is the same key in a map. How the
+/// country calling code was found, the carrier code and the raw input are
+/// ignored, see `PhoneNumber::exactly_equal` to compare them too.
+///
+/// With the `string-serde` feature it's serialized as an E.164 string, see
+/// `phonenumber::serde`. With the `redact-debug` feature its `Debug` output is
+/// the one of `PhoneNumber::redacted`.
+#[derive(Clone)]
 pub struct PhoneNumber {
     /// The country calling code for this number, as defined by the International
     /// Telecommunication Union (ITU). For example, this would be 1 for NANPA
@@ -51,6 +68,21 @@ pub struct PhoneNumber {
     /// spaces, parentheses), nor any alphanumeric spellings.
     pub(crate) national: NationalNumber,
 
+    /// The extension, carrier code and raw input, which most numbers don't
+    /// have, boxed so they take no space when they're all missing.
+    pub(crate) extras: Option<Box<Extras>>,
+}
+
+// Keep the numbers small, since they're often held by the millions.
+const _: () = assert!(std::mem::size_of::<PhoneNumber>() <= 32);
+
+/// The most digits of a number in the E.164 format `PhoneNumber` can hold, a
+/// `u16` country code, up to `u8::MAX` leading zeros and a `u64`.
+const E164_DIGITS: usize = 5 + u8::MAX as usize + 20;
+
+/// The optional parts of a `PhoneNumber`.
+#[derive(Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub(crate) struct Extras {
     /// Extension is not standardized in ITU recommendations, except for being
     /// defined as a series of numbers with a maximum length of 40 digits. It is
     /// defined as a string here to accommodate for the possible use of a leading
@@ -69,20 +101,78 @@ pub struct PhoneNumber {
     /// Note this is the "preferred" code, which means other codes may work as
     /// well.
     pub(crate) carrier: Option<Carrier>,
+
+    /// The string the number was parsed from, with all the formatting and
+    /// alpha characters that were present. This is only set when parsing with
+    /// `parse_and_keep_raw_input`.
+    pub(crate) raw_input: Option<Box<str>>,
+}
+
+impl Extras {
+    /// Create the extras of a number, `None` when there are none.
+    pub(crate) fn new(
+        extension: Option<Extension>,
+        carrier: Option<Carrier>,
+        raw_input: Option<Box<str>>,
+    ) -> Option<Box<Extras>> {
+        let extras = Extras {
+            extension,
+            carrier,
+            raw_input,
+        };
+
+        if extras.is_empty() {
+            None
+        } else {
+            Some(Box::new(extras))
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.extension.is_none() && self.carrier.is_none() && self.raw_input.is_none()
+    }
 }
 
+/// Builder for `PhoneNumber`, created with `PhoneNumber::builder`.
+#[derive(Clone, Debug)]
+pub struct PhoneNumberBuilder(PhoneNumber);
+
 /// Wrapper to make it easier to access information about the country of a
 /// phone number.
 pub struct Country<'a>(&'a PhoneNumber);
 
+/// A phone number showing only its country calling code and the last two
+/// digits, like "+46•••••••67", for logs.
+///
+/// Numbers with national numbers of four digits or less don't show any digit.
+#[derive(Copy, Clone)]
+pub struct Redacted<'a>(&'a PhoneNumber);
+
+/// The parts of a phone number, to lay it out in ways the formatter doesn't,
+/// like "(+49) 30 / 901820".
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Components {
+    /// The country calling code.
+    pub country_code: u16,
+
+    /// The national destination code, like the area code of fixed line
+    /// numbers, with the mobile token of regions like Argentina, if the
+    /// number has one.
+    pub national_destination_code: Option<String>,
+
+    /// The rest of the national significant number, all of it without a
+    /// national destination code.
+    pub subscriber_number: String,
+}
+
 /// The phone number type.
 #[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum Type {
-    ///
+    /// Fixed line numbers.
     FixedLine,
 
-    ///
+    /// Mobile numbers.
     Mobile,
 
     /// In some regions (e.g. the USA), it is impossible to distinguish between
@@ -92,7 +182,7 @@ pub enum Type {
     /// Freephone lines.
     TollFree,
 
-    ///
+    /// Premium rate lines.
     PremiumRate,
 
     /// The cost of this call is shared between the caller and the recipient, and
@@ -108,7 +198,7 @@ pub enum Type {
     /// Voice over IP numbers. This includes TSoIP (Telephony Service over IP).
     Voip,
 
-    ///
+    /// Pager numbers.
     Pager,
 
     /// Used for "Universal Access Numbers" or "Company Numbers". They may be
@@ -116,22 +206,22 @@ pub enum Type {
     /// company.
     Uan,
 
-    ///
+    /// Emergency numbers.
     Emergency,
 
     /// Used for "Voice Mail Access Numbers".
     Voicemail,
 
-    ///
+    /// Short codes.
     ShortCode,
 
-    ///
+    /// Standard rate short codes.
     StandardRate,
 
-    ///
+    /// Carrier specific short codes.
     Carrier,
 
-    ///
+    /// Numbers that cannot be dialled from outside the country.
     NoInternational,
 
     /// A phone number is of type UNKNOWN when it does not fit any of the known
@@ -147,6 +237,39 @@ impl FromStr for PhoneNumber {
     }
 }
 
+impl PartialEq for PhoneNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.code.value == other.code.value
+            && self.national == other.national
+            && self.extension() == other.extension()
+    }
+}
+
+impl Eq for PhoneNumber {}
+
+impl Hash for PhoneNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code.value.hash(state);
+        self.national.hash(state);
+        self.extension().hash(state);
+    }
+}
+
+/// Phone numbers are ordered like their E.164 strings, then by extension, so
+/// numbers with the same country calling code are next to each other, and
+/// calling codes are ordered as strings, like +1, +44, +49, +7.
+impl Ord for PhoneNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_e164(other)
+    }
+}
+
+impl PartialOrd for PhoneNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl fmt::Display for PhoneNumber {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.format())
@@ -154,11 +277,87 @@ impl fmt::Display for PhoneNumber {
 }
 
 impl PhoneNumber {
+    /// Create a builder for a phone number from its parts, like the country
+    /// calling code and national number stored in a database, without
+    /// formatting and parsing it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use phonenumber::PhoneNumber;
+    ///
+    /// let number = PhoneNumber::builder(39, 236618300)
+    ///     .leading_zeros(1)
+    ///     .extension("12")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!("+390236618300", number.to_string());
+    /// assert_eq!(Some("12"), number.extension().map(|e| e.as_ref()));
+    /// ```
+    pub fn builder(code: u16, national: u64) -> PhoneNumberBuilder {
+        PhoneNumberBuilder(PhoneNumber {
+            code: country::Code {
+                value: code,
+                source: country::Source::default(),
+            },
+            national: NationalNumber {
+                value: national,
+                zeros: 0,
+            },
+            extras: None,
+        })
+    }
+
+    /// Create a number from parts already checked, for the `phonenumber!`
+    /// macro of `phonenumber-macros`, usable in constants.
+    #[doc(hidden)]
+    pub const fn __from_checked_parts(code: u16, national: u64, zeros: u8) -> PhoneNumber {
+        PhoneNumber {
+            code: country::Code {
+                value: code,
+                source: country::Source::Plus,
+            },
+            national: NationalNumber {
+                value: national,
+                zeros,
+            },
+            extras: None,
+        }
+    }
+
+    /// Check the number could have been built with `PhoneNumber::builder`, for
+    /// numbers put together from untrusted parts, like deserialized ones.
+    pub(crate) fn checked(self) -> Result<PhoneNumber, error::Build> {
+        let code = self.code.value;
+
+        if !consts::CALLING_CODES.contains(&code) {
+            return Err(error::Build::InvalidCountryCode(code));
+        }
+
+        let national = &self.national;
+        if national.zeros as usize + national.value.to_string().len() > consts::MAX_LENGTH_FOR_NSN {
+            return Err(error::Build::TooLong);
+        }
+
+        if let Some(extension) = self.extension() {
+            Extension::new(extension.as_ref())?;
+        }
+
+        Ok(self)
+    }
+
     /// Get information about the country for the phone number.
-    pub fn country(&self) -> Country {
+    pub fn country(&self) -> Country<'_> {
         Country(self)
     }
 
+    /// Get a redacted version of the number, which can be logged, the full
+    /// number is still available with `format`.
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+
     /// Get the country code.
     pub fn code(&self) -> &country::Code {
         &self.code
@@ -169,14 +368,127 @@ impl PhoneNumber {
         &self.national
     }
 
+    /// Compare the numbers like their E.164 strings, then by extension, the
+    /// same as `Ord`.
+    pub fn cmp_e164(&self, other: &Self) -> Ordering {
+        let (mut a, mut b) = ([0; E164_DIGITS], [0; E164_DIGITS]);
+
+        self.e164_digits(&mut a)
+            .cmp(other.e164_digits(&mut b))
+            .then_with(|| self.extension().cmp(&other.extension()))
+    }
+
+    /// Write the digits of the E.164 string into the buffer, which fits any
+    /// number of leading zeros.
+    fn e164_digits<'b>(&self, buffer: &'b mut [u8; E164_DIGITS]) -> &'b [u8] {
+        let mut cursor = &mut buffer[..];
+        write!(cursor, "{}{}", self.code.value, self.national).unwrap();
+        let length = E164_DIGITS - cursor.len();
+
+        &buffer[..length]
+    }
+
+    /// Check if the numbers are equal including how the country calling code
+    /// was found, the carrier code and the raw input.
+    pub fn exactly_equal(&self, other: &Self) -> bool {
+        self == other
+            && self.code.source == other.code.source
+            && self.carrier() == other.carrier()
+            && self.raw_input() == other.raw_input()
+    }
+
+    /// Get the canonical bytes of the number, to hash it the same way in
+    /// other languages or services.
+    ///
+    /// They're the plus sign 0x2B, the ASCII digits of the country calling
+    /// code, the ASCII digits of the national significant number including its
+    /// leading zeros, and if the number has an extension, the semicolon 0x3B
+    /// followed by the bytes of the extension. For example "+390236618300;12".
+    ///
+    /// How the number was parsed, its carrier code and raw input are left out,
+    /// like for `Eq`. The representation will stay the same in future versions.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let extension = self.extension().map(|e| e.as_bytes()).unwrap_or_default();
+
+        // The E.164 digits with the plus sign, and the separator.
+        let mut bytes = Vec::with_capacity(
+            2 + consts::MAX_LENGTH_FOR_COUNTRY_CODE
+                + self.national.zeros() as usize
+                + consts::MAX_LENGTH_FOR_NSN
+                + extension.len(),
+        );
+        self.format_append_bytes(formatter::Mode::E164, &mut bytes);
+
+        if self.extension().is_some() {
+            bytes.push(b';');
+            bytes.extend_from_slice(extension);
+        }
+
+        bytes
+    }
+
+    /// Feed the canonical bytes of the number to the hasher, without the
+    /// length prefix `Hash` adds to byte slices, so the hash only depends on
+    /// `canonical_bytes`.
+    pub fn canonical_hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&self.canonical_bytes());
+    }
+
+    /// Get the national number as an integer, without its leading zeros.
+    pub fn national_number(&self) -> u64 {
+        self.national.value
+    }
+
+    /// Get the digits of the national number, with its leading zeros, like
+    /// "0669883461" for an Italian fixed line number.
+    pub fn national_number_str(&self) -> String {
+        self.national.to_string()
+    }
+
+    /// Get the national significant number, the digits of the national number
+    /// including the leading zeros kept when dialling internationally, like
+    /// `getNationalSignificantNumber` in libphonenumber.
+    pub fn significant_national_number(&self) -> String {
+        self.national_number_str()
+    }
+
     /// Get the extension.
     pub fn extension(&self) -> Option<&Extension> {
-        self.extension.as_ref()
+        self.extras.as_ref().and_then(|e| e.extension.as_ref())
+    }
+
+    /// Set the extension, made of digits and dialling characters.
+    pub fn set_extension<S: Into<String>>(&mut self, extension: S) -> Result<(), error::Extension> {
+        self.extras_mut().extension = Some(Extension::new(extension)?);
+        Ok(())
+    }
+
+    /// Remove the extension, returning it.
+    pub fn remove_extension(&mut self) -> Option<Extension> {
+        let extension = self.extras.as_mut().and_then(|e| e.extension.take());
+
+        if self.extras.as_ref().map_or(false, |e| e.is_empty()) {
+            self.extras = None;
+        }
+
+        extension
     }
 
     /// Get the carrier.
     pub fn carrier(&self) -> Option<&Carrier> {
-        self.carrier.as_ref()
+        self.extras.as_ref().and_then(|e| e.carrier.as_ref())
+    }
+
+    /// Get the raw input the number was parsed from, if it was kept.
+    ///
+    /// It isn't serialized, so it's `None` for deserialized numbers.
+    pub fn raw_input(&self) -> Option<&str> {
+        self.extras.as_ref().and_then(|e| e.raw_input.as_deref())
+    }
+
+    /// Get the extras of the number, adding them if there are none.
+    fn extras_mut(&mut self) -> &mut Extras {
+        self.extras.get_or_insert_with(Default::default)
     }
 
     /// Prepare a formatter for this `PhoneNumber`.
@@ -195,6 +507,19 @@ impl PhoneNumber {
         formatter::format(self)
     }
 
+    /// Append the number formatted in the given mode to the string, without
+    /// allocating when it has the capacity for it, like for exports of many
+    /// numbers.
+    pub fn format_append(&self, mode: formatter::Mode, out: &mut String) {
+        self.format().mode(mode).append(out)
+    }
+
+    /// Append the number formatted in the given mode to the buffer in UTF-8,
+    /// which for `Mode::E164` is pure ASCII.
+    pub fn format_append_bytes(&self, mode: formatter::Mode, out: &mut Vec<u8>) {
+        self.format().mode(mode).append_bytes(out)
+    }
+
     /// Prepare a formatter for this `PhoneNumber` with the given `Database`.
     pub fn format_with<'n, 'd>(
         &'n self,
@@ -203,16 +528,273 @@ impl PhoneNumber {
         formatter::format_with(database, self)
     }
 
+    /// Get the national prefix needed to dial the number from within its
+    /// country, like "0" in the UK, or `None` when there is none, like in
+    /// Italy.
+    ///
+    /// In the US this is "1", needed for long distance calls even though the
+    /// national format doesn't include it, see
+    /// `Formatter::includes_national_prefix`.
+    pub fn national_dialing_prefix(&self) -> Option<String> {
+        self.national_dialing_prefix_with(&metadata::global())
+    }
+
+    /// Get the national prefix needed to dial the number from within its
+    /// country with the given `Database`.
+    pub fn national_dialing_prefix_with(&self, database: &Database) -> Option<String> {
+        let meta = database.main_by_code(self.code.value())?;
+        formatter::national_dialing_prefix(meta, &self.national.to_string()).map(Into::into)
+    }
+
+    /// Compose the digits to dial to reach the number with the given dial
+    /// plan, like "9011493012345" for "+49 30 12345" from the US behind the
+    /// trunk access code "9".
+    pub fn dial_string(&self, plan: &DialPlan) -> Result<String, DialError> {
+        self.dial_string_with(&metadata::global(), plan)
+    }
+
+    /// Compose the digits to dial to reach the number with the given dial
+    /// plan and `Database`.
+    pub fn dial_string_with(
+        &self,
+        database: &Database,
+        plan: &DialPlan,
+    ) -> Result<String, DialError> {
+        dial::dial_string_with(database, self, plan)
+    }
+
+    /// Get the keys to find the number by in a contact search: the E.164
+    /// format, the national significant number, and its last 10 and 7 digits.
+    pub fn lookup_keys(&self) -> LookupKeys {
+        lookup::lookup_keys(self)
+    }
+
+    /// Check whether a dialled string reaches the number, like "0701234567"
+    /// in Sweden for "+46701234567".
+    ///
+    /// The digits dialled have to end with the national significant number,
+    /// preceded by nothing, the country calling code with or without a plus
+    /// sign or an international prefix, or what parsing the number in its
+    /// regions strips, like the national prefix and carrier codes. Shorter
+    /// strings match when they are its last 7 digits or more, like local
+    /// numbers dialled without the area code. The extension is ignored.
+    pub fn matches_dialed_string(&self, dialed: &str) -> bool {
+        self.matches_dialed_string_with(&metadata::global(), dialed)
+    }
+
+    /// Check whether a dialled string reaches the number with the given
+    /// `Database`.
+    pub fn matches_dialed_string_with(&self, database: &Database, dialed: &str) -> bool {
+        lookup::matches_dialed_string_with(database, self, dialed)
+    }
+
     /// Get the metadata that applies to this phone number from the given
     /// database.
     pub fn metadata<'a>(&self, database: &'a Database) -> Option<&'a Metadata> {
         match validator::source_for(database, self.code.value(), &self.national.to_string())? {
             Left(region) => database.by_id(region.as_ref()),
-            Right(code) => database.by_code(&code).and_then(|m| m.into_iter().next()),
+            Right(code) => database.non_geographical(code),
         }
     }
 
-    /// Check if the phone number is valid.
+    /// Get the type of the phone number, based on the metadata patterns of
+    /// the region it belongs to.
+    pub fn number_type(&self) -> Type {
+        metadata::with_global(|database| self.number_type_with(database)).unwrap_or(Type::Unknown)
+    }
+
+    /// Get the type of the phone number with the given `Database`.
+    pub fn number_type_with(&self, database: &Database) -> Type {
+        self.metadata(database)
+            .map(|meta| validator::number_type(meta, &self.national.to_string()))
+            .unwrap_or(Type::Unknown)
+    }
+
+    /// Check if the phone number is assigned to a geographical area, which is
+    /// the case for fixed-line numbers and mobile numbers in some regions.
+    pub fn is_geographical(&self) -> bool {
+        self.is_geographical_with(&metadata::global())
+    }
+
+    /// Check if the phone number is assigned to a geographical area with the
+    /// given `Database`.
+    pub fn is_geographical_with(&self, database: &Database) -> bool {
+        geographical(self.number_type_with(database), self.code.value())
+    }
+
+    /// Get the length of the geographical area code, or 0 if the number has
+    /// none, for example because it's not geographical.
+    ///
+    /// The number "+49 30 901820" has the area code "30", so its length is 2.
+    pub fn length_of_geographical_area_code(&self) -> usize {
+        self.length_of_geographical_area_code_with(&metadata::global())
+    }
+
+    /// Get the length of the geographical area code with the given
+    /// `Database`.
+    pub fn length_of_geographical_area_code_with(&self, database: &Database) -> usize {
+        let meta = if let Some(meta) = self.metadata(database) {
+            meta
+        } else {
+            return 0;
+        };
+
+        // Without a national prefix there is no area code to dial, except for
+        // numbers with an italian leading zero.
+        if meta.national_prefix().is_none() && self.national.zeros == 0 {
+            return 0;
+        }
+
+        let kind = self.number_type_with(database);
+        let code = self.code.value();
+
+        if kind == Type::Mobile
+            && consts::GEO_MOBILE_COUNTRIES_WITHOUT_MOBILE_AREA_CODES.contains(&code)
+        {
+            return 0;
+        }
+
+        if !geographical(kind, code) {
+            return 0;
+        }
+
+        self.length_of_national_destination_code_with(database)
+    }
+
+    /// Get the length of the national destination code, or 0 if the number
+    /// has none.
+    ///
+    /// This is the area code for geographical numbers, and the operator code
+    /// for others; the mobile token is included where there is one.
+    pub fn length_of_national_destination_code(&self) -> usize {
+        self.length_of_national_destination_code_with(&metadata::global())
+    }
+
+    /// Get the length of the national destination code with the given
+    /// `Database`.
+    pub fn length_of_national_destination_code_with(&self, database: &Database) -> usize {
+        let mut number = self.clone();
+        number.remove_extension();
+
+        // The international format starts with "+CODE ", so the first group is
+        // empty, the second is the country code and the third is the national
+        // destination code, unless it's the last one.
+        let formatted = number
+            .format_with(database)
+            .mode(formatter::Mode::International)
+            .to_string();
+        let groups = consts::NON_DIGITS.split(&formatted).collect::<Vec<_>>();
+
+        if groups.len() <= 3 {
+            return 0;
+        }
+
+        if self.number_type_with(database) == Type::Mobile
+            && consts::MOBILE_TOKEN_MAPPINGS.contains_key(&self.code.value())
+        {
+            return groups[2].len() + groups[3].len();
+        }
+
+        groups[2].len()
+    }
+
+    /// Split the number into its country calling code, national destination
+    /// code and subscriber number.
+    pub fn components(&self) -> Components {
+        self.components_with(&metadata::global())
+    }
+
+    /// Split the number into its country calling code, national destination
+    /// code and subscriber number with the given `Database`.
+    pub fn components_with(&self, database: &Database) -> Components {
+        let mut subscriber = self.significant_national_number();
+        let length = self.length_of_national_destination_code_with(database);
+
+        let ndc = if length > 0 && length < subscriber.len() {
+            let rest = subscriber.split_off(length);
+            Some(std::mem::replace(&mut subscriber, rest))
+        } else {
+            None
+        };
+
+        Components {
+            country_code: self.code.value,
+            national_destination_code: ndc,
+            subscriber_number: subscriber,
+        }
+    }
+
+    /// Check if the phone number is possible, only looking at its length.
+    pub fn is_possible(&self) -> bool {
+        validator::is_possible(self)
+    }
+
+    /// Check if the phone number is possible with the given `Database`.
+    pub fn is_possible_with(&self, database: &Database) -> bool {
+        validator::is_possible_with(database, self)
+    }
+
+    /// Check if the phone number is possible, returning why it isn't.
+    pub fn is_possible_with_reason(&self) -> validator::Validation {
+        validator::is_possible_with_reason(self)
+    }
+
+    /// Check if the phone number is possible with the given `Database`,
+    /// returning why it isn't.
+    pub fn is_possible_with_reason_with(&self, database: &Database) -> validator::Validation {
+        validator::is_possible_with_reason_with(database, self)
+    }
+
+    /// Check if the phone number is possible for the given type.
+    pub fn is_possible_for_type(&self, kind: Type) -> bool {
+        validator::is_possible_for_type(self, kind)
+    }
+
+    /// Check if the phone number is possible for the given type with the given
+    /// `Database`.
+    pub fn is_possible_for_type_with(&self, database: &Database, kind: Type) -> bool {
+        validator::is_possible_for_type_with(database, self, kind)
+    }
+
+    /// Check if the phone number is possible for the given type, returning why
+    /// it isn't.
+    pub fn is_possible_for_type_with_reason(&self, kind: Type) -> validator::Validation {
+        validator::is_possible_for_type_with_reason(self, kind)
+    }
+
+    /// Check if the phone number is possible for the given type with the given
+    /// `Database`, returning why it isn't.
+    pub fn is_possible_for_type_with_reason_with(
+        &self,
+        database: &Database,
+        kind: Type,
+    ) -> validator::Validation {
+        validator::is_possible_for_type_with_reason_with(database, self, kind)
+    }
+
+    /// Check if the phone number is valid and of one of the given types.
+    pub fn is_valid_for_types(&self, types: &[Type]) -> bool {
+        self.is_valid_for_types_with(&metadata::global(), types)
+    }
+
+    /// Check if the phone number is valid and of one of the given types with
+    /// the given `Database`.
+    pub fn is_valid_for_types_with(&self, database: &Database, types: &[Type]) -> bool {
+        self.is_valid_with(database) && types.contains(&self.number_type_with(database))
+    }
+
+    /// Validate the phone number, explaining why it is valid or not.
+    pub fn validate(&self) -> validator::ValidationReport {
+        validator::validate(self)
+    }
+
+    /// Validate the phone number with the given `Database`, explaining why it
+    /// is valid or not.
+    pub fn validate_with(&self, database: &Database) -> validator::ValidationReport {
+        validator::validate_with(database, self)
+    }
+
+    /// Check if the phone number is valid, ignoring any extension.
     pub fn is_valid(&self) -> bool {
         validator::is_valid(self)
     }
@@ -221,6 +803,161 @@ impl PhoneNumber {
     pub fn is_valid_with(&self, database: &Database) -> bool {
         validator::is_valid_with(database, self)
     }
+
+    /// Turn the phone number into a `ValidatedPhoneNumber`, handing it back
+    /// with the reason if it's not valid.
+    pub fn validated(self) -> Result<ValidatedPhoneNumber, (PhoneNumber, ValidationError)> {
+        self.validated_with(&metadata::global())
+    }
+
+    /// Turn the phone number into a `ValidatedPhoneNumber` with the given
+    /// `Database`.
+    pub fn validated_with(
+        self,
+        database: &Database,
+    ) -> Result<ValidatedPhoneNumber, (PhoneNumber, ValidationError)> {
+        ValidatedPhoneNumber::new_with(database, self)
+    }
+
+    /// Check if the phone number is valid for the given region, or for `None`
+    /// for the non-geographical entity with its country calling code.
+    pub fn is_valid_for_region<C: Into<Option<country::Id>>>(&self, country: C) -> bool {
+        validator::is_valid_for_region(self, country)
+    }
+
+    /// Check if the phone number is valid for the given region with the given
+    /// `Database`.
+    pub fn is_valid_for_region_with<C: Into<Option<country::Id>>>(
+        &self,
+        database: &Database,
+        country: C,
+    ) -> bool {
+        validator::is_valid_for_region_with(database, self, country)
+    }
+
+    /// Strip trailing digits from a number that is too long until it becomes
+    /// valid, returning whether a valid number was found.
+    ///
+    /// The number is left untouched if it can't be made valid.
+    pub fn truncate_too_long(&mut self) -> bool {
+        self.truncate_too_long_with(&metadata::global())
+    }
+
+    /// Strip trailing digits from a number that is too long until it becomes
+    /// valid with the given `Database`, returning whether a valid number was
+    /// found.
+    pub fn truncate_too_long_with(&mut self, database: &Database) -> bool {
+        if self.is_valid_with(database) {
+            return true;
+        }
+
+        let mut number = self.clone();
+
+        loop {
+            number.national.value /= 10;
+
+            if number.national.value == 0
+                || number.is_possible_with_reason_with(database) == validator::Validation::TooShort
+            {
+                return false;
+            }
+
+            if number.is_valid_with(database) {
+                break;
+            }
+        }
+
+        self.national = number.national;
+        true
+    }
+}
+
+impl PhoneNumberBuilder {
+    /// Set how the country calling code was inferred.
+    pub fn source(mut self, source: country::Source) -> Self {
+        self.0.code.source = source;
+        self
+    }
+
+    /// Set the number of leading zeros of the national number, like the one of
+    /// Italian fixed line numbers.
+    pub fn leading_zeros(mut self, zeros: u8) -> Self {
+        self.0.national.zeros = zeros;
+        self
+    }
+
+    /// Set the extension, checked when building.
+    pub fn extension<S: Into<String>>(mut self, extension: S) -> Self {
+        self.0.extras_mut().extension = Some(Extension(extension.into().into_boxed_str()));
+        self
+    }
+
+    /// Set the preferred domestic carrier selection code.
+    pub fn carrier<S: Into<String>>(mut self, carrier: S) -> Self {
+        self.0.extras_mut().carrier = Some(carrier.into().into());
+        self
+    }
+
+    /// Set the raw input the number was parsed from.
+    pub fn raw_input<S: Into<String>>(mut self, raw_input: S) -> Self {
+        self.0.extras_mut().raw_input = Some(raw_input.into().into_boxed_str());
+        self
+    }
+
+    /// Build the phone number, checking the country calling code is known, the
+    /// national number isn't too long and the extension is valid.
+    ///
+    /// The number isn't validated, see `PhoneNumber::is_valid`.
+    pub fn build(self) -> Result<PhoneNumber, error::Build> {
+        self.0.checked()
+    }
+}
+
+impl<'a> fmt::Display for Redacted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let national = self.0.national.to_string();
+        let shown = if national.len() > 4 { 2 } else { 0 };
+
+        write!(f, "+{}", self.0.code.value)?;
+
+        for _ in 0..national.len() - shown {
+            f.write_str("\u{2022}")?;
+        }
+
+        f.write_str(&national[national.len() - shown..])
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PhoneNumber({})", self)
+    }
+}
+
+impl<'a> ::serde::Serialize for Redacted<'a> {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "redact-debug")]
+impl fmt::Debug for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.redacted(), f)
+    }
+}
+
+#[cfg(not(feature = "redact-debug"))]
+impl fmt::Debug for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PhoneNumber")
+            .field("code", &self.code)
+            .field("national", &self.national)
+            .field("extension", &self.extension())
+            .field("carrier", &self.carrier())
+            .field("raw_input", &self.raw_input())
+            .finish()
+    }
 }
 
 impl<'a> Country<'a> {
@@ -229,7 +966,11 @@ impl<'a> Country<'a> {
     }
 
     pub fn id(&self) -> Option<country::Id> {
-        self.0.metadata(&DATABASE).map(|m| m.id().parse().unwrap())
+        self.id_with(&metadata::global())
+    }
+
+    pub fn id_with(&self, database: &Database) -> Option<country::Id> {
+        self.0.metadata(database).and_then(|m| m.id().parse().ok())
     }
 }
 
@@ -241,10 +982,26 @@ impl<'a> Deref for Country<'a> {
     }
 }
 
+/// Check if numbers of the given type are geographical in the country with
+/// the given calling code.
+fn geographical(kind: Type, code: u16) -> bool {
+    match kind {
+        Type::FixedLine | Type::FixedLineOrMobile => true,
+        Type::Mobile => consts::GEO_MOBILE_COUNTRIES.contains(&code),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
     use crate::country;
+    use crate::error;
+    use crate::formatter::Mode;
     use crate::parser;
+    use crate::phone_number::{PhoneNumber, Type};
 
     #[test]
     fn country_id() {
@@ -284,4 +1041,487 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn number_type() {
+        assert_eq!(
+            Type::FixedLineOrMobile,
+            parser::parse(None, "+1 650 253 0000")
+                .unwrap()
+                .number_type()
+        );
+
+        assert_eq!(
+            Type::Mobile,
+            parser::parse(Some(country::GB), "07912 345678")
+                .unwrap()
+                .number_type()
+        );
+
+        assert_eq!(
+            Type::TollFree,
+            parser::parse(Some(country::GB), "0800 123 4567")
+                .unwrap()
+                .number_type()
+        );
+
+        assert_eq!(
+            Type::PremiumRate,
+            parser::parse(Some(country::GB), "0909 123 4567")
+                .unwrap()
+                .number_type()
+        );
+
+        assert_eq!(
+            Type::Mobile,
+            parser::parse(None, "+54 9 11 2345 6789")
+                .unwrap()
+                .number_type()
+        );
+
+        assert_eq!(
+            Type::FixedLine,
+            parser::parse(None, "+49 30 123456").unwrap().number_type()
+        );
+
+        assert_eq!(
+            Type::Unknown,
+            parser::parse(None, "+1 2530000").unwrap().number_type()
+        );
+    }
+
+    #[test]
+    fn truncate_too_long() {
+        let mut number = parser::parse(None, "+1 650 253 00001").unwrap();
+        assert!(number.truncate_too_long());
+        assert_eq!(parser::parse(None, "+1 650 253 0000").unwrap(), number);
+
+        // Leading zeros are kept while truncating.
+        let mut number = parser::parse(None, "+39 02 3661 8300 00").unwrap();
+        assert!(number.truncate_too_long());
+        assert_eq!(parser::parse(None, "+39 02 3661 8300 0").unwrap(), number);
+
+        let mut number = parser::parse(None, "+1 650 253 0000").unwrap();
+        assert!(number.truncate_too_long());
+        assert_eq!(parser::parse(None, "+1 650 253 0000").unwrap(), number);
+
+        let mut number = parser::parse(None, "+1 253 000 00001").unwrap();
+        assert!(!number.truncate_too_long());
+        assert_eq!(parser::parse(None, "+1 253 000 00001").unwrap(), number);
+    }
+
+    #[test]
+    fn geographical() {
+        let number = parser::parse(None, "+49 30 901820").unwrap();
+        assert!(number.is_geographical());
+        assert_eq!(2, number.length_of_geographical_area_code());
+        assert_eq!(2, number.length_of_national_destination_code());
+
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+        assert!(number.is_geographical());
+        assert_eq!(3, number.length_of_geographical_area_code());
+        assert_eq!(3, number.length_of_national_destination_code());
+
+        let number = parser::parse(None, "+61 2 9374 4000").unwrap();
+        assert!(number.is_geographical());
+        assert_eq!(1, number.length_of_geographical_area_code());
+
+        // Mobile numbers aren't geographical in Great Britain.
+        let number = parser::parse(None, "+44 7912 345678").unwrap();
+        assert!(!number.is_geographical());
+        assert_eq!(0, number.length_of_geographical_area_code());
+        assert_eq!(4, number.length_of_national_destination_code());
+
+        // But they are in Argentina, with the mobile token included.
+        let number = parser::parse(None, "+54 9 11 8765 4321").unwrap();
+        assert!(number.is_geographical());
+        assert_eq!(3, number.length_of_geographical_area_code());
+
+        let number = parser::parse(None, "+1 800 253 0000").unwrap();
+        assert!(!number.is_geographical());
+        assert_eq!(0, number.length_of_geographical_area_code());
+        assert_eq!(3, number.length_of_national_destination_code());
+
+        // Non-geographical entities have no area code.
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert!(!number.is_geographical());
+        assert_eq!(0, number.length_of_geographical_area_code());
+    }
+
+    #[test]
+    fn valid_for_types() {
+        let types = [Type::Mobile, Type::FixedLineOrMobile];
+
+        assert!(parser::parse(None, "+44 7912 345678")
+            .unwrap()
+            .is_valid_for_types(&types));
+        assert!(!parser::parse(None, "+44 800 123 4567")
+            .unwrap()
+            .is_valid_for_types(&types));
+        assert!(!parser::parse(None, "+44 791 234567")
+            .unwrap()
+            .is_valid_for_types(&types));
+    }
+
+    #[test]
+    fn builder() {
+        for (country, input) in &[
+            (None, "+49 30 901820"),
+            (Some(country::DE), "030 901820"),
+            (Some(country::IT), "02 3661 8300"),
+            (None, "+39 02 3661 8300 ext. 12"),
+            (None, "+800 1234 5678"),
+            (Some(country::BR), "0 12 11 95472 1234"),
+            (Some(country::US), "650 253 0000 x123"),
+        ] {
+            let number = parser::parse(*country, input).unwrap();
+
+            let mut builder =
+                PhoneNumber::builder(number.code().value(), number.national().value())
+                    .source(number.code().source())
+                    .leading_zeros(number.national().zeros());
+
+            if let Some(extension) = number.extension() {
+                builder = builder.extension(extension.as_ref());
+            }
+
+            if let Some(carrier) = number.carrier() {
+                builder = builder.carrier(carrier.as_ref());
+            }
+
+            assert_eq!(number, builder.build().unwrap(), "{}", input);
+        }
+
+        let number = PhoneNumber::builder(39, 236618300)
+            .leading_zeros(1)
+            .raw_input("02 3661 8300")
+            .build()
+            .unwrap();
+        assert!(number.is_valid());
+        assert_eq!(Some(country::IT), number.country().id());
+        assert_eq!("0236618300", number.national().to_string());
+        assert_eq!(Some("02 3661 8300"), number.raw_input());
+
+        assert!(matches!(
+            PhoneNumber::builder(999, 123456).build(),
+            Err(error::Build::InvalidCountryCode(999))
+        ));
+        assert!(PhoneNumber::builder(49, 12345678901234567).build().is_ok());
+        assert!(matches!(
+            PhoneNumber::builder(49, 12345678901234567)
+                .leading_zeros(1)
+                .build(),
+            Err(error::Build::TooLong)
+        ));
+    }
+
+    #[test]
+    fn national_number() {
+        let number = parser::parse(Some(country::IT), "06 6988 3461").unwrap();
+        assert_eq!(669883461, number.national_number());
+        assert_eq!("0669883461", number.national_number_str());
+        assert_eq!("0669883461", number.significant_national_number());
+
+        let number = parser::parse(None, "+49 30 901820").unwrap();
+        assert_eq!(30901820, number.national_number());
+        assert_eq!("30901820", number.national_number_str());
+        assert_eq!("30901820", number.significant_national_number());
+    }
+
+    #[test]
+    fn ord() {
+        use crate::formatter::Mode;
+        use crate::metadata::DATABASE;
+
+        // A simple linear congruential generator, for a reproducible corpus.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move |limit: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % limit
+        };
+
+        let codes = DATABASE.supported_calling_codes().collect::<Vec<_>>();
+        let mut corpus = Vec::new();
+
+        for _ in 0..2000 {
+            let code = codes[random(codes.len() as u64) as usize];
+            let digits = random(12) as u32 + 1;
+            let mut builder = PhoneNumber::builder(code, random(10u64.pow(digits)))
+                .leading_zeros(random(3) as u8);
+
+            if random(4) == 0 {
+                builder = builder.extension(random(1000).to_string());
+            }
+
+            corpus.push(builder.build().unwrap());
+        }
+
+        for (_, meta) in DATABASE.iter_regions() {
+            if let Some(example) = meta.descriptors().general().example() {
+                let id = meta.id().parse().unwrap();
+                corpus.push(parser::parse(Some(id), example).unwrap());
+            }
+        }
+
+        // Shuffle the corpus.
+        for i in (1..corpus.len()).rev() {
+            corpus.swap(i, random(i as u64 + 1) as usize);
+        }
+
+        let mut by_ord = corpus.clone();
+        by_ord.sort();
+
+        let mut by_string = corpus;
+        by_string.sort_by_key(|n| {
+            (
+                n.format().mode(Mode::E164).to_string(),
+                n.extension().cloned(),
+            )
+        });
+
+        assert_eq!(by_string, by_ord);
+
+        // The source is ignored.
+        let a = parser::parse(None, "+39 06 6988 3461").unwrap();
+        let b = parser::parse(Some(country::IT), "06 6988 3461").unwrap();
+        assert_eq!(std::cmp::Ordering::Equal, a.cmp(&b));
+
+        let c = parser::parse(None, "+44 20 7031 3000").unwrap();
+        let d = parser::parse(None, "+7 495 123 4567").unwrap();
+        assert!(a < c && c < d);
+    }
+
+    #[test]
+    fn components() {
+        use crate::phone_number::Components;
+
+        for (number, ndc, subscriber) in [
+            ("+49 30 901820", Some("30"), "901820"),
+            ("+1 650 253 0000", Some("650"), "2530000"),
+            ("+33 1 42 68 53 00", Some("1"), "42685300"),
+            ("+39 06 6988 3461", Some("06"), "69883461"),
+            // Mobile numbers aren't geographical in Great Britain.
+            ("+44 7912 345678", Some("7912"), "345678"),
+            // The mobile token is part of the national destination code.
+            ("+54 9 11 8765 4321", Some("911"), "87654321"),
+            ("+290 22012", None, "22012"),
+        ] {
+            let parsed = parser::parse(None, number).unwrap();
+
+            assert_eq!(
+                Components {
+                    country_code: parsed.code().value(),
+                    national_destination_code: ndc.map(String::from),
+                    subscriber_number: subscriber.into(),
+                },
+                parsed.components(),
+                "{}",
+                number
+            );
+        }
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(32, std::mem::size_of::<PhoneNumber>());
+
+        // Nothing is allocated for numbers without extension, carrier code or
+        // raw input.
+        let mut number = parser::parse(None, "+1 650 253 0000 ext. 12").unwrap();
+        assert!(number.extras.is_some());
+        number.remove_extension();
+        assert!(number.extras.is_none());
+
+        assert!(parser::parse(None, "+1 650 253 0000")
+            .unwrap()
+            .extras
+            .is_none());
+        assert!(PhoneNumber::builder(1, 6502530000)
+            .build()
+            .unwrap()
+            .extras
+            .is_none());
+    }
+
+    #[test]
+    fn equality() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |number: &PhoneNumber| {
+            let mut hasher = DefaultHasher::new();
+            number.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        for (number, forms) in [
+            (
+                "+39 06 6988 3461",
+                [
+                    (None, "+39 06 6988 3461"),
+                    (None, "+390669883461"),
+                    (Some(country::IT), "06 6988 3461"),
+                    (Some(country::US), "011 39 06-6988-3461"),
+                    (None, "tel:+39-06-6988-3461"),
+                ],
+            ),
+            (
+                "+1 650 253 0000 ext. 12",
+                [
+                    (None, "+1 650 253 0000 ext. 12"),
+                    (Some(country::US), "(650) 253-0000 x12"),
+                    (Some(country::US), "1 650 253 0000;ext=12"),
+                    (Some(country::DE), "00 1 650 253 0000 #12"),
+                    (None, "tel:+1-650-253-0000;ext=12"),
+                ],
+            ),
+        ] {
+            let expected = parser::parse(None, number).unwrap();
+            let mut set = HashSet::new();
+
+            for (country, form) in forms {
+                for parsed in [
+                    parser::parse(country, form).unwrap(),
+                    parser::parse_and_keep_raw_input(country, form).unwrap(),
+                ] {
+                    assert_eq!(expected, parsed, "{}", form);
+                    assert_eq!(hash(&expected), hash(&parsed), "{}", form);
+                    set.insert(parsed);
+                }
+            }
+
+            assert_eq!(1, set.len(), "{}", number);
+        }
+
+        let a = parser::parse(None, "+39 06 6988 3461").unwrap();
+        let b = parser::parse_and_keep_raw_input(Some(country::IT), "06 6988 3461").unwrap();
+        assert!(a.exactly_equal(&a.clone()));
+        assert!(!a.exactly_equal(&b));
+
+        // The leading zeros and extension count.
+        let c = PhoneNumber::builder(39, 669883461).build().unwrap();
+        let mut d = a.clone();
+        d.set_extension("1").unwrap();
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+    }
+
+    #[test]
+    fn redacted() {
+        let number = parser::parse(None, "+46 70 123 45 67").unwrap();
+        assert_eq!("+46•••••••67", number.redacted().to_string());
+        assert_eq!(
+            "PhoneNumber(+46•••••••67)",
+            format!("{:?}", number.redacted())
+        );
+        assert_eq!(
+            r#""+46•••••••67""#,
+            serde_json::to_string(&number.redacted()).unwrap()
+        );
+
+        for length in 1..=17 {
+            let national = "123456789".repeat(2)[..length].parse::<u64>().unwrap();
+            let number = PhoneNumber::builder(46, national).build().unwrap();
+            let redacted = number.redacted().to_string();
+            let digits = redacted[3..].chars().filter(char::is_ascii_digit).count();
+
+            assert!(redacted.starts_with("+46"), "{}", redacted);
+            assert_eq!(length, redacted[3..].chars().count(), "{}", redacted);
+            assert_eq!(if length > 4 { 2 } else { 0 }, digits, "{}", redacted);
+            assert!(!redacted.contains(&national.to_string()), "{}", redacted);
+        }
+
+        let number = parser::parse(Some(country::IT), "06 6988 3461 ext. 12").unwrap();
+        let redacted = number.redacted().to_string();
+        assert!(redacted.ends_with("61"));
+        assert!(!redacted.contains("12"));
+
+        #[cfg(feature = "redact-debug")]
+        assert_eq!(format!("{:?}", number.redacted()), format!("{:?}", number));
+    }
+
+    #[test]
+    fn canonical_bytes() {
+        for line in include_str!("../tests/fixtures/canonical_bytes.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let (input, hex) = line.split_once('\t').unwrap();
+            let bytes = parser::parse(None, input).unwrap().canonical_bytes();
+
+            assert_eq!(
+                hex,
+                bytes
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>(),
+                "{}",
+                input
+            );
+        }
+
+        // Every leading zero is kept, the raw input and carrier are left out.
+        let number = PhoneNumber::builder(39, 1234)
+            .leading_zeros(2)
+            .carrier("12")
+            .raw_input("+39 001234")
+            .build()
+            .unwrap();
+        assert_eq!(b"+39001234".to_vec(), number.canonical_bytes());
+
+        let number = parser::parse(None, "+49 30 123456;ext=0").unwrap();
+        assert_eq!(b"+4930123456;0".to_vec(), number.canonical_bytes());
+
+        let mut hasher = DefaultHasher::new();
+        number.canonical_hash(&mut hasher);
+        let mut bytes = DefaultHasher::new();
+        bytes.write(b"+4930123456;0");
+        assert_eq!(bytes.finish(), hasher.finish());
+    }
+
+    #[test]
+    fn national_dialing_prefix() {
+        for (input, prefix, national, included) in [
+            ("+39 06 698 83461", None, "06 6988 3461", false),
+            ("+44 20 7031 3000", Some("0"), "020 7031 3000", true),
+            ("+1 650 253 0000", Some("1"), "(650) 253-0000", false),
+            ("+36 1 234 5678", Some("06"), "(06 1) 234 5678", true),
+            ("+49 30 123456", Some("0"), "030 123456", true),
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            let formatter = number.format().mode(Mode::National);
+
+            assert_eq!(prefix, number.national_dialing_prefix().as_deref());
+            assert_eq!(national, formatter.to_string());
+            assert_eq!(included, formatter.includes_national_prefix(), "{}", input);
+            assert!(!number
+                .format()
+                .mode(Mode::International)
+                .includes_national_prefix());
+        }
+
+        // Calling from the same country is formatted nationally.
+        let number = parser::parse(None, "+44 20 7031 3000").unwrap();
+        assert!(number
+            .format()
+            .mode(Mode::International)
+            .calling_from(country::GG)
+            .includes_national_prefix());
+        assert!(!number
+            .format()
+            .mode(Mode::International)
+            .calling_from(country::DE)
+            .includes_national_prefix());
+
+        // Non-geographical entities have no national prefix.
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert_eq!(None, number.national_dialing_prefix());
+        assert!(!number
+            .format()
+            .mode(Mode::National)
+            .includes_national_prefix());
+    }
 }
@@ -0,0 +1,284 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use thiserror::Error;
+
+use crate::country;
+use crate::extension::Extension;
+use crate::metadata::{self, Database};
+use crate::phone_number::{PhoneNumber, Type};
+use crate::validator;
+
+/// Ways a phone number can violate a `ValidationPolicy`.
+#[derive(Error, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PolicyViolation {
+    /// The number is not valid.
+    #[error("invalid number")]
+    Invalid,
+
+    /// The number belongs to a region that is not allowed.
+    #[error("region {0:?} is not allowed")]
+    Region(Option<country::Id>),
+
+    /// The number is of a type that is not allowed.
+    #[error("number type {0:?} is not allowed")]
+    Type(Type),
+
+    /// The number has an extension, which is not allowed.
+    #[error("extensions are not allowed")]
+    Extension,
+
+    /// The extension of the number is longer than allowed.
+    #[error("extension of {0} characters is too long")]
+    ExtensionTooLong(usize),
+}
+
+/// How the extension of a phone number is handled by validation, matching
+/// with `is_number_match_with_policy` and parsing with `parse_with_policy`.
+///
+/// Whether a number is valid only depends on its national significant number,
+/// so by default the extension is ignored and any extension a `PhoneNumber`
+/// can hold, up to `consts::MAX_LENGTH_FOR_EXTENSION` characters, is accepted.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug)]
+pub enum ExtensionPolicy {
+    /// Accept any extension, the default.
+    #[default]
+    Ignore,
+
+    /// Accept only numbers without an extension.
+    RequireAbsent,
+
+    /// Accept extensions of at most the given number of characters.
+    ValidateLength(usize),
+}
+
+impl ExtensionPolicy {
+    /// Check the extension of a phone number against the policy.
+    pub fn check(&self, extension: Option<&Extension>) -> Result<(), PolicyViolation> {
+        match (*self, extension) {
+            (_, None) | (ExtensionPolicy::Ignore, _) => Ok(()),
+            (ExtensionPolicy::RequireAbsent, Some(_)) => Err(PolicyViolation::Extension),
+            (ExtensionPolicy::ValidateLength(max), Some(extension)) => {
+                if extension.len() > max {
+                    Err(PolicyViolation::ExtensionTooLong(extension.len()))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// A policy on which valid phone numbers are acceptable.
+///
+/// By default any valid number is accepted, whatever its extension.
+#[derive(Clone, Debug)]
+pub struct ValidationPolicy<'d> {
+    database: Option<&'d Database>,
+    types: Option<Vec<Type>>,
+    regions: Option<Vec<country::Id>>,
+    extensions: ExtensionPolicy,
+}
+
+impl Default for ValidationPolicy<'static> {
+    fn default() -> Self {
+        ValidationPolicy::new()
+    }
+}
+
+impl ValidationPolicy<'static> {
+    /// Create a policy accepting any valid number.
+    pub fn new() -> Self {
+        ValidationPolicy {
+            database: None,
+            types: None,
+            regions: None,
+            extensions: ExtensionPolicy::Ignore,
+        }
+    }
+}
+
+impl<'d> ValidationPolicy<'d> {
+    /// Use the given `Database` for validation.
+    pub fn database<'a>(self, database: &'a Database) -> ValidationPolicy<'a> {
+        ValidationPolicy {
+            database: Some(database),
+            types: self.types,
+            regions: self.regions,
+            extensions: self.extensions,
+        }
+    }
+
+    /// Only accept numbers of the given types.
+    pub fn allowed_types(mut self, types: &[Type]) -> Self {
+        self.types = Some(types.to_vec());
+        self
+    }
+
+    /// Only accept numbers from the given regions.
+    pub fn allowed_regions(mut self, regions: &[country::Id]) -> Self {
+        self.regions = Some(regions.to_vec());
+        self
+    }
+
+    /// Whether to accept numbers with an extension, a shorthand for the
+    /// `Ignore` and `RequireAbsent` extension policies.
+    pub fn allow_extensions(self, value: bool) -> Self {
+        self.extensions(if value {
+            ExtensionPolicy::Ignore
+        } else {
+            ExtensionPolicy::RequireAbsent
+        })
+    }
+
+    /// How to handle the extension of numbers.
+    pub fn extensions(mut self, policy: ExtensionPolicy) -> Self {
+        self.extensions = policy;
+        self
+    }
+
+    /// Check the phone number against the policy.
+    pub fn check(&self, number: &PhoneNumber) -> Result<(), PolicyViolation> {
+        let global;
+        let database = match self.database {
+            Some(database) => database,
+            None => {
+                global = metadata::global();
+                &global
+            }
+        };
+
+        if !validator::is_valid_with(database, number) {
+            return Err(PolicyViolation::Invalid);
+        }
+
+        if let Some(regions) = self.regions.as_ref() {
+            let region = number
+                .metadata(database)
+                .and_then(|meta| meta.id().parse().ok());
+
+            if !region.map(|r| regions.contains(&r)).unwrap_or(false) {
+                return Err(PolicyViolation::Region(region));
+            }
+        }
+
+        if let Some(types) = self.types.as_ref() {
+            let kind = number.number_type_with(database);
+
+            if !types.contains(&kind) {
+                return Err(PolicyViolation::Type(kind));
+            }
+        }
+
+        self.extensions.check(number.extension())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::parser;
+    use crate::phone_number::Type;
+    use crate::policy::{ExtensionPolicy, PolicyViolation, ValidationPolicy};
+
+    #[test]
+    fn check() {
+        let policy = ValidationPolicy::new()
+            .allowed_types(&[Type::Mobile, Type::FixedLineOrMobile])
+            .allow_extensions(false);
+
+        assert_eq!(
+            Ok(()),
+            policy.check(&parser::parse(None, "+44 7912 345678").unwrap())
+        );
+        assert_eq!(
+            Ok(()),
+            policy.check(&parser::parse(None, "+1 650 253 0000").unwrap())
+        );
+        assert_eq!(
+            Err(PolicyViolation::Type(Type::PremiumRate)),
+            policy.check(&parser::parse(None, "+44 909 876 5432").unwrap())
+        );
+        assert_eq!(
+            Err(PolicyViolation::Extension),
+            policy.check(&parser::parse(None, "+1 650 253 0000 ext. 1234").unwrap())
+        );
+        assert_eq!(
+            Err(PolicyViolation::Invalid),
+            policy.check(&parser::parse(None, "+44 791 234567").unwrap())
+        );
+
+        let policy = ValidationPolicy::default().allowed_regions(&[country::DE, country::AT]);
+
+        assert_eq!(
+            Ok(()),
+            policy.check(&parser::parse(None, "+49 30 901820").unwrap())
+        );
+        assert_eq!(
+            Err(PolicyViolation::Region(Some(country::FR))),
+            policy.check(&parser::parse(None, "+33 1 23 45 67 89").unwrap())
+        );
+        assert_eq!(
+            Err(PolicyViolation::Region(None)),
+            policy.check(&parser::parse(None, "+800 1234 5678").unwrap())
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let mut number = parser::parse(None, "+1 650 253 0000").unwrap();
+        number.set_extension("1234567890123456789012345").unwrap();
+
+        // Validity only looks at the national significant number.
+        assert!(number.is_valid());
+
+        for (policy, result) in [
+            (ExtensionPolicy::Ignore, Ok(())),
+            (
+                ExtensionPolicy::RequireAbsent,
+                Err(PolicyViolation::Extension),
+            ),
+            (
+                ExtensionPolicy::ValidateLength(6),
+                Err(PolicyViolation::ExtensionTooLong(25)),
+            ),
+            (ExtensionPolicy::ValidateLength(25), Ok(())),
+        ] {
+            assert_eq!(
+                result,
+                ValidationPolicy::new().extensions(policy).check(&number),
+                "{:?}",
+                policy
+            );
+            assert_eq!(result, policy.check(number.extension()));
+        }
+
+        assert_eq!(Ok(()), ValidationPolicy::default().check(&number));
+        assert_eq!(ExtensionPolicy::Ignore, ExtensionPolicy::default());
+
+        // Numbers without an extension pass any policy.
+        number.remove_extension();
+
+        for policy in [
+            ExtensionPolicy::Ignore,
+            ExtensionPolicy::RequireAbsent,
+            ExtensionPolicy::ValidateLength(0),
+        ] {
+            assert_eq!(
+                Ok(()),
+                ValidationPolicy::new().extensions(policy).check(&number)
+            );
+        }
+    }
+}
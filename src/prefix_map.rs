@@ -0,0 +1,149 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::num::ParseIntError;
+
+/// Prefix maps by language and country calling code.
+pub type PrefixMaps = BTreeMap<String, BTreeMap<u16, PrefixMap>>;
+
+/// Languages which don't fall back to English descriptions, since those would
+/// likely not be understood.
+const NO_ENGLISH_FALLBACK: &[&str] = &["zh", "ja", "ko"];
+
+/// Descriptions, like carrier names, of phone number prefixes including the
+/// country calling code.
+///
+/// The descriptions are deduplicated and kept in a single string pool, which
+/// keeps the maps compact when loading them.
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct PrefixMap {
+    /// The sorted prefixes, with the index of their description.
+    prefixes: Vec<(u64, u32)>,
+
+    /// The lengths of the prefixes, longest first.
+    lengths: Vec<u8>,
+
+    /// The end of each description in the pool.
+    ends: Vec<u32>,
+
+    /// All the descriptions one after the other.
+    pool: String,
+}
+
+impl PrefixMap {
+    /// Parse the upstream `prefix|description` format, skipping comments and
+    /// empty lines.
+    #[allow(unused)] // This is unused in the library
+    pub fn parse(content: &str) -> Result<Self, ParseIntError> {
+        let mut map = PrefixMap::default();
+        let mut seen = BTreeMap::new();
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (prefix, description) = line.split_once('|').unwrap_or((line, ""));
+            let prefix = prefix.trim();
+            let description = description.trim();
+
+            let index = *seen.entry(description.to_owned()).or_insert_with(|| {
+                map.pool.push_str(description);
+                map.ends.push(map.pool.len() as u32);
+                map.ends.len() as u32 - 1
+            });
+
+            map.prefixes.push((prefix.parse()?, index));
+
+            if !map.lengths.contains(&(prefix.len() as u8)) {
+                map.lengths.push(prefix.len() as u8);
+            }
+        }
+
+        map.prefixes.sort_unstable();
+        map.lengths.sort_unstable_by(|a, b| b.cmp(a));
+
+        Ok(map)
+    }
+
+    /// Get the description of the longest prefix of the given digits, if any.
+    ///
+    /// An empty description means there is none for the prefix, even if a
+    /// shorter prefix has one.
+    #[allow(unused)] // This is unused in the build script
+    pub fn get(&self, digits: &str) -> Option<&str> {
+        for &length in &self.lengths {
+            let prefix = match digits.get(..length as usize).map(str::parse::<u64>) {
+                Some(Ok(prefix)) => prefix,
+                _ => continue,
+            };
+
+            if let Ok(index) = self.prefixes.binary_search_by_key(&prefix, |&(p, _)| p) {
+                let index = self.prefixes[index].1 as usize;
+                let start = if index == 0 { 0 } else { self.ends[index - 1] };
+                let description = &self.pool[start as usize..self.ends[index] as usize];
+
+                return if description.is_empty() {
+                    None
+                } else {
+                    Some(description)
+                };
+            }
+        }
+
+        None
+    }
+}
+
+/// Get the description of the longest prefix of the given digits, which start
+/// with the country calling code, in the given language, falling back to
+/// English.
+#[allow(unused)] // This is unused in the build script
+pub fn describe<'a>(maps: &'a PrefixMaps, lang: &str, code: u16, digits: &str) -> Option<&'a str> {
+    let map = maps.get(lang).and_then(|m| m.get(&code)).or_else(|| {
+        if NO_ENGLISH_FALLBACK.contains(&lang) {
+            None
+        } else {
+            maps.get("en").and_then(|m| m.get(&code))
+        }
+    })?;
+
+    map.get(digits)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_map::PrefixMap;
+
+    #[test]
+    fn longest_prefix() {
+        let map = PrefixMap::parse(
+            "# Comment\n\
+             \n\
+             4915|Short\n\
+             49151|Long\n\
+             491512|\n\
+             4917|Other\n",
+        )
+        .unwrap();
+
+        assert_eq!(Some("Long"), map.get("4915112345678"));
+        assert_eq!(Some("Short"), map.get("4915212345678"));
+        assert_eq!(Some("Other"), map.get("4917212345678"));
+        assert_eq!(None, map.get("4915123456789"));
+        assert_eq!(None, map.get("4930123456"));
+        assert_eq!(None, map.get("49"));
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `PhoneNumber` message of libphonenumber's `phonenumber.proto`, to
+//! exchange numbers with services using the other implementations.
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use phonenumber::proto;
+//!
+//! let number = phonenumber::parse(None, "+39 06 6988 3461").unwrap();
+//! let message = proto::PhoneNumber::from(&number);
+//! assert_eq!(Some(true), message.italian_leading_zero);
+//!
+//! let decoded = proto::PhoneNumber::decode(&message.encode()).unwrap();
+//! assert_eq!(number, phonenumber::PhoneNumber::try_from(decoded).unwrap());
+//! ```
+
+use std::convert::{TryFrom, TryInto};
+
+use crate::carrier::Carrier;
+use crate::country;
+use crate::error;
+use crate::extension::Extension;
+
+/// The `PhoneNumber` protobuf message, with the optional fields of proto2 as
+/// `Option`s.
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct PhoneNumber {
+    /// Field 1, the country calling code.
+    pub country_code: i32,
+
+    /// Field 2, the national number without leading zeros.
+    pub national_number: u64,
+
+    /// Field 3.
+    pub extension: Option<String>,
+
+    /// Field 4, whether the national number has leading zeros.
+    pub italian_leading_zero: Option<bool>,
+
+    /// Field 8, the number of leading zeros when `italian_leading_zero` is
+    /// set, 1 when missing.
+    pub number_of_leading_zeros: Option<i32>,
+
+    /// Field 5.
+    pub raw_input: Option<String>,
+
+    /// Field 6, the `CountryCodeSource` value, kept as a number so values
+    /// from newer versions survive decoding.
+    pub country_code_source: Option<i32>,
+
+    /// Field 7.
+    pub preferred_domestic_carrier_code: Option<String>,
+}
+
+/// The values of the `CountryCodeSource` enumeration.
+pub mod country_code_source {
+    pub const UNSPECIFIED: i32 = 0;
+    pub const FROM_NUMBER_WITH_PLUS_SIGN: i32 = 1;
+    pub const FROM_NUMBER_WITH_IDD: i32 = 5;
+    pub const FROM_NUMBER_WITHOUT_PLUS_SIGN: i32 = 10;
+    pub const FROM_DEFAULT_COUNTRY: i32 = 20;
+}
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH: u8 = 2;
+const FIXED32: u8 = 5;
+
+impl PhoneNumber {
+    /// Encode the message in the protobuf binary format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        key(&mut out, 1, VARINT);
+        varint(&mut out, self.country_code as i64 as u64);
+        key(&mut out, 2, VARINT);
+        varint(&mut out, self.national_number);
+
+        if let Some(value) = &self.extension {
+            string(&mut out, 3, value);
+        }
+
+        if let Some(value) = self.italian_leading_zero {
+            key(&mut out, 4, VARINT);
+            varint(&mut out, value as u64);
+        }
+
+        if let Some(value) = &self.raw_input {
+            string(&mut out, 5, value);
+        }
+
+        if let Some(value) = self.country_code_source {
+            key(&mut out, 6, VARINT);
+            varint(&mut out, value as i64 as u64);
+        }
+
+        if let Some(value) = &self.preferred_domestic_carrier_code {
+            string(&mut out, 7, value);
+        }
+
+        if let Some(value) = self.number_of_leading_zeros {
+            key(&mut out, 8, VARINT);
+            varint(&mut out, value as i64 as u64);
+        }
+
+        out
+    }
+
+    /// Decode a message in the protobuf binary format, skipping unknown
+    /// fields.
+    pub fn decode(mut input: &[u8]) -> Result<Self, error::Proto> {
+        let mut message = PhoneNumber::default();
+        let mut country_code = None;
+        let mut national_number = None;
+
+        while !input.is_empty() {
+            let key = read_varint(&mut input)?;
+            let (field, kind) = (key >> 3, (key & 7) as u8);
+
+            match (field, kind) {
+                (1, VARINT) => country_code = Some(read_varint(&mut input)? as i32),
+                (2, VARINT) => national_number = Some(read_varint(&mut input)?),
+                (3, LENGTH) => message.extension = Some(read_string(&mut input)?),
+                (4, VARINT) => message.italian_leading_zero = Some(read_varint(&mut input)? != 0),
+                (5, LENGTH) => message.raw_input = Some(read_string(&mut input)?),
+                (6, VARINT) => message.country_code_source = Some(read_varint(&mut input)? as i32),
+                (7, LENGTH) => {
+                    message.preferred_domestic_carrier_code = Some(read_string(&mut input)?)
+                }
+                (8, VARINT) => {
+                    message.number_of_leading_zeros = Some(read_varint(&mut input)? as i32)
+                }
+                (1..=8, kind) => return Err(error::Proto::WireType(kind)),
+
+                (_, VARINT) => {
+                    read_varint(&mut input)?;
+                }
+                (_, FIXED64) => {
+                    read_bytes(&mut input, 8)?;
+                }
+                (_, LENGTH) => {
+                    let length = read_varint(&mut input)?;
+                    read_bytes(&mut input, length)?;
+                }
+                (_, FIXED32) => {
+                    read_bytes(&mut input, 4)?;
+                }
+                (_, kind) => return Err(error::Proto::WireType(kind)),
+            }
+        }
+
+        message.country_code = country_code.ok_or(error::Proto::MissingField("country_code"))?;
+        message.national_number =
+            national_number.ok_or(error::Proto::MissingField("national_number"))?;
+
+        Ok(message)
+    }
+}
+
+fn key(out: &mut Vec<u8>, field: u64, kind: u8) {
+    varint(out, field << 3 | kind as u64);
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+fn string(out: &mut Vec<u8>, field: u64, value: &str) {
+    key(out, field, LENGTH);
+    varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, error::Proto> {
+    let mut value = 0;
+
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or(error::Proto::Truncated)?;
+        *input = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+
+    Err(error::Proto::Truncated)
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8], length: u64) -> Result<&'a [u8], error::Proto> {
+    let length = usize::try_from(length).map_err(|_| error::Proto::Truncated)?;
+
+    if input.len() < length {
+        return Err(error::Proto::Truncated);
+    }
+
+    let (bytes, rest) = input.split_at(length);
+    *input = rest;
+    Ok(bytes)
+}
+
+fn read_string(input: &mut &[u8]) -> Result<String, error::Proto> {
+    let length = read_varint(input)?;
+    Ok(String::from_utf8(read_bytes(input, length)?.to_vec())?)
+}
+
+/// The `CountryCodeSource` value for a source.
+fn source_value(source: country::Source) -> i32 {
+    match source {
+        country::Source::Plus => country_code_source::FROM_NUMBER_WITH_PLUS_SIGN,
+        country::Source::Idd => country_code_source::FROM_NUMBER_WITH_IDD,
+        country::Source::Number => country_code_source::FROM_NUMBER_WITHOUT_PLUS_SIGN,
+        country::Source::Default => country_code_source::FROM_DEFAULT_COUNTRY,
+    }
+}
+
+/// The source for a `CountryCodeSource` value, unspecified and unknown values
+/// being the default one.
+fn source(value: Option<i32>) -> country::Source {
+    match value {
+        Some(country_code_source::FROM_NUMBER_WITH_PLUS_SIGN) => country::Source::Plus,
+        Some(country_code_source::FROM_NUMBER_WITH_IDD) => country::Source::Idd,
+        Some(country_code_source::FROM_NUMBER_WITHOUT_PLUS_SIGN) => country::Source::Number,
+        Some(country_code_source::FROM_DEFAULT_COUNTRY) => country::Source::Default,
+        _ => country::Source::default(),
+    }
+}
+
+impl TryFrom<PhoneNumber> for crate::PhoneNumber {
+    type Error = error::Build;
+
+    fn try_from(message: PhoneNumber) -> Result<Self, Self::Error> {
+        let code = message
+            .country_code
+            .try_into()
+            .map_err(|_| error::Build::CountryCodeOutOfRange(message.country_code))?;
+
+        // Like libphonenumber, the number of leading zeros only counts when
+        // there are any, and is at least one then.
+        let zeros = if message.italian_leading_zero == Some(true) {
+            message
+                .number_of_leading_zeros
+                .unwrap_or(1)
+                .clamp(1, u8::MAX as i32) as u8
+        } else {
+            0
+        };
+
+        let mut builder = crate::PhoneNumber::builder(code, message.national_number)
+            .source(source(message.country_code_source))
+            .leading_zeros(zeros);
+
+        if let Some(extension) = message.extension {
+            builder = builder.extension(extension);
+        }
+
+        if let Some(carrier) = message.preferred_domestic_carrier_code {
+            builder = builder.carrier(carrier);
+        }
+
+        if let Some(raw_input) = message.raw_input {
+            builder = builder.raw_input(raw_input);
+        }
+
+        builder.build()
+    }
+}
+
+impl<'a> From<&'a crate::PhoneNumber> for PhoneNumber {
+    fn from(number: &'a crate::PhoneNumber) -> PhoneNumber {
+        let zeros = number.national().zeros();
+
+        PhoneNumber {
+            country_code: number.code().value().into(),
+            national_number: number.national().value(),
+            extension: number.extension().map(|e| Extension::as_ref(e).to_owned()),
+            italian_leading_zero: if zeros > 0 { Some(true) } else { None },
+            number_of_leading_zeros: if zeros > 1 { Some(zeros.into()) } else { None },
+            raw_input: number.raw_input().map(str::to_owned),
+            country_code_source: Some(source_value(number.code().source())),
+            preferred_domestic_carrier_code: number
+                .carrier()
+                .map(|c| Carrier::as_ref(c).to_owned()),
+        }
+    }
+}
+
+impl From<crate::PhoneNumber> for PhoneNumber {
+    fn from(number: crate::PhoneNumber) -> PhoneNumber {
+        PhoneNumber::from(&number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::country;
+    use crate::error;
+    use crate::parser;
+    use crate::proto::{self, country_code_source};
+    use crate::PhoneNumber;
+
+    fn corpus() -> Vec<PhoneNumber> {
+        let mut numbers = Vec::new();
+
+        for (country, number) in [
+            (None, "+1 650 253 0000"),
+            (None, "+39 06 6988 3461"),
+            (None, "+39 00 1234 5678"),
+            (Some(country::US), "011 44 20 7031 3000"),
+            (Some(country::US), "1 650 253 0000 ext. 1234"),
+            (Some(country::DE), "030 123456"),
+            (None, "+800 1234 5678"),
+        ] {
+            numbers.push(parser::parse(country, number).unwrap());
+            numbers.push(parser::parse_and_keep_raw_input(country, number).unwrap());
+        }
+
+        for zeros in [0, 1, 2, 3] {
+            numbers.push(
+                PhoneNumber::builder(225, 20212345)
+                    .source(country::Source::Number)
+                    .leading_zeros(zeros)
+                    .extension("12,#3")
+                    .carrier("15")
+                    .raw_input("whatever")
+                    .build()
+                    .unwrap(),
+            );
+        }
+
+        numbers
+    }
+
+    #[test]
+    fn round_trip() {
+        for number in corpus() {
+            let message = proto::PhoneNumber::from(&number);
+            assert!(number.exactly_equal(&PhoneNumber::try_from(message.clone()).unwrap()));
+            assert_eq!(
+                message,
+                proto::PhoneNumber::decode(&message.encode()).unwrap()
+            );
+            assert_eq!(
+                message,
+                proto::PhoneNumber::from(PhoneNumber::try_from(message.clone()).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let number = parser::parse(None, "+39 06 6988 3461").unwrap();
+        let message = proto::PhoneNumber::from(&number);
+        assert_eq!(39, message.country_code);
+        assert_eq!(669883461, message.national_number);
+        assert_eq!(Some(true), message.italian_leading_zero);
+        assert_eq!(None, message.number_of_leading_zeros);
+        assert_eq!(
+            Some(country_code_source::FROM_NUMBER_WITH_PLUS_SIGN),
+            message.country_code_source
+        );
+        assert_eq!(None, message.extension);
+        assert_eq!(None, message.raw_input);
+        assert_eq!(None, message.preferred_domestic_carrier_code);
+
+        let number = PhoneNumber::builder(39, 1234)
+            .leading_zeros(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some(2),
+            proto::PhoneNumber::from(&number).number_of_leading_zeros
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let base = proto::PhoneNumber {
+            country_code: 39,
+            national_number: 669883461,
+            ..Default::default()
+        };
+
+        // Unknown and unspecified sources are the default one.
+        for source in [None, Some(0), Some(2), Some(-1), Some(1000)] {
+            let message = proto::PhoneNumber {
+                country_code_source: source,
+                ..base.clone()
+            };
+
+            assert_eq!(
+                country::Source::default(),
+                PhoneNumber::try_from(message).unwrap().code().source()
+            );
+        }
+
+        // Leading zeros only count with `italian_leading_zero`, and are one
+        // by default.
+        for (italian, count, zeros) in [
+            (None, None, 0),
+            (Some(false), Some(2), 0),
+            (Some(true), None, 1),
+            (Some(true), Some(0), 1),
+            (Some(true), Some(-3), 1),
+            (Some(true), Some(2), 2),
+        ] {
+            let message = proto::PhoneNumber {
+                italian_leading_zero: italian,
+                number_of_leading_zeros: count,
+                ..base.clone()
+            };
+
+            assert_eq!(
+                zeros,
+                PhoneNumber::try_from(message).unwrap().national().zeros()
+            );
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for (code, national, extension) in [
+            (-1, 1234, None),
+            (100_000, 1234, None),
+            (999, 1234, None),
+            (1, 12_345_678_901_234_567_890, None),
+            (1, 6502530000, Some("x")),
+        ] {
+            let message = proto::PhoneNumber {
+                country_code: code,
+                national_number: national,
+                extension: extension.map(String::from),
+                ..Default::default()
+            };
+
+            assert!(PhoneNumber::try_from(message).is_err());
+        }
+
+        assert!(matches!(
+            PhoneNumber::try_from(proto::PhoneNumber {
+                country_code: -1,
+                ..Default::default()
+            }),
+            Err(error::Build::CountryCodeOutOfRange(-1))
+        ));
+    }
+
+    #[test]
+    fn wire() {
+        // Encoded with the reference implementation.
+        let bytes = [
+            0x08, 0x27, 0x10, 0xc5, 0xb8, 0xb6, 0xbf, 0x02, 0x20, 0x01, 0x30, 0x01,
+        ];
+        let message = proto::PhoneNumber::decode(&bytes).unwrap();
+        assert_eq!(39, message.country_code);
+        assert_eq!(669883461, message.national_number);
+        assert_eq!(Some(true), message.italian_leading_zero);
+        assert_eq!(Some(1), message.country_code_source);
+
+        let mut message = message;
+        message.country_code = -1;
+        message.raw_input = Some("ü".into());
+        message.number_of_leading_zeros = Some(3);
+        assert_eq!(
+            message,
+            proto::PhoneNumber::decode(&message.encode()).unwrap()
+        );
+
+        // Unknown fields are skipped.
+        let mut unknown = bytes.to_vec();
+        unknown.extend_from_slice(&[0x48, 0x05, 0x52, 0x02, 0x61, 0x62, 0x5d, 0, 0, 0, 0]);
+        assert_eq!(
+            proto::PhoneNumber::decode(&bytes).unwrap(),
+            proto::PhoneNumber::decode(&unknown).unwrap()
+        );
+
+        assert!(matches!(
+            proto::PhoneNumber::decode(&bytes[..4]),
+            Err(error::Proto::Truncated)
+        ));
+        assert!(matches!(
+            proto::PhoneNumber::decode(&bytes[..2]),
+            Err(error::Proto::MissingField("national_number"))
+        ));
+        assert!(matches!(
+            proto::PhoneNumber::decode(&[0x08, 0x27, 0x12, 0x00]),
+            Err(error::Proto::WireType(2))
+        ));
+        assert!(matches!(
+            proto::PhoneNumber::decode(&[0x08, 0x27, 0x10, 0x01, 0x2a, 0x01, 0xff]),
+            Err(error::Proto::Utf8(_))
+        ));
+    }
+}
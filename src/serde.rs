@@ -0,0 +1,640 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializing phone numbers as strings, with `#[serde(with = "...")]`.
+//!
+//! ```
+//! # extern crate serde_derive;
+//! use phonenumber::PhoneNumber;
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Contact {
+//!     #[serde(with = "phonenumber::serde::e164")]
+//!     phone: PhoneNumber,
+//! }
+//! ```
+//!
+//! With the `string-serde` feature `PhoneNumber` itself is serialized in the
+//! E.164 format.
+
+use std::fmt::Write;
+
+use ::serde::de::{Deserialize, Deserializer, Error};
+use ::serde::ser::{self, Serializer};
+
+use crate::formatter::Mode;
+use crate::parser;
+use crate::phone_number::PhoneNumber;
+
+fn serialize<S: Serializer>(
+    number: &PhoneNumber,
+    serializer: S,
+    mode: Mode,
+) -> Result<S::Ok, S::Error> {
+    // Only the E.164 format doesn't need the metadata, serializers expect
+    // formatting to never fail otherwise.
+    if mode == Mode::E164 {
+        return serializer.collect_str(&number.format().mode(mode));
+    }
+
+    let mut formatted = String::new();
+    write!(formatted, "{}", number.format().mode(mode)).map_err(|_| {
+        <S::Error as ser::Error>::custom(format!(
+            "missing metadata to format phone number {} as {:?}",
+            number.format().mode(Mode::E164),
+            mode
+        ))
+    })?;
+
+    serializer.serialize_str(&formatted)
+}
+
+fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    prefix: &str,
+) -> Result<PhoneNumber, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    if !value.starts_with(prefix) {
+        return Err(D::Error::custom(format!(
+            "expected a phone number starting with {:?}, found {:?}",
+            prefix, value
+        )));
+    }
+
+    parser::parse(None, &value)
+        .map_err(|err| D::Error::custom(format!("invalid phone number {:?}: {}", value, err)))
+}
+
+/// Phone numbers as E.164 strings, like "+46701234567".
+///
+/// Extensions are dropped when serializing, see `rfc3966` to keep them.
+pub mod e164 {
+    use ::serde::de::Deserializer;
+    use ::serde::ser::Serializer;
+
+    use crate::formatter::Mode;
+    use crate::phone_number::PhoneNumber;
+
+    /// Serialize the number as an E.164 string.
+    pub fn serialize<S: Serializer>(
+        number: &PhoneNumber,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::serialize(number, serializer, Mode::E164)
+    }
+
+    /// Deserialize a number from a string starting with "+".
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PhoneNumber, D::Error> {
+        super::deserialize(deserializer, "+")
+    }
+}
+
+/// Phone numbers as RFC3966 URIs, like "tel:+46-70-123-45-67;ext=123",
+/// keeping the extension.
+pub mod rfc3966 {
+    use ::serde::de::Deserializer;
+    use ::serde::ser::Serializer;
+
+    use crate::formatter::Mode;
+    use crate::phone_number::PhoneNumber;
+
+    /// Serialize the number as an RFC3966 URI.
+    pub fn serialize<S: Serializer>(
+        number: &PhoneNumber,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::serialize(number, serializer, Mode::Rfc3966)
+    }
+
+    /// Deserialize a number from an RFC3966 URI, starting with "tel:+".
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PhoneNumber, D::Error> {
+        super::deserialize(deserializer, "tel:+")
+    }
+}
+
+/// Phone numbers serialized as E.164 strings, and deserialized from either
+/// strings or the struct layout of `PhoneNumber` without the `string-serde`
+/// feature, for migrating stored numbers to strings.
+///
+/// Strings are parsed without a default region, so they need a leading "+",
+/// RFC3966 URIs work too.
+///
+/// Telling the two apart needs a self-describing format like JSON, formats
+/// like `bincode` which can't say what comes next fail to deserialize.
+pub mod flexible {
+    use std::convert::TryFrom;
+    use std::fmt;
+
+    use ::serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+    use ::serde::de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Visitor};
+    use ::serde::ser::Serializer;
+
+    use crate::carrier::Carrier;
+    use crate::country;
+    use crate::error;
+    use crate::extension::Extension;
+    use crate::national_number::NationalNumber;
+    use crate::parser;
+    use crate::phone_number::{Extras, PhoneNumber};
+
+    /// The struct layout of `PhoneNumber`, which stays available with the
+    /// `string-serde` feature.
+    ///
+    /// It's stored by users of formats like `bincode`, which can't skip
+    /// missing or unknown fields, so it mustn't change. The raw input isn't
+    /// part of it, and is lost when serializing.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "PhoneNumber")]
+    pub(super) struct Legacy {
+        code: country::Code,
+        national: NationalNumber,
+        extension: Option<Extension>,
+        carrier: Option<Carrier>,
+    }
+
+    /// The struct layout of `PhoneNumber` borrowing from it, to serialize it
+    /// without copying the strings.
+    #[cfg(not(feature = "string-serde"))]
+    #[derive(Serialize)]
+    #[serde(rename = "PhoneNumber")]
+    pub(super) struct LegacyRef<'a> {
+        code: &'a country::Code,
+        national: &'a NationalNumber,
+        extension: Option<&'a Extension>,
+        carrier: Option<&'a Carrier>,
+    }
+
+    // Like the builder, so numbers with an unknown country calling code or a
+    // national number too long for any phone number can't be deserialized.
+    impl TryFrom<Legacy> for PhoneNumber {
+        type Error = error::Build;
+
+        fn try_from(value: Legacy) -> Result<Self, Self::Error> {
+            PhoneNumber {
+                code: value.code,
+                national: value.national,
+                extras: Extras::new(value.extension, value.carrier, None),
+            }
+            .checked()
+        }
+    }
+
+    impl Legacy {
+        /// Check the deserialized number, see `TryFrom<Legacy>`.
+        pub(super) fn into_number<E: Error>(self) -> Result<PhoneNumber, E> {
+            PhoneNumber::try_from(self)
+                .map_err(|err| E::custom(format!("invalid phone number: {}", err)))
+        }
+    }
+
+    impl From<&PhoneNumber> for Legacy {
+        fn from(value: &PhoneNumber) -> Self {
+            Legacy {
+                code: value.code,
+                national: value.national,
+                extension: value.extension().cloned(),
+                carrier: value.carrier().cloned(),
+            }
+        }
+    }
+
+    #[cfg(not(feature = "string-serde"))]
+    impl<'a> From<&'a PhoneNumber> for LegacyRef<'a> {
+        fn from(value: &'a PhoneNumber) -> Self {
+            LegacyRef {
+                code: &value.code,
+                national: &value.national,
+                extension: value.extension(),
+                carrier: value.carrier(),
+            }
+        }
+    }
+
+    struct Flexible;
+
+    impl<'de> Visitor<'de> for Flexible {
+        type Value = PhoneNumber;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a phone number string or struct")
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<PhoneNumber, E> {
+            parser::parse(None, value)
+                .map_err(|err| E::custom(format!("invalid phone number {:?}: {}", value, err)))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<PhoneNumber, A::Error> {
+            Legacy::deserialize(MapAccessDeserializer::new(map))?.into_number()
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<PhoneNumber, A::Error> {
+            Legacy::deserialize(SeqAccessDeserializer::new(seq))?.into_number()
+        }
+    }
+
+    /// Serialize the number as an E.164 string.
+    pub fn serialize<S: Serializer>(
+        number: &PhoneNumber,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::e164::serialize(number, serializer)
+    }
+
+    /// Deserialize a number from a string or the struct layout.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PhoneNumber, D::Error> {
+        deserializer.deserialize_any(Flexible)
+    }
+}
+
+#[cfg(not(feature = "string-serde"))]
+impl ::serde::Serialize for PhoneNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ::serde::Serialize::serialize(&flexible::LegacyRef::from(self), serializer)
+    }
+}
+
+#[cfg(not(feature = "string-serde"))]
+impl<'de> ::serde::Deserialize<'de> for PhoneNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        flexible::Legacy::deserialize(deserializer)?.into_number()
+    }
+}
+
+#[cfg(feature = "string-serde")]
+impl ::serde::Serialize for PhoneNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        e164::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "string-serde")]
+impl<'de> ::serde::Deserialize<'de> for PhoneNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        e164::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_derive::{Deserialize, Serialize};
+
+    use crate::metadata::Database;
+    use crate::parser;
+    use crate::phone_number::PhoneNumber;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct E164 {
+        #[serde(with = "crate::serde::e164")]
+        phone: PhoneNumber,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Flexible {
+        #[serde(with = "crate::serde::flexible")]
+        phone: PhoneNumber,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Rfc3966 {
+        #[serde(with = "crate::serde::rfc3966")]
+        phone: PhoneNumber,
+    }
+
+    #[test]
+    fn e164() {
+        let phone = parser::parse(None, "+46 70 123 45 67").unwrap();
+        let json = serde_json::to_string(&E164 {
+            phone: phone.clone(),
+        })
+        .unwrap();
+
+        assert_eq!(r#"{"phone":"+46701234567"}"#, json);
+        assert_eq!(E164 { phone }, serde_json::from_str(&json).unwrap());
+
+        assert_eq!(
+            "expected a phone number starting with \"+\", found \"0701234567\" at line 1 column 22",
+            serde_json::from_str::<E164>(r#"{"phone":"0701234567"}"#)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "invalid phone number \"+999 1234\": invalid country code at line 1 column 21",
+            serde_json::from_str::<E164>(r#"{"phone":"+999 1234"}"#)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "invalid type: integer `46701234567`, expected a string at line 1 column 20",
+            serde_json::from_str::<E164>(r#"{"phone":46701234567}"#)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn rfc3966() {
+        let phone = parser::parse(None, "+46 70 123 45 67 ext. 123").unwrap();
+        let json = serde_json::to_string(&Rfc3966 {
+            phone: phone.clone(),
+        })
+        .unwrap();
+
+        assert_eq!(r#"{"phone":"tel:+46-70-123-45-67;ext=123"}"#, json);
+        assert_eq!(Rfc3966 { phone }, serde_json::from_str(&json).unwrap());
+
+        assert_eq!(
+            "expected a phone number starting with \"tel:+\", found \"+46701234567\" at line 1 column 24",
+            serde_json::from_str::<Rfc3966>(r#"{"phone":"+46701234567"}"#)
+                .unwrap_err()
+                .to_string()
+        );
+
+        // A number parsed with a database knowing a calling code the global
+        // one doesn't.
+        let database = Database::parse(
+            r#"
+            <phoneNumberMetadata>
+              <territories>
+                <territory id="001" countryCode="801">
+                  <generalDesc>
+                    <nationalNumberPattern>\d{8}</nationalNumberPattern>
+                  </generalDesc>
+                </territory>
+              </territories>
+            </phoneNumberMetadata>
+            "#,
+        )
+        .unwrap();
+        let phone = parser::parse_with(&database, None, "+801 1234 5678").unwrap();
+
+        assert_eq!(
+            "missing metadata to format phone number +80112345678 as Rfc3966",
+            serde_json::to_string(&Rfc3966 { phone })
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn flexible() {
+        let phone = parser::parse(None, "+46 70 123 45 67 ext. 123").unwrap();
+
+        for json in &[
+            r#"{"phone":"+46701234567;ext=123"}"#,
+            r#"{"phone":"tel:+46-70-123-45-67;ext=123"}"#,
+            r#"{"phone":{"code":{"value":46,"source":"plus"},"national":{"value":701234567,"zeros":0},"extension":"123","carrier":null}}"#,
+            r#"{"phone":[[46,"plus"],[701234567,0],"123",null]}"#,
+        ] {
+            assert_eq!(
+                Flexible {
+                    phone: phone.clone()
+                },
+                serde_json::from_str(json).unwrap(),
+                "{}",
+                json
+            );
+        }
+
+        assert_eq!(
+            r#"{"phone":"+46701234567"}"#,
+            serde_json::to_string(&Flexible { phone }).unwrap()
+        );
+
+        assert_eq!(
+            "invalid phone number \"0701234567\": invalid country code at line 1 column 21",
+            serde_json::from_str::<Flexible>(r#"{"phone":"0701234567"}"#)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "invalid type: integer `46701234567`, expected a phone number string or struct at line 1 column 20",
+            serde_json::from_str::<Flexible>(r#"{"phone":46701234567}"#)
+                .unwrap_err()
+                .to_string()
+        );
+
+        // Bincode can't tell strings from structs.
+        let bytes = bincode::serialize(&E164 {
+            phone: parser::parse(None, "+46701234567").unwrap(),
+        })
+        .unwrap();
+        assert!(bincode::deserialize::<Flexible>(&bytes).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        for (json, error) in [
+            (
+                r#"{"phone":{"code":{"value":999,"source":"plus"},"national":{"value":1234567,"zeros":0},"extension":null,"carrier":null}}"#,
+                "invalid phone number: invalid country code: 999 at line 1 column 118",
+            ),
+            (
+                r#"{"phone":{"code":{"value":0,"source":"plus"},"national":{"value":1234567,"zeros":0},"extension":null,"carrier":null}}"#,
+                "invalid phone number: invalid country code: 0 at line 1 column 116",
+            ),
+            (
+                r#"{"phone":{"code":{"value":46,"source":"plus"},"national":{"value":123456789012345678,"zeros":0},"extension":null,"carrier":null}}"#,
+                "invalid phone number: the number is too long at line 1 column 128",
+            ),
+            (
+                r#"{"phone":{"code":{"value":39,"source":"plus"},"national":{"value":1234,"zeros":20},"extension":null,"carrier":null}}"#,
+                "invalid phone number: the number is too long at line 1 column 115",
+            ),
+            (
+                r#"{"phone":{"code":{"value":46,"source":"plus"},"national":{"value":1234567890123456789012345,"zeros":0},"extension":null,"carrier":null}}"#,
+                "invalid type: floating point `1.2345678901234568e+24`, expected u64 at line 1 column 91",
+            ),
+            (
+                r#"{"phone":{"code":{"value":46,"source":"plus"},"national":{"value":701234567,"zeros":0},"extension":"12a","carrier":null}}"#,
+                "invalid character in extension: 'a' at line 1 column 104",
+            ),
+            (r#"{"phone":[[999,"plus"],[1234567,0],null,null]}"#, "invalid phone number: invalid country code: 999 at line 1 column 45"),
+        ] {
+            assert_eq!(
+                error,
+                serde_json::from_str::<Flexible>(json)
+                    .unwrap_err()
+                    .to_string(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn legacy() {
+        use crate::serde::flexible::Legacy;
+
+        let phone = parser::parse_and_keep_raw_input(None, "+46 70 123 45 67 ext. 123").unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&phone).unwrap(),
+            serde_json::to_string(&Legacy::from(&phone)).unwrap()
+        );
+        assert_eq!(
+            bincode::serialize(&phone).unwrap(),
+            bincode::serialize(&Legacy::from(&phone)).unwrap()
+        );
+
+        // The raw input isn't serialized.
+        let json = serde_json::to_string(&phone).unwrap();
+        assert!(!json.contains("raw_input"));
+
+        let phone = parser::parse(None, "+46 70 123 45 67 ext. 123").unwrap();
+        assert!(phone.exactly_equal(&serde_json::from_str::<PhoneNumber>(&json).unwrap()));
+        assert!(phone.exactly_equal(
+            &crate::serde::flexible::deserialize(&mut serde_json::Deserializer::from_str(&json))
+                .unwrap()
+        ));
+    }
+
+    /// Numbers serialized by earlier versions, in `bincode` and JSON, with
+    /// the input they were parsed from.
+    #[cfg(not(feature = "string-serde"))]
+    const STORED: &[(Option<crate::country::Id>, &str, &[u8], &str)] = &[
+        (
+            Some(crate::country::IT),
+            "06 6988 3461",
+            &[
+                0x27, 0x00, 0x03, 0x00, 0x00, 0x00, 0x45, 0x9c, 0xed, 0x27, 0x00, 0x00, 0x00, 0x00,
+                0x01, 0x00, 0x00,
+            ],
+            r#"{"code":{"value":39,"source":"default"},"national":{"value":669883461,"zeros":1},"extension":null,"carrier":null}"#,
+        ),
+        (
+            None,
+            "+39 06 6988 3461",
+            &[
+                0x27, 0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0x9c, 0xed, 0x27, 0x00, 0x00, 0x00, 0x00,
+                0x01, 0x00, 0x00,
+            ],
+            r#"{"code":{"value":39,"source":"plus"},"national":{"value":669883461,"zeros":1},"extension":null,"carrier":null}"#,
+        ),
+        (
+            None,
+            "+1 650 253 0000 ext. 123",
+            &[
+                0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xd0, 0xbb, 0x94, 0x83, 0x01, 0x00, 0x00, 0x00,
+                0x00, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x31, 0x32, 0x33, 0x00,
+            ],
+            r#"{"code":{"value":1,"source":"plus"},"national":{"value":6502530000,"zeros":0},"extension":"123","carrier":null}"#,
+        ),
+        (
+            Some(crate::country::BR),
+            "0 15 11 91234 5678",
+            &[
+                0x37, 0x00, 0x03, 0x00, 0x00, 0x00, 0x4e, 0xf8, 0x07, 0xc6, 0x02, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x31, 0x35,
+            ],
+            r#"{"code":{"value":55,"source":"default"},"national":{"value":11912345678,"zeros":0},"extension":null,"carrier":"15"}"#,
+        ),
+    ];
+
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn stored() {
+        for &(country, input, bytes, json) in STORED {
+            let phone = parser::parse(country, input).unwrap();
+
+            let decoded = bincode::deserialize::<PhoneNumber>(bytes).unwrap();
+            assert!(phone.exactly_equal(&decoded), "{}", input);
+
+            let decoded = serde_json::from_str::<PhoneNumber>(json).unwrap();
+            assert!(phone.exactly_equal(&decoded), "{}", input);
+        }
+    }
+
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn malformed_bincode() {
+        let mut bytes = bincode::serialize(&parser::parse(None, "+46701234567").unwrap()).unwrap();
+        assert!(bincode::deserialize::<PhoneNumber>(&bytes).is_ok());
+
+        // Country calling code 999.
+        bytes[..2].copy_from_slice(&999u16.to_le_bytes());
+        assert_eq!(
+            "invalid phone number: invalid country code: 999",
+            bincode::deserialize::<PhoneNumber>(&bytes)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    // The struct representation is stored by bincode users, so its layout
+    // mustn't change from the one of the previous release, in `STORED`.
+    #[cfg(not(feature = "string-serde"))]
+    #[test]
+    fn bincode_layout() {
+        use crate::country::Source;
+
+        let phone = PhoneNumber::builder(39, 669883461).build().unwrap();
+        assert_eq!(
+            vec![
+                0x27, 0x00, // Country calling code.
+                0x03, 0x00, 0x00, 0x00, // Source::Default.
+                0x45, 0x9c, 0xed, 0x27, 0x00, 0x00, 0x00, 0x00, // National number.
+                0x00, // Leading zeros.
+                0x00, // No extension.
+                0x00, // No carrier.
+            ],
+            bincode::serialize(&phone).unwrap()
+        );
+
+        let phone = PhoneNumber::builder(39, 669883461)
+            .source(Source::Plus)
+            .leading_zeros(1)
+            .extension("12")
+            .carrier("15")
+            .build()
+            .unwrap();
+        let bytes = vec![
+            0x27, 0x00, // Country calling code.
+            0x00, 0x00, 0x00, 0x00, // Source::Plus.
+            0x45, 0x9c, 0xed, 0x27, 0x00, 0x00, 0x00, 0x00, // National number.
+            0x01, // Leading zeros.
+            0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, b'1', b'2', // Extension.
+            0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, b'1', b'5', // Carrier.
+        ];
+
+        assert_eq!(bytes, bincode::serialize(&phone).unwrap());
+        assert!(phone.exactly_equal(&bincode::deserialize(&bytes).unwrap()));
+
+        // Numbers serialize to what the previous release stored.
+        for &(country, input, bytes, json) in STORED {
+            let phone = parser::parse(country, input).unwrap();
+            assert_eq!(bytes, &bincode::serialize(&phone).unwrap()[..], "{}", input);
+            assert_eq!(json, serde_json::to_string(&phone).unwrap(), "{}", input);
+        }
+    }
+
+    #[cfg(feature = "string-serde")]
+    #[test]
+    fn string_serde() {
+        let phone = parser::parse(None, "+46 70 123 45 67").unwrap();
+        let json = serde_json::to_string(&phone).unwrap();
+
+        assert_eq!(r#""+46701234567""#, json);
+        assert_eq!(phone, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Short numbers, like emergency and service numbers, which are only valid
+//! when dialled from within their region.
+//!
+//! Like elsewhere a region of `None` stands for the non-geographical entities,
+//! which have no short numbers, so nothing is a short number dialled from
+//! them.
+
+use crate::country;
+use crate::metadata::{self, Database, Descriptor, Metadata};
+use crate::parser::helper::{self, AsCharExt};
+use crate::phone_number::PhoneNumber;
+
+/// Regions where dialling extra digits after an emergency number doesn't
+/// connect to it.
+const EXACT_EMERGENCY: &[country::Id] = &[country::BR, country::CL, country::NI];
+
+/// The expected cost of calling a short number.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ShortNumberCost {
+    /// Calling the number is free.
+    TollFree,
+
+    /// Calling the number costs as much as a regular call.
+    Standard,
+
+    /// Calling the number costs more than a regular call.
+    PremiumRate,
+
+    /// The cost is not known.
+    Unknown,
+}
+
+/// Check if the number is possible as a short number dialled from the given
+/// region, only looking at its length.
+pub fn is_possible_short_number<C: Into<Option<country::Id>>>(
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    metadata::with_global(|database| is_possible_short_number_with(database, number, region))
+        .unwrap_or(false)
+}
+
+/// Check if the number is possible as a short number dialled from the given
+/// region with the short number metadata of the given `Database`.
+pub fn is_possible_short_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    let meta = if let Some(meta) = short_metadata(database, number, region.into()) {
+        meta
+    } else {
+        return false;
+    };
+
+    meta.descriptors()
+        .general()
+        .possible_length()
+        .contains(&(number.national().to_string().len() as u16))
+}
+
+/// Check if the number is a valid short number dialled from the given region.
+pub fn is_valid_short_number<C: Into<Option<country::Id>>>(
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    metadata::with_global(|database| is_valid_short_number_with(database, number, region))
+        .unwrap_or(false)
+}
+
+/// Check if the number is a valid short number dialled from the given region
+/// with the short number metadata of the given `Database`.
+pub fn is_valid_short_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    let meta = if let Some(meta) = short_metadata(database, number, region.into()) {
+        meta
+    } else {
+        return false;
+    };
+
+    let national = number.national().to_string();
+
+    meta.descriptors().general().is_match(&national)
+        && meta
+            .descriptors()
+            .short_code()
+            .map(|d| d.is_match(&national))
+            .unwrap_or(false)
+}
+
+/// Get the expected cost of calling the short number from the given region.
+pub fn expected_cost<C: Into<Option<country::Id>>>(
+    number: &PhoneNumber,
+    region: C,
+) -> ShortNumberCost {
+    metadata::with_global(|database| expected_cost_with(database, number, region))
+        .unwrap_or(ShortNumberCost::Unknown)
+}
+
+/// Get the expected cost of calling the short number from the given region with
+/// the short number metadata of the given `Database`.
+pub fn expected_cost_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: C,
+) -> ShortNumberCost {
+    let meta = if let Some(meta) = short_metadata(database, number, region.into()) {
+        meta
+    } else {
+        return ShortNumberCost::Unknown;
+    };
+
+    let national = number.national().to_string();
+    let descriptors = meta.descriptors();
+
+    if !descriptors
+        .general()
+        .possible_length()
+        .contains(&(national.len() as u16))
+    {
+        return ShortNumberCost::Unknown;
+    }
+
+    let is_match = |desc: Option<&Descriptor>| desc.map(|d| d.is_match(&national)).unwrap_or(false);
+
+    if is_match(descriptors.premium_rate()) {
+        ShortNumberCost::PremiumRate
+    } else if is_match(descriptors.standard_rate()) {
+        ShortNumberCost::Standard
+    } else if is_match(descriptors.toll_free()) {
+        ShortNumberCost::TollFree
+    } else if is_match(descriptors.emergency()) {
+        // Emergency numbers are always free.
+        ShortNumberCost::TollFree
+    } else {
+        ShortNumberCost::Unknown
+    }
+}
+
+/// Check if the number is a short number only reachable from the networks of
+/// some carriers when dialled from the given region, so it shouldn't be shown
+/// to users on other networks.
+pub fn is_carrier_specific<C: Into<Option<country::Id>>>(number: &PhoneNumber, region: C) -> bool {
+    metadata::with_global(|database| is_carrier_specific_with(database, number, region))
+        .unwrap_or(false)
+}
+
+/// Check if the number is a carrier specific short number dialled from the
+/// given region with the short number metadata of the given `Database`.
+pub fn is_carrier_specific_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    matches(database, number, region.into(), |meta| {
+        meta.descriptors().carrier()
+    })
+}
+
+/// Check if the number is a short number for SMS services when dialled from
+/// the given region, which may not be reachable by calls.
+pub fn is_sms_service<C: Into<Option<country::Id>>>(number: &PhoneNumber, region: C) -> bool {
+    metadata::with_global(|database| is_sms_service_with(database, number, region)).unwrap_or(false)
+}
+
+/// Check if the number is a short number for SMS services dialled from the
+/// given region with the short number metadata of the given `Database`.
+pub fn is_sms_service_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: C,
+) -> bool {
+    matches(database, number, region.into(), |meta| {
+        meta.descriptors().sms_services()
+    })
+}
+
+/// Check if the number matches the descriptor of the short number metadata
+/// for the region.
+fn matches<F>(
+    database: &Database,
+    number: &PhoneNumber,
+    region: Option<country::Id>,
+    descriptor: F,
+) -> bool
+where
+    F: FnOnce(&Metadata) -> Option<&Descriptor>,
+{
+    short_metadata(database, number, region)
+        .and_then(descriptor)
+        .map(|d| d.is_match(&number.national().to_string()))
+        .unwrap_or(false)
+}
+
+/// Check if dialling the input from the given region would connect to an
+/// emergency service, which includes emergency numbers followed by other
+/// digits where the region allows it.
+pub fn connects_to_emergency_number<C: Into<Option<country::Id>>>(input: &str, region: C) -> bool {
+    metadata::with_global(|database| connects_to_emergency_number_with(database, input, region))
+        .unwrap_or(false)
+}
+
+/// Check if dialling the input from the given region would connect to an
+/// emergency service with the short number metadata of the given `Database`.
+pub fn connects_to_emergency_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    input: &str,
+    region: C,
+) -> bool {
+    emergency(database, input, region.into(), true)
+}
+
+/// Check if the input is exactly an emergency number of the given region.
+pub fn is_emergency_number<C: Into<Option<country::Id>>>(input: &str, region: C) -> bool {
+    metadata::with_global(|database| is_emergency_number_with(database, input, region))
+        .unwrap_or(false)
+}
+
+/// Check if the input is exactly an emergency number of the given region with
+/// the short number metadata of the given `Database`.
+pub fn is_emergency_number_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    input: &str,
+    region: C,
+) -> bool {
+    emergency(database, input, region.into(), false)
+}
+
+fn emergency(database: &Database, input: &str, region: Option<country::Id>, prefix: bool) -> bool {
+    let input = if let Ok((_, input)) = helper::extract(input) {
+        input
+    } else {
+        return false;
+    };
+
+    // Emergency numbers can't be dialled in international format.
+    if input.starts_with(|c: char| c.is_plus()) {
+        return false;
+    }
+
+    let region = if let Some(region) = region {
+        region
+    } else {
+        return false;
+    };
+
+    let pattern = if let Some(desc) = database
+        .short_metadata(region)
+        .and_then(|m| m.descriptors().emergency())
+    {
+        desc.national_number()
+    } else {
+        return false;
+    };
+
+    let digits = input
+        .chars()
+        .filter_map(|c| c.as_dec_digit())
+        .collect::<String>();
+
+    if prefix && !EXACT_EMERGENCY.contains(&region) {
+        digits
+            .char_indices()
+            .map(|(i, c)| &digits[..i + c.len_utf8()])
+            .any(|p| pattern.is_match(p))
+    } else {
+        pattern.is_match(&digits)
+    }
+}
+
+/// Get the short number metadata for the region, as long as the number can
+/// be dialled from it.
+fn short_metadata<'a>(
+    database: &'a Database,
+    number: &PhoneNumber,
+    region: Option<country::Id>,
+) -> Option<&'a Metadata> {
+    database
+        .short_metadata(region?)
+        .filter(|meta| meta.country_code() == number.code().value())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::parser;
+    use crate::shortnumber::{self, ShortNumberCost};
+
+    #[test]
+    fn valid() {
+        let number = parser::parse(Some(country::US), "911").unwrap();
+        assert!(shortnumber::is_possible_short_number(&number, country::US));
+        assert!(shortnumber::is_valid_short_number(&number, country::US));
+        assert!(!shortnumber::is_valid_short_number(&number, country::DE));
+
+        let number = parser::parse(Some(country::DE), "112").unwrap();
+        assert!(shortnumber::is_valid_short_number(&number, country::DE));
+
+        let number = parser::parse(Some(country::DE), "116117").unwrap();
+        assert!(shortnumber::is_valid_short_number(&number, country::DE));
+
+        let number = parser::parse(Some(country::FR), "15").unwrap();
+        assert!(shortnumber::is_valid_short_number(&number, country::FR));
+
+        let number = parser::parse(Some(country::US), "6502530000").unwrap();
+        assert!(!shortnumber::is_possible_short_number(&number, country::US));
+        assert!(!shortnumber::is_valid_short_number(&number, country::US));
+    }
+
+    #[test]
+    fn cost() {
+        assert_eq!(
+            ShortNumberCost::TollFree,
+            shortnumber::expected_cost(
+                &parser::parse(Some(country::US), "911").unwrap(),
+                country::US
+            )
+        );
+
+        assert_eq!(
+            ShortNumberCost::TollFree,
+            shortnumber::expected_cost(
+                &parser::parse(Some(country::DE), "112").unwrap(),
+                country::DE
+            )
+        );
+
+        assert_eq!(
+            ShortNumberCost::PremiumRate,
+            shortnumber::expected_cost(
+                &parser::parse(Some(country::US), "24280").unwrap(),
+                country::US
+            )
+        );
+
+        assert_eq!(
+            ShortNumberCost::Standard,
+            shortnumber::expected_cost(
+                &parser::parse(Some(country::US), "23333").unwrap(),
+                country::US
+            )
+        );
+
+        assert_eq!(
+            ShortNumberCost::Unknown,
+            shortnumber::expected_cost(
+                &parser::parse(Some(country::US), "6502530000").unwrap(),
+                country::US
+            )
+        );
+    }
+
+    #[test]
+    fn carrier_specific() {
+        let number = parser::parse(Some(country::US), "611").unwrap();
+        assert!(shortnumber::is_valid_short_number(&number, country::US));
+        assert!(shortnumber::is_carrier_specific(&number, country::US));
+        assert!(!shortnumber::is_carrier_specific(&number, country::DE));
+
+        let number = parser::parse(Some(country::US), "911").unwrap();
+        assert!(!shortnumber::is_carrier_specific(&number, country::US));
+
+        let number = parser::parse(Some(country::US), "6502530000").unwrap();
+        assert!(!shortnumber::is_carrier_specific(&number, country::US));
+    }
+
+    #[test]
+    fn sms_service() {
+        let number = parser::parse(Some(country::US), "21234").unwrap();
+        assert!(shortnumber::is_sms_service(&number, country::US));
+        assert!(!shortnumber::is_sms_service(&number, country::DE));
+
+        let number = parser::parse(Some(country::US), "911").unwrap();
+        assert!(!shortnumber::is_sms_service(&number, country::US));
+
+        let number = parser::parse(Some(country::US), "6502530000").unwrap();
+        assert!(!shortnumber::is_sms_service(&number, country::US));
+    }
+
+    #[test]
+    fn emergency() {
+        assert!(shortnumber::is_emergency_number("911", country::US));
+        assert!(shortnumber::is_emergency_number("9-1-1", country::US));
+        assert!(!shortnumber::is_emergency_number("911123", country::US));
+        assert!(shortnumber::connects_to_emergency_number(
+            "911",
+            country::US
+        ));
+        assert!(shortnumber::connects_to_emergency_number(
+            "9-1-1",
+            country::US
+        ));
+        assert!(shortnumber::connects_to_emergency_number(
+            "911123",
+            country::US
+        ));
+        assert!(!shortnumber::connects_to_emergency_number(
+            "+911",
+            country::US
+        ));
+        assert!(!shortnumber::connects_to_emergency_number(
+            "999",
+            country::US
+        ));
+
+        assert!(shortnumber::is_emergency_number("112", country::DE));
+        assert!(shortnumber::connects_to_emergency_number(
+            "112",
+            country::DE
+        ));
+
+        assert!(shortnumber::is_emergency_number("999", country::GB));
+        assert!(shortnumber::connects_to_emergency_number(
+            "999",
+            country::GB
+        ));
+        assert!(!shortnumber::is_emergency_number("911", country::DE));
+
+        // Brazil only connects exact emergency numbers.
+        assert!(shortnumber::connects_to_emergency_number(
+            "190",
+            country::BR
+        ));
+        assert!(!shortnumber::connects_to_emergency_number(
+            "1900",
+            country::BR
+        ));
+    }
+
+    #[test]
+    fn non_geographical() {
+        // Non-geographical entities have no short numbers.
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert!(!shortnumber::is_possible_short_number(&number, None));
+        assert!(!shortnumber::is_valid_short_number(&number, None));
+        assert_eq!(
+            ShortNumberCost::Unknown,
+            shortnumber::expected_cost(&number, None)
+        );
+        assert!(!shortnumber::is_emergency_number("112", None));
+        assert!(!shortnumber::connects_to_emergency_number("112", None));
+
+        let number = parser::parse(Some(country::US), "911").unwrap();
+        assert!(shortnumber::is_valid_short_number(
+            &number,
+            Some(country::US)
+        ));
+        assert!(!shortnumber::is_valid_short_number(&number, None));
+    }
+}
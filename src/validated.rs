@@ -0,0 +1,246 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::ops::Deref;
+
+use ::serde::de::{Deserialize, Deserializer, Error as _};
+use ::serde::ser::{Serialize, Serializer};
+use thiserror::Error;
+
+use crate::country;
+use crate::metadata::{self, Database};
+use crate::phone_number::PhoneNumber;
+use crate::validator::{self, ValidationReport};
+
+/// Why a phone number couldn't be turned into a `ValidatedPhoneNumber`, with
+/// the diagnostics of the validation.
+#[derive(Error, Clone, Eq, PartialEq, Debug)]
+#[error("invalid phone number: {0}")]
+pub struct ValidationError(ValidationReport);
+
+impl ValidationError {
+    /// The diagnostics on why the number isn't valid.
+    pub fn report(&self) -> &ValidationReport {
+        &self.0
+    }
+}
+
+/// A phone number that was valid when it was created, unlike a `PhoneNumber`
+/// which may only have been parsed or checked for being possible.
+///
+/// It derefs to the `PhoneNumber`, and remembers the region it was validated
+/// for and the version of the database it was validated against, since
+/// numbers valid with one version of the metadata may not be with another.
+///
+/// It's serialized like the `PhoneNumber`, and validated again with the
+/// global database when deserialized.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ValidatedPhoneNumber {
+    number: PhoneNumber,
+    region: Option<country::Id>,
+    version: String,
+}
+
+impl ValidatedPhoneNumber {
+    /// Validate the number with the given `Database`, handing it back with
+    /// the reason if it's not valid.
+    pub fn new_with(
+        database: &Database,
+        number: PhoneNumber,
+    ) -> Result<Self, (PhoneNumber, ValidationError)> {
+        if !validator::is_valid_with(database, &number) {
+            let report = validator::validate_with(database, &number);
+            return Err((number, ValidationError(report)));
+        }
+
+        Ok(ValidatedPhoneNumber {
+            region: number.country().id_with(database),
+            version: database.version().into(),
+            number,
+        })
+    }
+
+    /// Deserialize a number and validate it with the given `Database`, instead
+    /// of the global one.
+    pub fn deserialize_with<'de, D: Deserializer<'de>>(
+        database: &Database,
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        ValidatedPhoneNumber::new_with(database, PhoneNumber::deserialize(deserializer)?)
+            .map_err(|(_, err)| D::Error::custom(err))
+    }
+
+    /// The region the number was validated for, `None` for numbers of
+    /// non-geographical entities.
+    pub fn region(&self) -> Option<country::Id> {
+        self.region
+    }
+
+    /// The version of the metadata the number was validated against.
+    pub fn database_version(&self) -> &str {
+        &self.version
+    }
+
+    /// Get the `PhoneNumber` back.
+    pub fn into_inner(self) -> PhoneNumber {
+        self.number
+    }
+}
+
+impl Deref for ValidatedPhoneNumber {
+    type Target = PhoneNumber;
+
+    fn deref(&self) -> &PhoneNumber {
+        &self.number
+    }
+}
+
+impl AsRef<PhoneNumber> for ValidatedPhoneNumber {
+    fn as_ref(&self) -> &PhoneNumber {
+        &self.number
+    }
+}
+
+impl From<ValidatedPhoneNumber> for PhoneNumber {
+    fn from(value: ValidatedPhoneNumber) -> Self {
+        value.number
+    }
+}
+
+impl fmt::Display for ValidatedPhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.number, f)
+    }
+}
+
+impl Serialize for ValidatedPhoneNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.number.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValidatedPhoneNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let database = metadata::try_global().map_err(D::Error::custom)?;
+        ValidatedPhoneNumber::deserialize_with(&database, deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::country;
+    use crate::metadata::{Database, DATABASE};
+    use crate::parser;
+    use crate::phone_number::Type;
+    use crate::validated::ValidatedPhoneNumber;
+    use crate::validator::Validation;
+
+    /// Ascension Island, with mobile numbers starting with "4" or, in the
+    /// newer metadata, "5".
+    const METADATA: &str = r#"
+        <phoneNumberMetadata>
+          <territories>
+            <territory id="AC" countryCode="247" internationalPrefix="00">
+              <generalDesc>
+                <nationalNumberPattern>[4-6]\d{4}</nationalNumberPattern>
+              </generalDesc>
+              <fixedLine>
+                <possibleLengths national="5"/>
+                <nationalNumberPattern>6[2-467]\d{3}</nationalNumberPattern>
+              </fixedLine>
+              <mobile>
+                <possibleLengths national="5"/>
+                <nationalNumberPattern>4\d{4}</nationalNumberPattern>
+              </mobile>
+            </territory>
+          </territories>
+        </phoneNumberMetadata>
+    "#;
+
+    #[test]
+    fn validated() {
+        let number = parser::parse(None, "+1 650 253 0000").unwrap();
+        let validated = number.clone().validated().unwrap();
+
+        // Anything taking a `PhoneNumber` works through `Deref`.
+        assert_eq!(number, *validated);
+        assert_eq!(Some(country::US), validated.region());
+        assert_eq!(DATABASE.version(), validated.database_version());
+        assert_eq!(Type::FixedLineOrMobile, validated.number_type());
+        assert_eq!("+16502530000", validated.to_string());
+        assert_eq!(number, validated.into_inner());
+
+        let validated = parser::parse(None, "+800 1234 5678")
+            .unwrap()
+            .validated()
+            .unwrap();
+        assert_eq!(None, validated.region());
+    }
+
+    #[test]
+    fn invalid() {
+        // Possible, but not valid.
+        let number = parser::parse(None, "+1 555 000 0000").unwrap();
+        let (back, err) = number.clone().validated().unwrap_err();
+        assert_eq!(number, back);
+        assert_eq!(Validation::IsPossible, err.report().possible());
+        assert!(err.report().types().is_empty());
+        assert!(err.to_string().starts_with("invalid phone number: "));
+
+        // Only possible for local calls.
+        let number = parser::parse(None, "+49 12").unwrap();
+        let (_, err) = number.validated().unwrap_err();
+        assert_eq!(Validation::IsPossibleLocalOnly, err.report().possible());
+    }
+
+    #[test]
+    fn serde() {
+        let old = Database::parse(METADATA).unwrap();
+        let new = Database::parse(METADATA.replace(
+            "<nationalNumberPattern>4\\d{4}",
+            "<nationalNumberPattern>5\\d{4}",
+        ))
+        .unwrap();
+
+        let number = parser::parse_with(&old, None, "+247 40123").unwrap();
+        let validated = number.clone().validated_with(&old).unwrap();
+        assert_eq!(Some(country::AC), validated.region());
+        assert!(number.clone().validated_with(&new).is_err());
+
+        let json = serde_json::to_string(&validated).unwrap();
+        assert_eq!(serde_json::to_string(&number).unwrap(), json);
+
+        let back = ValidatedPhoneNumber::deserialize_with(
+            &old,
+            &mut serde_json::Deserializer::from_str(&json),
+        )
+        .unwrap();
+        assert_eq!(validated, back);
+
+        let err = ValidatedPhoneNumber::deserialize_with(
+            &new,
+            &mut serde_json::Deserializer::from_str(&json),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("invalid phone number: "));
+
+        // The global database is used by default.
+        let json = serde_json::to_string(&parser::parse(None, "+1 555 000 0000").unwrap()).unwrap();
+        assert!(serde_json::from_str::<ValidatedPhoneNumber>(&json).is_err());
+
+        let json = serde_json::to_string(&parser::parse(None, "+1 650 253 0000").unwrap()).unwrap();
+        assert!(serde_json::from_str::<ValidatedPhoneNumber>(&json).is_ok());
+    }
+}
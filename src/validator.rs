@@ -13,12 +13,15 @@
 // limitations under the License.
 
 use either::*;
+use std::borrow::Cow;
+use std::fmt;
+use std::str;
 
 use crate::consts;
 use crate::country;
-use crate::metadata::{Database, Metadata, DATABASE};
+use crate::metadata::{self, Database, Descriptor, Metadata};
 use crate::parser;
-use crate::parser::helper::Number as ParseNumber;
+use crate::parser::helper::AsCharExt;
 use crate::phone_number::{PhoneNumber, Type};
 
 /// Possible outcomes when testing if a `PhoneNumber` is possible.
@@ -88,9 +91,221 @@ pub fn is_viable<S: AsRef<str>>(string: S) -> bool {
     parser::valid::phone_number(string).is_ok()
 }
 
+/// Check if the provided string is a phone number in the strict E.164 format,
+/// see `parse_e164`, without allocating.
+pub fn is_e164<S: AsRef<str>>(string: S) -> bool {
+    metadata::with_global(|database| is_e164_with(database, string)).unwrap_or(false)
+}
+
+/// Check if the provided string is a phone number in the strict E.164 format
+/// using the given `Database`.
+pub fn is_e164_with<S: AsRef<str>>(database: &Database, string: S) -> bool {
+    parser::e164(database, string.as_ref()).is_ok()
+}
+
+/// Check if the provided string is a viable phone number using letters for
+/// some of its digits, like "1800 SIX-FLAG".
+///
+/// Any extension is ignored, and at least three letters are needed. After a
+/// plus sign the country calling code can be followed by letters only, like
+/// "+1 CALL-ME-NOW".
+pub fn is_alpha_number<S: AsRef<str>>(string: S) -> bool {
+    let mut string = string.as_ref();
+
+    if !is_viable(string) && !is_viable_international_alpha(string) {
+        return false;
+    }
+
+    if let Some(extension) = consts::EXTN_PATTERN.find(string) {
+        let number = &string[..extension.start()];
+
+        if is_viable(number) || is_viable_international_alpha(number) {
+            string = number;
+        }
+    }
+
+    consts::VALID_ALPHA_PHONE.is_match(string)
+}
+
+/// Check if the string is viable once its letters are read as the digits they
+/// stand for, which is only done in the international format, where the
+/// country calling code is all that comes before the letters.
+fn is_viable_international_alpha(string: &str) -> bool {
+    let rest = match string.trim_start().strip_prefix(|c: char| c.is_plus()) {
+        Some(rest) => rest.trim_start(),
+        None => return false,
+    };
+
+    if !rest.starts_with(|c: char| c.as_dec_digit().is_some()) {
+        return false;
+    }
+
+    is_viable(
+        string
+            .chars()
+            .map(|c| {
+                consts::ALPHA_MAPPINGS
+                    .get(&c.to_ascii_uppercase())
+                    .copied()
+                    .unwrap_or(c)
+            })
+            .collect::<String>(),
+    )
+}
+
+/// Check if the string is a possible phone number, only looking at the number
+/// of digits for its country calling code.
+///
+/// This is a faster alternative to parsing the number and checking if it's
+/// possible, which doesn't allocate and doesn't match any number patterns.
+pub fn is_possible_str<S: AsRef<str>>(country: Option<country::Id>, string: S) -> bool {
+    is_possible_str_with(&metadata::global(), country, string)
+}
+
+/// Check if the string is a possible phone number with the given `Database`,
+/// only looking at the number of digits for its country calling code.
+pub fn is_possible_str_with<S: AsRef<str>>(
+    database: &Database,
+    country: Option<country::Id>,
+    string: S,
+) -> bool {
+    let string = string.as_ref();
+    let mut buffer = [0u8; consts::MAX_LENGTH_FOR_NSN + consts::MAX_LENGTH_FOR_COUNTRY_CODE + 5];
+    let mut count = 0;
+    let mut plus = false;
+
+    for (index, ch) in string.char_indices() {
+        let digit = if let Some(digit) = ch.as_dec_digit() {
+            digit
+        } else if ch.is_plus() && count == 0 {
+            plus = true;
+            continue;
+        } else if count > 0 && is_extension(&string[index..]) {
+            break;
+        } else if let Some(&digit) = consts::ALPHA_MAPPINGS.get(&ch.to_ascii_uppercase()) {
+            digit
+        } else {
+            continue;
+        };
+
+        if count == buffer.len() {
+            return false;
+        }
+
+        buffer[count] = digit as u8;
+        count += 1;
+    }
+
+    // The buffer only ever holds ASCII digits.
+    let mut digits = str::from_utf8(&buffer[..count]).unwrap();
+    let default = country.and_then(|c| database.by_id(c.as_ref()));
+
+    if !plus {
+        if let Some(idd) = default.and_then(|m| m.international_prefix.as_ref()) {
+            // Most international prefixes are plain digits, like "00" or "011",
+            // which are stripped without compiling the pattern. The others
+            // have alternatives only the pattern matches, like the carrier
+            // selection codes in Brazil's "00(?:1[245]|2[1-35]|...)".
+            let end = if idd.as_str().bytes().all(|b| b.is_ascii_digit()) {
+                digits.starts_with(idd.as_str()).then(|| idd.as_str().len())
+            } else {
+                idd.find(digits).filter(|m| m.start() == 0).map(|m| m.end())
+            };
+
+            if let Some(end) = end {
+                if !digits[end..].starts_with('0') {
+                    plus = true;
+                    digits = &digits[end..];
+                }
+            }
+        }
+    }
+
+    let (meta, mut national) = if plus {
+        // Country codes never start with a 0.
+        if digits.starts_with('0') {
+            return false;
+        }
+
+        let found = (1..=consts::MAX_LENGTH_FOR_COUNTRY_CODE.min(digits.len())).find_map(|len| {
+            digits[..len]
+                .parse::<u16>()
+                .ok()
+                .and_then(|code| database.main_by_code(code))
+                .map(|meta| (meta, &digits[len..]))
+        });
+
+        if let Some(found) = found {
+            found
+        } else {
+            return false;
+        }
+    } else if let Some(meta) = default {
+        // Numbers can start with the country code without the plus.
+        let code = (1..=consts::MAX_LENGTH_FOR_COUNTRY_CODE.min(digits.len()))
+            .find(|&len| digits[..len].parse() == Ok(meta.country_code))
+            .filter(|_| !length(meta, digits, Type::Unknown).is_possible());
+
+        match code {
+            Some(len) => (meta, &digits[len..]),
+            None => (meta, digits),
+        }
+    } else {
+        return false;
+    };
+
+    if let Some(rest) = meta
+        .national_prefix
+        .as_ref()
+        .and_then(|prefix| national.strip_prefix(prefix.as_str()))
+    {
+        if !matches!(
+            length(meta, rest, Type::Unknown),
+            Validation::TooShort | Validation::IsPossibleLocalOnly | Validation::InvalidLength
+        ) {
+            national = rest;
+        }
+    }
+
+    if national.len() < consts::MIN_LENGTH_FOR_NSN || national.len() > consts::MAX_LENGTH_FOR_NSN {
+        return false;
+    }
+
+    // The lengths of regions sharing a country code are all held by the main
+    // region.
+    database
+        .main_by_code(meta.country_code)
+        .map(|main| length(main, national, Type::Unknown).is_possible())
+        .unwrap_or(false)
+}
+
+/// Check if the rest of the string is an extension, i.e. an extension marker
+/// followed by nothing but its digits.
+fn is_extension(rest: &str) -> bool {
+    let marker = ["extension", "extn", "ext", "x", "#", ";ext=", ","]
+        .iter()
+        .find(|m| {
+            rest.get(..m.len())
+                .map(|p| p.eq_ignore_ascii_case(m))
+                .unwrap_or(false)
+        });
+
+    if let Some(marker) = marker {
+        let digits = rest[marker.len()..]
+            .trim_start_matches(|c: char| c == '.' || c == ':' || c.is_whitespace());
+
+        !digits.is_empty() && digits.len() <= 7 && digits.chars().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
 /// Check if the phone number is valid.
+///
+/// Only the national significant number is checked, any extension is ignored,
+/// see `ValidationPolicy` and `ExtensionPolicy` to check it too.
 pub fn is_valid(number: &PhoneNumber) -> bool {
-    is_valid_with(&DATABASE, number)
+    metadata::with_global(|database| is_valid_with(database, number)).unwrap_or(false)
 }
 
 /// Check if the phone number is valid with the given `Database`.
@@ -100,31 +315,150 @@ pub fn is_valid_with(database: &Database, number: &PhoneNumber) -> bool {
     source_for(database, code, &national)
         .and_then(|meta| match meta {
             Left(region) => database.by_id(region.as_ref()),
-            Right(code) => database.by_code(&code).and_then(|m| m.into_iter().next()),
+            Right(code) => database.non_geographical(code),
         })
         .map(|meta| number_type(meta, &national) != Type::Unknown)
         .unwrap_or(false)
 }
 
-pub fn length(meta: &Metadata, number: &ParseNumber, kind: Type) -> Validation {
+/// Check if the phone number is valid for the given region, which for numbers
+/// sharing a country calling code with other regions means it isn't enough for
+/// it to be valid for any of them.
+///
+/// For `None` it has to be valid for the non-geographical entity with its
+/// country calling code, so `number.country().id()` can be passed for any
+/// number.
+pub fn is_valid_for_region<C: Into<Option<country::Id>>>(number: &PhoneNumber, country: C) -> bool {
+    is_valid_for_region_with(&metadata::global(), number, country)
+}
+
+/// Check if the phone number is valid for the given region with the given
+/// `Database`.
+pub fn is_valid_for_region_with<C: Into<Option<country::Id>>>(
+    database: &Database,
+    number: &PhoneNumber,
+    country: C,
+) -> bool {
+    let meta = match country.into() {
+        Some(country) => database.by_id(country.as_ref()),
+        None => database.non_geographical(number.country().code()),
+    };
+
+    meta.filter(|meta| meta.country_code() == number.country().code())
+        .map(|meta| number_type(meta, &number.national.to_string()) != Type::Unknown)
+        .unwrap_or(false)
+}
+
+/// Check if the phone number is possible, only looking at its length.
+pub fn is_possible(number: &PhoneNumber) -> bool {
+    is_possible_with(&metadata::global(), number)
+}
+
+/// Check if the phone number is possible with the given `Database`.
+pub fn is_possible_with(database: &Database, number: &PhoneNumber) -> bool {
+    is_possible_with_reason_with(database, number).is_possible()
+}
+
+/// Check if the phone number is possible, returning why it isn't.
+pub fn is_possible_with_reason(number: &PhoneNumber) -> Validation {
+    is_possible_with_reason_with(&metadata::global(), number)
+}
+
+/// Check if the phone number is possible with the given `Database`, returning
+/// why it isn't.
+pub fn is_possible_with_reason_with(database: &Database, number: &PhoneNumber) -> Validation {
+    is_possible_for_type_with_reason_with(database, number, Type::Unknown)
+}
+
+/// Check if the phone number is possible for the given type, only looking at
+/// its length.
+pub fn is_possible_for_type(number: &PhoneNumber, kind: Type) -> bool {
+    is_possible_for_type_with(&metadata::global(), number, kind)
+}
+
+/// Check if the phone number is possible for the given type with the given
+/// `Database`.
+pub fn is_possible_for_type_with(database: &Database, number: &PhoneNumber, kind: Type) -> bool {
+    is_possible_for_type_with_reason_with(database, number, kind).is_possible()
+}
+
+/// Check if the phone number is possible for the given type, returning why it
+/// isn't.
+pub fn is_possible_for_type_with_reason(number: &PhoneNumber, kind: Type) -> Validation {
+    is_possible_for_type_with_reason_with(&metadata::global(), number, kind)
+}
+
+/// Check if the phone number is possible for the given type with the given
+/// `Database`, returning why it isn't.
+pub fn is_possible_for_type_with_reason_with(
+    database: &Database,
+    number: &PhoneNumber,
+    kind: Type,
+) -> Validation {
+    // The lengths of regions sharing a country code are all held by the main
+    // region.
+    let meta = if let Some(meta) = database
+        .by_code(&number.country().code())
+        .and_then(|m| m.into_iter().next())
+    {
+        meta
+    } else {
+        return Validation::InvalidCountryCode;
+    };
+
+    length(meta, &number.national.to_string(), kind)
+}
+
+pub fn length(meta: &Metadata, national: &str, kind: Type) -> Validation {
     let desc = if let Some(desc) = meta.descriptors().get(kind) {
         desc
+    } else if kind == Type::FixedLineOrMobile {
+        // Without fixed-line numbers only the mobile ones are left to check.
+        return length(meta, national, Type::Mobile);
     } else {
         return Validation::InvalidLength;
     };
 
-    let length = number.national.len() as u16;
-    let local = &desc.possible_local_length[..];
-    let possible = if desc.possible_length.is_empty() {
-        &desc.possible_length[..]
+    // Types without lengths of their own take them from the general
+    // description.
+    fn lengths<'a>(meta: &'a Metadata, desc: &'a Descriptor) -> &'a [u16] {
+        if desc.possible_length.is_empty() {
+            &meta.descriptors.general.possible_length
+        } else {
+            &desc.possible_length
+        }
+    }
+
+    let mobile = meta
+        .descriptors
+        .mobile
+        .as_ref()
+        .filter(|_| kind == Type::FixedLineOrMobile);
+
+    let (possible, local) = if let Some(mobile) = mobile {
+        let mut possible = lengths(meta, desc).to_vec();
+        possible.extend(lengths(meta, mobile));
+        possible.sort_unstable();
+        possible.dedup();
+
+        let mut local = desc.possible_local_length.clone();
+        local.extend(&mobile.possible_local_length);
+        local.sort_unstable();
+        local.dedup();
+
+        (Cow::Owned(possible), Cow::Owned(local))
     } else {
-        &meta.descriptors.general.possible_length[..]
+        (
+            Cow::Borrowed(lengths(meta, desc)),
+            Cow::Borrowed(&desc.possible_local_length[..]),
+        )
     };
 
     if possible.is_empty() {
         return Validation::InvalidLength;
     }
 
+    let length = national.len() as u16;
     let minimum = possible[0];
 
     if local.contains(&length) {
@@ -142,6 +476,167 @@ pub fn length(meta: &Metadata, number: &ParseNumber, kind: Type) -> Validation {
     }
 }
 
+/// Diagnostics on why a phone number is valid or not.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ValidationReport {
+    national: String,
+    region: Option<country::Id>,
+    regions: Vec<country::Id>,
+    possible: Validation,
+    types: Vec<Type>,
+}
+
+impl ValidationReport {
+    /// The national significant number that was validated.
+    pub fn national(&self) -> &str {
+        &self.national
+    }
+
+    /// The region the number was found to belong to, if any.
+    pub fn region(&self) -> Option<country::Id> {
+        self.region
+    }
+
+    /// All the regions using the country calling code of the number.
+    pub fn regions(&self) -> &[country::Id] {
+        &self.regions
+    }
+
+    /// The verdict on the length of the number.
+    pub fn possible(&self) -> Validation {
+        self.possible
+    }
+
+    /// The number types whose patterns the number matches.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    /// Whether the number has a possible length but its leading digits don't
+    /// match any number type.
+    pub fn is_leading_digits_mismatch(&self) -> bool {
+        self.possible.is_possible() && self.types.is_empty()
+    }
+
+    /// Whether the number is valid.
+    pub fn is_valid(&self) -> bool {
+        self.region.is_some() && !self.types.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "national number {}", self.national)?;
+
+        match self.region {
+            Some(region) => write!(f, ", region {}", region.as_ref())?,
+
+            None if self.regions.is_empty() => write!(f, ", no region")?,
+
+            None => {
+                write!(f, ", no matching region among ")?;
+
+                for (i, region) in self.regions.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "/")?;
+                    }
+
+                    write!(f, "{}", region.as_ref())?;
+                }
+            }
+        }
+
+        write!(
+            f,
+            ", length {}",
+            match self.possible {
+                Validation::IsPossible => "possible",
+                Validation::IsPossibleLocalOnly => "possible locally only",
+                Validation::InvalidCountryCode => "unknown for the country code",
+                Validation::TooShort => "too short",
+                Validation::InvalidLength => "invalid",
+                Validation::TooLong => "too long",
+            }
+        )?;
+
+        if self.types.is_empty() {
+            write!(f, ", matches no number type")?;
+        } else {
+            write!(f, ", matches {:?}", self.types)?;
+        }
+
+        if self.is_leading_digits_mismatch() {
+            write!(f, " (leading digits)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Validate the phone number, explaining the outcome.
+pub fn validate(number: &PhoneNumber) -> ValidationReport {
+    validate_with(&metadata::global(), number)
+}
+
+/// Validate the phone number with the given `Database`, explaining the
+/// outcome.
+pub fn validate_with(database: &Database, number: &PhoneNumber) -> ValidationReport {
+    let code = number.code.value();
+    let national = number.national.to_string();
+
+    let regions = database
+        .region(&code)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|r| r.parse().ok())
+        .collect();
+
+    let (region, meta) = match source_for(database, code, &national) {
+        Some(Left(region)) => (Some(region), database.by_id(region.as_ref())),
+        Some(Right(code)) => (
+            None,
+            database.by_code(&code).and_then(|m| m.into_iter().next()),
+        ),
+        None => (
+            None,
+            database.by_code(&code).and_then(|m| m.into_iter().next()),
+        ),
+    };
+
+    let types = meta
+        .map(|meta| {
+            [
+                Type::FixedLine,
+                Type::Mobile,
+                Type::TollFree,
+                Type::PremiumRate,
+                Type::SharedCost,
+                Type::PersonalNumber,
+                Type::Voip,
+                Type::Pager,
+                Type::Uan,
+                Type::Voicemail,
+            ]
+            .into_iter()
+            .filter(|&kind| {
+                meta.descriptors
+                    .get(kind)
+                    .map(|d| d.is_match(&national))
+                    .unwrap_or(false)
+            })
+            .collect()
+        })
+        .unwrap_or_default();
+
+    ValidationReport {
+        possible: is_possible_with_reason_with(database, number),
+        national,
+        region,
+        regions,
+        types,
+    }
+}
+
 /// Find the metadata source.
 pub fn source_for(
     database: &Database,
@@ -312,8 +807,12 @@ pub fn number_type(meta: &Metadata, value: &str) -> Type {
 #[cfg(test)]
 mod test {
     use crate::country;
+    use crate::formatter::Mode;
+    use crate::metadata::{Database, DATABASE};
+    use crate::national_number::NationalNumber;
     use crate::parser;
-    use crate::validator;
+    use crate::phone_number::{PhoneNumber, Type};
+    use crate::validator::{self, Validation};
 
     #[test]
     fn validate() {
@@ -369,4 +868,366 @@ mod test {
             &parser::parse(None, "+800 123456789").unwrap()
         ));
     }
+
+    #[test]
+    fn non_geographical() {
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert!(validator::is_valid(&number));
+        assert_eq!(Type::TollFree, number.number_type());
+        assert_eq!(
+            "+800 1234 5678",
+            number.format().mode(Mode::International).to_string()
+        );
+
+        for &(input, kind, formatted) in &[
+            ("+808 1234 5678", Type::SharedCost, "+808 1234 5678"),
+            ("+870 301234567", Type::Mobile, "+870 301 234 567"),
+            ("+878 101234567890", Type::Voip, "+878 10 12345 67890"),
+            ("+881 612345678", Type::Mobile, "+881 6 123 45678"),
+            ("+882 3421234", Type::Mobile, "+882 34 21234"),
+            ("+883 510012345", Type::Voip, "+883 510 012 345"),
+            ("+888 12345678901", Type::Uan, "+888 123 456 78901"),
+            ("+979 123456789", Type::PremiumRate, "+979 1 2345 6789"),
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            assert!(validator::is_valid(&number), "{}", input);
+            assert_eq!(kind, number.number_type(), "{}", input);
+            assert_eq!(
+                formatted,
+                number.format().mode(Mode::International).to_string()
+            );
+        }
+
+        // Every global network has a valid example number.
+        for code in DATABASE.supported_global_network_calling_codes() {
+            let meta = DATABASE.non_geographical(code).unwrap();
+            let example = [
+                Type::FixedLine,
+                Type::Mobile,
+                Type::TollFree,
+                Type::PremiumRate,
+                Type::SharedCost,
+                Type::Voip,
+                Type::Uan,
+            ]
+            .iter()
+            .filter_map(|&kind| meta.descriptors().get(kind))
+            .find_map(|d| d.example())
+            .unwrap();
+
+            let number = parser::parse(None, format!("+{}{}", code, example)).unwrap();
+            assert!(validator::is_valid(&number), "+{} {}", code, example);
+        }
+    }
+
+    #[test]
+    fn de() {
+        // German numbers have lengths varying with the area code, fixed line
+        // numbers can have from 5 to 15 digits and mobile ones 10 or 11.
+        for &(input, kind) in &[
+            ("030 901820", Type::FixedLine),
+            ("030 12345678", Type::FixedLine),
+            ("040 12345", Type::FixedLine),
+            ("040 123456", Type::FixedLine),
+            ("069 12345678", Type::FixedLine),
+            ("069 1234567890", Type::FixedLine),
+            ("03375 12345", Type::FixedLine),
+            ("07531 123456", Type::FixedLine),
+            ("032 123456789", Type::FixedLine),
+            ("032 12345678901", Type::FixedLine),
+            ("0151 12345678", Type::Mobile),
+            ("01520 1234567", Type::Mobile),
+            ("0160 1234567", Type::Mobile),
+            ("0176 12345678", Type::Mobile),
+            ("0164 12345", Type::Pager),
+            ("0800 1234567", Type::TollFree),
+            ("0900 1 234567", Type::PremiumRate),
+            ("0180 1234567", Type::SharedCost),
+            ("0700 12345678", Type::PersonalNumber),
+        ] {
+            let number = parser::parse(Some(country::DE), input).unwrap();
+            assert!(validator::is_valid(&number), "{}", input);
+            assert_eq!(kind, number.number_type(), "{}", input);
+            assert_eq!(
+                Validation::IsPossible,
+                validator::is_possible_with_reason(&number),
+                "{}",
+                input
+            );
+        }
+
+        for &(input, possible) in &[
+            // Too short for a fixed line number, but a possible length for
+            // pagers.
+            ("040 12", Validation::IsPossible),
+            // Mobile numbers one digit too short or too long.
+            ("0151 1234567", Validation::IsPossible),
+            ("0151 123456789", Validation::IsPossible),
+            // Longer than any German number.
+            ("069 12345678901234", Validation::TooLong),
+        ] {
+            let number = parser::parse(Some(country::DE), input).unwrap();
+            assert!(!validator::is_valid(&number), "{}", input);
+            assert_eq!(Type::Unknown, number.number_type(), "{}", input);
+            assert_eq!(
+                possible,
+                validator::is_possible_with_reason(&number),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn possible() {
+        assert_eq!(
+            Validation::IsPossible,
+            validator::is_possible_with_reason(&parser::parse(None, "+1 650 253 0000").unwrap())
+        );
+
+        assert_eq!(
+            Validation::IsPossibleLocalOnly,
+            validator::is_possible_with_reason(&parser::parse(None, "+1 253 0000").unwrap())
+        );
+
+        assert_eq!(
+            Validation::IsPossibleLocalOnly,
+            validator::is_possible_with_reason(&parser::parse(Some(country::DE), "123").unwrap())
+        );
+
+        assert_eq!(
+            Validation::TooShort,
+            validator::is_possible_with_reason(&parser::parse(None, "+44 123").unwrap())
+        );
+
+        assert_eq!(
+            Validation::TooLong,
+            validator::is_possible_with_reason(&parser::parse(None, "+1 650 253 00001").unwrap())
+        );
+
+        assert_eq!(
+            Validation::InvalidLength,
+            validator::is_possible_with_reason(&parser::parse(None, "+41 44 668 1800 1").unwrap())
+        );
+
+        assert_eq!(
+            Validation::InvalidCountryCode,
+            validator::is_possible_with_reason(&PhoneNumber {
+                code: country::Code {
+                    value: 999,
+                    source: country::Source::Plus,
+                },
+
+                national: NationalNumber {
+                    value: 123456789,
+                    zeros: 0,
+                },
+
+                extras: None,
+            })
+        );
+
+        assert!(validator::is_possible(
+            &parser::parse(None, "+44 20 7031 3000").unwrap()
+        ));
+
+        assert!(!validator::is_possible(
+            &parser::parse(None, "+44 20 7031 30001").unwrap()
+        ));
+    }
+
+    #[test]
+    fn possible_for_type() {
+        let number = parser::parse(None, "+49 30 12345").unwrap();
+
+        assert!(validator::is_possible_for_type(&number, Type::FixedLine));
+        assert!(!validator::is_possible_for_type(&number, Type::Mobile));
+        assert!(validator::is_possible_for_type(
+            &number,
+            Type::FixedLineOrMobile
+        ));
+        assert_eq!(
+            Validation::TooShort,
+            validator::is_possible_for_type_with_reason(&number, Type::Mobile)
+        );
+
+        assert_eq!(
+            Validation::IsPossible,
+            validator::is_possible_for_type_with_reason(
+                &parser::parse(None, "+1 800 253 0000").unwrap(),
+                Type::TollFree
+            )
+        );
+
+        // Local numbers are only possible for the types that have them.
+        let number = parser::parse(None, "+1 253 0000").unwrap();
+
+        assert_eq!(
+            Validation::IsPossibleLocalOnly,
+            validator::is_possible_for_type_with_reason(&number, Type::FixedLine)
+        );
+        assert_eq!(
+            Validation::TooShort,
+            validator::is_possible_for_type_with_reason(&number, Type::TollFree)
+        );
+
+        assert_eq!(
+            Validation::TooLong,
+            validator::is_possible_for_type_with_reason(
+                &parser::parse(None, "+1 800 253 00000").unwrap(),
+                Type::TollFree
+            )
+        );
+    }
+
+    #[test]
+    fn report() {
+        let report = validator::validate(&parser::parse(None, "+33 1 23 45").unwrap());
+        assert!(!report.is_valid());
+        assert_eq!("12345", report.national());
+        assert_eq!(Some(country::FR), report.region());
+        assert_eq!(Validation::TooShort, report.possible());
+        assert!(report.types().is_empty());
+        assert!(!report.is_leading_digits_mismatch());
+        assert_eq!(
+            "national number 12345, region FR, length too short, matches no number type",
+            report.to_string()
+        );
+
+        let report = validator::validate(&parser::parse(None, "+44 6912 345678").unwrap());
+        assert!(!report.is_valid());
+        assert_eq!(None, report.region());
+        assert_eq!(
+            &[country::GB, country::GG, country::IM, country::JE][..],
+            report.regions()
+        );
+        assert_eq!(Validation::IsPossible, report.possible());
+        assert!(report.is_leading_digits_mismatch());
+        assert_eq!(
+            "national number 6912345678, no matching region among GB/GG/IM/JE, \
+             length possible, matches no number type (leading digits)",
+            report.to_string()
+        );
+
+        let report = validator::validate(&parser::parse(None, "+44 7912 345678").unwrap());
+        assert!(report.is_valid());
+        assert_eq!(Some(country::GB), report.region());
+        assert_eq!(&[Type::Mobile][..], report.types());
+    }
+
+    #[test]
+    fn alpha() {
+        assert!(validator::is_alpha_number("1800 six-flags"));
+        assert!(validator::is_alpha_number("1800 six-flags ext. 1234"));
+        assert!(validator::is_alpha_number("1800 SIX-FLAG ext. 1234"));
+        assert!(validator::is_alpha_number("+800 six-flags"));
+        assert!(validator::is_alpha_number("180 six-flags"));
+        assert!(validator::is_alpha_number("+1 800 call-me-now"));
+        assert!(validator::is_alpha_number("+1 call-me-now"));
+        assert!(validator::is_alpha_number("+1 CALL-ME-NOW ext. 1234"));
+
+        assert!(!validator::is_alpha_number("1800 123-1234"));
+        assert!(!validator::is_alpha_number("1 six-flags"));
+        assert!(!validator::is_alpha_number("18 six-flags"));
+        assert!(!validator::is_alpha_number("1800 123-1234 extension: 1234"));
+        assert!(!validator::is_alpha_number("+800 1234-1234"));
+        assert!(!validator::is_alpha_number("abc"));
+        assert!(!validator::is_alpha_number("+abc"));
+        assert!(!validator::is_alpha_number("+1 ab"));
+    }
+
+    #[test]
+    fn possible_str() {
+        let corpus = [
+            (None, "+1 650 253 0000"),
+            (Some(country::US), "650 253 0000"),
+            (Some(country::US), "(650) 253-0000 ext. 1234"),
+            (Some(country::US), "1-800-FLOWERS"),
+            (Some(country::US), "011 44 20 7031 3000"),
+            (Some(country::US), "1 650 253 0000"),
+            (Some(country::US), "253 0000"),
+            (Some(country::US), "12"),
+            (None, "+44 20 7031 3000"),
+            (Some(country::GB), "020 7031 3000"),
+            (Some(country::GB), "00 1 650 253 0000"),
+            (Some(country::BR), "0015 1 650 253 0000"),
+            (Some(country::BR), "0016 1 650 253 0000"),
+            (Some(country::AU), "0011 44 20 7031 3000"),
+            (None, "+44 20 7031 30001"),
+            (None, "+49 30 123456"),
+            (Some(country::DE), "030 123456"),
+            (Some(country::DE), "123"),
+            (None, "+39 02 3661 8300"),
+            (None, "+41 44 668 1800 1"),
+            (None, "+800 1234 5678"),
+            (Some(country::RU), "8 800 555 35 35"),
+            (None, "+999 123 456"),
+            (None, "650 253 0000"),
+        ];
+
+        for (country, input) in corpus {
+            let full = parser::parse(country, input)
+                .map(|n| validator::is_possible(&n))
+                .unwrap_or(false);
+
+            assert_eq!(
+                full,
+                validator::is_possible_str(country, input),
+                "{:?} {:?}",
+                country,
+                input
+            );
+        }
+
+        assert!(validator::is_possible_str(None, "+1 650 253 0000"));
+        assert!(!validator::is_possible_str(None, "+44 20 7031 30001"));
+
+        // Plain digit international prefixes don't need the pattern compiled.
+        let database = Database::from_bytes(&DATABASE.to_bytes().unwrap()).unwrap();
+        assert!(validator::is_possible_str_with(
+            &database,
+            Some(country::US),
+            "011 44 20 7031 3000"
+        ));
+        assert!(!database
+            .by_id("US")
+            .unwrap()
+            .international_prefix()
+            .unwrap()
+            .is_compiled());
+    }
+
+    #[test]
+    fn for_region() {
+        // The region of any number can be passed back, `None` for those of
+        // non-geographical entities.
+        for input in &[
+            "+800 1234 5678",
+            "+49 30 123456",
+            "+1 650 253 0000",
+            "+44 7912 345678",
+        ] {
+            let number = parser::parse(None, input).unwrap();
+            assert!(
+                validator::is_valid_for_region(&number, number.country().id()),
+                "{}",
+                input
+            );
+        }
+
+        let number = parser::parse(None, "+800 1234 5678").unwrap();
+        assert!(number.is_valid_for_region(None));
+        assert!(!number.is_valid_for_region(country::US));
+
+        let number = parser::parse(None, "+49 30 123456").unwrap();
+        assert!(number.is_valid_for_region(country::DE));
+        assert!(number.is_valid_for_region(Some(country::DE)));
+        assert!(!number.is_valid_for_region(None));
+
+        // Valid for Canada, not the US sharing its country calling code.
+        let number = parser::parse(None, "+1 613 555 0123").unwrap();
+        assert!(number.is_valid_for_region(country::CA));
+        assert!(!number.is_valid_for_region(country::US));
+        assert!(!number.is_valid_for_region(None));
+    }
 }
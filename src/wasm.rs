@@ -0,0 +1,175 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JavaScript bindings, built for `wasm32-unknown-unknown` with `wasm-pack`.
+//!
+//! The embedded metadata is decoded on first use. To keep the module small,
+//! build it with `PHONENUMBER_REGIONS` set to the regions needed, or load a
+//! database encoded with `Database::to_bytes` with `loadMetadata`.
+//!
+//! ```js
+//! import { parse, Mode } from "phonenumber";
+//!
+//! const number = parse("030 123456", "DE");
+//! number.isValid(); // true
+//! number.format(Mode.International); // "+49 30 123456"
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::country;
+use crate::formatter;
+use crate::metadata::{self, Database};
+use crate::phone_number::{self, PhoneNumber};
+
+/// A parsed phone number.
+#[wasm_bindgen(js_name = PhoneNumber)]
+pub struct JsPhoneNumber(PhoneNumber);
+
+/// The formatting modes, see `phonenumber::Mode`.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Mode {
+    E164,
+    International,
+    National,
+    Rfc3966,
+}
+
+/// The phone number types, see `phonenumber::Type`.
+#[wasm_bindgen]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NumberType {
+    FixedLine,
+    Mobile,
+    FixedLineOrMobile,
+    TollFree,
+    PremiumRate,
+    SharedCost,
+    PersonalNumber,
+    Voip,
+    Pager,
+    Uan,
+    Emergency,
+    Voicemail,
+    ShortCode,
+    StandardRate,
+    Carrier,
+    NoInternational,
+    Unknown,
+}
+
+impl From<Mode> for formatter::Mode {
+    fn from(mode: Mode) -> formatter::Mode {
+        match mode {
+            Mode::E164 => formatter::Mode::E164,
+            Mode::International => formatter::Mode::International,
+            Mode::National => formatter::Mode::National,
+            Mode::Rfc3966 => formatter::Mode::Rfc3966,
+        }
+    }
+}
+
+impl From<phone_number::Type> for NumberType {
+    fn from(kind: phone_number::Type) -> NumberType {
+        use crate::phone_number::Type;
+
+        match kind {
+            Type::FixedLine => NumberType::FixedLine,
+            Type::Mobile => NumberType::Mobile,
+            Type::FixedLineOrMobile => NumberType::FixedLineOrMobile,
+            Type::TollFree => NumberType::TollFree,
+            Type::PremiumRate => NumberType::PremiumRate,
+            Type::SharedCost => NumberType::SharedCost,
+            Type::PersonalNumber => NumberType::PersonalNumber,
+            Type::Voip => NumberType::Voip,
+            Type::Pager => NumberType::Pager,
+            Type::Uan => NumberType::Uan,
+            Type::Emergency => NumberType::Emergency,
+            Type::Voicemail => NumberType::Voicemail,
+            Type::ShortCode => NumberType::ShortCode,
+            Type::StandardRate => NumberType::StandardRate,
+            Type::Carrier => NumberType::Carrier,
+            Type::NoInternational => NumberType::NoInternational,
+            Type::Unknown => NumberType::Unknown,
+        }
+    }
+}
+
+/// Parse a phone number, in the given region, like "DE", when it's not in the
+/// international format.
+#[wasm_bindgen]
+pub fn parse(input: &str, region: Option<String>) -> Result<JsPhoneNumber, JsError> {
+    let region = region
+        .map(|id| id.parse::<country::Id>())
+        .transpose()
+        .map_err(|_| JsError::new("invalid region"))?;
+
+    Ok(JsPhoneNumber(crate::parser::parse(region, input)?))
+}
+
+/// Replace the metadata with a database encoded with `Database::to_bytes`,
+/// like one fetched as an `ArrayBuffer`.
+#[wasm_bindgen(js_name = loadMetadata)]
+pub fn load_metadata(bytes: &[u8]) -> Result<(), JsError> {
+    metadata::set_global(Database::from_bytes(bytes)?);
+    Ok(())
+}
+
+#[wasm_bindgen(js_class = PhoneNumber)]
+impl JsPhoneNumber {
+    /// Format the number.
+    pub fn format(&self, mode: Mode) -> String {
+        self.0.format().mode(mode.into()).to_string()
+    }
+
+    /// Check if the number is valid.
+    #[wasm_bindgen(js_name = isValid)]
+    pub fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    /// Get the type of the number.
+    #[wasm_bindgen(js_name = numberType)]
+    pub fn number_type(&self) -> NumberType {
+        self.0.number_type().into()
+    }
+
+    /// Format the number in the E.164 format.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.format(Mode::E164)
+    }
+}
+
+// Creating `JsError`s only works on WebAssembly, so only the successful paths
+// are tested natively.
+#[cfg(test)]
+mod test {
+    use crate::wasm::{self, Mode, NumberType};
+
+    #[test]
+    fn parse() {
+        let number = wasm::parse("030 123456", Some("DE".into())).unwrap();
+        assert!(number.is_valid());
+        assert_eq!(NumberType::FixedLine, number.number_type());
+        assert_eq!("+49 30 123456", number.format(Mode::International));
+        assert_eq!("030 123456", number.format(Mode::National));
+
+        let number = wasm::parse("+1 650 253 0000", None).unwrap();
+        assert_eq!(NumberType::FixedLineOrMobile, number.number_type());
+        assert_eq!("+16502530000", number.to_js_string());
+        assert_eq!("tel:+1-650-253-0000", number.format(Mode::Rfc3966));
+    }
+}
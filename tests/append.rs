@@ -0,0 +1,80 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Count the allocations of appending formatted numbers to a buffer with
+//! enough capacity, which there should be none of. This has to be the only
+//! test in the binary for no other allocations to be counted.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use phonenumber::Mode;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn append_e164() {
+    let numbers = [
+        "+1 650 253 0000",
+        "+44 20 7031 3000",
+        "+39 02 3661 8300",
+        "+49 30 123456",
+        "+800 1234 5678",
+    ]
+    .iter()
+    .map(|input| phonenumber::parse(None, input).unwrap())
+    .collect::<Vec<_>>();
+
+    let mut string = String::with_capacity(1024);
+    let mut bytes = Vec::with_capacity(1024);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+
+    for number in &numbers {
+        number.format_append(Mode::E164, &mut string);
+        string.push('\n');
+
+        number.format_append_bytes(Mode::E164, &mut bytes);
+        bytes.push(b'\n');
+    }
+
+    assert_eq!(0, ALLOCATIONS.load(Ordering::Relaxed) - before);
+    assert_eq!(
+        "+16502530000\n+442070313000\n+390236618300\n+4930123456\n+80012345678\n",
+        string
+    );
+    assert_eq!(string.as_bytes(), &bytes[..]);
+    assert!(bytes.is_ascii());
+}
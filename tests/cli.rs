@@ -0,0 +1,109 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_phonenumber"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn json(args: &[&str]) -> Value {
+    let output = run(args);
+    assert!(output.status.success(), "{:?}", output);
+
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn parse() {
+    let output = run(&["parse", "--region", "SE", "070-123 45 67"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("        Valid: true\n"));
+    assert!(stdout.contains("         Type: mobile\n"));
+    assert!(stdout.contains("        E.164: +46701234567\n"));
+    assert!(stdout.contains("International: +46 70 123 45 67\n"));
+    assert!(stdout.contains("     National: 070-123 45 67\n"));
+    assert!(stdout.contains("      RFC3966: tel:+46-70-123-45-67\n"));
+
+    let value = json(&["parse", "--json", "+49 30 123456"]);
+    assert_eq!(Value::Bool(true), value["valid"]);
+    assert_eq!("fixed_line", value["type"]);
+    assert_eq!("+49 30 123456", value["formats"]["international"]);
+    assert_eq!("030 123456", value["formats"]["national"]);
+}
+
+#[test]
+fn find() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli-find.txt");
+    fs::write(
+        &path,
+        "Call +1 650 253 0000 or\n(650) 253-0001, not 12345 or 2020-01-01.\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run(&["find", "--region", "US", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        "1:6: +1 650 253 0000 +16502530000\n2:1: (650) 253-0001 +16502530001\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    let value = json(&["find", "--region", "US", "--json", path]);
+    let found = value.as_array().unwrap();
+    assert_eq!(2, found.len());
+    assert_eq!("(650) 253-0001", found[1]["text"]);
+    assert_eq!(2, found[1]["line"]);
+    assert_eq!("+16502530001", found[1]["formats"]["e164"]);
+}
+
+#[test]
+fn example() {
+    let value = json(&["example", "--region", "DE", "--type", "mobile", "--json"]);
+    assert_eq!(Value::Bool(true), value["valid"]);
+    assert_eq!("mobile", value["type"]);
+
+    let value = json(&["example", "--region", "DE", "--json"]);
+    assert_eq!("fixed_line", value["type"]);
+}
+
+#[test]
+fn errors() {
+    for args in [
+        &[][..],
+        &["bogus"],
+        &["parse"],
+        &["parse", "--region", "XX", "030 123456"],
+        &["parse", "030 123456"],
+        &["example", "--region", "DE", "--type", "bogus"],
+        &["find", "/nonexistent"],
+    ] {
+        let output = run(args);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("error: "));
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Run the same checks through the functions using the global database and
+//! their `_with` variants given a database that disagrees with it, to make
+//! sure neither leaks into the other.
+
+use phonenumber::metadata::Database;
+use phonenumber::{country, ExtensionError, ExtensionPolicy, Mode, ParseError, Type, Validation};
+
+/// Germany with only ten digit fixed-line numbers in Berlin, formatted with
+/// a dash, and nothing else.
+const METADATA: &str = r#"
+    <phoneNumberMetadata>
+      <territories>
+        <territory id="DE" countryCode="49" internationalPrefix="00" nationalPrefix="0"
+                   nationalPrefixFormattingRule="$NP$FG">
+          <availableFormats>
+            <numberFormat pattern="(\d{2})(\d{8})">
+              <format>$1-$2</format>
+            </numberFormat>
+          </availableFormats>
+          <generalDesc>
+            <nationalNumberPattern>30\d{8}</nationalNumberPattern>
+          </generalDesc>
+          <fixedLine>
+            <possibleLengths national="10"/>
+            <exampleNumber>3098765432</exampleNumber>
+            <nationalNumberPattern>30\d{8}</nationalNumberPattern>
+          </fixedLine>
+        </territory>
+      </territories>
+    </phoneNumberMetadata>
+"#;
+
+fn custom() -> Database {
+    Database::parse(METADATA).unwrap()
+}
+
+#[test]
+fn parse() {
+    let database = custom();
+
+    assert!(phonenumber::parse(None, "+1 650 253 0000").is_ok());
+    assert_eq!(
+        Err(ParseError::UnsupportedRegion),
+        phonenumber::parse_with(&database, None, "+1 650 253 0000")
+    );
+
+    assert!(phonenumber::parse_e164("+16502530000").is_ok());
+    assert!(phonenumber::parse_e164_with(&database, "+16502530000").is_err());
+
+    assert!(phonenumber::is_e164("+16502530000"));
+    assert!(!phonenumber::is_e164_with(&database, "+16502530000"));
+    assert!(phonenumber::is_e164_with(&database, "+493012345678"));
+
+    let policy = ExtensionPolicy::ValidateLength(3);
+    assert!(phonenumber::parse_with_policy(None, "+1 650 253 0000 ext. 123", policy).is_ok());
+    assert_eq!(
+        Err(ParseError::UnsupportedRegion),
+        phonenumber::parse_with_policy_with(&database, None, "+1 650 253 0000 ext. 123", policy)
+    );
+    assert!(phonenumber::parse_with_policy_with(
+        &database,
+        None,
+        "+49 30 12345678 ext. 123",
+        policy
+    )
+    .is_ok());
+    assert_eq!(
+        Err(ParseError::InvalidExtension(ExtensionError::TooLong(4))),
+        phonenumber::parse_with_policy_with(&database, None, "+49 30 12345678 ext. 1234", policy)
+    );
+}
+
+#[test]
+fn validity() {
+    let database = custom();
+    let short = phonenumber::parse(Some(country::DE), "030 123456").unwrap();
+    let long = phonenumber::parse(Some(country::DE), "030 12345678").unwrap();
+
+    assert!(short.is_valid());
+    assert!(!short.is_valid_with(&database));
+    assert!(phonenumber::is_valid(&short));
+    assert!(!phonenumber::is_valid_with(&database, &short));
+    assert!(long.is_valid_with(&database));
+
+    assert_eq!(Type::FixedLine, short.number_type());
+    assert_eq!(Type::Unknown, short.number_type_with(&database));
+    assert_eq!(Type::FixedLine, long.number_type_with(&database));
+
+    assert!(short.is_valid_for_region(country::DE));
+    assert!(!short.is_valid_for_region_with(&database, country::DE));
+
+    assert!(short.validate().is_valid());
+    assert!(!short.validate_with(&database).is_valid());
+
+    // The global database is unaffected by having used the custom one.
+    assert!(short.is_valid());
+}
+
+#[test]
+fn possibility() {
+    let database = custom();
+    let short = phonenumber::parse(Some(country::DE), "030 123456").unwrap();
+
+    assert!(short.is_possible());
+    assert!(!short.is_possible_with(&database));
+    assert_eq!(Validation::IsPossible, short.is_possible_with_reason());
+    assert_eq!(
+        Validation::TooShort,
+        short.is_possible_with_reason_with(&database)
+    );
+
+    assert!(phonenumber::is_possible_str(
+        Some(country::DE),
+        "030 123456"
+    ));
+    assert!(!phonenumber::is_possible_str_with(
+        &database,
+        Some(country::DE),
+        "030 123456"
+    ));
+}
+
+#[test]
+fn formatting() {
+    let database = custom();
+    let number = phonenumber::parse(Some(country::DE), "030 12345678").unwrap();
+
+    assert_eq!(
+        "030 12345678",
+        number.format().mode(Mode::National).to_string()
+    );
+    assert_eq!(
+        "030-12345678",
+        number
+            .format_with(&database)
+            .mode(Mode::National)
+            .to_string()
+    );
+    assert_eq!(
+        "030-12345678",
+        phonenumber::format_with(&database, &number)
+            .mode(Mode::National)
+            .to_string()
+    );
+}
+
+#[test]
+fn matching() {
+    let database = custom();
+
+    assert_eq!(
+        phonenumber::MatchType::ExactMatch,
+        phonenumber::is_number_match("+1 650 253 0000", "+1 650 253 0000")
+    );
+    assert_eq!(
+        phonenumber::MatchType::NotANumber,
+        phonenumber::is_number_match_with(&database, "+1 650 253 0000", "+1 650 253 0000")
+    );
+
+    let policy = ExtensionPolicy::RequireAbsent;
+    assert_eq!(
+        phonenumber::MatchType::ExactMatch,
+        phonenumber::is_number_match_with_policy("+1 650 253 0000", "+1 650 253 0000", policy)
+    );
+    assert_eq!(
+        phonenumber::MatchType::NotANumber,
+        phonenumber::is_number_match_with_policy_with(
+            &database,
+            "+1 650 253 0000",
+            "+1 650 253 0000",
+            policy
+        )
+    );
+    assert_eq!(
+        phonenumber::MatchType::ExactMatch,
+        phonenumber::is_number_match_with_policy_with(
+            &database,
+            "+49 30 12345678",
+            "+49 30 12345678",
+            policy
+        )
+    );
+    assert_eq!(
+        phonenumber::MatchType::NotANumber,
+        phonenumber::is_number_match_with_policy_with(
+            &database,
+            "+49 30 12345678 ext. 1",
+            "+49 30 12345678",
+            policy
+        )
+    );
+}
+
+#[test]
+fn examples() {
+    let database = custom();
+
+    let number = phonenumber::example_number(country::DE).unwrap();
+    assert!(number.is_valid());
+
+    let number = phonenumber::example_number_with(&database, country::DE).unwrap();
+    assert_eq!(3098765432, number.national().value());
+    assert!(number.is_valid_with(&database));
+
+    assert!(phonenumber::example_number(country::US).is_some());
+    assert_eq!(
+        None,
+        phonenumber::example_number_with(&database, country::US)
+    );
+
+    let mut seed = 0;
+    let number = phonenumber::random_number_for_region_with(&database, country::DE, || {
+        seed += 1;
+        seed
+    })
+    .unwrap();
+    assert!(number.is_valid_with(&database));
+}
@@ -0,0 +1,107 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Swap the global database back and forth while other threads are using it.
+//! This replaces the global database, so it has to be the only test in the
+//! binary.
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use phonenumber::metadata::{self, Database, DATABASE};
+use phonenumber::{Mode, ParseError, Type};
+
+#[test]
+fn global() {
+    let xml = std::fs::read_to_string("assets/PhoneNumberMetadata.xml")
+        .unwrap()
+        .replacen(
+            "<territories>",
+            r#"<territories>
+              <territory id="001" countryCode="801">
+                <generalDesc>
+                  <nationalNumberPattern>\d{8}</nationalNumberPattern>
+                </generalDesc>
+                <voip>
+                  <possibleLengths national="8"/>
+                  <nationalNumberPattern>\d{8}</nationalNumberPattern>
+                </voip>
+              </territory>"#,
+            1,
+        );
+    let database = Database::parse(xml).unwrap();
+
+    assert!(matches!(
+        phonenumber::parse(None, "+801 1234 5678"),
+        Err(ParseError::InvalidCountryCode)
+    ));
+
+    // A thread that already used the previous database picks up the new
+    // one on its next call.
+    let barrier = Arc::new(Barrier::new(2));
+    let stale = {
+        let barrier = barrier.clone();
+
+        thread::spawn(move || {
+            assert!(phonenumber::parse(None, "+1 650 253 0000").is_ok());
+            barrier.wait();
+            barrier.wait();
+            assert!(phonenumber::parse(None, "+801 1234 5678").is_ok());
+        })
+    };
+
+    barrier.wait();
+    metadata::set_global(database.clone());
+    barrier.wait();
+    stale.join().unwrap();
+
+    let number = phonenumber::parse(None, "+801 1234 5678").unwrap();
+    assert!(phonenumber::is_valid(&number));
+    assert_eq!(Type::Voip, number.number_type());
+    assert_eq!(
+        "+801 12345678",
+        number.format().mode(Mode::International).to_string()
+    );
+
+    // Swap back and forth while other threads are parsing.
+    let threads = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                for _ in 0..200 {
+                    let number = phonenumber::parse(None, "+1 650 253 0000").unwrap();
+                    assert!(phonenumber::is_valid(&number));
+                    assert_eq!(
+                        "+1 650-253-0000",
+                        number.format().mode(Mode::International).to_string()
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for i in 0..50 {
+        if i % 2 == 0 {
+            metadata::set_global(DATABASE.clone());
+        } else {
+            metadata::set_global(database.clone());
+        }
+    }
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    metadata::set_global(DATABASE.clone());
+    assert!(phonenumber::parse(None, "+801 1234 5678").is_err());
+}
@@ -0,0 +1,45 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Check the short number functions follow the global database once it's
+//! replaced. This swaps the global database, so it has to be the only test in
+//! the binary.
+
+use phonenumber::metadata::{self, Database, DATABASE};
+use phonenumber::{country, shortnumber};
+
+#[test]
+fn global() {
+    assert!(shortnumber::is_emergency_number("911", country::US));
+    assert!(shortnumber::is_emergency_number("112", country::DE));
+
+    metadata::set_global(
+        Database::from_metadata(DATABASE.by_id("DE").cloned())
+            .with_short_metadata(DATABASE.short_metadata(country::DE).cloned()),
+    );
+
+    assert!(!shortnumber::is_emergency_number("911", country::US));
+    assert!(!shortnumber::connects_to_emergency_number(
+        "911",
+        country::US
+    ));
+    assert!(shortnumber::is_emergency_number("112", country::DE));
+
+    // The `_with` variants keep using the database they're given.
+    assert!(shortnumber::is_emergency_number_with(
+        &DATABASE,
+        "911",
+        country::US
+    ));
+}
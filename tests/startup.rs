@@ -0,0 +1,61 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parse from many threads at once before anything touched the metadata, so
+//! they all race to load it. This has to be the only test in the binary for
+//! the metadata to still be unloaded.
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use phonenumber::{country, metadata, Mode};
+
+const THREADS: usize = 32;
+
+#[test]
+fn concurrent_initialization() {
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let threads = (0..THREADS)
+        .map(|i| {
+            let barrier = barrier.clone();
+
+            thread::spawn(move || {
+                barrier.wait();
+
+                let number = if i % 2 == 0 {
+                    phonenumber::parse(Some(country::DE), "030 123456").unwrap()
+                } else {
+                    phonenumber::parse(None, "+1 650 253 0000").unwrap()
+                };
+
+                assert!(number.is_valid());
+                number.format().mode(Mode::E164).to_string()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (i, thread) in threads.into_iter().enumerate() {
+        let expected = if i % 2 == 0 {
+            "+4930123456"
+        } else {
+            "+16502530000"
+        };
+
+        assert_eq!(expected, thread.join().unwrap());
+    }
+
+    // Everyone ended up with the same database.
+    assert!(Arc::ptr_eq(&metadata::global(), &metadata::global()));
+}
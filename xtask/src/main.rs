@@ -0,0 +1,324 @@
+// Copyright (C) 2017 1aim GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maintenance tasks, run with `cargo xtask <task>`.
+//!
+//! - `metadata <PhoneNumberMetadata.xml> [--out <database.bin>]`: check new
+//!   upstream metadata, print a summary of the changes from the current one,
+//!   and replace `assets/PhoneNumberMetadata.xml` with it so the build script
+//!   regenerates the embedded database. With `--out` the encoded database is
+//!   written as well.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use phonenumber::metadata::{Database, Descriptor, Format, Metadata};
+
+const USAGE: &str = "usage: cargo xtask metadata <PhoneNumberMetadata.xml> [--out <database.bin>]";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["metadata", path] => metadata(Path::new(path), None),
+        ["metadata", path, "--out", out] => metadata(Path::new(path), Some(Path::new(out))),
+        _ => Err(USAGE.into()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// The root of the workspace, where the `assets` live.
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+/// Load upstream XML metadata, any element or attribute the loader doesn't
+/// know about fails instead of being dropped.
+fn load(path: &Path) -> Result<Database, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    Database::from_reader(file).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Encode the database like the embedded one, making sure it loads back.
+fn generate(database: &Database) -> Result<Vec<u8>, String> {
+    let bytes = database.to_bytes().map_err(|err| err.to_string())?;
+    let decoded = Database::from_bytes(&bytes).map_err(|err| err.to_string())?;
+
+    if Summary::new(database, &decoded) != Summary::default() {
+        return Err("the encoded database doesn't match the metadata".into());
+    }
+
+    Ok(bytes)
+}
+
+fn metadata(path: &Path, out: Option<&Path>) -> Result<(), String> {
+    let assets = root().join("assets/PhoneNumberMetadata.xml");
+    let new = load(path)?;
+    let old = load(&assets)?;
+    let bytes = generate(&new)?;
+
+    print!("{}", Summary::new(&old, &new));
+
+    if fs::canonicalize(path).ok() != fs::canonicalize(&assets).ok() {
+        fs::copy(path, &assets).map_err(|err| format!("{}: {}", assets.display(), err))?;
+    }
+
+    if let Some(out) = out {
+        fs::write(out, bytes).map_err(|err| format!("{}: {}", out.display(), err))?;
+    }
+
+    Ok(())
+}
+
+/// The changes between two versions of the metadata, for release notes.
+#[derive(Eq, PartialEq, Default, Debug)]
+struct Summary {
+    /// Regions, or `+code` for non-geographical entities, only in the new
+    /// metadata.
+    added: Vec<String>,
+
+    /// Regions only in the old metadata.
+    removed: Vec<String>,
+
+    /// The number of changed patterns of regions in both.
+    changed: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    fn new(old: &Database, new: &Database) -> Self {
+        let old = entries(old);
+        let new = entries(new);
+
+        let mut summary = Summary {
+            added: new
+                .keys()
+                .filter(|id| !old.contains_key(*id))
+                .cloned()
+                .collect(),
+            removed: old
+                .keys()
+                .filter(|id| !new.contains_key(*id))
+                .cloned()
+                .collect(),
+            changed: BTreeMap::new(),
+        };
+
+        for (id, new) in &new {
+            let old = match old.get(id) {
+                Some(old) => old,
+                None => continue,
+            };
+
+            let (old, new) = (patterns(old), patterns(new));
+            let changed = old
+                .keys()
+                .chain(new.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter(|key| old.get(*key) != new.get(*key))
+                .count();
+
+            if changed > 0 {
+                summary.changed.insert(id.clone(), changed);
+            }
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list(values: &[String]) -> String {
+            if values.is_empty() {
+                "none".into()
+            } else {
+                values.join(", ")
+            }
+        }
+
+        writeln!(f, "Regions added: {}", list(&self.added))?;
+        writeln!(f, "Regions removed: {}", list(&self.removed))?;
+        writeln!(
+            f,
+            "Patterns changed: {}",
+            self.changed.values().sum::<usize>()
+        )?;
+
+        for (id, changed) in &self.changed {
+            writeln!(f, "  {}: {}", id, changed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// All the entries of the database, by region or `+code` for
+/// non-geographical entities.
+fn entries(database: &Database) -> BTreeMap<String, &Metadata> {
+    database
+        .iter_regions()
+        .map(|(id, meta)| (id.as_ref().to_owned(), meta))
+        .chain(
+            database
+                .supported_global_network_calling_codes()
+                .filter_map(|code| Some((format!("+{}", code), database.non_geographical(code)?))),
+        )
+        .collect()
+}
+
+/// All the patterns of a metadata entry, by where they are used.
+fn patterns(meta: &Metadata) -> BTreeMap<String, String> {
+    let mut result = BTreeMap::new();
+    let desc = meta.descriptors();
+
+    let descriptors: [(&str, Option<&Descriptor>); 17] = [
+        ("generalDesc", Some(desc.general())),
+        ("fixedLine", desc.fixed_line()),
+        ("mobile", desc.mobile()),
+        ("tollFree", desc.toll_free()),
+        ("premiumRate", desc.premium_rate()),
+        ("sharedCost", desc.shared_cost()),
+        ("personalNumber", desc.personal_number()),
+        ("voip", desc.voip()),
+        ("pager", desc.pager()),
+        ("uan", desc.uan()),
+        ("emergency", desc.emergency()),
+        ("voicemail", desc.voicemail()),
+        ("shortCode", desc.short_code()),
+        ("standardRate", desc.standard_rate()),
+        ("carrierSpecific", desc.carrier()),
+        ("noInternationalDialling", desc.no_international()),
+        ("smsServices", desc.sms_services()),
+    ];
+
+    for (name, descriptor) in descriptors {
+        if let Some(descriptor) = descriptor {
+            result.insert(name.into(), descriptor.national_number().as_str().into());
+        }
+    }
+
+    for (name, pattern) in [
+        ("internationalPrefix", meta.international_prefix()),
+        (
+            "nationalPrefixForParsing",
+            meta.national_prefix_for_parsing(),
+        ),
+        ("leadingDigits", meta.leading_digits()),
+    ] {
+        if let Some(pattern) = pattern {
+            result.insert(name.into(), pattern.as_str().into());
+        }
+    }
+
+    let describe = |format: &Format| {
+        let leading = format
+            .leading_digits()
+            .iter()
+            .map(|l| l.as_str())
+            .collect::<Vec<_>>();
+
+        format!(
+            "{} {} {:?}",
+            format.pattern().as_str(),
+            format.format(),
+            leading
+        )
+    };
+
+    let national = meta.formats().iter().map(describe).collect::<Vec<_>>();
+
+    // International formats without an `intlFormat` of their own are copies
+    // of the national ones, so they'd count every change twice.
+    let international = meta
+        .international_formats()
+        .iter()
+        .map(describe)
+        .filter(|format| !national.contains(format))
+        .collect::<Vec<_>>();
+
+    for (kind, formats) in [("numberFormat", national), ("intlFormat", international)] {
+        for (i, format) in formats.into_iter().enumerate() {
+            result.insert(format!("{} {}", kind, i), format);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{generate, load, Summary};
+    use phonenumber::metadata::Database;
+
+    #[test]
+    fn summary() {
+        let old = load(Path::new("fixtures/old.xml")).unwrap();
+        let new = load(Path::new("fixtures/new.xml")).unwrap();
+
+        assert_eq!(
+            include_str!("../fixtures/summary.txt"),
+            Summary::new(&old, &new).to_string()
+        );
+        assert_eq!(Summary::default(), Summary::new(&new, &new));
+    }
+
+    #[test]
+    fn generated() {
+        let new = load(Path::new("fixtures/new.xml")).unwrap();
+        let bytes = generate(&new).unwrap();
+
+        assert_eq!(bytes, generate(&new).unwrap());
+        assert_eq!(
+            bytes,
+            Database::from_bytes(&bytes).unwrap().to_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn schema_change() {
+        let xml = include_str!("../fixtures/new.xml");
+
+        for changed in [
+            xml.replace(
+                r#"<territory id="AD""#,
+                r#"<territory newAttribute="1" id="AD""#,
+            ),
+            xml.replace("<generalDesc>", "<generalDesc><newElement/>"),
+            xml.replace("<territories>", "<newElement/><territories>"),
+            xml.replace("<territories>", "<newElement></newElement><territories>"),
+        ] {
+            assert!(
+                Database::from_reader(changed.as_bytes()).is_err(),
+                "{}",
+                changed
+            );
+        }
+    }
+}